@@ -1,5 +1,7 @@
 use clap::Parser;
 
+use crate::network::NetworkDeployment;
+
 #[derive(Parser, Debug, Clone)]
 pub struct Config {
     #[arg(long, env = "API_BIND", default_value = "0.0.0.0:8081")]
@@ -14,4 +16,46 @@ pub struct Config {
     pub finality_window: u32,
     #[arg(long, env = "MAX_REQUESTS_PER_SEC", default_value_t = 200)]
     pub max_requests_per_sec: u64,
+    /// Requests each caller may make per minute.
+    #[arg(long, env = "RATE_LIMIT_PER_MINUTE", default_value_t = crate::ratelimit::DEFAULT_PER_MINUTE)]
+    pub rate_limit_per_minute: u64,
+    /// Callers tracked at once; further ones share one quota.
+    #[arg(long, env = "RATE_LIMIT_MAX_CALLERS", default_value_t = crate::ratelimit::DEFAULT_MAX_CALLERS)]
+    pub rate_limit_max_callers: usize,
+    /// Searches each client IP may make per minute, on top of the above.
+    #[arg(long, env = "SEARCH_RATE_LIMIT_PER_MINUTE", default_value_t = crate::search::DEFAULT_PER_MINUTE)]
+    pub search_rate_limit_per_minute: u64,
+    /// Keys accepted in `X-API-Key`, comma-separated. Callers sending any
+    /// other key are rate limited and counted by IP.
+    #[arg(
+        long = "api-key",
+        env = "API_KEYS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    pub api_keys: Vec<String>,
+    #[arg(long, env = "ADMIN_TOKEN")]
+    pub admin_token: Option<String>,
+    /// Adds a `Server-Timing` header with db/cache/serialize durations.
+    #[arg(long, env = "SERVER_TIMING", default_value_t = false)]
+    pub server_timing: bool,
+    #[arg(long, env = "USAGE_FLUSH_SECS", default_value_t = 60)]
+    pub usage_flush_secs: u64,
+    /// Days of `api_usage` buckets to keep; `0` keeps them forever.
+    #[arg(long, env = "USAGE_RETENTION_DAYS", default_value_t = 90)]
+    pub usage_retention_days: u64,
+    /// JSON-RPC endpoint of the daemon behind the proxy features; its health
+    /// is reported by `/readyz` and `/api/v1/status` when set.
+    #[arg(long, env = "DAEMON_URL")]
+    pub daemon_url: Option<String>,
+    /// Blocks the daemon may be ahead of or behind the ingested tip before it
+    /// is reported unhealthy.
+    #[arg(long, env = "DAEMON_MAX_LAG", default_value_t = 10)]
+    pub daemon_max_lag: u64,
+    #[arg(long, env = "DAEMON_PROBE_SECS", default_value_t = 15)]
+    pub daemon_probe_secs: u64,
+    /// Another network to serve from this process, as
+    /// `name|database_url|redis_url`; repeat the flag or comma-separate.
+    #[arg(long = "extra-network", env = "EXTRA_NETWORKS", value_delimiter = ',')]
+    pub extra_networks: Vec<NetworkDeployment>,
 }
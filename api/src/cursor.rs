@@ -0,0 +1,21 @@
+//! Opaque pagination tokens. A cursor holds the position after the last row
+//! of a page, serialized as JSON behind the tag of the listing it belongs to
+//! and hex-encoded, so clients hand it back verbatim instead of building one.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub trait Cursor: Serialize + DeserializeOwned {
+    /// Keeps a cursor issued by one listing from decoding in another.
+    const KIND: &'static str;
+
+    fn encode(&self) -> String {
+        hex::encode(serde_json::to_vec(&(Self::KIND, self)).expect("cursor serializes"))
+    }
+
+    /// `None` for anything not produced by [`Cursor::encode`] on this type.
+    fn decode(cursor: &str) -> Option<Self> {
+        let raw = hex::decode(cursor).ok()?;
+        let (kind, cursor): (String, Self) = serde_json::from_slice(&raw).ok()?;
+        (kind == Self::KIND).then_some(cursor)
+    }
+}
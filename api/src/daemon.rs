@@ -0,0 +1,147 @@
+//! Reachability of the daemon behind the proxy features (raw tx, broadcast,
+//! fee estimate). A background task probes it so `/readyz` and
+//! `/api/v1/status` report the last result without waiting on the daemon.
+
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use bex_core::{preflight::PROBE_TIMEOUT, rpc};
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::models::DaemonHealthView;
+
+/// Latest probe result, shared by every handler. The default is an API
+/// without a configured daemon.
+#[derive(Clone, Default)]
+pub struct DaemonHealth {
+    latest: Option<Arc<RwLock<DaemonHealthView>>>,
+}
+
+impl DaemonHealth {
+    /// A configured daemon that has not been probed yet.
+    pub fn configured(max_lag: u64) -> Self {
+        let view = DaemonHealthView {
+            reachable: false,
+            healthy: false,
+            height: None,
+            db_height: None,
+            lag: None,
+            max_lag: max_lag as i64,
+            checked_at: None,
+            error: Some("not probed yet".to_string()),
+        };
+        Self {
+            latest: Some(Arc::new(RwLock::new(view))),
+        }
+    }
+
+    /// `None` when no daemon is configured.
+    pub fn view(&self) -> Option<DaemonHealthView> {
+        self.latest
+            .as_ref()
+            .map(|latest| latest.read().expect("daemon health lock").clone())
+    }
+
+    fn record(&self, view: DaemonHealthView) {
+        if let Some(latest) = &self.latest {
+            *latest.write().expect("daemon health lock") = view;
+        }
+    }
+}
+
+/// The daemon's tip height, from `get_block_count` on its JSON-RPC endpoint.
+pub async fn daemon_height(http: &reqwest::Client, url: &str) -> Result<i64> {
+    let count = http
+        .post(url)
+        .json(&rpc::Request::new("get_block_count", ()))
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .context("request failed")?
+        .error_for_status()?
+        .json::<rpc::Response<rpc::GetBlockCountResult>>()
+        .await
+        .context("decode failed")?
+        .into_result()?
+        .count;
+    i64::try_from(count.saturating_sub(1)).context("height overflow")
+}
+
+/// Probes the daemon once and compares its tip with the ingested tip. A
+/// daemon that is more than `max_lag` blocks ahead of or behind the DB is
+/// reachable but not healthy.
+pub async fn probe(
+    http: &reqwest::Client,
+    url: &str,
+    db: &PgPool,
+    max_lag: u64,
+) -> DaemonHealthView {
+    let mut view = DaemonHealthView {
+        reachable: false,
+        healthy: false,
+        height: None,
+        db_height: None,
+        lag: None,
+        max_lag: max_lag as i64,
+        checked_at: Some(now()),
+        error: None,
+    };
+    match sqlx::query_scalar!("SELECT height FROM public.current_tip WHERE id = 1")
+        .fetch_optional(db)
+        .await
+    {
+        Ok(height) => view.db_height = height,
+        Err(err) => view.error = Some(format!("db error: {err}")),
+    }
+    match daemon_height(http, url).await {
+        Ok(height) => {
+            view.reachable = true;
+            view.height = Some(height);
+            view.lag = view.db_height.map(|db_height| height - db_height);
+            view.healthy = view.lag.is_some_and(|lag| lag.unsigned_abs() <= max_lag);
+            if view.error.is_none() && !view.healthy {
+                view.error = Some(match view.lag {
+                    Some(lag) => format!("daemon is {lag} blocks from the ingested tip"),
+                    None => "no blocks ingested yet".to_string(),
+                });
+            }
+        }
+        Err(err) => view.error = Some(format!("{err:#}")),
+    }
+    view
+}
+
+/// Probes the daemon every `interval`, starting immediately.
+pub fn spawn_prober(
+    health: DaemonHealth,
+    url: String,
+    db: PgPool,
+    max_lag: u64,
+    interval: Duration,
+) {
+    let http = reqwest::Client::new();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut was_healthy = true;
+        loop {
+            ticker.tick().await;
+            let view = probe(&http, &url, &db, max_lag).await;
+            if was_healthy && !view.healthy {
+                warn!(error = view.error.as_deref(), "daemon unhealthy");
+            }
+            was_healthy = view.healthy;
+            health.record(view);
+        }
+    });
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
@@ -0,0 +1,90 @@
+//! The OpenAPI document, parsed once at startup and served as JSON and YAML.
+//! `info.version` is the crate version and `info.x-git-sha` the commit the
+//! binary was built from, so a client report names the exact build.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    body::Body,
+    http::{header, HeaderValue, StatusCode},
+    response::Response,
+};
+
+use crate::util::{etag_of, weak_etag};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_SHA: &str = env!("GIT_SHA");
+
+const SPEC: &str = include_str!("../openapi.yaml");
+
+/// Both renderings of the document with their ETags.
+#[derive(Clone)]
+pub struct ApiDocs {
+    json: Arc<Rendered>,
+    yaml: Arc<Rendered>,
+}
+
+struct Rendered {
+    body: Vec<u8>,
+    etag: HeaderValue,
+}
+
+impl Rendered {
+    fn new(body: Vec<u8>) -> Self {
+        let etag = weak_etag(&etag_of(&body));
+        Self { body, etag }
+    }
+
+    fn respond(&self, content_type: &'static str) -> Response {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, content_type)
+            .header(header::ETAG, self.etag.clone())
+            .body(Body::from(self.body.clone()))
+            .unwrap()
+    }
+}
+
+impl ApiDocs {
+    pub fn load() -> Result<Self> {
+        Self::load_at("/")
+    }
+
+    /// The document for a network served under `base_path`, which becomes
+    /// its only server URL.
+    pub fn load_at(base_path: &str) -> Result<Self> {
+        let mut doc: serde_json::Value =
+            serde_yaml::from_str(SPEC).context("parse openapi.yaml")?;
+        doc["servers"] = serde_json::json!([{ "url": base_path }]);
+        let info = doc
+            .get_mut("info")
+            .and_then(|info| info.as_object_mut())
+            .context("openapi.yaml has no info object")?;
+        info.insert("version".into(), VERSION.into());
+        info.insert("x-git-sha".into(), GIT_SHA.into());
+
+        let json = serde_json::to_vec(&doc).context("render openapi json")?;
+        let yaml = serde_yaml::to_string(&doc).context("render openapi yaml")?;
+        Ok(Self {
+            json: Arc::new(Rendered::new(json)),
+            yaml: Arc::new(Rendered::new(yaml.into_bytes())),
+        })
+    }
+
+    pub fn json(&self) -> Response {
+        self.json.respond("application/json")
+    }
+
+    pub fn yaml(&self) -> Response {
+        self.yaml.respond("application/yaml")
+    }
+}
+
+/// The embedded document is a compile-time constant that
+/// [`ApiDocs::load`] is tested against, so this cannot fail at runtime.
+impl Default for ApiDocs {
+    fn default() -> Self {
+        Self::load().expect("embedded openapi.yaml")
+    }
+}
@@ -0,0 +1,139 @@
+//! Extractors for hex path parameters. A hash or key image is checked,
+//! lowercased and decoded once, and malformed input is rejected with an
+//! `application/problem+json` body (RFC 9457) before the handler runs, so
+//! every endpoint reports it the same way.
+
+use std::fmt;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use bex_core::hex::{decode_fixed, HexError};
+use serde::{de::Error as DeError, Deserialize, Deserializer};
+
+/// `N` bytes given as `2 * N` hex characters in either case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexParam<const N: usize> {
+    bytes: [u8; N],
+    hex: String,
+}
+
+impl<const N: usize> HexParam<N> {
+    pub fn parse(value: &str) -> Result<Self, HexError> {
+        let bytes = decode_fixed::<N>(value)?;
+        Ok(Self {
+            bytes,
+            hex: hex::encode(bytes),
+        })
+    }
+
+    /// The lowercase hex form, for cache keys and `decode($1, 'hex')`.
+    pub fn as_str(&self) -> &str {
+        &self.hex
+    }
+
+    pub fn bytes(&self) -> &[u8; N] {
+        &self.bytes
+    }
+}
+
+impl<const N: usize> fmt::Display for HexParam<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.hex)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for HexParam<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::parse(&value).map_err(D::Error::custom)
+    }
+}
+
+/// A block height or a 32-byte block hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockId {
+    Height(i64),
+    Hash(HexParam<32>),
+}
+
+impl BlockId {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        // A hash can be all digits, and with leading zeros it would also fit
+        // an i64, so only other lengths are tried as a height.
+        if value.len() != 64 {
+            if let Ok(height) = value.parse::<i64>() {
+                return Ok(BlockId::Height(height));
+            }
+        }
+        HexParam::parse(value)
+            .map(BlockId::Hash)
+            .map_err(|err| format!("must be a height or 64-character block hash ({err})"))
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockId::Height(height) => write!(f, "{height}"),
+            BlockId::Hash(hash) => hash.fmt(f),
+        }
+    }
+}
+
+/// A path parameter that failed to parse; answers 400 problem+json.
+#[derive(Debug)]
+pub struct ParamRejection {
+    pub detail: String,
+}
+
+impl IntoResponse for ParamRejection {
+    fn into_response(self) -> Response {
+        crate::util::problem(400, "Invalid parameter", &self.detail)
+    }
+}
+
+/// The route's single path parameter as `(name, value)`.
+async fn single_path_param<S: Send + Sync>(
+    parts: &mut Parts,
+    state: &S,
+) -> Result<(String, String), ParamRejection> {
+    let Path(mut params) = Path::<Vec<(String, String)>>::from_request_parts(parts, state)
+        .await
+        .map_err(|err| ParamRejection {
+            detail: err.body_text(),
+        })?;
+    match (params.pop(), params.is_empty()) {
+        (Some(param), true) => Ok(param),
+        _ => Err(ParamRejection {
+            detail: "expected exactly one path parameter".to_string(),
+        }),
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync, const N: usize> FromRequestParts<S> for HexParam<N> {
+    type Rejection = ParamRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let (name, value) = single_path_param(parts, state).await?;
+        Self::parse(&value).map_err(|err| ParamRejection {
+            detail: format!("`{name}`: {err}"),
+        })
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for BlockId {
+    type Rejection = ParamRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let (name, value) = single_path_param(parts, state).await?;
+        Self::parse(&value).map_err(|err| ParamRejection {
+            detail: format!("`{name}` {err}"),
+        })
+    }
+}
@@ -1,5 +1,16 @@
 pub mod config;
+pub mod cursor;
+pub mod daemon;
+pub mod docs;
+pub mod hex_param;
 pub mod models;
+pub mod network;
+pub mod preflight;
+pub mod query_param;
+pub mod ratelimit;
 pub mod routes;
+pub mod search;
 pub mod state;
+pub mod timing;
+pub mod usage;
 pub mod util;
@@ -1,10 +1,21 @@
 mod config;
+mod cursor;
+mod daemon;
+mod docs;
+mod hex_param;
 mod models;
+mod network;
+mod preflight;
+mod query_param;
+mod ratelimit;
 mod routes;
+mod search;
 mod state;
+mod timing;
+mod usage;
 mod util;
 
-use std::{iter, time::Duration};
+use std::{iter, net::SocketAddr, time::Duration};
 
 use anyhow::{anyhow, Result};
 use axum::{routing::get, Router};
@@ -41,22 +52,58 @@ async fn main() -> Result<()> {
 
     let cfg = Config::parse_from(args);
 
-    let db = PgPool::connect(&cfg.database_url).await?;
-    let client = redis::Client::open(cfg.redis_url.clone())?;
-    let cache = redis::aio::ConnectionManager::new(client).await?;
-
-    let state = AppState { db, cache };
+    let report = preflight::check(&cfg).await;
+    if report.failures() > 0 {
+        return Err(anyhow!("{report}"));
+    }
+    tracing::info!("{report}");
 
-    let router = Router::new()
-        .route("/healthz", get(routes::healthz))
-        .merge(routes::v1_router())
-        .with_state(state)
+    // One quota per caller across every network, not one per network.
+    let limiter = ratelimit::RateLimiter::new(cfg.rate_limit_per_minute)
+        .with_max_callers(cfg.rate_limit_max_callers);
+    let search = search::SearchGuard::new(cfg.search_rate_limit_per_minute);
+    let (primary, primary_state) = network_router(
+        &cfg,
+        &cfg.database_url,
+        &cfg.redis_url,
+        cfg.daemon_url.as_deref(),
+        docs::ApiDocs::load()?,
+        limiter.clone(),
+        search.sharing_quota(),
+    )
+    .await?;
+    let mut usage_targets = vec![(primary_state.db, primary_state.cache)];
+    let mut networks = Vec::with_capacity(cfg.extra_networks.len());
+    for extra in &cfg.extra_networks {
+        let docs = docs::ApiDocs::load_at(&format!("/{}", extra.name))?;
+        let (router, state) = network_router(
+            &cfg,
+            &extra.database_url,
+            &extra.redis_url,
+            None,
+            docs,
+            limiter.clone(),
+            search.sharing_quota(),
+        )
+        .await?;
+        tracing::info!(network = %extra.name, "serving extra network");
+        usage_targets.push((state.db, state.cache));
+        networks.push((extra.name.clone(), router));
+    }
+    usage::spawn_flusher(
+        usage_targets,
+        Duration::from_secs(cfg.usage_flush_secs),
+        (cfg.usage_retention_days > 0)
+            .then(|| Duration::from_secs(cfg.usage_retention_days * 86_400)),
+    );
+    let router = network::multiplex((cfg.network.clone(), primary), networks)
         .layer(CompressionLayer::new())
         .layer(GlobalConcurrencyLimitLayer::new(1024))
         .layer(TimeoutLayer::new(Duration::from_secs(10)))
         .layer(TraceLayer::new_for_http());
 
-    let app = RateLimitLayer::new(cfg.max_requests_per_sec, Duration::from_secs(1)).layer(router);
+    let app = RateLimitLayer::new(cfg.max_requests_per_sec, Duration::from_secs(1))
+        .layer(router.into_make_service_with_connect_info::<SocketAddr>());
 
     let listener = tokio::net::TcpListener::bind(&cfg.bind).await?;
     tracing::info!("api listening on {}", cfg.bind);
@@ -64,6 +111,67 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Connects one network's Postgres and Redis, starts its daemon prober and
+/// returns its routes behind the per-network middleware, with its state.
+/// `DAEMON_URL` only applies to the primary network; `limiter` and the
+/// search quota are shared by all of them.
+async fn network_router(
+    cfg: &Config,
+    database_url: &str,
+    redis_url: &str,
+    daemon_url: Option<&str>,
+    docs: docs::ApiDocs,
+    limiter: ratelimit::RateLimiter,
+    search: search::SearchGuard,
+) -> Result<(Router, AppState)> {
+    let db = PgPool::connect(database_url).await?;
+    let client = redis::Client::open(redis_url)?;
+    let cache = redis::aio::ConnectionManager::new(client).await?;
+
+    let state = AppState {
+        db,
+        cache,
+        admin_token: cfg.admin_token.clone().map(Into::into),
+        flights: Default::default(),
+        limiter,
+        api_keys: usage::ApiKeys::new(&cfg.api_keys),
+        daemon: match daemon_url {
+            Some(_) => daemon::DaemonHealth::configured(cfg.daemon_max_lag),
+            None => daemon::DaemonHealth::default(),
+        },
+        docs,
+        search,
+    };
+
+    if let Some(url) = daemon_url {
+        daemon::spawn_prober(
+            state.daemon.clone(),
+            url.to_owned(),
+            state.db.clone(),
+            cfg.daemon_max_lag,
+            Duration::from_secs(cfg.daemon_probe_secs),
+        );
+    }
+
+    let mut router = Router::new()
+        .route("/healthz", get(routes::healthz))
+        .route("/readyz", get(routes::readyz))
+        .merge(routes::v1_router())
+        .layer(axum::middleware::from_fn(util::conditional_get))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            ratelimit::enforce,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            usage::track,
+        ));
+    if cfg.server_timing {
+        router = router.layer(axum::middleware::from_fn(timing::server_timing));
+    }
+    Ok((router.with_state(state.clone()), state))
+}
+
 async fn run_probe(url: &str) -> Result<()> {
     let uri: http::Uri = url.parse()?;
     if uri.scheme_str() != Some("http") {
@@ -1,41 +1,223 @@
+use std::collections::BTreeMap;
+
+use bex_core::{Atomic, BlockHash, KeyImage, TxHash};
 use serde::Serialize;
 
-#[derive(Serialize, sqlx::FromRow)]
-pub struct BlockView {
-    pub height: i64,
-    pub hash: Option<String>,
-    pub ts: Option<i64>,
-    pub size_bytes: i32,
-    pub major_version: i32,
-    pub minor_version: i32,
-    pub tx_count: i32,
-    pub reward_nanos: i64,
+/// Defines a response model and its [`Describe`] impl in one place: each
+/// field is written `name: Type => ("type", "description")`, optionally
+/// followed by [`FieldDoc`] modifiers such as `.hex()` or `.nullable()`,
+/// and is documented under its Rust name, in order. Keys added to the JSON
+/// when the model is served (`age_seconds`) follow in `served { }`.
+macro_rules! describe {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident as $entity:literal {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident: $ty:ty => ($doc_ty:literal, $desc:literal $(,)?)
+                    $(.$modifier:ident($($arg:expr),*))*
+            ),* $(,)?
+        }
+        $(served {
+            $(
+                $served:ident => ($served_ty:literal, $served_desc:literal $(,)?)
+                    $(.$served_modifier:ident($($served_arg:expr),*))*
+            ),* $(,)?
+        })?
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl Describe for $name {
+            const ENTITY: &'static str = $entity;
+            const FIELDS: &'static [FieldDoc] = &[
+                $(FieldDoc::new(stringify!($field), $doc_ty, $desc)$(.$modifier($($arg),*))*,)*
+                $($(
+                    FieldDoc::new(stringify!($served), $served_ty, $served_desc)
+                        $(.$served_modifier($($served_arg),*))*,
+                )*)?
+            ];
+        }
+    };
 }
 
-#[derive(Serialize, sqlx::FromRow)]
-pub struct TxView {
-    pub hash: Option<String>,
-    pub block_height: Option<i64>,
-    pub ts: Option<i64>,
-    pub in_mempool: bool,
-    pub fee_nanos: Option<i64>,
-    pub size_bytes: i32,
-    pub version: i32,
-    pub unlock_time: i64,
-    pub extra_json: Option<String>,
-    pub rct_type: i32,
-    pub proof_type: Option<String>,
-    pub bp_plus: bool,
-    pub num_inputs: i32,
-    pub num_outputs: i32,
+describe! {
+    #[derive(Clone, Serialize, sqlx::FromRow)]
+    pub struct BlockView as "block" {
+        pub height: i64 => ("integer", "Block height, genesis is 0"),
+        pub hash: BlockHash => ("string", "Block hash").hex(),
+        pub ts: Option<i64> => ("integer", "Block header timestamp").epoch().nullable(),
+        pub size_bytes: i32 => ("integer", "Serialized block size").unit("bytes"),
+        pub major_version: i32 => ("integer", "Header major (hard fork) version"),
+        pub minor_version: i32 => ("integer", "Header minor version (vote)"),
+        pub tx_count: i32 => ("integer", "Transactions in the block, excluding the miner tx"),
+        pub reward_nanos: i64 => ("integer", "Miner reward including fees").atomic(),
+        pub confirmations: i64 => (
+            "integer",
+            "Blocks on top of this one including itself, derived from the current tip",
+        ),
+        pub is_final: bool => ("boolean", "Block is below the finality window"),
+        pub prev_height: Option<i64> => ("integer", "Height of the parent block").nullable(),
+        pub prev_hash: Option<BlockHash> => ("string", "Hash of the parent block").hex().nullable(),
+        pub next_height: Option<i64> => (
+            "integer",
+            "Height of the canonical child block, null at the tip",
+        )
+        .nullable(),
+        pub next_hash: Option<BlockHash> => ("string", "Hash of the canonical child block")
+            .hex()
+            .nullable(),
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub reward_xmr: Option<String> => (
+            "string",
+            "reward_nanos as fixed-point XMR, only with ?xmr=true",
+        )
+        .unit("xmr")
+        .encoding("decimal")
+        .nullable(),
+    }
+    served {
+        age_seconds => ("integer", "Seconds since ts when served, only with ?age=true")
+            .unit("seconds")
+            .nullable(),
+    }
 }
 
-#[derive(Serialize, sqlx::FromRow)]
-pub struct RingView {
-    pub tx_hash: Option<String>,
-    pub input_idx: i32,
-    pub ring_index: i32,
-    pub global_index: Option<i64>,
+impl BlockView {
+    /// Fills the fixed-point XMR display fields requested with `?xmr=true`.
+    pub fn fill_xmr(&mut self) {
+        self.reward_xmr = xmr_string(Some(self.reward_nanos));
+    }
+}
+
+describe! {
+    /// Per-block aggregates from `soft_facts`, served by
+    /// `/api/v1/block/:id/analytics`. The aggregates are null while
+    /// `analytics_pending` is set.
+    #[derive(Serialize, sqlx::FromRow)]
+    pub struct BlockAnalyticsView as "block_analytics" {
+        pub height: i64 => ("integer", "Block height"),
+        pub hash: BlockHash => ("string", "Block hash").hex(),
+        pub ts: Option<i64> => ("integer", "Block header timestamp").epoch().nullable(),
+        pub is_final: bool => ("boolean", "Block is below the finality window"),
+        pub analytics_pending: bool => (
+            "boolean",
+            "Aggregates not yet computed (or being recomputed) by the analytics worker",
+        ),
+        pub total_fee_nanos: Option<i64> => ("integer", "Sum of tx fees in the block")
+            .atomic()
+            .nullable(),
+        pub avg_ring_size: Option<rust_decimal::Decimal> => (
+            "string",
+            "Mean inputs per non-coinbase tx",
+        )
+        .encoding("decimal")
+        .nullable(),
+        pub median_fee_rate: Option<rust_decimal::Decimal> => ("string", "Median tx fee per byte")
+            .unit("atomic_units_per_byte")
+            .encoding("decimal")
+            .nullable(),
+        pub bp_total_bytes: Option<i64> => ("integer", "Range proof bytes across the block")
+            .unit("bytes")
+            .nullable(),
+        pub clsag_count: Option<i32> => ("integer", "CLSAG signatures in the block").nullable(),
+        pub nonstandard_count: Option<i32> => (
+            "integer",
+            "Txs breaking a rule of the block's hard fork, null if any tx is unchecked",
+        )
+        .nullable(),
+    }
+}
+
+describe! {
+    #[derive(Clone, Serialize, sqlx::FromRow)]
+    pub struct TxView as "tx" {
+        pub hash: TxHash => ("string", "Transaction hash").hex(),
+        pub block_height: Option<i64> => ("integer", "Including block, null while in the mempool")
+            .nullable(),
+        pub ts: Option<i64> => ("integer", "Timestamp of the including block").epoch().nullable(),
+        pub in_mempool: bool => ("boolean", "True while unconfirmed"),
+        pub fee_nanos: Option<i64> => ("integer", "Transaction fee").atomic().nullable(),
+        pub size_bytes: i32 => ("integer", "Serialized tx size").unit("bytes"),
+        pub version: i32 => ("integer", "Transaction format version"),
+        pub unlock_time: i64 => (
+            "integer",
+            "Block height if below 500000000, otherwise a unix timestamp",
+        ),
+        pub extra_json: Option<String> => (
+            "string",
+            "Tx extra as raw hex (`extra`) and/or decoded tags (`tags`)",
+        )
+        .encoding("json")
+        .nullable(),
+        pub rct_type: i32 => ("integer", "RingCT signature type (0 for pre-RingCT)"),
+        pub proof_type: Option<String> => ("string", "Range proof / signature family, e.g. CLSAG")
+            .nullable(),
+        pub bp_plus: bool => ("boolean", "Uses Bulletproofs+ range proofs"),
+        pub num_inputs: i32 => ("integer", "Number of inputs"),
+        pub num_outputs: i32 => ("integer", "Number of outputs"),
+        pub first_seen: Option<i64> => (
+            "integer",
+            "First mempool sighting by the ingestor, null if never seen unconfirmed",
+        )
+        .epoch()
+        .nullable(),
+        pub nonstandard: Option<bool> => (
+            "boolean",
+            "Breaks a rule (version, rct type, ring size) of the hard fork at its height; null while unconfirmed or unchecked",
+        )
+        .nullable(),
+        pub nonstandard_reasons: Option<Vec<String>> => (
+            "array",
+            "Names of the broken rules: version, rct_type, ring_size",
+        )
+        .nullable(),
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub fee_xmr: Option<String> => (
+            "string",
+            "fee_nanos as fixed-point XMR, only with ?xmr=true",
+        )
+        .unit("xmr")
+        .encoding("decimal")
+        .nullable(),
+    }
+    served {
+        age_seconds => (
+            "integer",
+            "Seconds since ts (first_seen while unconfirmed) when served, only with ?age=true",
+        )
+        .unit("seconds")
+        .nullable(),
+    }
+}
+
+impl TxView {
+    /// Fills the fixed-point XMR display fields requested with `?xmr=true`.
+    pub fn fill_xmr(&mut self) {
+        self.fee_xmr = xmr_string(self.fee_nanos);
+    }
+}
+
+fn xmr_string(nanos: Option<i64>) -> Option<String> {
+    nanos
+        .and_then(|n| Atomic::try_from(n).ok())
+        .map(Atomic::to_xmr_string)
+}
+
+describe! {
+    #[derive(Serialize, sqlx::FromRow)]
+    pub struct RingView as "ring_member" {
+        pub tx_hash: TxHash => ("string", "Transaction containing the ring").hex(),
+        pub input_idx: i32 => ("integer", "Input position within the tx"),
+        pub ring_index: i32 => ("integer", "Member position within the ring"),
+        pub global_index: Option<i64> => ("integer", "Global index of the referenced output")
+            .nullable(),
+    }
 }
 
 #[derive(Serialize)]
@@ -50,20 +232,237 @@ pub struct RingSetView {
     pub members: Vec<RingMemberView>,
 }
 
+/// A ring member slot that references the looked-up output.
 #[derive(Serialize, sqlx::FromRow)]
-pub struct KeyImageView {
-    pub key_image: Option<String>,
-    pub spending_tx: Option<String>,
+pub struct RingUseView {
+    pub tx_hash: TxHash,
+    pub block_height: i64,
+    pub input_idx: i32,
+    pub ring_index: i32,
+}
+
+/// One page of `/api/v1/rings/by-output/{global_index}`, oldest use first;
+/// pass `next_cursor` back as `cursor` with the same window to continue.
+#[derive(Serialize)]
+pub struct RingUsePage {
+    pub global_index: i64,
+    pub items: Vec<RingUseView>,
+    pub next_cursor: Option<String>,
+}
+
+/// What `HEAD /api/v1/block/:id` hashes into its ETag: enough to change
+/// when the block is replaced by a reorg or finalized.
+#[derive(Serialize, sqlx::FromRow)]
+pub struct BlockPresence {
+    pub height: i64,
+    pub hash: BlockHash,
+    pub is_final: bool,
+}
+
+/// What `HEAD /api/v1/tx/:hash` hashes into its ETag: enough to change when
+/// the tx is mined or dropped back to the pool.
+#[derive(Serialize, sqlx::FromRow)]
+pub struct TxPresence {
+    pub hash: TxHash,
     pub block_height: Option<i64>,
+    pub in_mempool: bool,
+}
+
+describe! {
+    #[derive(Serialize, sqlx::FromRow)]
+    pub struct KeyImageView as "key_image" {
+        pub key_image: KeyImage => ("string", "Key image").hex(),
+        pub spending_tx: TxHash => ("string", "Transaction that revealed the key image").hex(),
+        pub block_height: Option<i64> => ("integer", "Height of the spending tx").nullable(),
+    }
+}
+
+describe! {
+    #[derive(Serialize, sqlx::FromRow)]
+    pub struct MempoolView as "mempool_tx" {
+        pub hash: TxHash => ("string", "Transaction hash").hex(),
+        pub first_seen: Option<i64> => ("integer", "When the ingestor first saw the tx")
+            .epoch()
+            .nullable(),
+        pub last_seen: Option<i64> => ("integer", "Most recent mempool sighting")
+            .epoch()
+            .nullable(),
+        pub fee_rate: Option<rust_decimal::Decimal> => ("string", "Fee per byte")
+            .unit("atomic_units_per_byte")
+            .encoding("decimal")
+            .nullable(),
+        pub relayed_by: Option<String> => ("string", "Relaying peer, when known").nullable(),
+        pub size_bytes: Option<i32> => ("integer", "Serialized tx size").unit("bytes").nullable(),
+        pub relayed: Option<bool> => ("boolean", "Daemon has relayed the tx to peers").nullable(),
+        pub do_not_relay: Option<bool> => ("boolean", "Daemon holds the tx without relaying it")
+            .nullable(),
+        pub double_spend_seen: Option<bool> => (
+            "boolean",
+            "Daemon saw another tx spending the same key images",
+        )
+        .nullable(),
+    }
+    served {
+        age_seconds => ("integer", "Seconds since first_seen when served, only with ?age=true")
+            .unit("seconds")
+            .nullable(),
+    }
+}
+
+/// One page of `/api/v1/mempool?cursor=…`; pass `next_cursor` back as
+/// `cursor` with the same `sort` to continue.
+#[derive(Serialize)]
+pub struct MempoolPage {
+    pub items: Vec<MempoolView>,
+    pub next_cursor: Option<String>,
+}
+
+/// A mined tx matched by `/api/v1/txs/by-fee`. `fee_per_byte` is in atomic
+/// units and null for zero-size rows.
+#[derive(Serialize)]
+pub struct TxFeeView {
+    pub hash: TxHash,
+    pub block_height: i64,
+    pub block_position: Option<i32>,
+    pub fee_nanos: i64,
+    pub size_bytes: i32,
+    pub fee_per_byte: Option<f64>,
+}
+
+/// One page of `/api/v1/txs/by-fee`, highest fee first, over the block
+/// window `from_height..=to_height` actually searched.
+#[derive(Serialize)]
+pub struct TxFeePage {
+    pub items: Vec<TxFeeView>,
+    pub from_height: i64,
+    pub to_height: i64,
+    pub next_cursor: Option<String>,
+}
+
+/// One page of `/api/v1/blocks?cursor=…`; pass `next_cursor` back as
+/// `cursor` to continue below the last block of this page.
+#[derive(Serialize)]
+pub struct BlockPage {
+    pub items: Vec<BlockView>,
+    /// Chain tip when the listing started; later pages never go above it.
+    pub anchor_height: Option<i64>,
+    pub next_cursor: Option<String>,
+}
+
+describe! {
+    #[derive(Serialize, sqlx::FromRow)]
+    pub struct TipView as "tip" {
+        pub height: i64 => ("integer", "Height of the latest persisted block"),
+        pub hash: BlockHash => ("string", "Tip block hash").hex(),
+        pub prev_hash: BlockHash => ("string", "Parent of the tip block").hex(),
+        pub updated_at: Option<i64> => ("integer", "When the tip last advanced").epoch().nullable(),
+    }
+}
+
+/// Properties of the ingested dataset as a whole.
+#[derive(Serialize)]
+pub struct StatusView {
+    /// Some txs were ingested without range proofs or signatures, so proof
+    /// analytics (`bp_plus`, `proof_type`) only reflect their RingCT type.
+    pub pruned: bool,
+    pub tip_height: Option<i64>,
+    /// The daemon's tip at the ingestor's last poll; ahead of `tip_height`
+    /// by the blocks `--tip-confirmations` holds back.
+    pub daemon_tip_height: Option<i64>,
+    /// Last probe of the API's own daemon; `None` when `DAEMON_URL` is unset.
+    pub daemon: Option<DaemonHealthView>,
+}
+
+/// Whether the daemon behind the proxy features answered the last probe and
+/// is within `max_lag` blocks of the ingested tip. `lag` is the daemon's tip
+/// minus `db_height`.
+#[derive(Serialize, Clone, Debug)]
+pub struct DaemonHealthView {
+    pub reachable: bool,
+    pub healthy: bool,
+    pub height: Option<i64>,
+    pub db_height: Option<i64>,
+    pub lag: Option<i64>,
+    pub max_lag: i64,
+    pub checked_at: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// Readiness of the API's dependencies. An unhealthy daemon only degrades
+/// the proxy features, so it does not make the API unready.
+#[derive(Serialize)]
+pub struct ReadinessView {
+    /// `ok`, `degraded` (daemon unhealthy) or `unavailable`.
+    pub status: &'static str,
+    pub database: bool,
+    pub cache: bool,
+    pub daemon: Option<DaemonHealthView>,
+}
+
+/// Fee rates in atomic units per byte: the daemon's consensus base fee and
+/// what txs in the pool and in recent blocks actually paid.
+#[derive(Serialize)]
+pub struct BaseFeeView {
+    /// `None` until the ingestor has polled the daemon's `get_fee_estimate`.
+    pub daemon: Option<DaemonFeeView>,
+    pub mempool: FeePercentiles,
+    /// Txs in the last `window_blocks` ingested blocks, miner txs excluded.
+    pub recent_blocks: FeePercentiles,
+    pub window_blocks: i64,
+}
+
+#[derive(Serialize)]
+pub struct DaemonFeeView {
+    pub fee_per_byte: i64,
+    /// Per-priority fees, lowest first; empty on daemons that omit them.
+    pub fees: Vec<i64>,
+    /// Wallets round fees up to a multiple of this.
+    pub quantization_mask: i64,
+    pub updated_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct FeePercentiles {
+    pub sample_size: i64,
+    pub p10: Option<f64>,
+    pub p25: Option<f64>,
+    pub p50: Option<f64>,
+    pub p75: Option<f64>,
+    pub p90: Option<f64>,
+}
+
+impl FeePercentiles {
+    /// From `percentile_cont` over `[0.1, 0.25, 0.5, 0.75, 0.9]`, which is
+    /// NULL for an empty sample.
+    pub fn new(sample_size: i64, values: Option<Vec<f64>>) -> Self {
+        let at = |i: usize| values.as_ref().and_then(|v| v.get(i).copied());
+        Self {
+            sample_size,
+            p10: at(0),
+            p25: at(1),
+            p50: at(2),
+            p75: at(3),
+            p90: at(4),
+        }
+    }
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct ReingestView {
+    pub height: i64,
+    pub status: String,
+    pub requested_at: Option<i64>,
 }
 
 #[derive(Serialize, sqlx::FromRow)]
-pub struct MempoolView {
-    pub hash: Option<String>,
-    pub first_seen: Option<i64>,
-    pub last_seen: Option<i64>,
-    pub fee_rate: Option<rust_decimal::Decimal>,
-    pub relayed_by: Option<String>,
+pub struct UsageView {
+    pub bucket: i64,
+    pub api_key: String,
+    pub route: String,
+    pub requests: i64,
+    pub errors: i64,
+    pub rate_limited: i64,
+    pub error_rate: f64,
 }
 
 #[derive(Serialize)]
@@ -72,29 +471,299 @@ pub struct SearchResult {
     pub value: serde_json::Value,
 }
 
+describe! {
+    #[derive(Serialize, sqlx::FromRow)]
+    pub struct InputView as "tx_input" {
+        pub idx: i32 => ("integer", "Input position within the tx"),
+        pub key_image: KeyImage => ("string", "Key image").hex(),
+        pub ring_size: i32 => ("integer", "Number of ring members"),
+        pub pseudo_out: Option<String> => ("string", "Pseudo output commitment").hex().nullable(),
+    }
+}
+
+describe! {
+    #[derive(Serialize, sqlx::FromRow)]
+    pub struct OutputView as "tx_output" {
+        pub idx_in_tx: i32 => ("integer", "Output position within the tx"),
+        pub global_index: Option<i64> => ("integer", "Global output index").nullable(),
+        pub amount: Option<rust_decimal::Decimal> => (
+            "string",
+            "Cleartext amount, null for RingCT outputs",
+        )
+        .atomic()
+        .encoding("decimal")
+        .nullable(),
+        pub commitment: String => ("string", "Pedersen commitment").hex(),
+        pub stealth_public_key: String => ("string", "One-time output public key").hex(),
+        pub spent_by_key_image: Option<KeyImage> => (
+            "string",
+            "Key image that spent this output, if known",
+        )
+        .hex()
+        .nullable(),
+        pub spent_in_tx: Option<TxHash> => ("string", "Spending transaction hash, if known")
+            .hex()
+            .nullable(),
+    }
+}
+
+/// Response of the multi-get endpoints.
+#[derive(Serialize)]
+pub struct BatchPage<T> {
+    pub items: Vec<T>,
+}
+
+/// One requested id of `POST /api/v1/blocks/batch`, in request order.
+/// `block` is null when `found` is false.
+#[derive(Serialize)]
+pub struct BlockBatchItem {
+    pub id: String,
+    pub found: bool,
+    pub block: Option<BlockView>,
+}
+
+/// One requested hash of `POST /api/v1/txs/batch`, in request order.
+#[derive(Serialize)]
+pub struct TxBatchItem {
+    pub id: String,
+    pub found: bool,
+    pub tx: Option<TxView>,
+}
+
+#[derive(Serialize)]
+pub struct TxDetailView {
+    #[serde(flatten)]
+    pub tx: TxView,
+    pub inputs: Vec<InputView>,
+    pub outputs: Vec<OutputView>,
+}
+
 #[derive(Serialize, sqlx::FromRow)]
-pub struct InputView {
-    pub idx: i32,
-    pub key_image: String,
-    pub ring_size: i32,
-    pub pseudo_out: Option<String>,
+pub struct OrphanedBlockView {
+    pub height: i64,
+    pub hash: BlockHash,
+    pub orphaned_at: Option<i64>,
+}
+
+/// Amount-0 output distribution, shaped like one entry of the daemon's
+/// `get_output_distribution` result.
+#[derive(Serialize)]
+pub struct RctOffsetsView {
+    pub amount: u64,
+    pub start_height: i64,
+    pub base: i64,
+    pub distribution: Vec<i64>,
+}
+
+/// One ranking from the periodically rebuilt leaderboards. `computed_at` is
+/// when the analytics worker last rebuilt it.
+#[derive(Serialize)]
+pub struct LeaderboardView<T> {
+    pub by: String,
+    pub computed_at: Option<i64>,
+    pub entries: Vec<T>,
 }
 
 #[derive(Serialize, sqlx::FromRow)]
-pub struct OutputView {
-    pub idx_in_tx: i32,
+pub struct TopBlockEntry {
+    pub rank: i32,
+    pub height: i64,
+    pub hash: BlockHash,
+    pub value: i64,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct TopOutputEntry {
+    pub rank: i32,
     pub global_index: Option<i64>,
-    pub amount: Option<rust_decimal::Decimal>,
-    pub commitment: String,
-    pub stealth_public_key: String,
-    pub spent_by_key_image: Option<String>,
-    pub spent_in_tx: Option<String>,
+    pub tx_hash: TxHash,
+    pub value: i64,
 }
 
+/// One UTC day of mining statistics. `day` is the epoch second the day
+/// starts at; the difficulty-derived fields are null when none of the day's
+/// blocks carry a difficulty.
 #[derive(Serialize)]
-pub struct TxDetailView {
-    #[serde(flatten)]
+pub struct MiningDayView {
+    pub day: i64,
+    pub blocks: i32,
+    pub nonce_buckets: Vec<i32>,
+    pub nonce_chi2: f64,
+    pub avg_difficulty: Option<f64>,
+    pub implied_hashrate: Option<f64>,
+    pub mean_solve_secs: Option<f64>,
+    pub residual_mean_abs_secs: Option<f64>,
+    pub residual_stddev_secs: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct MiningStatsView {
+    pub computed_at: Option<i64>,
+    pub days: Vec<MiningDayView>,
+}
+
+/// Where a tx sits in the chain, including blocks it was reorged out of.
+#[derive(Serialize)]
+pub struct TxContextView {
+    pub hash: TxHash,
+    pub in_mempool: bool,
+    pub block: Option<BlockView>,
+    pub position: Option<i32>,
+    pub confirmations: i64,
+    pub is_final: bool,
+    pub orphaned_at: Option<i64>,
+    pub orphaned_blocks: Vec<OrphanedBlockView>,
+}
+
+/// A ring member together with the output it references.
+#[derive(Serialize)]
+pub struct ResolvedRingMemberView {
+    pub ring_index: i32,
+    pub global_index: Option<i64>,
+    pub tx_hash: Option<TxHash>,
+    pub stealth_public_key: Option<String>,
+    pub commitment: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ResolvedRingSetView {
+    pub input_idx: i32,
+    pub members: Vec<ResolvedRingMemberView>,
+}
+
+/// Confirmation status of a bundled tx when the bundle was generated.
+#[derive(Serialize)]
+pub struct BundleStatus {
+    /// `mempool`, `confirmed`, `final`, or `detached` when the tx is neither
+    /// in a block nor in the mempool (e.g. reorged out and since evicted).
+    pub state: &'static str,
+    pub confirmations: i64,
+    pub position: Option<i32>,
+    pub tip_height: Option<i64>,
+}
+
+/// Everything known about a tx in one document, read from a single database
+/// snapshot, as served by `/api/v1/tx/{hash}/bundle`.
+#[derive(Serialize)]
+pub struct TxBundleView {
+    pub generated_at: i64,
     pub tx: TxView,
+    pub block: Option<BlockView>,
     pub inputs: Vec<InputView>,
     pub outputs: Vec<OutputView>,
+    pub rings: Vec<ResolvedRingSetView>,
+    pub status: BundleStatus,
+}
+
+/// Column-level description of a response field, served by `/api/v1/meta/schema`.
+#[derive(Serialize, Clone, Copy)]
+pub struct FieldDoc {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub ty: &'static str,
+    pub nullable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<&'static str>,
+    pub description: &'static str,
+}
+
+impl FieldDoc {
+    const fn new(name: &'static str, ty: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            ty,
+            nullable: false,
+            unit: None,
+            encoding: None,
+            description,
+        }
+    }
+
+    const fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    const fn unit(mut self, unit: &'static str) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    const fn encoding(mut self, encoding: &'static str) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    const fn hex(self) -> Self {
+        self.encoding("hex")
+    }
+
+    const fn epoch(self) -> Self {
+        self.unit("seconds").encoding("unix_epoch")
+    }
+
+    const fn atomic(self) -> Self {
+        self.unit("atomic_units")
+    }
+}
+
+#[derive(Serialize)]
+pub struct EntityDoc {
+    pub entity: &'static str,
+    pub fields: &'static [FieldDoc],
+}
+
+/// Implemented by every response model through `describe!`, so the data
+/// dictionary is written on the struct's own fields.
+pub trait Describe {
+    const ENTITY: &'static str;
+    const FIELDS: &'static [FieldDoc];
+
+    fn entity_doc() -> EntityDoc {
+        EntityDoc {
+            entity: Self::ENTITY,
+            fields: Self::FIELDS,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SchemaView {
+    /// Legend for the `unit` values used by entity fields.
+    pub units: BTreeMap<&'static str, &'static str>,
+    pub entities: Vec<EntityDoc>,
+}
+
+pub fn data_dictionary() -> SchemaView {
+    SchemaView {
+        units: BTreeMap::from([
+            (
+                "atomic_units",
+                "piconero; 1 XMR = 1000000000000. Fields suffixed _nanos use this unit.",
+            ),
+            (
+                "xmr",
+                "whole XMR as a fixed-point string with 12 decimals. Fields suffixed _xmr use this unit.",
+            ),
+            ("seconds", "whole seconds"),
+            ("bytes", "serialized size in bytes"),
+            (
+                "atomic_units_per_byte",
+                "fee in atomic units divided by the tx size in bytes",
+            ),
+        ]),
+        entities: vec![
+            BlockView::entity_doc(),
+            BlockAnalyticsView::entity_doc(),
+            TxView::entity_doc(),
+            InputView::entity_doc(),
+            OutputView::entity_doc(),
+            RingView::entity_doc(),
+            KeyImageView::entity_doc(),
+            MempoolView::entity_doc(),
+            TipView::entity_doc(),
+        ],
+    }
 }
@@ -0,0 +1,99 @@
+//! Serving several networks from one process. The primary network
+//! (`NETWORK`, `DATABASE_URL`, `REDIS_URL`) answers unprefixed paths; each
+//! `--extra-network` gets its own Postgres and Redis. Every network, the
+//! primary one included, is selected by a `/<network>` path prefix or by a
+//! `Host` whose first label is the network name (`stagenet.example.org`).
+
+use std::str::FromStr;
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap},
+    Router,
+};
+use tower::{service_fn, ServiceExt};
+
+/// First path segments the per-network router serves itself; a network
+/// named after one would shadow those routes of the primary network.
+const RESERVED: &[&str] = &["api", "api-docs", "healthz", "readyz"];
+
+/// One extra network, given as `name|database_url|redis_url`. URLs cannot
+/// contain an unescaped `|`, so the split is unambiguous. The name becomes a
+/// path segment and a host label, so it must be a lowercase slug.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkDeployment {
+    pub name: String,
+    pub database_url: String,
+    /// Kept apart from every other network's, so cached views never cross.
+    pub redis_url: String,
+}
+
+impl FromStr for NetworkDeployment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('|').map(str::trim);
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(name), Some(database_url), Some(redis_url), None)
+                if !name.is_empty() && !database_url.is_empty() && !redis_url.is_empty() =>
+            {
+                Ok(Self {
+                    name: name.to_owned(),
+                    database_url: database_url.to_owned(),
+                    redis_url: redis_url.to_owned(),
+                })
+            }
+            _ => Err(format!("expected name|database_url|redis_url, got {s:?}")),
+        }
+        .and_then(|deployment| {
+            let name = deployment.name.as_str();
+            if RESERVED.contains(&name) {
+                Err(format!("network name {name:?} is reserved for a route"))
+            } else if !is_slug(name) {
+                Err(format!(
+                    "network name {name:?} must be lowercase letters, digits and inner hyphens"
+                ))
+            } else {
+                Ok(deployment)
+            }
+        })
+    }
+}
+
+fn is_slug(name: &str) -> bool {
+    name.bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+}
+
+/// The network a request's `Host` names, if it is one of `names`.
+pub fn network_for_host<'a>(headers: &HeaderMap, names: &'a [String]) -> Option<&'a str> {
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    let label = host.split(['.', ':']).next()?;
+    names
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(label))
+        .map(String::as_str)
+}
+
+/// Routes `/<name>/...` to that network's router, then requests whose `Host`
+/// names a network, and everything else to the primary network, which is
+/// given with its name like the others.
+pub fn multiplex(primary: (String, Router), networks: Vec<(String, Router)>) -> Router {
+    let (primary_name, primary) = primary;
+    let mut networks = networks;
+    networks.push((primary_name, primary.clone()));
+    let names: Vec<String> = networks.iter().map(|(name, _)| name.clone()).collect();
+    let mut app = Router::new();
+    for (name, router) in &networks {
+        app = app.nest(&format!("/{name}"), router.clone());
+    }
+    app.fallback_service(service_fn(move |req: Request| {
+        let router = network_for_host(req.headers(), &names)
+            .and_then(|name| networks.iter().find(|(n, _)| n == name))
+            .map_or(&primary, |(_, router)| router)
+            .clone();
+        async move { router.oneshot(req).await }
+    }))
+}
@@ -0,0 +1,164 @@
+//! Startup validation for the API server: bind address, URL formats and
+//! reachability of Postgres and Redis, reported together before serving.
+
+use std::net::SocketAddr;
+
+use bex_core::preflight::{self, probe, url_scheme, Report};
+use sqlx::PgPool;
+
+use crate::config::Config;
+
+pub async fn check(cfg: &Config) -> Report {
+    let mut report = Report::new("api");
+
+    report.check("flags", check_flags(cfg));
+    report.check("NETWORK", preflight::network(&cfg.network));
+
+    match cfg.bind.parse::<SocketAddr>() {
+        Ok(addr) => {
+            report.check(
+                "API_BIND",
+                std::net::TcpListener::bind(addr)
+                    .map(|_| format!("{addr} available"))
+                    .map_err(|err| format!("cannot bind {addr}: {err}")),
+            );
+        }
+        Err(err) => {
+            report.check("API_BIND", Err(format!("{:?}: {err}", cfg.bind)));
+        }
+    }
+
+    check_postgres(&mut report, "DATABASE_URL", "postgres", &cfg.database_url).await;
+    check_redis(&mut report, "REDIS_URL", "redis", &cfg.redis_url).await;
+
+    if !cfg.extra_networks.is_empty() {
+        report.check("EXTRA_NETWORKS", check_networks(cfg));
+    }
+    for extra in &cfg.extra_networks {
+        let name = &extra.name;
+        check_postgres(
+            &mut report,
+            &format!("{name} database_url"),
+            &format!("{name} postgres"),
+            &extra.database_url,
+        )
+        .await;
+        check_redis(
+            &mut report,
+            &format!("{name} redis_url"),
+            &format!("{name} redis"),
+            &extra.redis_url,
+        )
+        .await;
+    }
+
+    // Reachability is not checked: an unreachable daemon only degrades the
+    // proxy features and is reported by `/readyz` once serving.
+    if let Some(url) = &cfg.daemon_url {
+        report.check("DAEMON_URL", url_scheme(url, &["http", "https"]));
+    }
+
+    report
+}
+
+async fn check_postgres(report: &mut Report, flag: &str, name: &str, url: &str) {
+    if report.check(flag, url_scheme(url, &["postgres", "postgresql"])) {
+        report.check(
+            name,
+            probe(async {
+                let pool = PgPool::connect(url).await?;
+                sqlx::query("SELECT 1").execute(&pool).await?;
+                pool.close().await;
+                Ok("connected".to_string())
+            })
+            .await,
+        );
+    } else {
+        report.skip(name, format!("{flag} invalid"));
+    }
+}
+
+async fn check_redis(report: &mut Report, flag: &str, name: &str, url: &str) {
+    if report.check(flag, url_scheme(url, &["redis", "rediss", "unix"])) {
+        report.check(
+            name,
+            probe(async {
+                let client = redis::Client::open(url)?;
+                let mut conn = client.get_multiplexed_async_connection().await?;
+                redis::cmd("PING").query_async::<_, ()>(&mut conn).await?;
+                Ok("connected".to_string())
+            })
+            .await,
+        );
+    } else {
+        report.skip(name, format!("{flag} invalid"));
+    }
+}
+
+/// Every network needs its own name, database and Redis: a database tracks
+/// one network, and a shared Redis would serve one network's cached views
+/// for another's.
+fn check_networks(cfg: &Config) -> Result<String, String> {
+    let mut problems = Vec::new();
+    let mut names = vec![cfg.network.as_str()];
+    let mut databases = vec![cfg.database_url.as_str()];
+    let mut caches = vec![cfg.redis_url.as_str()];
+    for extra in &cfg.extra_networks {
+        if let Err(err) = preflight::network(&extra.name) {
+            problems.push(err);
+        }
+        if names.contains(&extra.name.as_str()) {
+            problems.push(format!("network {} is served twice", extra.name));
+        }
+        if databases.contains(&extra.database_url.as_str()) {
+            problems.push(format!("{} shares its database_url", extra.name));
+        }
+        if caches.contains(&extra.redis_url.as_str()) {
+            problems.push(format!(
+                "{} shares its redis_url; give it its own Redis database",
+                extra.name
+            ));
+        }
+        names.push(&extra.name);
+        databases.push(&extra.database_url);
+        caches.push(&extra.redis_url);
+    }
+
+    if problems.is_empty() {
+        Ok(names[1..].join(", "))
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+fn check_flags(cfg: &Config) -> Result<String, String> {
+    let mut problems = Vec::new();
+    if cfg.max_requests_per_sec == 0 {
+        problems.push("MAX_REQUESTS_PER_SEC must be at least 1".to_string());
+    }
+    if cfg.rate_limit_per_minute == 0 {
+        problems.push("RATE_LIMIT_PER_MINUTE must be at least 1".to_string());
+    }
+    if cfg.search_rate_limit_per_minute == 0 {
+        problems.push("SEARCH_RATE_LIMIT_PER_MINUTE must be at least 1".to_string());
+    }
+    if cfg.usage_flush_secs == 0 {
+        problems.push("USAGE_FLUSH_SECS must be at least 1".to_string());
+    }
+    if cfg.daemon_probe_secs == 0 {
+        problems.push("DAEMON_PROBE_SECS must be at least 1".to_string());
+    }
+    if cfg
+        .admin_token
+        .as_deref()
+        .is_some_and(|t| t.trim().is_empty())
+    {
+        problems.push("ADMIN_TOKEN is set but empty; unset it to disable admin routes".to_string());
+    }
+
+    if problems.is_empty() {
+        Ok("valid".to_string())
+    } else {
+        Err(problems.join("; "))
+    }
+}
@@ -0,0 +1,147 @@
+//! Query-string extractor. Axum's `Query` answers a malformed parameter with
+//! a plain-text 400 that does not say which one; this extractor answers with
+//! `application/problem+json` (RFC 9457) naming the parameter and the value
+//! it expects, taken from the table each query type declares with
+//! [`query_params!`].
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+
+/// A query string an endpoint accepts.
+pub trait QueryParams: DeserializeOwned {
+    /// `(name, expected)` for each parameter, quoted when it is malformed.
+    const PARAMS: &'static [(&'static str, &'static str)];
+
+    fn expected(name: &str) -> Option<&'static str> {
+        Self::PARAMS
+            .iter()
+            .find(|(param, _)| *param == name)
+            .map(|(_, expected)| *expected)
+    }
+}
+
+/// Defines a query struct and its [`QueryParams`] table in one place: each
+/// field is written `name: Type => "expected"`, and the table lists the
+/// fields under their Rust names, in order. Fields must not be renamed for
+/// serde, since the table would no longer match the query string.
+macro_rules! query_params {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident: $ty:ty => $expected:literal
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl $crate::query_param::QueryParams for $name {
+            const PARAMS: &'static [(&'static str, &'static str)] =
+                &[$((stringify!($field), $expected)),*];
+        }
+    };
+}
+
+pub(crate) use query_params;
+
+/// The request's query string deserialized into `T`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Query<T>(pub T);
+
+/// A query string that failed to deserialize; answers 400 problem+json with
+/// the parameter in `invalid_params`.
+#[derive(Debug)]
+pub struct QueryRejection {
+    pub name: Option<String>,
+    pub expected: Option<&'static str>,
+    pub reason: String,
+}
+
+impl QueryRejection {
+    fn new<T: QueryParams>(err: serde_path_to_error::Error<serde_urlencoded::de::Error>) -> Self {
+        let reason = err.inner().to_string();
+        let path = err.path().to_string();
+        // A missing field is reported against the struct itself, so its name
+        // only appears in the message.
+        let name = if path == "." {
+            T::PARAMS
+                .iter()
+                .map(|(param, _)| *param)
+                .find(|param| reason.contains(&format!("`{param}`")))
+                .map(str::to_owned)
+        } else {
+            Some(path)
+        };
+        let expected = name.as_deref().and_then(T::expected);
+        Self {
+            name,
+            expected,
+            reason,
+        }
+    }
+
+    pub fn detail(&self) -> String {
+        match (&self.name, self.expected) {
+            (Some(name), Some(expected)) => {
+                format!("`{name}` must be {expected} ({})", self.reason)
+            }
+            (Some(name), None) => format!("`{name}`: {}", self.reason),
+            (None, _) => format!("invalid query string: {}", self.reason),
+        }
+    }
+}
+
+/// A parameter that parsed but failed a check the handler makes, such as a
+/// range or a cursor from another listing; answered like a malformed one.
+pub fn invalid<T: QueryParams>(name: &str, reason: impl Into<String>) -> Response {
+    QueryRejection {
+        name: Some(name.to_owned()),
+        expected: T::expected(name),
+        reason: reason.into(),
+    }
+    .into_response()
+}
+
+impl IntoResponse for QueryRejection {
+    fn into_response(self) -> Response {
+        let mut param = serde_json::json!({
+            "name": self.name,
+            "reason": self.reason,
+        });
+        if let Some(expected) = self.expected {
+            param["expected"] = expected.into();
+        }
+        crate::util::problem_with(
+            400,
+            "Invalid query parameter",
+            &self.detail(),
+            serde_json::json!({ "invalid_params": [param] }),
+        )
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync, T: QueryParams> FromRequestParts<S> for Query<T> {
+    type Rejection = QueryRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        serde_path_to_error::deserialize(deserializer)
+            .map(Query)
+            .map_err(QueryRejection::new::<T>)
+    }
+}
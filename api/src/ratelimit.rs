@@ -0,0 +1,199 @@
+//! Per-caller request quotas. Each caller (a key listed in `API_KEYS`, else
+//! the client IP) gets a fixed window of `RATE_LIMIT_PER_MINUTE` requests; an
+//! unknown `X-API-Key` counts against the IP. Every response carries
+//! the `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers
+//! and `/api/v1/limits` reports the same numbers as JSON. Counters live in
+//! the process, so each API replica enforces its own quota.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+
+use crate::{
+    state::AppState,
+    usage::{ApiKeys, ANONYMOUS},
+};
+
+pub const LIMIT_HEADER: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const REMAINING_HEADER: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RESET_HEADER: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+pub const DEFAULT_PER_MINUTE: u64 = 1200;
+const WINDOW: Duration = Duration::from_secs(60);
+/// Expired windows are swept once this many callers are tracked.
+const SWEEP_AT: usize = 10_000;
+/// Callers tracked at once by default; see [`RateLimiter::with_max_callers`].
+pub const DEFAULT_MAX_CALLERS: usize = 100_000;
+/// The window shared by new callers while the limiter is full.
+pub const OVERFLOW: &str = "overflow";
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: u64,
+    max_callers: usize,
+    windows: Arc<Mutex<HashMap<String, Window>>>,
+}
+
+struct Window {
+    started: Instant,
+    used: u64,
+}
+
+/// A caller's standing in the current window.
+#[derive(Debug, Clone, Serialize)]
+pub struct Quota {
+    pub caller: String,
+    pub limit: u64,
+    pub used: u64,
+    pub remaining: u64,
+    pub window_secs: u64,
+    /// Seconds until the window resets.
+    pub reset_secs: u64,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_PER_MINUTE)
+    }
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u64) -> Self {
+        Self {
+            limit: per_minute,
+            max_callers: DEFAULT_MAX_CALLERS,
+            windows: Default::default(),
+        }
+    }
+
+    /// Caps how many callers get a window of their own. Once that many are
+    /// tracked within a minute, further callers share the [`OVERFLOW`]
+    /// window, so a flood of addresses cannot grow memory without bound.
+    pub fn with_max_callers(mut self, max_callers: usize) -> Self {
+        self.max_callers = max_callers.max(1);
+        self
+    }
+
+    /// Counts one request, or returns `Err` without counting it when the
+    /// caller is over quota.
+    pub fn hit(&self, caller: &str) -> Result<Quota, Quota> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() >= SWEEP_AT.min(self.max_callers) {
+            windows.retain(|_, w| now.duration_since(w.started) < WINDOW);
+        }
+        let caller = if windows.len() >= self.max_callers && !windows.contains_key(caller) {
+            OVERFLOW
+        } else {
+            caller
+        };
+        let window = windows.entry(caller.to_owned()).or_insert(Window {
+            started: now,
+            used: 0,
+        });
+        if now.duration_since(window.started) >= WINDOW {
+            *window = Window {
+                started: now,
+                used: 0,
+            };
+        }
+        if window.used >= self.limit {
+            return Err(self.quota(caller, window, now));
+        }
+        window.used += 1;
+        Ok(self.quota(caller, window, now))
+    }
+
+    /// The caller's quota without counting a request.
+    pub fn peek(&self, caller: &str) -> Quota {
+        let now = Instant::now();
+        let windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        match windows
+            .get(caller)
+            .filter(|w| now.duration_since(w.started) < WINDOW)
+        {
+            Some(window) => self.quota(caller, window, now),
+            None => self.quota(
+                caller,
+                &Window {
+                    started: now,
+                    used: 0,
+                },
+                now,
+            ),
+        }
+    }
+
+    fn quota(&self, caller: &str, window: &Window, now: Instant) -> Quota {
+        let elapsed = now.duration_since(window.started);
+        Quota {
+            caller: caller.to_owned(),
+            limit: self.limit,
+            used: window.used,
+            remaining: self.limit.saturating_sub(window.used),
+            window_secs: WINDOW.as_secs(),
+            reset_secs: WINDOW.saturating_sub(elapsed).as_secs_f64().ceil() as u64,
+        }
+    }
+}
+
+/// Quota key for a request: the hashed API key if it is a configured one,
+/// else the client IP, else the shared anonymous bucket when the connection
+/// address is unknown.
+pub fn caller(
+    keys: &ApiKeys,
+    headers: &HeaderMap,
+    addr: Option<&ConnectInfo<SocketAddr>>,
+) -> String {
+    if let Some(key) = keys.authenticate(headers) {
+        return key;
+    }
+    match addr {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => ANONYMOUS.to_string(),
+    }
+}
+
+/// Rejects over-quota callers with 429 and stamps the quota headers on every
+/// response.
+pub async fn enforce(State(st): State<AppState>, req: Request, next: Next) -> Response {
+    let caller = caller(
+        &st.api_keys,
+        req.headers(),
+        req.extensions().get::<ConnectInfo<SocketAddr>>(),
+    );
+    match st.limiter.hit(&caller) {
+        Ok(quota) => {
+            let mut res = next.run(req).await;
+            set_headers(res.headers_mut(), &quota);
+            res
+        }
+        Err(quota) => too_many_requests(&quota),
+    }
+}
+
+/// 429 carrying `quota`'s headers and a `Retry-After` until its reset.
+pub fn too_many_requests(quota: &Quota) -> Response {
+    let mut res = crate::util::json_err(429, "rate limit exceeded");
+    set_headers(res.headers_mut(), quota);
+    res.headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(quota.reset_secs));
+    res
+}
+
+fn set_headers(headers: &mut HeaderMap, quota: &Quota) {
+    headers.insert(LIMIT_HEADER, HeaderValue::from(quota.limit));
+    headers.insert(REMAINING_HEADER, HeaderValue::from(quota.remaining));
+    headers.insert(RESET_HEADER, HeaderValue::from(quota.reset_secs));
+}
@@ -1,13 +1,23 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+};
 
 use axum::{
-    extract::{Path, Query, State},
+    extract::{rejection::JsonRejection, ConnectInfo, Path, State},
+    http::{header, HeaderMap},
     response::Response,
-    routing::get,
-    Router,
+    routing::{get, post},
+    Json, Router,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use bex_core::{BlockHash, KeyImage, TxHash};
 
+use crate::cursor::Cursor;
+use crate::hex_param::{BlockId, HexParam};
+use crate::query_param::{query_params, Query};
+use crate::timing::{Phase, Timed};
 use crate::util::json_ok;
 use crate::{models, state::AppState};
 
@@ -15,105 +25,439 @@ pub async fn healthz() -> Response {
     json_ok(serde_json::json!({"status": "ok"}))
 }
 
+/// 503 when Postgres or Redis is unreachable. An unhealthy daemon is
+/// reported as `degraded` with 200, since only the proxy features need it.
+pub async fn readyz(State(st): State<AppState>) -> Response {
+    let database = sqlx::query("SELECT 1").execute(&st.db).await.is_ok();
+    // A GET of a key that is never set is as cheap as PING and is also
+    // understood by the minimal Redis servers the tests run against.
+    let mut conn = st.cache.clone();
+    let cache = redis::cmd("GET")
+        .arg("readyz:probe")
+        .query_async::<_, Option<String>>(&mut conn)
+        .await
+        .is_ok();
+    let daemon = st.daemon.view();
+    let status = if !database || !cache {
+        "unavailable"
+    } else if daemon.as_ref().is_some_and(|d| !d.healthy) {
+        "degraded"
+    } else {
+        "ok"
+    };
+    let body = models::ReadinessView {
+        status,
+        database,
+        cache,
+        daemon,
+    };
+    let code = if status == "unavailable" { 503 } else { 200 };
+    crate::util::json_with_status(code, &body)
+}
+
 pub fn v1_router() -> Router<AppState> {
     Router::new()
-        .route("/api/v1/block/:id", get(get_block))
+        .route("/api/v1/block/:id", get(get_block).head(head_block))
+        .route("/api/v1/block/:id/analytics", get(get_block_analytics))
         .route("/api/v1/blocks", get(list_blocks))
-        .route("/api/v1/tx/:hash", get(get_tx))
+        .route("/api/v1/blocks/batch", post(blocks_batch))
+        .route("/api/v1/tx/:hash", get(get_tx).head(head_tx))
+        .route("/api/v1/txs/batch", post(txs_batch))
+        .route("/api/v1/txs/by-fee", get(get_txs_by_fee))
         .route("/api/v1/tx/:hash/rings", get(get_tx_rings))
+        .route(
+            "/api/v1/rings/by-output/:global_index",
+            get(get_rings_by_output),
+        )
+        .route("/api/v1/tx/:hash/context", get(get_tx_context))
+        .route("/api/v1/tx/:hash/bundle", get(get_tx_bundle))
         .route("/api/v1/mempool", get(get_mempool))
+        .route("/api/v1/tip", get(get_tip))
+        .route("/api/v1/status", get(get_status))
+        .route("/api/v1/fees/base", get(get_base_fee))
+        .route("/api/v1/outputs/rct-offsets", get(get_rct_offsets))
+        .route("/api/v1/stats/top-blocks", get(get_top_blocks))
+        .route("/api/v1/stats/mining", get(get_mining_stats))
+        .route("/api/v1/stats/top-outputs", get(get_top_outputs))
+        // HEAD runs the GET handler with the body dropped; the key image view
+        // is already a single indexed lookup.
         .route("/api/v1/key_image/:hex", get(get_key_image))
         .route("/api/v1/search", get(search))
+        .route("/api/v1/meta/schema", get(get_schema))
+        .route("/api/v1/admin/reingest/:height", post(admin_reingest))
+        .route("/api/v1/admin/usage", get(admin_usage))
+        .route("/api/v1/limits", get(get_limits))
         .route("/api-docs", get(openapi_docs))
+        .route("/api-docs.json", get(openapi_docs))
+        .route("/api-docs.yaml", get(openapi_yaml))
 }
 
-pub async fn openapi_docs() -> Response {
-    let body = include_str!("../openapi.yaml");
-    json_ok(serde_yaml::from_str::<serde_json::Value>(body).unwrap())
+pub async fn openapi_docs(State(st): State<AppState>) -> Response {
+    st.docs.json()
 }
 
-#[derive(Deserialize)]
-pub struct Page {
-    pub start: Option<i64>,
-    pub limit: Option<i64>,
+pub async fn openapi_yaml(State(st): State<AppState>) -> Response {
+    st.docs.yaml()
+}
+
+pub async fn get_schema() -> Response {
+    json_ok(models::data_dictionary())
+}
+
+query_params! {
+    #[derive(Deserialize)]
+    pub struct Page {
+        pub start: Option<i64> => "a block height",
+        pub limit: Option<i64> => "an integer, clamped to 1..=200",
+        /// `head`, or a `next_cursor` from an earlier page; switches the response
+        /// to a [`models::BlockPage`].
+        pub cursor: Option<String> => "`head` or a `next_cursor` string",
+        /// Always rejected: blocks are not attributed to pools yet (ADR 0003).
+        /// Accepted here only to answer it with a 400 instead of ignoring it.
+        pub pool: Option<String> => "absent; pool filtering is not supported",
+    }
+}
+
+query_params! {
+    /// `?xmr=true` adds fixed-point XMR strings next to atomic-unit amounts.
+    #[derive(Deserialize)]
+    pub struct Units {
+        #[serde(default)]
+        pub xmr: bool => "`true` or `false`",
+    }
+}
+
+impl Units {
+    fn cache_suffix(&self) -> &'static str {
+        if self.xmr {
+            ":xmr"
+        } else {
+            ""
+        }
+    }
+}
+
+query_params! {
+    /// `?age=true` adds `age_seconds` to block, tx and mempool views, computed per
+    /// response so it never ages inside the cache.
+    #[derive(Deserialize)]
+    pub struct Age {
+        #[serde(default)]
+        pub age: bool => "`true` or `false`",
+    }
 }
 
-pub async fn list_blocks(State(st): State<AppState>, Query(p): Query<Page>) -> Response {
+/// Head-versioned keys go stale by changing, not by expiring; the TTL only
+/// bounds how long superseded pages linger in Redis.
+const LATEST_BLOCKS_TTL_SECS: usize = 600;
+
+pub async fn list_blocks(
+    State(st): State<AppState>,
+    Query(p): Query<Page>,
+    Query(units): Query<Units>,
+    Query(age): Query<Age>,
+) -> Response {
+    if p.pool.is_some() {
+        return crate::util::problem(
+            400,
+            "Unsupported parameter",
+            "`pool`: blocks are not attributed to mining pools, so they cannot be filtered by one",
+        );
+    }
     let limit = p.limit.unwrap_or(20).clamp(1, 200);
+    if let Some(cursor) = p.cursor.as_deref() {
+        if p.start.is_some() {
+            return crate::query_param::invalid::<Page>(
+                "cursor",
+                "cannot be combined with `start`",
+            );
+        }
+        return list_blocks_from_cursor(&st, cursor, limit, &units, age.age).await;
+    }
 
-    let start_height = match p.start {
-        Some(s) if s >= 0 => s,
+    // The first page is keyed by the chain head, so it stays cached until the
+    // next block (or a reorg) changes the head and with it the key.
+    let (start_height, cache_key, ttl) = match p.start {
+        Some(s) if s >= 0 => (
+            s,
+            format!("blocks:{s}:{limit}{}", units.cache_suffix()),
+            3,
+        ),
         Some(_) => return crate::util::json_ok(Vec::<models::BlockView>::new()),
-        None => match sqlx::query_scalar!("SELECT MAX(height) FROM public.blocks")
-            .fetch_one(&st.db)
-            .await
+        None => match sqlx::query!(
+            r#"SELECT height, hash AS "hash: BlockHash" FROM public.blocks ORDER BY height DESC, hash DESC LIMIT 1"#
+        )
+        .fetch_optional(&st.db)
+        .timed(Phase::Db)
+        .await
         {
-            Ok(Some(h)) => h,
+            Ok(Some(head)) => (
+                head.height,
+                format!(
+                    "blocks:latest:{}:{}:{limit}{}",
+                    head.height,
+                    head.hash,
+                    units.cache_suffix()
+                ),
+                LATEST_BLOCKS_TTL_SECS,
+            ),
             Ok(None) => return crate::util::json_ok(Vec::<models::BlockView>::new()),
             Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
         },
     };
 
-    let cache_key = format!("blocks:{start_height}:{limit}");
-    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+    if let Some(resp) = crate::util::cached_response_aged(&st.cache, &cache_key, age.age).await {
         return resp;
     }
 
-    let rows = sqlx::query_as!(
+    match fetch_blocks_from(&st, start_height, None, limit).await {
+        Ok(mut v) => {
+            if units.xmr {
+                v.iter_mut().for_each(models::BlockView::fill_xmr);
+            }
+            crate::util::cached_json_aged(&st.cache, &cache_key, &v, ttl, age.age).await
+        }
+        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
+    }
+}
+
+/// Up to `limit` blocks at or below `start_height`, highest first and then by
+/// hash descending. With `after`, only blocks that sort after
+/// `(start_height, after)`, so a page can end between blocks of one height.
+async fn fetch_blocks_from(
+    st: &AppState,
+    start_height: i64,
+    after: Option<&BlockHash>,
+    limit: i64,
+) -> Result<Vec<models::BlockView>, sqlx::Error> {
+    sqlx::query_as!(
         models::BlockView,
         r#"
-SELECT height, encode(hash,'hex') AS hash, extract(epoch from block_timestamp)::bigint AS ts,
-       size_bytes, major_version, minor_version, tx_count, reward_nanos
-FROM public.blocks
+SELECT height AS "height!", hash AS "hash!: BlockHash", ts,
+       size_bytes AS "size_bytes!", major_version AS "major_version!",
+       minor_version AS "minor_version!", tx_count AS "tx_count!",
+       reward_nanos AS "reward_nanos!", confirmations AS "confirmations!",
+       is_final AS "is_final!",
+       prev_height, prev_hash AS "prev_hash: BlockHash",
+       next_height, next_hash AS "next_hash: BlockHash",
+       NULL::text AS reward_xmr
+FROM public.block_views
 WHERE height <= $1
-ORDER BY height DESC
-LIMIT $2
+  AND ($2::bytea IS NULL OR (height, hash) < ($1, $2))
+ORDER BY height DESC, hash DESC
+LIMIT $3
 "#,
         start_height,
+        after.map(|h| h.0.as_slice()),
         limit
     )
     .fetch_all(&st.db)
-    .await;
+    .timed(Phase::Db)
+    .await
+}
 
-    match rows {
-        Ok(v) => crate::util::cached_json(&st.cache, &cache_key, &v, 3).await,
-        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
-    }
+/// Blocks whose hash or height is listed, highest first, as every endpoint
+/// serving a [`models::BlockView`] by id reads them. `mined_in` is a tx's
+/// `(block_height, ts)`: the blocks key includes the timestamp, so it picks
+/// the tx's own block even when another block shares its height.
+async fn fetch_block_views<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    hashes: &[Vec<u8>],
+    heights: &[i64],
+    mined_in: Option<(i64, i64)>,
+) -> Result<Vec<models::BlockView>, sqlx::Error> {
+    let (mined_height, mined_ts) = mined_in.unzip();
+    sqlx::query_as!(
+        models::BlockView,
+        r#"
+SELECT height AS "height!", hash AS "hash!: BlockHash", ts,
+       size_bytes AS "size_bytes!", major_version AS "major_version!",
+       minor_version AS "minor_version!", tx_count AS "tx_count!",
+       reward_nanos AS "reward_nanos!", confirmations AS "confirmations!",
+       is_final AS "is_final!",
+       prev_height, prev_hash AS "prev_hash: BlockHash",
+       next_height, next_hash AS "next_hash: BlockHash",
+       NULL::text AS reward_xmr
+FROM public.block_views
+WHERE hash = ANY($1::bytea[]) OR height = ANY($2::bigint[])
+   OR (height = $3 AND ts = $4)
+ORDER BY height DESC, hash DESC
+"#,
+        hashes,
+        heights,
+        mined_height,
+        mined_ts
+    )
+    .fetch_all(db)
+    .timed(Phase::Db)
+    .await
 }
 
-pub async fn get_block(State(st): State<AppState>, Path(id): Path<String>) -> Response {
-    let cache_key = format!("block:{id}");
-    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
-        return resp;
-    }
+/// Where a cursor-paged block listing stands: the tip height the listing was
+/// anchored to and the last block handed out, by height and hash.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct BlocksCursor {
+    anchor: i64,
+    height: i64,
+    hash: BlockHash,
+}
 
-    let is_hex = id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit());
-    let row = if is_hex {
-        sqlx::query_as!(
-            models::BlockView,
-            r#"
-SELECT height, encode(hash,'hex') AS hash, extract(epoch from block_timestamp)::bigint AS ts,
-       size_bytes, major_version, minor_version, tx_count, reward_nanos
-FROM public.blocks WHERE hash = decode($1,'hex')
-"#,
-            id
+impl Cursor for BlocksCursor {
+    const KIND: &'static str = "blocks";
+}
+
+/// Cursor pagination over `/api/v1/blocks`. `cursor=head` anchors a listing
+/// at the current tip; every later page continues strictly after the last
+/// block handed out, by height and then hash, so blocks arriving mid-listing
+/// never shift it and siblings at one height are not skipped. A reorg that
+/// removed that block invalidates the cursor with 409.
+async fn list_blocks_from_cursor(
+    st: &AppState,
+    cursor: &str,
+    limit: i64,
+    units: &Units,
+    with_age: bool,
+) -> Response {
+    let (anchor, start, after) = if cursor == "head" {
+        match sqlx::query!(
+            r#"SELECT height, hash AS "hash: BlockHash" FROM public.blocks ORDER BY height DESC, hash DESC LIMIT 1"#
         )
         .fetch_optional(&st.db)
+        .timed(Phase::Db)
         .await
+        {
+            Ok(Some(head)) => (head.height, head.height, None),
+            Ok(None) => {
+                return crate::util::json_ok(models::BlockPage {
+                    items: Vec::new(),
+                    anchor_height: None,
+                    next_cursor: None,
+                })
+            }
+            Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+        }
     } else {
-        let h: i64 = id.parse().unwrap_or(-1);
-        sqlx::query_as!(
-            models::BlockView,
-            r#"
-SELECT height, encode(hash,'hex') AS hash, extract(epoch from block_timestamp)::bigint AS ts,
-       size_bytes, major_version, minor_version, tx_count, reward_nanos
-FROM public.blocks WHERE height = $1
-"#,
-            h
+        let Some(after) =
+            BlocksCursor::decode(cursor).filter(|c| (0..=c.anchor).contains(&c.height))
+        else {
+            return crate::query_param::invalid::<Page>("cursor", "unrecognized cursor");
+        };
+        match sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM public.blocks WHERE height = $1 AND hash = $2) AS "exists!""#,
+            after.height,
+            after.hash.0.as_slice()
         )
-        .fetch_optional(&st.db)
+        .fetch_one(&st.db)
+        .timed(Phase::Db)
         .await
+        {
+            Ok(true) => (after.anchor, after.height, Some(after.hash)),
+            Ok(false) => {
+                return crate::util::json_err(
+                    409,
+                    "chain reorganized since this cursor was issued; restart with cursor=head",
+                )
+            }
+            Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+        }
+    };
+
+    let cache_key = format!(
+        "blocks:cursor:{anchor}:{start}:{}:{limit}{}",
+        after.map_or_else(|| "head".to_owned(), |h| h.to_string()),
+        units.cache_suffix()
+    );
+    if let Some(resp) = crate::util::cached_response_aged(&st.cache, &cache_key, with_age).await {
+        return resp;
+    }
+
+    let mut items = match fetch_blocks_from(st, start, after.as_ref(), limit + 1).await {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(|b| {
+            BlocksCursor {
+                anchor,
+                height: b.height,
+                hash: b.hash,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+    if units.xmr {
+        items.iter_mut().for_each(models::BlockView::fill_xmr);
+    }
+    let page = models::BlockPage {
+        items,
+        anchor_height: Some(anchor),
+        next_cursor,
+    };
+    // Pages hold only blocks at or below the anchor, but their confirmations
+    // still move with each new block.
+    crate::util::cached_json_aged(&st.cache, &cache_key, &page, 3, with_age).await
+}
+
+pub async fn get_block(
+    State(st): State<AppState>,
+    id: BlockId,
+    Query(units): Query<Units>,
+    Query(age): Query<Age>,
+) -> Response {
+    let cache_key = format!("block:{id}{}", units.cache_suffix());
+    let flight =
+        match crate::util::cached_or_flight(&st.cache, &st.flights, &cache_key, age.age).await {
+            Ok(resp) => return resp,
+            Err(flight) => flight,
+        };
+
+    let row = match &id {
+        BlockId::Hash(hash) => fetch_block_views(&st.db, &[hash.bytes().to_vec()], &[], None).await,
+        BlockId::Height(height) => fetch_block_views(&st.db, &[], &[*height], None).await,
+    }
+    .map(|blocks| blocks.into_iter().next());
+
+    match row {
+        Ok(Some(mut v)) => {
+            if units.xmr {
+                v.fill_xmr();
+            }
+            crate::util::cached_json_aged(&st.cache, &cache_key, &v, 30, age.age).await
+        }
+        Ok(None) => flight.fail(404, "not found"),
+        Err(e) => flight.fail(500, &format!("db error: {e}")),
+    }
+}
+
+/// Existence check for pollers. Only the block's identity and finality are
+/// read, and the ETag hashes those instead of the full view, so it differs
+/// from the `GET` ETag. Axum drops the body of `HEAD` responses.
+pub async fn head_block(State(st): State<AppState>, id: BlockId) -> Response {
+    let (hash, height) = match &id {
+        BlockId::Hash(hash) => (Some(hash.as_str()), None),
+        BlockId::Height(height) => (None, Some(*height)),
     };
+    let cache_key = format!("block-head:{id}");
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return resp;
+    }
+
+    let row = sqlx::query_as!(
+        models::BlockPresence,
+        r#"
+SELECT height AS "height!", hash AS "hash!: BlockHash", is_final AS "is_final!"
+FROM public.block_views
+WHERE hash = decode($1,'hex') OR height = $2
+"#,
+        hash,
+        height
+    )
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
+    .await;
 
     match row {
         Ok(Some(v)) => crate::util::cached_json(&st.cache, &cache_key, &v, 30).await,
@@ -122,22 +466,69 @@ FROM public.blocks WHERE height = $1
     }
 }
 
-pub async fn get_tx(State(st): State<AppState>, Path(hash): Path<String>) -> Response {
-    if !crate::util::is_hex_64(&hash) {
-        return crate::util::json_err(400, "invalid hash");
-    }
-    let cache_key = format!("tx:{hash}");
+/// Soft facts of a finalized block only change on re-ingestion, which does
+/// not touch the API cache, so the TTL bounds how long a re-ingested block
+/// serves stale facts.
+const FINAL_ANALYTICS_TTL_SECS: usize = 300;
+const RECENT_ANALYTICS_TTL_SECS: usize = 30;
+
+pub async fn get_block_analytics(State(st): State<AppState>, id: BlockId) -> Response {
+    let (hash, height) = match &id {
+        BlockId::Hash(hash) => (Some(hash.as_str()), None),
+        BlockId::Height(height) => (None, Some(*height)),
+    };
+    let cache_key = format!("block-analytics:{id}");
     if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
         return resp;
     }
 
     let row = sqlx::query_as!(
+        models::BlockAnalyticsView,
+        r#"
+SELECT b.height, b.hash AS "hash: BlockHash", v.ts,
+       v.is_final AS "is_final!",
+       (b.analytics_pending OR s.block_height IS NULL) AS "analytics_pending!",
+       s.total_fee AS "total_fee_nanos?", s.avg_ring_size AS "avg_ring_size?",
+       s.median_fee_rate AS "median_fee_rate?", s.bp_total_bytes AS "bp_total_bytes?",
+       s.clsag_count AS "clsag_count?", s.nonstandard_count AS "nonstandard_count?"
+FROM public.blocks b
+JOIN public.block_views v ON v.height = b.height AND v.hash = b.hash
+LEFT JOIN public.soft_facts s ON s.block_height = b.height
+WHERE b.hash = decode($1,'hex') OR b.height = $2
+"#,
+        hash,
+        height
+    )
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    match row {
+        Ok(Some(v)) => {
+            let ttl = if v.is_final && !v.analytics_pending {
+                FINAL_ANALYTICS_TTL_SECS
+            } else {
+                RECENT_ANALYTICS_TTL_SECS
+            };
+            crate::util::cached_json(&st.cache, &cache_key, &v, ttl).await
+        }
+        Ok(None) => crate::util::json_err(404, "not found"),
+        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
+    }
+}
+
+/// The tx view shared by `/tx/{hash}` and the tx bundle.
+async fn fetch_tx_view<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    hash: &str,
+) -> Result<Option<models::TxView>, sqlx::Error> {
+    sqlx::query_as!(
         models::TxView,
         r#"
 SELECT
-  encode(tx_hash,'hex') AS hash,
+  tx_hash AS "hash: TxHash",
   block_height,
-  extract(epoch from block_timestamp)::bigint AS ts,
+  extract(epoch from NULLIF(block_timestamp, 'infinity'))::bigint AS ts,
   in_mempool,
   fee_nanos,
   size_bytes,
@@ -148,41 +539,49 @@ SELECT
   proof_type,
   bp_plus,
   num_inputs,
-  num_outputs
+  num_outputs,
+  extract(epoch from first_seen)::bigint AS first_seen,
+  nonstandard,
+  nonstandard_reasons,
+  NULL::text AS fee_xmr
 FROM public.txs WHERE tx_hash = decode($1,'hex')
 "#,
-        hash.as_str()
+        hash
     )
-    .fetch_optional(&st.db)
-    .await;
-
-    let tx = match row {
-        Ok(Some(v)) => v,
-        Ok(None) => return crate::util::json_err(404, "not found"),
-        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
-    };
+    .fetch_optional(db)
+    .timed(Phase::Db)
+    .await
+}
 
-    let inputs = match sqlx::query_as!(
+/// A tx's inputs in order.
+async fn fetch_inputs<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    hash: &str,
+) -> Result<Vec<models::InputView>, sqlx::Error> {
+    sqlx::query_as!(
         models::InputView,
         r#"
 SELECT idx,
-       encode(key_image,'hex') AS "key_image!",
+       key_image AS "key_image: KeyImage",
        ring_size,
        encode(pseudo_out,'hex') AS pseudo_out
 FROM public.tx_inputs
 WHERE tx_hash = decode($1,'hex')
 ORDER BY idx ASC
 "#,
-        hash.as_str()
+        hash
     )
-    .fetch_all(&st.db)
+    .fetch_all(db)
+    .timed(Phase::Db)
     .await
-    {
-        Ok(v) => v,
-        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
-    };
+}
 
-    let outputs = match sqlx::query_as!(
+/// A tx's outputs in order.
+async fn fetch_outputs<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    hash: &str,
+) -> Result<Vec<models::OutputView>, sqlx::Error> {
+    sqlx::query_as!(
         models::OutputView,
         r#"
 SELECT idx_in_tx,
@@ -190,19 +589,51 @@ SELECT idx_in_tx,
        amount,
        encode(commitment,'hex') AS "commitment!",
        encode(stealth_public_key,'hex') AS "stealth_public_key!",
-       encode(spent_by_key_image,'hex') AS spent_by_key_image,
-       encode(spent_in_tx,'hex') AS spent_in_tx
+       spent_by_key_image AS "spent_by_key_image: KeyImage",
+       spent_in_tx AS "spent_in_tx: TxHash"
 FROM public.outputs
 WHERE tx_hash = decode($1,'hex')
 ORDER BY idx_in_tx ASC
 "#,
-        hash.as_str()
+        hash
     )
-    .fetch_all(&st.db)
+    .fetch_all(db)
+    .timed(Phase::Db)
     .await
-    {
+}
+
+pub async fn get_tx(
+    State(st): State<AppState>,
+    hash: HexParam<32>,
+    Query(units): Query<Units>,
+    Query(age): Query<Age>,
+) -> Response {
+    let cache_key = format!("tx:{hash}{}", units.cache_suffix());
+    let flight =
+        match crate::util::cached_or_flight(&st.cache, &st.flights, &cache_key, age.age).await {
+            Ok(resp) => return resp,
+            Err(flight) => flight,
+        };
+
+    let row = fetch_tx_view(&st.db, hash.as_str()).await;
+
+    let mut tx = match row {
+        Ok(Some(v)) => v,
+        Ok(None) => return flight.fail(404, "not found"),
+        Err(e) => return flight.fail(500, &format!("db error: {e}")),
+    };
+    if units.xmr {
+        tx.fill_xmr();
+    }
+
+    let inputs = match fetch_inputs(&st.db, hash.as_str()).await {
         Ok(v) => v,
-        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+        Err(e) => return flight.fail(500, &format!("db error: {e}")),
+    };
+
+    let outputs = match fetch_outputs(&st.db, hash.as_str()).await {
+        Ok(v) => v,
+        Err(e) => return flight.fail(500, &format!("db error: {e}")),
     };
 
     let body = models::TxDetailView {
@@ -211,206 +642,1606 @@ ORDER BY idx_in_tx ASC
         outputs,
     };
 
-    crate::util::cached_json(&st.cache, &cache_key, &body, 60).await
+    crate::util::cached_json_aged(&st.cache, &cache_key, &body, 60, age.age).await
 }
 
-pub async fn get_mempool(State(st): State<AppState>) -> Response {
-    let cache_key = "mempool:latest";
-    if let Some(resp) = crate::util::cached_response(&st.cache, cache_key).await {
+/// Existence check for pollers waiting on a tx: one row of `txs` instead of
+/// the view with its inputs and outputs. The ETag changes when the tx is
+/// mined, so `If-None-Match` polling gets 304 until then.
+pub async fn head_tx(State(st): State<AppState>, hash: HexParam<32>) -> Response {
+    let cache_key = format!("tx-head:{hash}");
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
         return resp;
     }
 
-    let rows = sqlx::query_as!(
-        models::MempoolView,
+    let row = sqlx::query_as!(
+        models::TxPresence,
         r#"
-SELECT encode(tx_hash,'hex') AS hash,
-       extract(epoch from first_seen)::bigint AS first_seen,
-       extract(epoch from last_seen)::bigint AS last_seen,
-       fee_rate, relayed_by
-FROM public.mempool_txs
-ORDER BY last_seen DESC
-LIMIT 1000
-"#
+SELECT tx_hash AS "hash: TxHash", block_height, in_mempool
+FROM public.txs WHERE tx_hash = decode($1,'hex')
+"#,
+        hash.as_str()
     )
-    .fetch_all(&st.db)
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
     .await;
 
-    match rows {
-        Ok(v) => crate::util::cached_json(&st.cache, cache_key, &v, 2).await,
+    match row {
+        // Short-lived, so a poller sees the tx confirm soon after it does.
+        Ok(Some(v)) => crate::util::cached_json(&st.cache, &cache_key, &v, 10).await,
+        Ok(None) => crate::util::json_err(404, "not found"),
         Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
     }
 }
 
-pub async fn get_tx_rings(State(st): State<AppState>, Path(hash): Path<String>) -> Response {
-    if !crate::util::is_hex_64(&hash) {
-        return crate::util::json_err(400, "invalid hash");
-    }
-    let cache_key = format!("rings:{hash}");
+pub async fn get_tx_context(
+    State(st): State<AppState>,
+    hash: HexParam<32>,
+    Query(units): Query<Units>,
+) -> Response {
+    let cache_key = format!("txctx:{hash}{}", units.cache_suffix());
     if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
         return resp;
     }
 
-    let rows = sqlx::query_as!(
-        models::RingView,
+    let tx = match sqlx::query!(
         r#"
-SELECT
-  encode(r.tx_hash,'hex') AS tx_hash,
-  r.input_idx,
-  r.ring_index,
-  o.global_index
-FROM public.rings r
-LEFT JOIN public.outputs o ON o.output_id = r.referenced_output_id
-WHERE r.tx_hash = decode($1,'hex')
-ORDER BY r.input_idx ASC, r.ring_index ASC
+SELECT tx_hash AS "hash: TxHash",
+       in_mempool,
+       block_height,
+       block_position,
+       extract(epoch from NULLIF(block_timestamp, 'infinity'))::bigint AS ts,
+       extract(epoch from orphaned_at)::bigint AS orphaned_at
+FROM public.txs WHERE tx_hash = decode($1,'hex')
 "#,
         hash.as_str()
     )
-    .fetch_all(&st.db)
-    .await;
-
-    let rows = match rows {
-        Ok(v) => v,
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(Some(v)) => v,
+        Ok(None) => return crate::util::json_err(404, "not found"),
         Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
     };
 
-    let mut grouped: BTreeMap<i32, Vec<models::RingMemberView>> = BTreeMap::new();
-    for row in rows {
-        grouped
-            .entry(row.input_idx)
-            .or_default()
-            .push(models::RingMemberView {
-                ring_index: row.ring_index,
-                global_index: row.global_index,
-            });
-    }
-
-    let rings: Vec<models::RingSetView> = grouped
-        .into_iter()
-        .map(|(input_idx, mut members)| {
-            members.sort_by_key(|m| m.ring_index);
-            models::RingSetView { input_idx, members }
-        })
-        .collect();
-
-    crate::util::cached_json(&st.cache, &cache_key, &rings, 60).await
-}
+    let mut block = match tx.block_height.zip(tx.ts) {
+        Some(mined_in) => match fetch_block_views(&st.db, &[], &[], Some(mined_in)).await {
+            Ok(v) => v.into_iter().next(),
+            Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+        },
+        None => None,
+    };
 
-pub async fn get_key_image(State(st): State<AppState>, Path(hex): Path<String>) -> Response {
-    if !crate::util::is_hex_64(&hex) {
-        return crate::util::json_err(400, "invalid key image");
-    }
-    let cache_key = format!("ki:{hex}");
-    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
-        return resp;
+    if units.xmr {
+        block.iter_mut().for_each(models::BlockView::fill_xmr);
     }
 
-    let row = sqlx::query_as!(
-        models::KeyImageView,
+    let orphaned_blocks = match sqlx::query_as!(
+        models::OrphanedBlockView,
         r#"
-SELECT
-  encode(ti.key_image,'hex') AS key_image,
-  encode(t.tx_hash,'hex') AS spending_tx,
-  t.block_height
-FROM public.tx_inputs ti
-JOIN public.txs t ON t.tx_hash = ti.tx_hash
-WHERE ti.key_image = decode($1,'hex')
-ORDER BY t.block_height DESC NULLS LAST
-LIMIT 1
+SELECT block_height AS height,
+       block_hash AS "hash: BlockHash",
+       extract(epoch from orphaned_at)::bigint AS orphaned_at
+FROM public.orphaned_tx_blocks
+WHERE tx_hash = decode($1,'hex')
+ORDER BY orphaned_at DESC, block_height DESC, block_hash ASC
 "#,
-        hex.as_str()
+        hash.as_str()
     )
-    .fetch_optional(&st.db)
-    .await;
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
 
-    match row {
-        Ok(Some(v)) => crate::util::cached_json(&st.cache, &cache_key, &v, 120).await,
-        Ok(None) => crate::util::json_err(404, "not found"),
-        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
-    }
-}
+    let body = models::TxContextView {
+        hash: tx.hash,
+        in_mempool: tx.in_mempool,
+        confirmations: block.as_ref().map_or(0, |b| b.confirmations),
+        is_final: block.as_ref().is_some_and(|b| b.is_final),
+        position: block.as_ref().and(tx.block_position),
+        block,
+        orphaned_at: tx.orphaned_at,
+        orphaned_blocks,
+    };
 
-#[derive(Deserialize)]
-pub struct Q {
-    pub q: String,
+    // Short TTL: confirmations move with every block.
+    crate::util::cached_json(&st.cache, &cache_key, &body, 5).await
 }
 
-pub async fn search(State(st): State<AppState>, Query(Q { q }): Query<Q>) -> Response {
-    let s = q.trim();
-    if s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit()) {
-        if sqlx::query_scalar!(
-            "SELECT 1 FROM public.txs WHERE tx_hash = decode($1,'hex') LIMIT 1",
-            s
-        )
-        .fetch_optional(&st.db)
-        .await
-        .ok()
-        .flatten()
-        .is_some()
-        {
-            return crate::util::json_ok(models::SearchResult {
-                kind: "tx".to_owned(),
-                value: serde_json::Value::String(s.to_owned()),
-            });
-        }
-        if sqlx::query_scalar!(
-            "SELECT 1 FROM public.blocks WHERE hash = decode($1,'hex') LIMIT 1",
-            s
-        )
-        .fetch_optional(&st.db)
-        .await
-        .ok()
-        .flatten()
-        .is_some()
+/// Bundles change with every block (confirmations), so they are cached briefly.
+const TX_BUNDLE_TTL_SECS: usize = 5;
+
+/// `Content-Disposition` so browsers save the bundle instead of showing it.
+fn bundle_download(mut resp: Response, hash: &HexParam<32>) -> Response {
+    if resp.status().is_success() {
+        if let Ok(value) =
+            header::HeaderValue::from_str(&format!("attachment; filename=\"tx-{hash}.json\""))
         {
-            return crate::util::json_ok(models::SearchResult {
-                kind: "block".to_owned(),
-                value: serde_json::Value::String(s.to_owned()),
-            });
+            resp.headers_mut()
+                .insert(header::CONTENT_DISPOSITION, value);
         }
-        if sqlx::query_scalar!(
-            "SELECT 1 FROM public.tx_inputs WHERE key_image = decode($1,'hex') LIMIT 1",
-            s
-        )
-        .fetch_optional(&st.db)
+    }
+    resp
+}
+
+/// Proof-of-inclusion bundle: the tx, its block, inputs, outputs, resolved
+/// rings and confirmation status, all read in one repeatable-read snapshot so
+/// the parts agree with each other.
+pub async fn get_tx_bundle(
+    State(st): State<AppState>,
+    hash: HexParam<32>,
+    Query(units): Query<Units>,
+) -> Response {
+    let cache_key = format!("txbundle:{hash}{}", units.cache_suffix());
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return bundle_download(resp, &hash);
+    }
+
+    let mut db = match st.db.begin().timed(Phase::Db).await {
+        Ok(db) => db,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+    if let Err(e) = sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *db)
+        .timed(Phase::Db)
         .await
-        .ok()
-        .flatten()
-        .is_some()
-        {
-            return crate::util::json_ok(models::SearchResult {
-                kind: "key_image".to_owned(),
-                value: serde_json::Value::String(s.to_owned()),
-            });
+    {
+        return crate::util::json_err(500, &format!("db error: {e}"));
+    }
+
+    let mut tx = match fetch_tx_view(&mut *db, hash.as_str()).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return crate::util::json_err(404, "not found"),
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let snapshot = match sqlx::query!(
+        r#"
+SELECT extract(epoch from now())::bigint AS "generated_at!",
+       (SELECT height FROM public.current_tip WHERE id = 1) AS tip_height,
+       (SELECT block_position FROM public.txs WHERE tx_hash = decode($1,'hex')) AS position
+"#,
+        hash.as_str()
+    )
+    .fetch_one(&mut *db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let mut block = match tx.block_height.zip(tx.ts) {
+        Some(mined_in) => match fetch_block_views(&mut *db, &[], &[], Some(mined_in)).await {
+            Ok(v) => v.into_iter().next(),
+            Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+        },
+        None => None,
+    };
+
+    let inputs = match fetch_inputs(&mut *db, hash.as_str()).await {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let outputs = match fetch_outputs(&mut *db, hash.as_str()).await {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let members = match sqlx::query!(
+        r#"
+SELECT r.input_idx,
+       r.ring_index,
+       o.global_index AS "global_index?",
+       o.tx_hash AS "tx_hash?: TxHash",
+       encode(o.stealth_public_key,'hex') AS stealth_public_key,
+       encode(o.commitment,'hex') AS commitment
+FROM public.rings r
+LEFT JOIN public.outputs o ON o.output_id = r.referenced_output_id
+WHERE r.tx_hash = decode($1,'hex')
+ORDER BY r.input_idx ASC, r.ring_index ASC
+"#,
+        hash.as_str()
+    )
+    .fetch_all(&mut *db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+    drop(db);
+
+    let mut rings: Vec<models::ResolvedRingSetView> = Vec::new();
+    for m in members {
+        let member = models::ResolvedRingMemberView {
+            ring_index: m.ring_index,
+            global_index: m.global_index,
+            tx_hash: m.tx_hash,
+            stealth_public_key: m.stealth_public_key,
+            commitment: m.commitment,
+        };
+        match rings.last_mut() {
+            Some(ring) if ring.input_idx == m.input_idx => ring.members.push(member),
+            _ => rings.push(models::ResolvedRingSetView {
+                input_idx: m.input_idx,
+                members: vec![member],
+            }),
+        }
+    }
+
+    if units.xmr {
+        tx.fill_xmr();
+        block.iter_mut().for_each(models::BlockView::fill_xmr);
+    }
+    let state = match &block {
+        Some(b) if b.is_final => "final",
+        Some(_) => "confirmed",
+        None if tx.in_mempool => "mempool",
+        None => "detached",
+    };
+    let status = models::BundleStatus {
+        state,
+        confirmations: block.as_ref().map_or(0, |b| b.confirmations),
+        position: block.as_ref().and(snapshot.position),
+        tip_height: snapshot.tip_height,
+    };
+    let body = models::TxBundleView {
+        generated_at: snapshot.generated_at,
+        tx,
+        block,
+        inputs,
+        outputs,
+        rings,
+        status,
+    };
+
+    let resp = crate::util::cached_json(&st.cache, &cache_key, &body, TX_BUNDLE_TTL_SECS).await;
+    bundle_download(resp, &hash)
+}
+
+const MAX_MEMPOOL_LIMIT: i64 = 1000;
+
+query_params! {
+    #[derive(Deserialize)]
+    pub struct MempoolQuery {
+        pub sort: Option<String> => "one of last_seen, fee_rate, first_seen, size",
+        /// `head`, or a `next_cursor` from an earlier page; switches the response
+        /// from a bare array to a [`models::MempoolPage`].
+        pub cursor: Option<String> => "`head` or a `next_cursor` string",
+        pub limit: Option<i64> => "an integer, clamped to 1..=1000",
+    }
+}
+
+/// Mempool orderings. Each maps to one numeric key sorted descending (with
+/// the tx hash as tie-breaker), which keeps keyset pagination uniform.
+const MEMPOOL_SORTS: &[&str] = &["last_seen", "fee_rate", "first_seen", "size"];
+
+/// Position after the last row of a page: the sort it belongs to, that row's
+/// sort key and its hash.
+#[derive(Serialize, Deserialize)]
+struct MempoolCursor {
+    sort: String,
+    key: rust_decimal::Decimal,
+    hash: TxHash,
+}
+
+impl Cursor for MempoolCursor {
+    const KIND: &'static str = "mempool";
+}
+
+pub async fn get_mempool(
+    State(st): State<AppState>,
+    Query(q): Query<MempoolQuery>,
+    Query(age): Query<Age>,
+) -> Response {
+    let sort = q.sort.as_deref().unwrap_or("last_seen");
+    if !MEMPOOL_SORTS.contains(&sort) {
+        return crate::query_param::invalid::<MempoolQuery>(
+            "sort",
+            format!("unknown sort `{sort}`"),
+        );
+    }
+    // Without a cursor the listing keeps its original shape: a bare array of
+    // up to 1000 rows. Paging starts at `cursor=head`.
+    let paged = q.cursor.is_some();
+    let default_limit = if paged { 100 } else { MAX_MEMPOOL_LIMIT };
+    let limit = q.limit.unwrap_or(default_limit).clamp(1, MAX_MEMPOOL_LIMIT);
+    let after = match q.cursor.as_deref() {
+        Some("head") | None => None,
+        Some(c) => match MempoolCursor::decode(c).filter(|c| c.sort == sort) {
+            Some(c) => Some((c.key, c.hash)),
+            None => {
+                return crate::query_param::invalid::<MempoolQuery>(
+                    "cursor",
+                    "not a cursor for this sort",
+                )
+            }
+        },
+    };
+
+    let cache_key = format!(
+        "mempool:{sort}:{limit}:{}",
+        q.cursor.as_deref().unwrap_or("list")
+    );
+    if let Some(resp) = crate::util::cached_response_aged(&st.cache, &cache_key, age.age).await {
+        return resp;
+    }
+
+    let (after_key, after_hash) = after.unzip();
+    let rows = sqlx::query!(
+        r#"
+WITH m AS (
+  SELECT tx_hash, first_seen, last_seen, fee_rate, relayed_by, size_bytes,
+         relayed, do_not_relay, double_spend_seen,
+         CASE $1
+           WHEN 'fee_rate' THEN COALESCE(fee_rate, -1)
+           WHEN 'size' THEN COALESCE(size_bytes, -1)::numeric
+           WHEN 'first_seen' THEN -extract(epoch from first_seen)
+           ELSE extract(epoch from last_seen)
+         END AS sort_key
+  FROM public.mempool_txs
+  WHERE mined_height IS NULL
+)
+SELECT tx_hash AS "hash: TxHash",
+       extract(epoch from first_seen)::bigint AS first_seen,
+       extract(epoch from last_seen)::bigint AS last_seen,
+       fee_rate, relayed_by, size_bytes,
+       relayed, do_not_relay, double_spend_seen,
+       sort_key AS "sort_key!"
+FROM m
+WHERE $2::numeric IS NULL
+   OR sort_key < $2
+   OR (sort_key = $2 AND tx_hash > $3)
+ORDER BY sort_key DESC, tx_hash ASC
+LIMIT $4
+"#,
+        sort,
+        after_key,
+        after_hash.as_ref().map(|h| h.0.as_slice()),
+        limit + 1
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    let mut rows = match rows {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+    let next_cursor = if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().map(|r| {
+            MempoolCursor {
+                sort: sort.to_owned(),
+                key: r.sort_key,
+                hash: r.hash,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+    let items: Vec<_> = rows
+        .into_iter()
+        .map(|r| models::MempoolView {
+            hash: r.hash,
+            first_seen: r.first_seen,
+            last_seen: r.last_seen,
+            fee_rate: r.fee_rate,
+            relayed_by: r.relayed_by,
+            size_bytes: r.size_bytes,
+            relayed: r.relayed,
+            do_not_relay: r.do_not_relay,
+            double_spend_seen: r.double_spend_seen,
+        })
+        .collect();
+    if !paged {
+        return crate::util::cached_json_aged(&st.cache, &cache_key, &items, 2, age.age).await;
+    }
+    let page = models::MempoolPage { items, next_cursor };
+    crate::util::cached_json_aged(&st.cache, &cache_key, &page, 2, age.age).await
+}
+
+const MAX_FEE_LOOKUP_LIMIT: i64 = 500;
+/// Widest block window `/txs/by-fee` searches; also the default window below
+/// the tip when neither bound is given.
+pub const MAX_FEE_WINDOW_BLOCKS: i64 = 10_000;
+
+query_params! {
+    #[derive(Deserialize)]
+    pub struct TxsByFeeQuery {
+        pub min: Option<i64> => "a non-negative fee in atomic units",
+        pub max: Option<i64> => "a fee in atomic units, at least `min`",
+        pub from_height: Option<i64> => "a block height",
+        pub to_height: Option<i64> => "a block height, at least `from_height`",
+        pub cursor: Option<String> => "a `next_cursor` string",
+        pub limit: Option<i64> => "an integer, clamped to 1..=500",
+    }
+}
+
+/// Position after the last row of a page in `(fee, height, hash)` order.
+#[derive(Serialize, Deserialize)]
+struct FeeCursor {
+    fee: i64,
+    height: i64,
+    hash: TxHash,
+}
+
+impl Cursor for FeeCursor {
+    const KIND: &'static str = "txs-by-fee";
+}
+
+/// Mined txs whose total fee lies in `min..=max` within a block window,
+/// highest fee first.
+pub async fn get_txs_by_fee(
+    State(st): State<AppState>,
+    Query(q): Query<TxsByFeeQuery>,
+) -> Response {
+    let min = q.min.unwrap_or(0);
+    let max = q.max.unwrap_or(i64::MAX);
+    if min < 0 {
+        return crate::query_param::invalid::<TxsByFeeQuery>("min", "negative");
+    }
+    if min > max {
+        return crate::query_param::invalid::<TxsByFeeQuery>("max", "below `min`");
+    }
+    let limit = q.limit.unwrap_or(50).clamp(1, MAX_FEE_LOOKUP_LIMIT);
+    let after = match q.cursor.as_deref() {
+        Some(c) => match FeeCursor::decode(c) {
+            Some(c) => Some((c.fee, c.height, c.hash)),
+            None => {
+                return crate::query_param::invalid::<TxsByFeeQuery>(
+                    "cursor",
+                    "unrecognized cursor",
+                )
+            }
+        },
+        None => None,
+    };
+
+    let (from_height, to_height) = match (q.from_height, q.to_height) {
+        (Some(from), Some(to)) => (from, to),
+        (Some(from), None) => (from, from.saturating_add(MAX_FEE_WINDOW_BLOCKS - 1)),
+        (None, Some(to)) => (to.saturating_sub(MAX_FEE_WINDOW_BLOCKS - 1).max(0), to),
+        (None, None) => {
+            let tip =
+                match sqlx::query_scalar!("SELECT height FROM public.current_tip WHERE id = 1")
+                    .fetch_optional(&st.db)
+                    .timed(Phase::Db)
+                    .await
+                {
+                    Ok(tip) => tip.unwrap_or(0),
+                    Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+                };
+            ((tip - MAX_FEE_WINDOW_BLOCKS + 1).max(0), tip)
+        }
+    };
+    if from_height < 0 || from_height > to_height {
+        return crate::util::json_err(
+            400,
+            "from_height must be non-negative and at most to_height",
+        );
+    }
+    if to_height - from_height >= MAX_FEE_WINDOW_BLOCKS {
+        return crate::util::json_err(
+            400,
+            &format!("block window is limited to {MAX_FEE_WINDOW_BLOCKS} blocks"),
+        );
+    }
+
+    let cache_key = format!(
+        "txs-by-fee:{min}:{max}:{from_height}:{to_height}:{limit}:{}",
+        q.cursor.as_deref().unwrap_or("first")
+    );
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return resp;
+    }
+
+    let (after_fee, after_height, after_hash) = match after {
+        Some((fee, height, hash)) => (Some(fee), Some(height), Some(hash)),
+        None => (None, None, None),
+    };
+    let rows = sqlx::query!(
+        r#"
+SELECT tx_hash AS "hash: TxHash",
+       block_height AS "block_height!",
+       block_position,
+       fee_nanos AS "fee_nanos!",
+       size_bytes,
+       fee_nanos::double precision / NULLIF(size_bytes, 0) AS fee_per_byte
+FROM public.txs
+WHERE block_height BETWEEN $1 AND $2
+  AND fee_nanos BETWEEN $3 AND $4
+  AND ($5::bigint IS NULL
+       OR fee_nanos < $5
+       OR (fee_nanos = $5 AND block_height < $6)
+       OR (fee_nanos = $5 AND block_height = $6 AND tx_hash > $7))
+ORDER BY fee_nanos DESC, block_height DESC, tx_hash ASC
+LIMIT $8
+"#,
+        from_height,
+        to_height,
+        min,
+        max,
+        after_fee,
+        after_height,
+        after_hash.as_ref().map(|h| h.0.as_slice()),
+        limit + 1
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    let mut rows = match rows {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+    let next_cursor = if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().map(|r| {
+            FeeCursor {
+                fee: r.fee_nanos,
+                height: r.block_height,
+                hash: r.hash,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+    let page = models::TxFeePage {
+        items: rows
+            .into_iter()
+            .map(|r| models::TxFeeView {
+                hash: r.hash,
+                block_height: r.block_height,
+                block_position: r.block_position,
+                fee_nanos: r.fee_nanos,
+                size_bytes: r.size_bytes,
+                fee_per_byte: r.fee_per_byte,
+            })
+            .collect(),
+        from_height,
+        to_height,
+        next_cursor,
+    };
+    crate::util::cached_json(&st.cache, &cache_key, &page, 10).await
+}
+
+pub async fn get_tip(State(st): State<AppState>) -> Response {
+    let cache_key = "tip:current";
+    if let Some(resp) = crate::util::cached_response(&st.cache, cache_key).await {
+        return resp;
+    }
+
+    let row = sqlx::query_as!(
+        models::TipView,
+        r#"
+SELECT height,
+       hash AS "hash: BlockHash",
+       prev_hash AS "prev_hash: BlockHash",
+       extract(epoch from updated_at)::bigint AS updated_at
+FROM public.current_tip
+WHERE id = 1
+"#
+    )
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    match row {
+        Ok(Some(v)) => crate::util::cached_json(&st.cache, cache_key, &v, 2).await,
+        Ok(None) => crate::util::json_err(404, "not found"),
+        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
+    }
+}
+
+pub async fn get_status(State(st): State<AppState>) -> Response {
+    let cache_key = "status:current";
+    if let Some(resp) = crate::util::cached_response(&st.cache, cache_key).await {
+        return resp;
+    }
+
+    let row = sqlx::query!(
+        r#"
+SELECT COALESCE((SELECT value = 'true' FROM public.dataset_metadata WHERE key = 'pruned'), FALSE) AS "pruned!",
+       (SELECT height FROM public.current_tip WHERE id = 1) AS tip_height,
+       (SELECT value::bigint FROM public.dataset_metadata WHERE key = 'daemon_tip') AS daemon_tip_height
+"#
+    )
+    .fetch_one(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    match row {
+        Ok(r) => {
+            let v = models::StatusView {
+                pruned: r.pruned,
+                tip_height: r.tip_height,
+                daemon_tip_height: r.daemon_tip_height,
+                daemon: st.daemon.view(),
+            };
+            crate::util::cached_json(&st.cache, cache_key, &v, 5).await
+        }
+        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
+    }
+}
+
+/// Ingested blocks whose txs make up `recent_blocks` in `/fees/base`.
+const FEE_WINDOW_BLOCKS: i64 = 10;
+
+pub async fn get_base_fee(State(st): State<AppState>) -> Response {
+    let cache_key = "fees:base";
+    if let Some(resp) = crate::util::cached_response(&st.cache, cache_key).await {
+        return resp;
+    }
+
+    let daemon = sqlx::query_as!(
+        models::DaemonFeeView,
+        r#"
+SELECT fee_per_byte, fees, quantization_mask,
+       extract(epoch from updated_at)::bigint AS updated_at
+FROM public.fee_estimate
+WHERE id = 1
+"#
+    )
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
+    .await;
+    let daemon = match daemon {
+        Ok(d) => d,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let observed = sqlx::query!(
+        r#"
+WITH mempool AS (
+  SELECT fee_rate::double precision AS rate
+  FROM public.mempool_txs
+  WHERE mined_height IS NULL AND fee_rate IS NOT NULL
+), recent AS (
+  SELECT fee_nanos::double precision / size_bytes AS rate
+  FROM public.txs
+  WHERE block_height > (SELECT height FROM public.current_tip WHERE id = 1) - $1
+    AND fee_nanos > 0 AND size_bytes > 0
+)
+SELECT (SELECT COUNT(*) FROM mempool) AS "mempool_count!",
+       (SELECT percentile_cont(ARRAY[0.1, 0.25, 0.5, 0.75, 0.9]) WITHIN GROUP (ORDER BY rate)
+          FROM mempool) AS mempool_percentiles,
+       (SELECT COUNT(*) FROM recent) AS "recent_count!",
+       (SELECT percentile_cont(ARRAY[0.1, 0.25, 0.5, 0.75, 0.9]) WITHIN GROUP (ORDER BY rate)
+          FROM recent) AS recent_percentiles
+"#,
+        FEE_WINDOW_BLOCKS
+    )
+    .fetch_one(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    match observed {
+        Ok(r) => {
+            let v = models::BaseFeeView {
+                daemon,
+                mempool: models::FeePercentiles::new(r.mempool_count, r.mempool_percentiles),
+                recent_blocks: models::FeePercentiles::new(r.recent_count, r.recent_percentiles),
+                window_blocks: FEE_WINDOW_BLOCKS,
+            };
+            crate::util::cached_json(&st.cache, cache_key, &v, 10).await
         }
+        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
     }
-    if let Ok(h) = s.parse::<i64>() {
-        if sqlx::query_scalar!("SELECT 1 FROM public.blocks WHERE height=$1 LIMIT 1", h)
-            .fetch_optional(&st.db)
+}
+
+/// Upper bound on heights per rct-offsets request; wallets page through longer
+/// ranges.
+const MAX_RCT_OFFSETS_SPAN: i64 = 100_000;
+
+query_params! {
+    #[derive(Deserialize)]
+    pub struct RctOffsetsQuery {
+        pub from_height: Option<i64> => "a non-negative block height",
+        pub to_height: Option<i64> => "a block height, at least `from_height`",
+        pub cumulative: Option<bool> => "`true` or `false`",
+    }
+}
+
+pub async fn get_rct_offsets(
+    State(st): State<AppState>,
+    Query(q): Query<RctOffsetsQuery>,
+) -> Response {
+    let from_height = q.from_height.unwrap_or(0);
+    if from_height < 0 {
+        return crate::query_param::invalid::<RctOffsetsQuery>("from_height", "negative");
+    }
+    let cumulative = q.cumulative.unwrap_or(true);
+
+    let to_height = match q.to_height {
+        Some(h) => h,
+        None => match sqlx::query_scalar!("SELECT MAX(height) FROM public.rct_output_counts")
+            .fetch_one(&st.db)
+            .timed(Phase::Db)
             .await
-            .ok()
-            .flatten()
-            .is_some()
         {
-            return crate::util::json_ok(models::SearchResult {
-                kind: "height".to_owned(),
-                value: serde_json::json!(h),
-            });
+            Ok(Some(h)) => h,
+            Ok(None) => return crate::util::json_err(404, "not found"),
+            Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+        },
+    };
+    if to_height < from_height {
+        return crate::query_param::invalid::<RctOffsetsQuery>("to_height", "below `from_height`");
+    }
+    let span = to_height - from_height + 1;
+    if span > MAX_RCT_OFFSETS_SPAN {
+        return crate::query_param::invalid::<RctOffsetsQuery>(
+            "to_height",
+            format!("range spans more than {MAX_RCT_OFFSETS_SPAN} heights"),
+        );
+    }
+
+    let cache_key = format!("rct-offsets:{from_height}:{to_height}:{cumulative}");
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return resp;
+    }
+
+    let rows = match sqlx::query!(
+        r#"
+SELECT rct_outputs, cumulative
+FROM public.rct_output_counts
+WHERE height BETWEEN $1 AND $2
+ORDER BY height ASC
+"#,
+        from_height,
+        to_height
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    // A gap or an unknown running total means the ingestor has not covered
+    // every height from genesis; a partial answer would skew decoy selection.
+    let totals: Option<Vec<i64>> = rows.iter().map(|r| r.cumulative).collect();
+    let (Some(totals), Some(first)) = (totals.filter(|t| t.len() as i64 == span), rows.first())
+    else {
+        return crate::util::json_err(409, "output distribution incomplete for range");
+    };
+
+    let body = if cumulative {
+        models::RctOffsetsView {
+            amount: 0,
+            start_height: from_height,
+            base: 0,
+            distribution: totals,
         }
-        if sqlx::query_scalar!(
-            "SELECT 1 FROM public.outputs WHERE global_index=$1 LIMIT 1",
-            h
-        )
-        .fetch_optional(&st.db)
-        .await
-        .ok()
-        .flatten()
-        .is_some()
-        {
-            return crate::util::json_ok(models::SearchResult {
-                kind: "global_index".to_owned(),
-                value: serde_json::json!(h),
-            });
+    } else {
+        models::RctOffsetsView {
+            amount: 0,
+            start_height: from_height,
+            base: totals[0] - i64::from(first.rct_outputs),
+            distribution: rows.iter().map(|r| i64::from(r.rct_outputs)).collect(),
+        }
+    };
+
+    crate::util::cached_json(&st.cache, &cache_key, &body, 10).await
+}
+
+/// Matches the ingestor's `LEADERBOARD_SIZE`; rankings are not stored deeper.
+pub const MAX_LEADERBOARD_LIMIT: i64 = 100;
+const LEADERBOARD_TTL_SECS: usize = 60;
+
+/// Ids per `POST /api/v1/blocks/batch` or `/api/v1/txs/batch` request.
+pub const MAX_BATCH_IDS: usize = 100;
+
+#[derive(Deserialize)]
+pub struct BatchRequest {
+    pub ids: Vec<String>,
+}
+
+/// The requested ids, lowercased, or why the request is rejected. Items are
+/// matched on the parsed id, so each is echoed back as sent.
+fn batch_ids(body: Result<Json<BatchRequest>, JsonRejection>) -> Result<Vec<String>, String> {
+    let Json(req) = body.map_err(|e| e.body_text())?;
+    if req.ids.is_empty() {
+        return Err("ids required".to_string());
+    }
+    if req.ids.len() > MAX_BATCH_IDS {
+        return Err(format!("at most {MAX_BATCH_IDS} ids per request"));
+    }
+    Ok(req.ids.iter().map(|id| id.to_ascii_lowercase()).collect())
+}
+
+/// Blocks by height or hash, in request order, each marked found or not.
+pub async fn blocks_batch(
+    State(st): State<AppState>,
+    Query(units): Query<Units>,
+    body: Result<Json<BatchRequest>, JsonRejection>,
+) -> Response {
+    let ids = match batch_ids(body) {
+        Ok(ids) => ids,
+        Err(msg) => return crate::util::json_err(400, &msg),
+    };
+    let mut hashes = Vec::new();
+    let mut heights = Vec::new();
+    // Parsed forms, so `007` finds height 7 and `by_id` keys match.
+    let mut keys = Vec::with_capacity(ids.len());
+    for id in &ids {
+        match BlockId::parse(id) {
+            Ok(BlockId::Hash(hash)) => {
+                hashes.push(hash.bytes().to_vec());
+                keys.push(hash.as_str().to_owned());
+            }
+            Ok(BlockId::Height(height)) if height >= 0 => {
+                heights.push(height);
+                keys.push(height.to_string());
+            }
+            Ok(BlockId::Height(_)) => {
+                return crate::util::json_err(400, &format!("invalid block id {id}"))
+            }
+            Err(err) => {
+                return crate::util::problem(400, "Invalid parameter", &format!("`{id}` {err}"))
+            }
         }
     }
-    crate::util::json_err(404, "no match")
+
+    let rows = fetch_block_views(&st.db, &hashes, &heights, None).await;
+    let rows = match rows {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let by_id: HashMap<String, &models::BlockView> = rows
+        .iter()
+        .flat_map(|b| [(b.hash.to_hex(), b), (b.height.to_string(), b)])
+        .collect();
+    let items: Vec<models::BlockBatchItem> = ids
+        .into_iter()
+        .zip(keys)
+        .map(|(id, key)| {
+            let block = by_id.get(&key).map(|b| {
+                let mut b = (*b).clone();
+                if units.xmr {
+                    b.fill_xmr();
+                }
+                b
+            });
+            models::BlockBatchItem {
+                id,
+                found: block.is_some(),
+                block,
+            }
+        })
+        .collect();
+    json_ok(models::BatchPage { items })
+}
+
+/// Txs by hash, in request order, each marked found or not. Unlike
+/// `/api/v1/tx/:hash`, inputs and outputs are not included.
+pub async fn txs_batch(
+    State(st): State<AppState>,
+    Query(units): Query<Units>,
+    body: Result<Json<BatchRequest>, JsonRejection>,
+) -> Response {
+    let ids = match batch_ids(body) {
+        Ok(ids) => ids,
+        Err(msg) => return crate::util::json_err(400, &msg),
+    };
+    let mut hashes = Vec::with_capacity(ids.len());
+    let mut keys = Vec::with_capacity(ids.len());
+    for id in &ids {
+        match HexParam::<32>::parse(id) {
+            Ok(hash) => {
+                hashes.push(hash.bytes().to_vec());
+                keys.push(hash.as_str().to_owned());
+            }
+            Err(err) => {
+                return crate::util::problem(400, "Invalid parameter", &format!("`{id}`: {err}"))
+            }
+        }
+    }
+
+    let rows = sqlx::query_as!(
+        models::TxView,
+        r#"
+SELECT
+  tx_hash AS "hash: TxHash",
+  block_height,
+  extract(epoch from NULLIF(block_timestamp, 'infinity'))::bigint AS ts,
+  in_mempool,
+  fee_nanos,
+  size_bytes,
+  version,
+  unlock_time,
+  extra::text AS extra_json,
+  rct_type,
+  proof_type,
+  bp_plus,
+  num_inputs,
+  num_outputs,
+  extract(epoch from first_seen)::bigint AS first_seen,
+  nonstandard,
+  nonstandard_reasons,
+  NULL::text AS fee_xmr
+FROM public.txs WHERE tx_hash = ANY($1::bytea[])
+"#,
+        &hashes
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await;
+    let rows = match rows {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let by_id: HashMap<String, &models::TxView> =
+        rows.iter().map(|t| (t.hash.to_hex(), t)).collect();
+    let items: Vec<models::TxBatchItem> = ids
+        .into_iter()
+        .zip(keys)
+        .map(|(id, key)| {
+            let tx = by_id.get(&key).map(|t| {
+                let mut t = (*t).clone();
+                if units.xmr {
+                    t.fill_xmr();
+                }
+                t
+            });
+            models::TxBatchItem {
+                id,
+                found: tx.is_some(),
+                tx,
+            }
+        })
+        .collect();
+    json_ok(models::BatchPage { items })
+}
+
+query_params! {
+    #[derive(Deserialize)]
+    pub struct LeaderboardQuery {
+        pub by: Option<String> => "one of the orderings listed for the endpoint",
+        pub limit: Option<i64> => "an integer, clamped to 1..=100",
+    }
+}
+
+pub async fn get_top_blocks(
+    State(st): State<AppState>,
+    Query(q): Query<LeaderboardQuery>,
+) -> Response {
+    let by = q.by.as_deref().unwrap_or("tx_count");
+    if !matches!(by, "tx_count" | "size") {
+        return crate::query_param::invalid::<LeaderboardQuery>(
+            "by",
+            format!("`{by}` is not one of tx_count, size"),
+        );
+    }
+    let limit = q.limit.unwrap_or(20).clamp(1, MAX_LEADERBOARD_LIMIT);
+
+    let cache_key = format!("top-blocks:{by}:{limit}");
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return resp;
+    }
+
+    // Entries whose block was since reorged out are dropped until the next
+    // rebuild rather than served with a stale hash.
+    let entries = match sqlx::query_as!(
+        models::TopBlockEntry,
+        r#"
+SELECT t.rank, t.height, t.hash AS "hash: BlockHash", t.value
+FROM public.top_blocks t
+JOIN public.blocks b ON b.height = t.height AND b.hash = t.hash
+WHERE t.metric = $1
+ORDER BY t.rank ASC
+LIMIT $2
+"#,
+        by,
+        limit
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+    let computed_at = match sqlx::query_scalar!(
+        "SELECT EXTRACT(EPOCH FROM MAX(computed_at))::bigint FROM public.top_blocks WHERE metric = $1",
+        by
+    )
+    .fetch_one(&st.db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let body = models::LeaderboardView {
+        by: by.to_owned(),
+        computed_at,
+        entries,
+    };
+    crate::util::cached_json(&st.cache, &cache_key, &body, LEADERBOARD_TTL_SECS).await
+}
+
+pub const MAX_MINING_DAYS: i64 = 365;
+
+query_params! {
+    #[derive(Deserialize)]
+    pub struct MiningStatsQuery {
+        pub days: Option<i64> => "an integer, clamped to 1..=365",
+    }
+}
+
+/// Per-day nonce distribution and solve-time residuals, newest day first.
+pub async fn get_mining_stats(
+    State(st): State<AppState>,
+    Query(q): Query<MiningStatsQuery>,
+) -> Response {
+    let days = q.days.unwrap_or(30).clamp(1, MAX_MINING_DAYS);
+
+    let cache_key = format!("stats-mining:{days}");
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return resp;
+    }
+
+    let rows = match sqlx::query!(
+        r#"
+SELECT EXTRACT(EPOCH FROM day::timestamp)::bigint AS "day!",
+       blocks,
+       nonce_buckets,
+       nonce_chi2,
+       avg_difficulty,
+       implied_hashrate,
+       mean_solve_secs,
+       residual_mean_abs_secs,
+       residual_stddev_secs,
+       EXTRACT(EPOCH FROM computed_at)::bigint AS "computed_at!"
+FROM public.mining_daily
+ORDER BY day DESC
+LIMIT $1
+"#,
+        days
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let body = models::MiningStatsView {
+        computed_at: rows.iter().map(|r| r.computed_at).max(),
+        days: rows
+            .into_iter()
+            .map(|r| models::MiningDayView {
+                day: r.day,
+                blocks: r.blocks,
+                nonce_buckets: r.nonce_buckets,
+                nonce_chi2: r.nonce_chi2,
+                avg_difficulty: r.avg_difficulty,
+                implied_hashrate: r.implied_hashrate,
+                mean_solve_secs: r.mean_solve_secs,
+                residual_mean_abs_secs: r.residual_mean_abs_secs,
+                residual_stddev_secs: r.residual_stddev_secs,
+            })
+            .collect(),
+    };
+    crate::util::cached_json(&st.cache, &cache_key, &body, LEADERBOARD_TTL_SECS).await
+}
+
+pub async fn get_top_outputs(
+    State(st): State<AppState>,
+    Query(q): Query<LeaderboardQuery>,
+) -> Response {
+    let by = q.by.as_deref().unwrap_or("ring_references");
+    if by != "ring_references" {
+        return crate::query_param::invalid::<LeaderboardQuery>(
+            "by",
+            format!("`{by}` is not ring_references"),
+        );
+    }
+    let limit = q.limit.unwrap_or(20).clamp(1, MAX_LEADERBOARD_LIMIT);
+
+    let cache_key = format!("top-outputs:{by}:{limit}");
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return resp;
+    }
+
+    let entries = match sqlx::query_as!(
+        models::TopOutputEntry,
+        r#"
+SELECT rank, global_index, tx_hash AS "tx_hash: TxHash", value
+FROM public.top_outputs
+WHERE metric = $1
+ORDER BY rank ASC
+LIMIT $2
+"#,
+        by,
+        limit
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+    let computed_at = match sqlx::query_scalar!(
+        "SELECT EXTRACT(EPOCH FROM MAX(computed_at))::bigint FROM public.top_outputs WHERE metric = $1",
+        by
+    )
+    .fetch_one(&st.db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let body = models::LeaderboardView {
+        by: by.to_owned(),
+        computed_at,
+        entries,
+    };
+    crate::util::cached_json(&st.cache, &cache_key, &body, LEADERBOARD_TTL_SECS).await
+}
+
+pub async fn get_tx_rings(State(st): State<AppState>, hash: HexParam<32>) -> Response {
+    let cache_key = format!("rings:{hash}");
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return resp;
+    }
+
+    let rows = sqlx::query_as!(
+        models::RingView,
+        r#"
+SELECT
+  r.tx_hash AS "tx_hash: TxHash",
+  r.input_idx,
+  r.ring_index,
+  o.global_index
+FROM public.rings r
+LEFT JOIN public.outputs o ON o.output_id = r.referenced_output_id
+WHERE r.tx_hash = decode($1,'hex')
+ORDER BY r.input_idx ASC, r.ring_index ASC
+"#,
+        hash.as_str()
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    let rows = match rows {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let mut grouped: BTreeMap<i32, Vec<models::RingMemberView>> = BTreeMap::new();
+    for row in rows {
+        grouped
+            .entry(row.input_idx)
+            .or_default()
+            .push(models::RingMemberView {
+                ring_index: row.ring_index,
+                global_index: row.global_index,
+            });
+    }
+
+    let rings: Vec<models::RingSetView> = grouped
+        .into_iter()
+        .map(|(input_idx, mut members)| {
+            members.sort_by_key(|m| m.ring_index);
+            models::RingSetView { input_idx, members }
+        })
+        .collect();
+
+    crate::util::cached_json(&st.cache, &cache_key, &rings, 60).await
+}
+
+const MAX_RING_USES_LIMIT: i64 = 500;
+
+query_params! {
+    #[derive(Deserialize)]
+    pub struct RingUseQuery {
+        pub from_height: Option<i64> => "a non-negative block height",
+        pub to_height: Option<i64> => "a block height, at least `from_height`",
+        pub cursor: Option<String> => "a `next_cursor` string",
+        pub limit: Option<i64> => "an integer, clamped to 1..=500",
+    }
+}
+
+/// Position after the last ring slot of a page, in `(block_height, tx_hash,
+/// input_idx)` order, tied to the output it was issued for.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct RingUseCursor {
+    global_index: i64,
+    height: i64,
+    tx_hash: TxHash,
+    input_idx: i32,
+}
+
+impl Cursor for RingUseCursor {
+    const KIND: &'static str = "ring";
+}
+
+/// Txs whose rings reference the output with this global index, within an
+/// optional `[from_height, to_height]` window of the referencing tx's block.
+pub async fn get_rings_by_output(
+    State(st): State<AppState>,
+    Path(global_index): Path<i64>,
+    Query(q): Query<RingUseQuery>,
+) -> Response {
+    if global_index < 0 {
+        return crate::util::invalid_param(
+            "global_index",
+            "a non-negative output index",
+            "negative",
+        );
+    }
+    let from_height = q.from_height.unwrap_or(0);
+    let to_height = q.to_height.unwrap_or(i64::MAX);
+    if from_height < 0 {
+        return crate::query_param::invalid::<RingUseQuery>("from_height", "negative");
+    }
+    if to_height < from_height {
+        return crate::query_param::invalid::<RingUseQuery>("to_height", "below `from_height`");
+    }
+    let limit = q.limit.unwrap_or(100).clamp(1, MAX_RING_USES_LIMIT);
+    let after = match q.cursor.as_deref() {
+        Some(c) => match RingUseCursor::decode(c).filter(|c| c.global_index == global_index) {
+            Some(v) => Some(v),
+            None => {
+                return crate::query_param::invalid::<RingUseQuery>(
+                    "cursor",
+                    "not a cursor for this output",
+                )
+            }
+        },
+        None => None,
+    };
+
+    let cache_key = format!(
+        "rings:by-output:{global_index}:{from_height}:{to_height}:{limit}:{}",
+        q.cursor.as_deref().unwrap_or("first")
+    );
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return resp;
+    }
+
+    let output_id = match sqlx::query_scalar!(
+        "SELECT output_id FROM public.outputs WHERE global_index = $1",
+        global_index
+    )
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
+    .await
+    {
+        Ok(Some(id)) => id,
+        Ok(None) => return crate::util::json_err(404, "output not found"),
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+
+    let rows = sqlx::query_as!(
+        models::RingUseView,
+        r#"
+SELECT r.tx_hash AS "tx_hash: TxHash",
+       t.block_height AS "block_height!",
+       r.input_idx,
+       r.ring_index
+FROM public.rings r
+JOIN public.tx_inputs ti ON ti.tx_hash = r.tx_hash AND ti.idx = r.input_idx
+JOIN public.txs t ON t.block_timestamp = ti.tx_block_timestamp AND t.tx_hash = r.tx_hash
+WHERE r.referenced_output_id = $1
+  AND t.block_height BETWEEN $2 AND $3
+  AND ($4::bigint IS NULL
+       OR (t.block_height, r.tx_hash, r.input_idx) > ($4, $5, $6))
+ORDER BY t.block_height ASC, r.tx_hash ASC, r.input_idx ASC
+LIMIT $7
+"#,
+        output_id,
+        from_height,
+        to_height,
+        after.map(|c| c.height),
+        after.as_ref().map(|c| c.tx_hash.0.as_slice()),
+        after.map(|c| c.input_idx),
+        limit + 1
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    let mut items = match rows {
+        Ok(v) => v,
+        Err(e) => return crate::util::json_err(500, &format!("db error: {e}")),
+    };
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(|r| {
+            RingUseCursor {
+                global_index,
+                height: r.block_height,
+                tx_hash: r.tx_hash,
+                input_idx: r.input_idx,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+    let page = models::RingUsePage {
+        global_index,
+        items,
+        next_cursor,
+    };
+    crate::util::cached_json(&st.cache, &cache_key, &page, 10).await
+}
+
+pub async fn get_key_image(State(st): State<AppState>, hex: HexParam<32>) -> Response {
+    let cache_key = format!("ki:{hex}");
+    if let Some(resp) = crate::util::cached_response(&st.cache, &cache_key).await {
+        return resp;
+    }
+
+    let row = sqlx::query_as!(
+        models::KeyImageView,
+        r#"
+SELECT
+  ti.key_image AS "key_image: KeyImage",
+  t.tx_hash AS "spending_tx: TxHash",
+  t.block_height
+FROM public.tx_inputs ti
+JOIN public.txs t ON t.tx_hash = ti.tx_hash
+WHERE ti.key_image = decode($1,'hex')
+ORDER BY t.block_height DESC NULLS LAST, t.tx_hash ASC
+LIMIT 1
+"#,
+        hex.as_str()
+    )
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    match row {
+        Ok(Some(v)) => crate::util::cached_json(&st.cache, &cache_key, &v, 120).await,
+        Ok(None) => crate::util::json_err(404, "not found"),
+        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
+    }
+}
+
+query_params! {
+    #[derive(Deserialize)]
+    pub struct Q {
+        pub q: String => "a block height, output index or 8 to 64 character hex hash prefix",
+    }
+}
+
+/// Shortest hex string treated as a hash prefix; anything shorter matches too
+/// many rows to be useful.
+pub const MIN_SEARCH_PREFIX: usize = 8;
+
+pub async fn search(
+    State(st): State<AppState>,
+    addr: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Query(Q { q }): Query<Q>,
+) -> Response {
+    let Some(crate::search::Terms { prefix, number }) = crate::search::parse(&q) else {
+        return crate::query_param::invalid::<Q>("q", "not a height, index or hash prefix");
+    };
+    // Per client IP, whether or not an API key is sent.
+    let caller = match &addr {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => crate::ratelimit::caller(&st.api_keys, &headers, None),
+    };
+    if let Err(quota) = st.search.hit(&caller) {
+        return crate::ratelimit::too_many_requests(&quota);
+    }
+    let q = q.trim().to_ascii_lowercase();
+    if st.search.known_miss(&q) {
+        return crate::util::json_err(404, "no match");
+    }
+
+    // One round trip: each branch stops at its first hit (the lowest hash for
+    // prefixes, so an ambiguous prefix always resolves the same way) and the
+    // rank keeps the old precedence (numbers before hashes, then tx, block,
+    // key image).
+    let hit = sqlx::query!(
+        r#"
+SELECT m.kind AS "kind!", m.hash, m.num
+FROM (
+  (SELECT 'height' AS kind, 1 AS rank, NULL::text AS hash, height AS num
+   FROM public.blocks WHERE height = $2 LIMIT 1)
+  UNION ALL
+  (SELECT 'global_index', 2, NULL, global_index
+   FROM public.outputs WHERE global_index = $2 LIMIT 1)
+  UNION ALL
+  (SELECT 'tx', 3, encode(tx_hash, 'hex'), NULL
+   FROM public.txs WHERE encode(tx_hash, 'hex') LIKE $1 || '%' ORDER BY 3 LIMIT 1)
+  UNION ALL
+  (SELECT 'block', 4, encode(hash, 'hex'), NULL
+   FROM public.blocks WHERE encode(hash, 'hex') LIKE $1 || '%' ORDER BY 3 LIMIT 1)
+  UNION ALL
+  (SELECT 'key_image', 5, encode(key_image, 'hex'), NULL
+   FROM public.tx_inputs WHERE encode(key_image, 'hex') LIKE $1 || '%' ORDER BY 3 LIMIT 1)
+) m
+ORDER BY m.rank
+LIMIT 1
+"#,
+        prefix,
+        number
+    )
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    match hit {
+        Ok(Some(row)) => {
+            let value = match (row.hash, row.num) {
+                (Some(hash), _) => serde_json::Value::String(hash),
+                (None, Some(num)) => serde_json::json!(num),
+                (None, None) => return crate::util::json_err(404, "no match"),
+            };
+            crate::util::json_ok(models::SearchResult {
+                kind: row.kind,
+                value,
+            })
+        }
+        Ok(None) => {
+            st.search.record_miss(&q);
+            crate::util::json_err(404, "no match")
+        }
+        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
+    }
+}
+
+/// Checks `Authorization: Bearer <ADMIN_TOKEN>`; returns the error response to
+/// send when the caller is not an operator.
+fn require_admin(st: &AppState, headers: &HeaderMap) -> Option<Response> {
+    let Some(expected) = st.admin_token.as_deref() else {
+        return Some(crate::util::json_err(404, "not found"));
+    };
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match provided {
+        Some(token) if crate::util::constant_time_eq(token.as_bytes(), expected.as_bytes()) => None,
+        _ => Some(crate::util::json_err(401, "unauthorized")),
+    }
+}
+
+pub async fn admin_reingest(
+    State(st): State<AppState>,
+    headers: HeaderMap,
+    Path(height): Path<i64>,
+) -> Response {
+    if let Some(resp) = require_admin(&st, &headers) {
+        return resp;
+    }
+    if height < 0 {
+        return crate::util::invalid_param("height", "a non-negative block height", "negative");
+    }
+
+    // Re-posting a queued or running height is a no-op; finished requests are
+    // queued again.
+    let row = sqlx::query_as!(
+        models::ReingestView,
+        r#"
+INSERT INTO public.reingest_requests (height, requested_by)
+VALUES ($1, 'api')
+ON CONFLICT (height) DO UPDATE
+  SET status = 'queued', last_error = NULL, requested_at = NOW(), updated_at = NOW()
+  WHERE public.reingest_requests.status IN ('done', 'failed')
+RETURNING height, status, extract(epoch from requested_at)::bigint AS requested_at
+"#,
+        height
+    )
+    .fetch_optional(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    let row = match row {
+        Ok(Some(v)) => Ok(v),
+        Ok(None) => {
+            sqlx::query_as!(
+                models::ReingestView,
+                r#"
+SELECT height, status, extract(epoch from requested_at)::bigint AS requested_at
+FROM public.reingest_requests WHERE height = $1
+"#,
+                height
+            )
+            .fetch_one(&st.db)
+            .timed(Phase::Db)
+            .await
+        }
+        Err(e) => Err(e),
+    };
+
+    match row {
+        Ok(v) => crate::util::json_with_status(202, &v),
+        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
+    }
+}
+
+query_params! {
+    #[derive(Deserialize)]
+    pub struct UsageQuery {
+        pub since: Option<i64> => "a unix timestamp in seconds",
+        pub granularity: Option<String> => "one of minute, hour, day",
+        pub key: Option<String> => "an API key name",
+        pub route: Option<String> => "a route path",
+    }
+}
+
+pub async fn admin_usage(
+    State(st): State<AppState>,
+    headers: HeaderMap,
+    Query(q): Query<UsageQuery>,
+) -> Response {
+    if let Some(resp) = require_admin(&st, &headers) {
+        return resp;
+    }
+    let granularity = q.granularity.as_deref().unwrap_or("hour");
+    if !matches!(granularity, "minute" | "hour" | "day") {
+        return crate::query_param::invalid::<UsageQuery>(
+            "granularity",
+            format!("unknown granularity `{granularity}`"),
+        );
+    }
+
+    // Counters land in Postgres once a minute closes, so the current minute
+    // is never included.
+    let rows = sqlx::query_as!(
+        models::UsageView,
+        r#"
+SELECT extract(epoch from date_trunc($1, bucket))::bigint AS "bucket!",
+       api_key,
+       route,
+       SUM(requests)::bigint AS "requests!",
+       SUM(errors)::bigint AS "errors!",
+       SUM(rate_limited)::bigint AS "rate_limited!",
+       COALESCE(SUM(errors)::float8 / NULLIF(SUM(requests), 0), 0) AS "error_rate!"
+FROM public.api_usage
+WHERE bucket >= COALESCE(to_timestamp($2::bigint), NOW() - interval '24 hours')
+  AND ($3::text IS NULL OR api_key = $3)
+  AND ($4::text IS NULL OR route = $4)
+GROUP BY 1, api_key, route
+ORDER BY 1 DESC, SUM(requests) DESC, api_key ASC NULLS FIRST, route ASC
+LIMIT 10000
+"#,
+        granularity,
+        q.since,
+        q.key,
+        q.route
+    )
+    .fetch_all(&st.db)
+    .timed(Phase::Db)
+    .await;
+
+    match rows {
+        Ok(v) => crate::util::json_ok(v),
+        Err(e) => crate::util::json_err(500, &format!("db error: {e}")),
+    }
+}
+
+/// The caller's quota as of this request, which is already counted.
+pub async fn get_limits(
+    State(st): State<AppState>,
+    headers: HeaderMap,
+    addr: Option<ConnectInfo<SocketAddr>>,
+) -> Response {
+    let caller = crate::ratelimit::caller(&st.api_keys, &headers, addr.as_ref());
+    json_ok(st.limiter.peek(&caller))
 }
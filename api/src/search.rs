@@ -0,0 +1,106 @@
+//! Abuse protection for `/api/v1/search`. A search runs several index probes
+//! and its results are not cached, so it gets a stricter per-IP quota than
+//! the global one, malformed queries are rejected before touching the
+//! database, and recent misses are remembered briefly so a scanner repeating
+//! them gets its 404 from memory.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
+
+use crate::ratelimit::{Quota, RateLimiter};
+
+pub const DEFAULT_PER_MINUTE: u64 = 120;
+/// How long a miss is answered from memory. Short, so a block or tx that
+/// lands meanwhile is found soon after.
+const MISS_TTL: Duration = Duration::from_secs(10);
+/// Misses remembered at once; the oldest are dropped first.
+const MISS_CAPACITY: usize = 4096;
+
+/// A block height or output index, or an 8 to 64 digit hex hash prefix.
+static VALID_QUERY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:[0-9]{1,19}|[0-9a-fA-F]{8,64})$").expect("search regex"));
+
+/// What a search may match once the query passed [`parse`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct Terms {
+    /// Lowercase hex prefix of a tx, block or key image hash.
+    pub prefix: Option<String>,
+    /// Block height or output global index.
+    pub number: Option<i64>,
+}
+
+/// `None` for queries no stored value can match.
+pub fn parse(q: &str) -> Option<Terms> {
+    let q = q.trim();
+    if !VALID_QUERY.is_match(q) {
+        return None;
+    }
+    let prefix = (q.len() >= crate::routes::MIN_SEARCH_PREFIX).then(|| q.to_ascii_lowercase());
+    let number = q.parse::<i64>().ok();
+    (prefix.is_some() || number.is_some()).then_some(Terms { prefix, number })
+}
+
+#[derive(Clone)]
+pub struct SearchGuard {
+    limiter: RateLimiter,
+    misses: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl Default for SearchGuard {
+    fn default() -> Self {
+        Self::new(DEFAULT_PER_MINUTE)
+    }
+}
+
+impl SearchGuard {
+    pub fn new(per_minute: u64) -> Self {
+        Self {
+            limiter: RateLimiter::new(per_minute),
+            misses: Default::default(),
+        }
+    }
+
+    /// A guard for another network: it draws on the same quota, but keeps
+    /// misses of its own since a miss on one network says nothing about
+    /// another.
+    pub fn sharing_quota(&self) -> Self {
+        Self {
+            limiter: self.limiter.clone(),
+            misses: Default::default(),
+        }
+    }
+
+    /// Counts one search against `caller`'s quota; see [`RateLimiter::hit`].
+    pub fn hit(&self, caller: &str) -> Result<Quota, Quota> {
+        self.limiter.hit(caller)
+    }
+
+    /// Whether `q` missed within the last few seconds.
+    pub fn known_miss(&self, q: &str) -> bool {
+        let misses = self.misses.lock().unwrap_or_else(|e| e.into_inner());
+        misses.get(q).is_some_and(|at| at.elapsed() < MISS_TTL)
+    }
+
+    pub fn record_miss(&self, q: &str) {
+        let now = Instant::now();
+        let mut misses = self.misses.lock().unwrap_or_else(|e| e.into_inner());
+        if misses.len() >= MISS_CAPACITY {
+            misses.retain(|_, at| now.duration_since(*at) < MISS_TTL);
+            if misses.len() >= MISS_CAPACITY {
+                if let Some(oldest) = misses
+                    .iter()
+                    .min_by_key(|(_, at)| **at)
+                    .map(|(q, _)| q.clone())
+                {
+                    misses.remove(&oldest);
+                }
+            }
+        }
+        misses.insert(q.to_owned(), now);
+    }
+}
@@ -1,8 +1,29 @@
+use std::sync::Arc;
+
 use redis::aio::ConnectionManager;
 use sqlx::PgPool;
 
+use crate::{
+    daemon::DaemonHealth, docs::ApiDocs, ratelimit::RateLimiter, search::SearchGuard,
+    usage::ApiKeys, util::SingleFlight,
+};
+
 #[derive(Clone)]
 pub struct AppState {
     pub db: PgPool,
     pub cache: ConnectionManager,
+    /// Bearer token for `/api/v1/admin/*`; admin routes are disabled when unset.
+    pub admin_token: Option<Arc<str>>,
+    /// Coalesces concurrent cache misses on hot keys (`block:*`, `tx:*`).
+    pub flights: SingleFlight,
+    /// Per-caller request quotas, enforced by [`crate::ratelimit::enforce`].
+    pub limiter: RateLimiter,
+    /// `API_KEYS`; only these identify a caller by `X-API-Key`.
+    pub api_keys: ApiKeys,
+    /// Last probe of `DAEMON_URL`, refreshed by [`crate::daemon::spawn_prober`].
+    pub daemon: DaemonHealth,
+    /// The OpenAPI document, parsed once at startup.
+    pub docs: ApiDocs,
+    /// Stricter per-IP quota and recent misses for `/api/v1/search`.
+    pub search: SearchGuard,
 }
@@ -0,0 +1,107 @@
+//! `Server-Timing` breakdown of each request, enabled with `SERVER_TIMING`.
+//! Handlers and the cache helpers attribute their awaits to a phase; the
+//! middleware sums them per request and reports
+//! `db;dur=…, cache;dur=…, serialize;dur=…, total;dur=…` in milliseconds, the
+//! format browser devtools and OpenTelemetry's browser instrumentation read.
+//! Outside the middleware (flag off, background tasks) recording is a no-op.
+
+use std::{
+    cell::RefCell,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+pub const SERVER_TIMING_HEADER: HeaderName = HeaderName::from_static("server-timing");
+
+#[derive(Debug, Clone, Copy)]
+pub enum Phase {
+    Db,
+    Cache,
+    Serialize,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Phases {
+    db: Duration,
+    cache: Duration,
+    serialize: Duration,
+}
+
+tokio::task_local! {
+    static PHASES: RefCell<Phases>;
+}
+
+/// Adds `elapsed` to `phase` for the current request.
+pub fn record(phase: Phase, elapsed: Duration) {
+    let _ = PHASES.try_with(|phases| {
+        let mut phases = phases.borrow_mut();
+        let slot = match phase {
+            Phase::Db => &mut phases.db,
+            Phase::Cache => &mut phases.cache,
+            Phase::Serialize => &mut phases.serialize,
+        };
+        *slot += elapsed;
+    });
+}
+
+/// Runs `f`, attributing its duration to `phase`.
+pub fn measure<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let out = f();
+    record(phase, start.elapsed());
+    out
+}
+
+async fn measure_async<F: Future>(phase: Phase, fut: F) -> F::Output {
+    let start = Instant::now();
+    let out = fut.await;
+    record(phase, start.elapsed());
+    out
+}
+
+/// `.timed(Phase::Db)` on a query future attributes the time spent awaiting
+/// it to that phase.
+pub trait Timed: Future + Sized {
+    fn timed(self, phase: Phase) -> impl Future<Output = Self::Output>;
+}
+
+impl<F: Future> Timed for F {
+    fn timed(self, phase: Phase) -> impl Future<Output = Self::Output> {
+        measure_async(phase, self)
+    }
+}
+
+/// Collects the phases of the wrapped request and stamps `Server-Timing` on
+/// its response.
+pub async fn server_timing(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let (mut res, phases) = PHASES
+        .scope(RefCell::default(), async {
+            let res = next.run(req).await;
+            (res, PHASES.with(|phases| *phases.borrow()))
+        })
+        .await;
+    let value = header_value(&phases, start.elapsed());
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        res.headers_mut().append(SERVER_TIMING_HEADER, value);
+    }
+    res
+}
+
+fn header_value(phases: &Phases, total: Duration) -> String {
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    format!(
+        "db;dur={:.3}, cache;dur={:.3}, serialize;dur={:.3}, total;dur={:.3}",
+        ms(phases.db),
+        ms(phases.cache),
+        ms(phases.serialize),
+        ms(total)
+    )
+}
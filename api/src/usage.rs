@@ -0,0 +1,338 @@
+//! Per-key, per-route request counters. Requests increment per-minute Redis
+//! hashes; a background task folds closed minutes into `public.api_usage`,
+//! which backs `/api/v1/admin/usage`, and drops buckets older than
+//! `USAGE_RETENTION_DAYS`. Only keys listed in `API_KEYS` get counters of
+//! their own; every other request counts as `anonymous`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use redis::aio::ConnectionManager;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::state::AppState;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+pub const ANONYMOUS: &str = "anonymous";
+
+const BUCKET_SECS: i64 = 60;
+const BUCKETS_KEY: &str = "usage:buckets";
+/// Unflushed counters expire after a day so a stopped flusher cannot grow
+/// Redis without bound.
+const BUCKET_TTL_SECS: i64 = 86_400;
+
+/// Expired buckets are deleted at most this often.
+const PRUNE_EVERY: Duration = Duration::from_secs(3600);
+
+/// Records one request against the caller's key and the matched route.
+pub async fn track(State(st): State<AppState>, req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |p| p.as_str().to_string());
+    let api_key = st
+        .api_keys
+        .authenticate(req.headers())
+        .unwrap_or_else(|| ANONYMOUS.to_string());
+
+    let res = next.run(req).await;
+
+    let status = res.status();
+    let cache = st.cache.clone();
+    tokio::spawn(async move {
+        if let Err(err) = record(&cache, &api_key, &route, status).await {
+            warn!(error = %err, "failed to record api usage");
+        }
+    });
+    res
+}
+
+fn api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+}
+
+/// Short, stable identifier for a key. Only a hash prefix is kept so
+/// counters never expose the key itself.
+fn id_of(digest: &[u8]) -> String {
+    hex::encode(&digest[..8])
+}
+
+/// The keys accepted in `X-API-Key` (`API_KEYS`), held as SHA-256 digests.
+/// Any other key is treated like no key at all.
+#[derive(Clone, Default)]
+pub struct ApiKeys(Arc<HashSet<[u8; 32]>>);
+
+impl ApiKeys {
+    pub fn new<S: AsRef<str>>(keys: impl IntoIterator<Item = S>) -> Self {
+        Self(Arc::new(
+            keys.into_iter()
+                .map(|key| key.as_ref().trim().to_owned())
+                .filter(|key| !key.is_empty())
+                .map(|key| Sha256::digest(key.as_bytes()).into())
+                .collect(),
+        ))
+    }
+
+    /// A hash prefix identifying the request's API key, if it is a
+    /// configured one.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Option<String> {
+        let digest: [u8; 32] = Sha256::digest(api_key(headers)?.as_bytes()).into();
+        self.0.contains(&digest).then(|| id_of(&digest))
+    }
+}
+
+async fn record(
+    cache: &ConnectionManager,
+    api_key: &str,
+    route: &str,
+    status: StatusCode,
+) -> Result<()> {
+    let bucket = current_bucket();
+    let hash = bucket_key(bucket);
+    let mut pipe = redis::pipe();
+    pipe.cmd("HINCRBY")
+        .arg(&hash)
+        .arg(field(api_key, route, "requests"))
+        .arg(1)
+        .ignore();
+    if status.is_client_error() || status.is_server_error() {
+        pipe.cmd("HINCRBY")
+            .arg(&hash)
+            .arg(field(api_key, route, "errors"))
+            .arg(1)
+            .ignore();
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        pipe.cmd("HINCRBY")
+            .arg(&hash)
+            .arg(field(api_key, route, "rate_limited"))
+            .arg(1)
+            .ignore();
+    }
+    pipe.cmd("EXPIRE")
+        .arg(&hash)
+        .arg(BUCKET_TTL_SECS)
+        .ignore()
+        .cmd("SADD")
+        .arg(BUCKETS_KEY)
+        .arg(bucket)
+        .ignore();
+
+    let mut conn = cache.clone();
+    pipe.query_async::<_, ()>(&mut conn).await?;
+    Ok(())
+}
+
+/// Per-(key, route) totals for one bucket.
+#[derive(Default)]
+struct Counters {
+    requests: i64,
+    errors: i64,
+    rate_limited: i64,
+}
+
+/// Moves every closed bucket from Redis into Postgres. Returns the number of
+/// (bucket, key, route) rows written.
+pub async fn flush_once(db: &PgPool, cache: &ConnectionManager) -> Result<usize> {
+    let mut conn = cache.clone();
+    let current = current_bucket();
+    let buckets: Vec<i64> = redis::cmd("SMEMBERS")
+        .arg(BUCKETS_KEY)
+        .query_async(&mut conn)
+        .await?;
+
+    let mut written = 0;
+    for bucket in buckets.into_iter().filter(|b| *b < current) {
+        // Drop the bucket from the set before renaming: a straggling request
+        // re-adds it and recreates the hash, which the next pass picks up.
+        redis::cmd("SREM")
+            .arg(BUCKETS_KEY)
+            .arg(bucket)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        let live = bucket_key(bucket);
+        let staged = format!("{live}:flush");
+        // A pass that failed to store left its staged hash behind; write it
+        // out before the rename below replaces it.
+        written += flush_staged(db, &mut conn, bucket, &staged).await?;
+        let renamed: redis::RedisResult<()> = redis::cmd("RENAME")
+            .arg(&live)
+            .arg(&staged)
+            .query_async(&mut conn)
+            .await;
+        if renamed.is_err() {
+            // Nothing recorded since the last flush (or it expired).
+            continue;
+        }
+        written += flush_staged(db, &mut conn, bucket, &staged).await?;
+    }
+    Ok(written)
+}
+
+/// Writes the staged hash of `bucket` to Postgres and deletes it. If the
+/// write fails the hash is kept and the bucket returned to the set, so the
+/// next pass retries it.
+async fn flush_staged(
+    db: &PgPool,
+    conn: &mut ConnectionManager,
+    bucket: i64,
+    staged: &str,
+) -> Result<usize> {
+    let fields: HashMap<String, i64> = redis::cmd("HGETALL").arg(staged).query_async(conn).await?;
+    if fields.is_empty() {
+        return Ok(0);
+    }
+    let rows = parse_fields(fields);
+    if let Err(err) = store_bucket(db, bucket, &rows).await {
+        redis::cmd("SADD")
+            .arg(BUCKETS_KEY)
+            .arg(bucket)
+            .query_async::<_, ()>(conn)
+            .await?;
+        return Err(err);
+    }
+    redis::cmd("DEL")
+        .arg(staged)
+        .query_async::<_, ()>(conn)
+        .await?;
+    Ok(rows.len())
+}
+
+/// Flushes every network's Redis into its Postgres every `interval`; with a
+/// `retention`, also deletes older buckets once an hour.
+pub fn spawn_flusher(
+    networks: Vec<(PgPool, ConnectionManager)>,
+    interval: Duration,
+    retention: Option<Duration>,
+) {
+    tokio::spawn(async move {
+        let mut last_prune: Option<Instant> = None;
+        loop {
+            tokio::time::sleep(interval).await;
+            for (db, cache) in &networks {
+                if let Err(err) = flush_once(db, cache).await {
+                    warn!(error = %err, "api usage flush failed");
+                }
+            }
+            let Some(retention) = retention else {
+                continue;
+            };
+            if last_prune.is_some_and(|at| at.elapsed() < PRUNE_EVERY) {
+                continue;
+            }
+            last_prune = Some(Instant::now());
+            for (db, _) in &networks {
+                if let Err(err) = prune_once(db, retention).await {
+                    warn!(error = %err, "api usage prune failed");
+                }
+            }
+        }
+    });
+}
+
+/// Deletes buckets that started more than `retention` ago. Returns the
+/// number of rows removed.
+pub async fn prune_once(db: &PgPool, retention: Duration) -> Result<u64> {
+    let res = sqlx::query!(
+        "DELETE FROM public.api_usage WHERE bucket < NOW() - make_interval(secs => $1)",
+        retention.as_secs_f64()
+    )
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+async fn store_bucket(
+    db: &PgPool,
+    bucket: i64,
+    rows: &HashMap<(String, String), Counters>,
+) -> Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let mut keys = Vec::with_capacity(rows.len());
+    let mut routes = Vec::with_capacity(rows.len());
+    let mut requests = Vec::with_capacity(rows.len());
+    let mut errors = Vec::with_capacity(rows.len());
+    let mut rate_limited = Vec::with_capacity(rows.len());
+    for ((key, route), c) in rows {
+        keys.push(key.clone());
+        routes.push(route.clone());
+        requests.push(c.requests);
+        errors.push(c.errors);
+        rate_limited.push(c.rate_limited);
+    }
+
+    sqlx::query!(
+        r#"
+INSERT INTO public.api_usage (bucket, api_key, route, requests, errors, rate_limited)
+SELECT to_timestamp($1::bigint), u.api_key, u.route, u.requests, u.errors, u.rate_limited
+FROM UNNEST($2::text[], $3::text[], $4::bigint[], $5::bigint[], $6::bigint[])
+  AS u(api_key, route, requests, errors, rate_limited)
+ON CONFLICT (bucket, api_key, route) DO UPDATE
+SET requests = public.api_usage.requests + EXCLUDED.requests,
+    errors = public.api_usage.errors + EXCLUDED.errors,
+    rate_limited = public.api_usage.rate_limited + EXCLUDED.rate_limited
+"#,
+        bucket,
+        &keys,
+        &routes,
+        &requests,
+        &errors,
+        &rate_limited
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+fn parse_fields(fields: HashMap<String, i64>) -> HashMap<(String, String), Counters> {
+    let mut rows: HashMap<(String, String), Counters> = HashMap::new();
+    for (name, value) in fields {
+        let mut parts = name.splitn(3, '\t');
+        let (Some(key), Some(route), Some(counter)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let entry = rows
+            .entry((key.to_string(), route.to_string()))
+            .or_default();
+        match counter {
+            "requests" => entry.requests += value,
+            "errors" => entry.errors += value,
+            "rate_limited" => entry.rate_limited += value,
+            _ => {}
+        }
+    }
+    rows
+}
+
+fn field(api_key: &str, route: &str, counter: &str) -> String {
+    format!("{api_key}\t{route}\t{counter}")
+}
+
+fn bucket_key(bucket: i64) -> String {
+    format!("usage:{bucket}")
+}
+
+fn current_bucket() -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    now - now % BUCKET_SECS
+}
@@ -1,68 +1,463 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
 use axum::{
     body::Body,
-    http::{HeaderValue, StatusCode},
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
     response::Response,
 };
 use redis::aio::ConnectionManager;
 use serde::Serialize;
+use serde_json::Value;
 use sha2::{Digest, Sha256};
+use tokio::sync::OwnedMutexGuard;
 use tracing::debug;
 
+use crate::timing::{self, Phase, Timed};
+
 pub fn json_ok<T: Serialize>(data: T) -> Response {
-    let payload = serde_json::to_vec(&data).unwrap();
+    let payload = timing::measure(Phase::Serialize, || serde_json::to_vec(&data).unwrap());
     make_json_response(payload, StatusCode::OK)
 }
 
+pub fn json_with_status<T: Serialize>(code: u16, data: &T) -> Response {
+    let payload = timing::measure(Phase::Serialize, || serde_json::to_vec(data).unwrap());
+    make_json_response(payload, StatusCode::from_u16(code).unwrap())
+}
+
 pub fn json_err(code: u16, msg: &str) -> Response {
     let payload = serde_json::to_vec(&serde_json::json!({"error": msg})).unwrap();
     make_json_response(payload, StatusCode::from_u16(code).unwrap())
 }
 
+/// An RFC 9457 `application/problem+json` error. `error` repeats `detail`
+/// for clients written against the plain `{"error": ...}` shape.
+pub fn problem(code: u16, title: &str, detail: &str) -> Response {
+    problem_with(code, title, detail, serde_json::json!({}))
+}
+
+/// [`problem`] with the members of `extensions` added to the body.
+pub fn problem_with(
+    code: u16,
+    title: &str,
+    detail: &str,
+    extensions: serde_json::Value,
+) -> Response {
+    let status = StatusCode::from_u16(code).unwrap();
+    let mut body = serde_json::json!({
+        "type": "about:blank",
+        "title": title,
+        "status": code,
+        "detail": detail,
+        "error": detail,
+    });
+    if let (Some(body), serde_json::Value::Object(extensions)) = (body.as_object_mut(), extensions)
+    {
+        body.extend(extensions);
+    }
+    let payload = serde_json::to_vec(&body).unwrap();
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/problem+json")
+        .body(Body::from(payload))
+        .unwrap()
+}
+
+/// A 400 problem for a path parameter that parsed but is out of range,
+/// listed in `invalid_params` the way query parameters are.
+pub fn invalid_param(name: &str, expected: &str, reason: &str) -> Response {
+    problem_with(
+        400,
+        "Invalid parameter",
+        &format!("`{name}` must be {expected} ({reason})"),
+        serde_json::json!({
+            "invalid_params": [{ "name": name, "reason": reason, "expected": expected }],
+        }),
+    )
+}
+
 pub async fn cached_json<T: Serialize>(
     cache: &ConnectionManager,
     key: &str,
     data: &T,
     ttl_secs: usize,
 ) -> Response {
-    let payload = serde_json::to_vec(data).unwrap();
+    let entry = store_cached(cache, key, data, ttl_secs).await;
+    json_response_with_etag(entry.payload, &entry.etag, StatusCode::OK)
+}
+
+pub async fn cached_response(cache: &ConnectionManager, key: &str) -> Option<Response> {
+    load_cached(cache, key)
+        .await
+        .map(|entry| json_response_with_etag(entry.payload, &entry.etag, StatusCode::OK))
+}
+
+/// [`cached_json`] for views that accept `?age=true`.
+pub async fn cached_json_aged<T: Serialize>(
+    cache: &ConnectionManager,
+    key: &str,
+    data: &T,
+    ttl_secs: usize,
+    with_age: bool,
+) -> Response {
+    let entry = store_cached(cache, key, data, ttl_secs).await;
+    aged_response(entry, with_age)
+}
+
+/// [`cached_response`] for views that accept `?age=true`.
+pub async fn cached_response_aged(
+    cache: &ConnectionManager,
+    key: &str,
+    with_age: bool,
+) -> Option<Response> {
+    load_cached(cache, key)
+        .await
+        .map(|entry| aged_response(entry, with_age))
+}
+
+/// Marks cache values that carry their ETag ahead of the payload.
+const ETAG_PREFIX: &[u8] = b"etag:";
+
+/// A cached payload with the ETag computed when it was stored, so hits are
+/// served without hashing the payload again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub etag: String,
+    pub payload: Vec<u8>,
+}
+
+impl CacheEntry {
+    pub fn new(payload: Vec<u8>) -> Self {
+        Self {
+            etag: etag_of(&payload),
+            payload,
+        }
+    }
+
+    /// `etag:<hex>\n` followed by the payload.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut value =
+            Vec::with_capacity(ETAG_PREFIX.len() + self.etag.len() + 1 + self.payload.len());
+        value.extend_from_slice(ETAG_PREFIX);
+        value.extend_from_slice(self.etag.as_bytes());
+        value.push(b'\n');
+        value.extend_from_slice(&self.payload);
+        value
+    }
+
+    /// Values written before ETags were stored hold the bare payload and get
+    /// their ETag computed here until they expire.
+    pub fn decode(value: Vec<u8>) -> Self {
+        let stored = value
+            .strip_prefix(ETAG_PREFIX)
+            .and_then(|rest| rest.iter().position(|&b| b == b'\n').map(|end| (rest, end)))
+            .and_then(|(rest, end)| {
+                let etag = std::str::from_utf8(&rest[..end]).ok()?;
+                Some((etag.to_owned(), ETAG_PREFIX.len() + end + 1))
+            });
+        match stored {
+            Some((etag, start)) => Self {
+                etag,
+                payload: value[start..].to_vec(),
+            },
+            None => Self::new(value),
+        }
+    }
+}
+
+async fn store_cached<T: Serialize>(
+    cache: &ConnectionManager,
+    key: &str,
+    data: &T,
+    ttl_secs: usize,
+) -> CacheEntry {
+    let payload = timing::measure(Phase::Serialize, || serde_json::to_vec(data).unwrap());
+    let entry = CacheEntry::new(payload);
     let mut conn = cache.clone();
     let _: Result<(), _> = redis::cmd("SETEX")
         .arg(key)
         .arg(ttl_secs)
-        .arg(&payload)
+        .arg(entry.encode())
         .query_async::<_, ()>(&mut conn)
+        .timed(Phase::Cache)
         .await;
-    make_json_response(payload, StatusCode::OK)
+    entry
 }
 
-pub async fn cached_response(cache: &ConnectionManager, key: &str) -> Option<Response> {
+async fn load_cached(cache: &ConnectionManager, key: &str) -> Option<CacheEntry> {
     let mut conn = cache.clone();
     match redis::cmd("GET")
         .arg(key)
         .query_async::<_, Option<Vec<u8>>>(&mut conn)
+        .timed(Phase::Cache)
         .await
     {
         Ok(Some(bytes)) => {
             debug!(cache_key = key, "cache hit");
-            Some(make_json_response(bytes, StatusCode::OK))
+            Some(CacheEntry::decode(bytes))
         }
         _ => None,
     }
 }
 
+/// How long a 404 from a coalesced lookup is answered from memory. Short, so
+/// a block or tx that arrives is served within seconds.
+const NOT_FOUND_TTL: Duration = Duration::from_secs(5);
+/// 404s remembered at once; the oldest are dropped first.
+const NOT_FOUND_CAPACITY: usize = 10_000;
+
+/// An error answer the owner of a flight hands to the requests queued on it.
+#[derive(Debug, Clone)]
+struct Failure {
+    code: u16,
+    message: String,
+}
+
+type Slot = Arc<tokio::sync::Mutex<Option<Failure>>>;
+
+/// Per-key request coalescing for cache misses. Concurrent requests for the
+/// same key queue behind the first. Once it has filled the cache, the others
+/// find the entry on their second lookup; if it failed instead, they get the
+/// same error without querying Postgres again. A 404 is also remembered for a
+/// few seconds, so repeated lookups of a missing id never reach Postgres.
+#[derive(Clone, Default)]
+pub struct SingleFlight {
+    inflight: Arc<Mutex<HashMap<String, Slot>>>,
+    not_found: Arc<Mutex<HashMap<String, (Instant, String)>>>,
+}
+
+/// Held while the owner computes and caches `key`; dropping it lets the next
+/// waiter in.
+pub struct Flight {
+    flights: SingleFlight,
+    key: String,
+    guard: Option<OwnedMutexGuard<Option<Failure>>>,
+}
+
+impl SingleFlight {
+    pub async fn acquire(&self, key: &str) -> Flight {
+        let slot = {
+            let mut inflight = self.inflight.lock().unwrap_or_else(|e| e.into_inner());
+            Arc::clone(inflight.entry(key.to_owned()).or_default())
+        };
+        let guard = slot.lock_owned().await;
+        Flight {
+            flights: self.clone(),
+            key: key.to_owned(),
+            guard: Some(guard),
+        }
+    }
+
+    /// The message of a 404 answered for `key` within [`NOT_FOUND_TTL`].
+    fn known_not_found(&self, key: &str) -> Option<String> {
+        let not_found = self.not_found.lock().unwrap_or_else(|e| e.into_inner());
+        not_found
+            .get(key)
+            .filter(|(at, _)| at.elapsed() < NOT_FOUND_TTL)
+            .map(|(_, message)| message.clone())
+    }
+
+    fn record_not_found(&self, key: &str, message: &str) {
+        let now = Instant::now();
+        let mut not_found = self.not_found.lock().unwrap_or_else(|e| e.into_inner());
+        if not_found.len() >= NOT_FOUND_CAPACITY {
+            not_found.retain(|_, (at, _)| now.duration_since(*at) < NOT_FOUND_TTL);
+            if not_found.len() >= NOT_FOUND_CAPACITY {
+                if let Some(oldest) = not_found
+                    .iter()
+                    .min_by_key(|(_, (at, _))| *at)
+                    .map(|(key, _)| key.clone())
+                {
+                    not_found.remove(&oldest);
+                }
+            }
+        }
+        not_found.insert(key.to_owned(), (now, message.to_owned()));
+    }
+}
+
+impl Flight {
+    /// Answers `json_err(code, message)` and hands the same answer to every
+    /// request queued on this key; a 404 is also remembered briefly.
+    pub fn fail(mut self, code: u16, message: &str) -> Response {
+        if let Some(guard) = self.guard.as_mut() {
+            **guard = Some(Failure {
+                code,
+                message: message.to_owned(),
+            });
+        }
+        if code == 404 {
+            self.flights.record_not_found(&self.key, message);
+        }
+        json_err(code, message)
+    }
+
+    /// The error the previous owner of this key failed with, if any.
+    fn failure(&self) -> Option<Failure> {
+        self.guard.as_ref().and_then(|guard| (**guard).clone())
+    }
+}
+
+impl Drop for Flight {
+    fn drop(&mut self) {
+        let mut inflight = self
+            .flights
+            .inflight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        self.guard.take();
+        // Only the map's reference left: nobody is waiting on this key.
+        if inflight
+            .get(&self.key)
+            .is_some_and(|slot| Arc::strong_count(slot) == 1)
+        {
+            inflight.remove(&self.key);
+        }
+    }
+}
+
+/// Cache lookup that coalesces misses through `flights`. Returns the cached
+/// response, a recent 404 or the error the request queued ahead failed with,
+/// or else the [`Flight`] to hold while querying and caching. The owner
+/// answers errors through [`Flight::fail`] so they are shared too.
+pub async fn cached_or_flight(
+    cache: &ConnectionManager,
+    flights: &SingleFlight,
+    key: &str,
+    with_age: bool,
+) -> Result<Response, Flight> {
+    if let Some(message) = flights.known_not_found(key) {
+        return Ok(json_err(404, &message));
+    }
+    if let Some(resp) = cached_response_aged(cache, key, with_age).await {
+        return Ok(resp);
+    }
+    let flight = flights.acquire(key).await;
+    if let Some(failure) = flight.failure() {
+        return Ok(json_err(failure.code, &failure.message));
+    }
+    match cached_response_aged(cache, key, with_age).await {
+        Some(resp) => Ok(resp),
+        None => Err(flight),
+    }
+}
+
+/// Ages are added on the way out, so the cached payload and the ETag derived
+/// from it stay the same from one second to the next.
+fn aged_response(entry: CacheEntry, with_age: bool) -> Response {
+    let CacheEntry { etag, payload } = entry;
+    if !with_age {
+        return json_response_with_etag(payload, &etag, StatusCode::OK);
+    }
+    let aged = timing::measure(Phase::Serialize, || {
+        let mut value = serde_json::from_slice::<Value>(&payload).ok()?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        insert_age_seconds(&mut value, now);
+        Some(serde_json::to_vec(&value).unwrap())
+    });
+    let Some(aged) = aged else {
+        return json_response_with_etag(payload, &etag, StatusCode::OK);
+    };
+    json_response_with_etag(aged, &etag, StatusCode::OK)
+}
+
+/// Adds `age_seconds` (`now` minus `ts`, or minus `first_seen` for
+/// unconfirmed txs) to every object carrying one of those timestamps.
+pub fn insert_age_seconds(value: &mut Value, now: i64) {
+    match value {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| insert_age_seconds(item, now)),
+        Value::Object(map) => {
+            map.values_mut()
+                .for_each(|field| insert_age_seconds(field, now));
+            let since = ["ts", "first_seen"]
+                .iter()
+                .find_map(|k| map.get(*k).and_then(Value::as_i64));
+            if let Some(since) = since {
+                map.insert("age_seconds".into(), Value::from((now - since).max(0)));
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn etag_of(payload: &[u8]) -> String {
+    hex::encode(Sha256::digest(payload))
+}
+
 fn make_json_response(payload: Vec<u8>, status: StatusCode) -> Response {
-    let etag = hex::encode(Sha256::digest(&payload));
+    let etag = etag_of(&payload);
+    json_response_with_etag(payload, &etag, status)
+}
+
+fn json_response_with_etag(payload: Vec<u8>, etag: &str, status: StatusCode) -> Response {
     Response::builder()
         .status(status)
         .header("Content-Type", "application/json")
-        .header(
-            "ETag",
-            HeaderValue::from_str(&format!("W/\"{etag}\"")).unwrap(),
-        )
+        .header(header::ETAG, weak_etag(etag))
         .body(Body::from(payload))
         .unwrap()
 }
 
-pub fn is_hex_64(value: &str) -> bool {
-    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+/// The `ETag` header for a payload digest. Always weak: compression and
+/// per-response fields such as `age_seconds` change the bytes sent without
+/// changing what the tag stands for.
+pub(crate) fn weak_etag(digest: &str) -> HeaderValue {
+    HeaderValue::from_str(&format!("W/\"{digest}\"")).expect("hex etag")
+}
+
+/// Whether an `If-None-Match` header value names `etag`. Uses the weak
+/// comparison RFC 9110 prescribes for this header, so `W/` is ignored.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    let etag = opaque(etag);
+    if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == etag)
+}
+
+/// Answers `GET`/`HEAD` requests whose `If-None-Match` names the response's
+/// ETag with a bodiless 304. Cached views carry their stored ETag, so a
+/// revalidated hit costs one Redis read and no hashing or transfer.
+pub async fn conditional_get(req: Request, next: Next) -> Response {
+    let revalidating = matches!(*req.method(), Method::GET | Method::HEAD);
+    let if_none_match = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    let res = next.run(req).await;
+    let (Some(if_none_match), true) = (if_none_match, revalidating) else {
+        return res;
+    };
+    let unchanged = res.status() == StatusCode::OK
+        && res
+            .headers()
+            .get(header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|etag| etag_matches(&if_none_match, etag));
+    if !unchanged {
+        return res;
+    }
+    let (mut parts, _) = res.into_parts();
+    parts.status = StatusCode::NOT_MODIFIED;
+    parts.headers.remove(header::CONTENT_TYPE);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::empty())
+}
+
+/// Compares two secrets without short-circuiting on the first differing byte.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
 }
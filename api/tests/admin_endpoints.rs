@@ -0,0 +1,195 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+async fn reingest_requires_token_and_is_idempotent() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let height = 970_000_i64;
+    sqlx::query("DELETE FROM public.reingest_requests WHERE height = $1")
+        .bind(height)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let state = api::state::AppState {
+        admin_token: Some("s3cret".into()),
+        ..common::state(pool.clone()).await
+    };
+    let app = api::routes::v1_router().with_state(state);
+
+    let request = |token: Option<&str>| {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri(format!("/api/v1/admin/reingest/{height}"));
+        if let Some(token) = token {
+            builder = builder.header("Authorization", format!("Bearer {token}"));
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(request(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(request(Some("wrong"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    for _ in 0..2 {
+        let response = app.clone().oneshot(request(Some("s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["height"], height);
+        assert_eq!(json["status"], "queued");
+    }
+
+    let rows: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM public.reingest_requests WHERE height = $1")
+            .bind(height)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(rows, 1);
+
+    sqlx::query("DELETE FROM public.reingest_requests WHERE height = $1")
+        .bind(height)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn usage_aggregates_per_key_and_route() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let key = "usage-test-key";
+    sqlx::query("DELETE FROM public.api_usage WHERE api_key = $1")
+        .bind(key)
+        .execute(&pool)
+        .await
+        .unwrap();
+    for (minute, requests, errors) in [(1_i64, 6_i64, 1_i64), (2, 4, 1)] {
+        sqlx::query(
+            r#"
+INSERT INTO public.api_usage (bucket, api_key, route, requests, errors, rate_limited)
+VALUES (date_trunc('hour', NOW()) + make_interval(mins => $1::int), $2, '/api/v1/tip', $3, $4, 0)
+"#,
+        )
+        .bind(minute)
+        .bind(key)
+        .bind(requests)
+        .bind(errors)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let state = api::state::AppState {
+        admin_token: Some("s3cret".into()),
+        ..common::state(pool.clone()).await
+    };
+    let app = api::routes::v1_router().with_state(state);
+
+    let request = |token: &str| {
+        Request::builder()
+            .uri(format!("/api/v1/admin/usage?key={key}&granularity=hour"))
+            .header("Authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(request("wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app.clone().oneshot(request("s3cret")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let rows: Vec<Value> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["route"], "/api/v1/tip");
+    assert_eq!(rows[0]["requests"], 10);
+    assert_eq!(rows[0]["errors"], 2);
+    assert_eq!(rows[0]["error_rate"], 0.2);
+
+    sqlx::query("DELETE FROM public.api_usage WHERE api_key = $1")
+        .bind(key)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn usage_prune_drops_expired_buckets() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let key = "usage-prune-key";
+    let cleanup = || async {
+        sqlx::query("DELETE FROM public.api_usage WHERE api_key = $1")
+            .bind(key)
+            .execute(&pool)
+            .await
+            .unwrap();
+    };
+    cleanup().await;
+    for days in [1_i32, 40] {
+        sqlx::query(
+            r#"
+INSERT INTO public.api_usage (bucket, api_key, route, requests)
+VALUES (date_trunc('minute', NOW()) - make_interval(days => $1), $2, '/api/v1/tip', 1)
+"#,
+        )
+        .bind(days)
+        .bind(key)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let pruned = api::usage::prune_once(&pool, std::time::Duration::from_secs(30 * 86_400))
+        .await
+        .unwrap();
+    assert!(pruned >= 1);
+    let kept: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM public.api_usage WHERE api_key = $1")
+        .bind(key)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(kept, 1);
+
+    cleanup().await;
+}
+
+#[test]
+fn usage_identifies_only_configured_keys() {
+    let keys = api::usage::ApiKeys::new(["good-key", " ", "other-key"]);
+    let headers = |key: &str| {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("x-api-key", key.parse().unwrap());
+        headers
+    };
+
+    let good = keys.authenticate(&headers("good-key")).unwrap();
+    assert_eq!(good.len(), 16);
+    assert!(!good.contains("good"));
+    assert_ne!(keys.authenticate(&headers("other-key")), Some(good));
+    assert_eq!(keys.authenticate(&headers("guessed-key")), None);
+    assert_eq!(keys.authenticate(&headers("")), None);
+    assert_eq!(keys.authenticate(&axum::http::HeaderMap::new()), None);
+}
@@ -0,0 +1,106 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+mod common;
+
+async fn post(app: &axum::Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn batch_lookups_keep_request_order_and_mark_missing() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let Some(block) = sqlx::query!(
+        r#"SELECT height, encode(hash, 'hex') AS "hash!" FROM public.blocks ORDER BY height DESC LIMIT 1"#
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap() else {
+        return;
+    };
+    let tx_hash: Option<String> =
+        sqlx::query_scalar("SELECT encode(tx_hash, 'hex') FROM public.txs LIMIT 1")
+            .fetch_optional(&pool)
+            .await
+            .unwrap();
+
+    let state = common::state(pool).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let missing = "00".repeat(32);
+    let (status, json) = post(
+        &app,
+        "/api/v1/blocks/batch?xmr=true",
+        json!({ "ids": [
+            missing,
+            block.height.to_string(),
+            block.hash.to_uppercase(),
+            format!("00{}", block.height),
+        ] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let items = json["items"].as_array().unwrap();
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[3]["id"], format!("00{}", block.height));
+    assert_eq!(items[0]["found"], false);
+    assert!(items[0]["block"].is_null());
+    for item in &items[1..] {
+        assert_eq!(item["found"], true);
+        assert_eq!(item["block"]["height"], block.height);
+        assert_eq!(item["block"]["hash"], block.hash);
+        assert!(item["block"]["reward_xmr"].is_string());
+    }
+
+    if let Some(tx_hash) = tx_hash {
+        let (status, json) = post(
+            &app,
+            "/api/v1/txs/batch",
+            json!({ "ids": [tx_hash, missing] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let items = json["items"].as_array().unwrap();
+        assert_eq!(items[0]["found"], true);
+        assert_eq!(items[0]["tx"]["hash"], tx_hash);
+        assert_eq!(items[1]["found"], false);
+        assert_eq!(items[1]["id"], missing);
+    }
+
+    let too_many: Vec<String> = (0..=api::routes::MAX_BATCH_IDS)
+        .map(|h| h.to_string())
+        .collect();
+    for (uri, body) in [
+        ("/api/v1/blocks/batch", json!({ "ids": [] })),
+        ("/api/v1/blocks/batch", json!({ "ids": too_many })),
+        ("/api/v1/blocks/batch", json!({ "ids": ["tip"] })),
+        ("/api/v1/txs/batch", json!({ "ids": ["12"] })),
+        ("/api/v1/txs/batch", json!({ "hashes": [] })),
+    ] {
+        let (status, _) = post(&app, uri, body.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri} {body}");
+    }
+}
@@ -0,0 +1,126 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const HEIGHT: i64 = 925_000_000;
+const BLOCK: &str = "c9";
+
+async fn cleanup(pool: &PgPool) {
+    sqlx::query("DELETE FROM public.blocks WHERE hash = decode(repeat($1, 32), 'hex')")
+        .bind(BLOCK)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn set_window(pool: &PgPool, window: Option<i64>) {
+    sqlx::query("DELETE FROM public.dataset_metadata WHERE key = 'finality_window'")
+        .execute(pool)
+        .await
+        .unwrap();
+    if let Some(window) = window {
+        sqlx::query(
+            "INSERT INTO public.dataset_metadata (key, value) VALUES ('finality_window', $1::text)",
+        )
+        .bind(window.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+}
+
+/// `is_final` of the block, read through a fresh cache.
+async fn is_final(pool: &PgPool) -> Value {
+    let app = api::routes::v1_router().with_state(common::state(pool.clone()).await);
+    let res = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/block/{HEIGHT}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice::<Value>(&body).unwrap()["is_final"].clone()
+}
+
+#[tokio::test]
+async fn is_final_follows_the_tip_not_the_stored_flag() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = PgPool::connect(&db).await.unwrap();
+    cleanup(&pool).await;
+    let saved_tip: Option<(i64, Vec<u8>, Vec<u8>)> =
+        sqlx::query_as("SELECT height, hash, prev_hash FROM public.current_tip WHERE id = 1")
+            .fetch_optional(&pool)
+            .await
+            .unwrap();
+    let saved_window: Option<String> = sqlx::query_scalar(
+        "SELECT value FROM public.dataset_metadata WHERE key = 'finality_window'",
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    // Stored as not final, as the ingestor leaves it until its next refresh.
+    sqlx::query(
+        "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes,
+           major_version, minor_version, nonce, tx_count, reward_nanos, is_final)
+         VALUES ($1, decode(repeat($2, 32), 'hex'), decode(repeat('00', 32), 'hex'),
+                 to_timestamp($1), 3000, 16, 16, 0, 1, 600000000000, FALSE)",
+    )
+    .bind(HEIGHT)
+    .bind(BLOCK)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO public.current_tip (id, height, hash, prev_hash)
+         VALUES (1, $1 + 5, decode(repeat('00', 32), 'hex'), decode(repeat('00', 32), 'hex'))
+         ON CONFLICT (id) DO UPDATE SET height = EXCLUDED.height",
+    )
+    .bind(HEIGHT)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    set_window(&pool, None).await;
+    assert_eq!(is_final(&pool).await, false);
+    set_window(&pool, Some(5)).await;
+    assert_eq!(is_final(&pool).await, true);
+    set_window(&pool, Some(6)).await;
+    assert_eq!(is_final(&pool).await, false);
+
+    match saved_tip {
+        Some((height, hash, prev_hash)) => {
+            sqlx::query(
+                "UPDATE public.current_tip SET height = $1, hash = $2, prev_hash = $3 WHERE id = 1",
+            )
+            .bind(height)
+            .bind(hash)
+            .bind(prev_hash)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        None => {
+            sqlx::query("DELETE FROM public.current_tip")
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+    set_window(&pool, saved_window.map(|w| w.parse().unwrap())).await;
+    cleanup(&pool).await;
+}
@@ -1,13 +1,14 @@
+use api::cursor::Cursor;
 use axum::{
     body::{to_bytes, Body},
     http::{Request, StatusCode},
 };
-use mini_redis::server;
-use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tokio::{net::TcpListener, sync::oneshot};
 use tower::ServiceExt;
 
+mod common;
+
 fn normalize_block(block: &Value, base_height: i64) -> Value {
     let height = block
         .get("height")
@@ -43,22 +44,7 @@ async fn health_and_blocks_routes_exist() {
     };
 
     let pool = sqlx::PgPool::connect(&db).await.unwrap();
-    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let addr = listener.local_addr().unwrap();
-    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
-    let server_task = tokio::spawn(async move {
-        let shutdown = async {
-            let _ = shutdown_rx.await;
-        };
-        let _ = server::run(listener, shutdown).await;
-    });
-    let redis_url = format!("redis://{}", addr);
-    let client = redis::Client::open(redis_url).unwrap();
-    let cache = ConnectionManager::new(client).await.unwrap();
-    let state = api::state::AppState {
-        db: pool.clone(),
-        cache,
-    };
+    let state = common::state(pool.clone()).await;
 
     let stats = sqlx::query!(
         r#"
@@ -166,6 +152,16 @@ ORDER BY height ASC
         *stable_heights.last().unwrap(),
         stable_start - (stable_window - 1)
     );
+    for pair in stable_blocks.windows(2) {
+        let (child, parent) = (&pair[0], &pair[1]);
+        if !parent["next_hash"].is_null() {
+            assert_eq!(parent["next_hash"], child["hash"]);
+            assert_eq!(parent["next_height"], child["height"]);
+        }
+        if !child["prev_hash"].is_null() {
+            assert_eq!(child["prev_hash"], parent["hash"]);
+        }
+    }
 
     let normalized: Vec<Value> = stable_blocks
         .iter()
@@ -187,7 +183,196 @@ ORDER BY height ASC
     let detail_block: Value = serde_json::from_slice(&detail_body).unwrap();
     let detail_normalized = normalize_block(&detail_block, min_height);
     insta::assert_json_snapshot!("block_detail_min_height", detail_normalized);
+}
+
+#[tokio::test]
+async fn cursor_pages_stay_below_their_anchor() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+    let get = |uri: String| {
+        let app = app.clone();
+        async move {
+            let res = app
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = res.status();
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+
+    let (status, page) = get("/api/v1/blocks?cursor=head&limit=2".into()).await;
+    assert_eq!(status, StatusCode::OK);
+    let Some(anchor) = page["anchor_height"].as_i64() else {
+        return;
+    };
+    let mut heights = Vec::new();
+    let mut page = page;
+    for _ in 0..3 {
+        assert_eq!(page["anchor_height"], anchor);
+        heights.extend(
+            page["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|b| b["height"].as_i64().unwrap()),
+        );
+        let Some(cursor) = page["next_cursor"].as_str() else {
+            break;
+        };
+        let (status, next) = get(format!("/api/v1/blocks?cursor={cursor}&limit=2")).await;
+        assert_eq!(status, StatusCode::OK);
+        page = next;
+    }
+    assert_eq!(heights[0], anchor);
+    assert!(heights.windows(2).all(|w| w[0] > w[1]), "{heights:?}");
+
+    // A cursor whose last block has since been replaced is refused.
+    #[derive(Serialize, Deserialize)]
+    struct BlocksCursor {
+        anchor: i64,
+        height: i64,
+        hash: String,
+    }
+    impl Cursor for BlocksCursor {
+        const KIND: &'static str = "blocks";
+    }
+    let stale = BlocksCursor {
+        anchor,
+        height: anchor,
+        hash: "00".repeat(32),
+    }
+    .encode();
+    let (status, _) = get(format!("/api/v1/blocks?cursor={stale}")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Cursors from other listings do not decode here.
+    let foreign = stale.replacen(&hex::encode("blocks"), &hex::encode("mempool"), 1);
+    for uri in [
+        "/api/v1/blocks?cursor=nothex".to_owned(),
+        "/api/v1/blocks?cursor=head&start=5".to_owned(),
+        format!("/api/v1/blocks?cursor={foreign}"),
+    ] {
+        let (status, _) = get(uri.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn top_blocks_leaderboard_is_ranked() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = common::state(pool).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/stats/top-blocks?by=size&limit=3")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["by"], "size");
+    let entries = json["entries"].as_array().unwrap();
+    assert!(entries.len() <= 3);
+    let values: Vec<i64> = entries
+        .iter()
+        .map(|e| e["value"].as_i64().unwrap())
+        .collect();
+    assert!(values.windows(2).all(|w| w[0] >= w[1]));
+
+    let bad = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/stats/top-blocks?by=fees")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(bad.status(), StatusCode::BAD_REQUEST);
+}
 
-    let _ = shutdown_tx.send(());
-    let _ = server_task.await;
+#[tokio::test]
+async fn block_analytics_reports_soft_facts() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let Some(block) = sqlx::query!(
+        r#"
+SELECT b.height, encode(b.hash, 'hex') AS "hash!", s.total_fee AS "total_fee?"
+FROM public.blocks b
+LEFT JOIN public.soft_facts s ON s.block_height = b.height
+ORDER BY b.height DESC
+LIMIT 1
+"#
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap() else {
+        return;
+    };
+
+    let state = common::state(pool).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    for id in [block.height.to_string(), block.hash.clone()] {
+        let res = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/block/{id}/analytics"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["height"], block.height);
+        assert_eq!(json["hash"], block.hash);
+        match block.total_fee {
+            Some(fee) => assert_eq!(json["total_fee_nanos"], fee),
+            None => {
+                assert_eq!(json["analytics_pending"], true);
+                assert!(json["total_fee_nanos"].is_null());
+            }
+        }
+    }
+
+    for (uri, status) in [
+        (
+            "/api/v1/block/not-a-block/analytics",
+            StatusCode::BAD_REQUEST,
+        ),
+        ("/api/v1/block/-5/analytics", StatusCode::NOT_FOUND),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), status, "{uri}");
+    }
 }
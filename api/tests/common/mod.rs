@@ -0,0 +1,35 @@
+//! Setup shared by the API integration tests. Each test binary compiles
+//! its own copy and uses only some of it.
+#![allow(dead_code)]
+
+use api::state::AppState;
+use mini_redis::server;
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+use tokio::net::TcpListener;
+
+/// A connection to a fresh mini-redis on an ephemeral port. The server runs
+/// until the test's runtime shuts down.
+pub async fn cache() -> ConnectionManager {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(server::run(listener, std::future::pending::<()>()));
+    let client = redis::Client::open(format!("redis://{addr}")).unwrap();
+    ConnectionManager::new(client).await.unwrap()
+}
+
+/// State over `db` and a fresh [`cache`] with every optional feature off;
+/// tests that need one override its field with struct update syntax.
+pub async fn state(db: PgPool) -> AppState {
+    AppState {
+        db,
+        cache: cache().await,
+        admin_token: None,
+        flights: Default::default(),
+        limiter: Default::default(),
+        api_keys: Default::default(),
+        daemon: Default::default(),
+        docs: Default::default(),
+        search: Default::default(),
+    }
+}
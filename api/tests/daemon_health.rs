@@ -0,0 +1,116 @@
+use api::daemon::{self, DaemonHealth};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+mod common;
+
+/// Serves `get_block_count` answering `count` on an ephemeral port.
+async fn fake_daemon(count: u64) -> String {
+    let app = Router::new().route(
+        "/json_rpc",
+        post(move || async move {
+            Json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": "0",
+                "result": {"count": count, "status": "OK"}
+            }))
+        }),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{addr}/json_rpc")
+}
+
+#[tokio::test]
+async fn probe_compares_daemon_tip_with_ingested_tip() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let tip: Option<i64> = sqlx::query_scalar("SELECT height FROM public.current_tip WHERE id = 1")
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+    let http = reqwest::Client::new();
+
+    // The daemon's count is its tip height plus one.
+    let base = tip.unwrap_or(0) as u64;
+    let near = daemon::probe(&http, &fake_daemon(base + 4).await, &pool, 10).await;
+    assert!(near.reachable);
+    assert_eq!(near.height, Some(base as i64 + 3));
+    assert!(near.checked_at.is_some());
+    match tip {
+        Some(_) => {
+            assert_eq!(near.lag, Some(3));
+            assert!(near.healthy);
+            assert!(near.error.is_none());
+        }
+        None => assert!(!near.healthy),
+    }
+
+    let far = daemon::probe(&http, &fake_daemon(base + 100).await, &pool, 10).await;
+    assert!(far.reachable);
+    assert!(!far.healthy);
+    assert!(far.error.is_some());
+
+    let unused = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/json_rpc", unused.local_addr().unwrap());
+    drop(unused);
+    let down = daemon::probe(&http, &url, &pool, 10).await;
+    assert!(!down.reachable);
+    assert!(!down.healthy);
+    assert!(down.height.is_none());
+}
+
+#[tokio::test]
+async fn readyz_degrades_on_unhealthy_daemon() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let base = common::state(pool).await;
+    let readyz = |daemon: DaemonHealth| {
+        let state = api::state::AppState {
+            daemon,
+            ..base.clone()
+        };
+        Router::new()
+            .route("/readyz", get(api::routes::readyz))
+            .with_state(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/readyz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+    };
+
+    let res = readyz(DaemonHealth::default()).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["database"], true);
+    assert_eq!(json["cache"], true);
+    assert!(json["daemon"].is_null());
+
+    // Configured but not probed yet counts as unhealthy.
+    let res = readyz(DaemonHealth::configured(10)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["status"], "degraded");
+    assert_eq!(json["daemon"]["reachable"], false);
+    assert_eq!(json["daemon"]["max_lag"], 10);
+}
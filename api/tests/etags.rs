@@ -0,0 +1,68 @@
+use api::util::{etag_matches, CacheEntry};
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+};
+use tower::ServiceExt;
+
+mod common;
+
+#[test]
+fn cache_entries_keep_their_etag() {
+    let entry = CacheEntry::new(br#"{"height":1}"#.to_vec());
+    assert_eq!(entry.etag.len(), 64);
+    assert_eq!(CacheEntry::decode(entry.encode()), entry);
+
+    // Values cached before ETags were stored are still served.
+    assert_eq!(CacheEntry::decode(br#"{"height":1}"#.to_vec()), entry);
+}
+
+#[test]
+fn if_none_match_uses_weak_comparison() {
+    assert!(etag_matches("W/\"abc\"", "W/\"abc\""));
+    assert!(etag_matches("\"abc\"", "W/\"abc\""));
+    assert!(etag_matches("\"x\", W/\"abc\"", "W/\"abc\""));
+    assert!(etag_matches("*", "W/\"abc\""));
+    assert!(!etag_matches("W/\"abd\"", "W/\"abc\""));
+}
+
+#[tokio::test]
+async fn cached_views_revalidate_with_304() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = common::state(pool).await;
+    let app = api::routes::v1_router()
+        .layer(axum::middleware::from_fn(api::util::conditional_get))
+        .with_state(state);
+    let request = |if_none_match: Option<&str>| {
+        let mut req = Request::builder().uri("/api/v1/status");
+        if let Some(tag) = if_none_match {
+            req = req.header(header::IF_NONE_MATCH, tag);
+        }
+        req.body(Body::empty()).unwrap()
+    };
+
+    let miss = app.clone().oneshot(request(None)).await.unwrap();
+    assert_eq!(miss.status(), StatusCode::OK);
+    let etag = miss.headers()[header::ETAG].to_str().unwrap().to_owned();
+    let body = to_bytes(miss.into_body(), usize::MAX).await.unwrap();
+
+    let hit = app.clone().oneshot(request(None)).await.unwrap();
+    assert_eq!(hit.headers()[header::ETAG], etag.as_str());
+    assert_eq!(to_bytes(hit.into_body(), usize::MAX).await.unwrap(), body);
+
+    let unchanged = app.clone().oneshot(request(Some(&etag))).await.unwrap();
+    assert_eq!(unchanged.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(unchanged.headers()[header::ETAG], etag.as_str());
+    assert!(to_bytes(unchanged.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let stale = app.oneshot(request(Some("W/\"stale\""))).await.unwrap();
+    assert_eq!(stale.status(), StatusCode::OK);
+}
@@ -0,0 +1,92 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+async fn base_fee_combines_daemon_estimate_and_pool_rates() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let previous: Option<(i64, Vec<i64>, i64)> = sqlx::query_as(
+        "SELECT fee_per_byte, fees, quantization_mask FROM public.fee_estimate WHERE id = 1",
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO public.fee_estimate (id, fee_per_byte, fees, quantization_mask)
+         VALUES (1, 20000, ARRAY[20000, 80000, 320000, 4000000]::bigint[], 10000)
+         ON CONFLICT (id) DO UPDATE
+         SET fee_per_byte = EXCLUDED.fee_per_byte, fees = EXCLUDED.fees,
+             quantization_mask = EXCLUDED.quantization_mask, updated_at = NOW()",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO public.mempool_txs (tx_hash, fee_rate)
+         VALUES (decode(repeat('f1', 32), 'hex'), 25000)
+         ON CONFLICT (tx_hash) DO UPDATE SET fee_rate = EXCLUDED.fee_rate, mined_height = NULL",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let res = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/fees/base")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["daemon"]["fee_per_byte"], 20_000);
+    assert_eq!(
+        json["daemon"]["fees"],
+        serde_json::json!([20_000, 80_000, 320_000, 4_000_000])
+    );
+    assert_eq!(json["daemon"]["quantization_mask"], 10_000);
+    assert!(json["mempool"]["sample_size"].as_i64().unwrap() >= 1);
+    assert!(json["mempool"]["p50"].is_number());
+    assert_eq!(json["window_blocks"], 10);
+
+    sqlx::query("DELETE FROM public.mempool_txs WHERE tx_hash = decode(repeat('f1', 32), 'hex')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    match previous {
+        Some((fee, fees, mask)) => {
+            sqlx::query(
+                "UPDATE public.fee_estimate
+                 SET fee_per_byte = $1, fees = $2, quantization_mask = $3 WHERE id = 1",
+            )
+            .bind(fee)
+            .bind(fees)
+            .bind(mask)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        None => {
+            sqlx::query("DELETE FROM public.fee_estimate")
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Method, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const HEIGHT: i64 = 930_000_000;
+const BLOCK: &str = "c8";
+const TX: &str = "c7";
+
+async fn cleanup(pool: &PgPool) {
+    sqlx::query("DELETE FROM public.txs WHERE tx_hash = decode(repeat($1, 32), 'hex')")
+        .bind(TX)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM public.blocks WHERE hash = decode(repeat($1, 32), 'hex')")
+        .bind(BLOCK)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn send(app: &Router, method: Method, uri: &str, if_none_match: Option<&str>) -> Response {
+    let mut req = Request::builder().method(method).uri(uri);
+    if let Some(tag) = if_none_match {
+        req = req.header(header::IF_NONE_MATCH, tag);
+    }
+    app.clone()
+        .oneshot(req.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+/// Status and ETag of a `HEAD`, asserting it came back without a body.
+async fn head(app: &Router, uri: &str) -> (StatusCode, Option<String>) {
+    let res = send(app, Method::HEAD, uri, None).await;
+    let status = res.status();
+    let etag = res
+        .headers()
+        .get(header::ETAG)
+        .map(|v| v.to_str().unwrap().to_owned());
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert!(body.is_empty(), "{uri}");
+    (status, etag)
+}
+
+#[tokio::test]
+async fn head_answers_existence_without_a_body() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = PgPool::connect(&db).await.unwrap();
+    cleanup(&pool).await;
+    sqlx::query(
+        "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes,
+           major_version, minor_version, nonce, tx_count, reward_nanos, is_final)
+         VALUES ($1, decode(repeat($2, 32), 'hex'), decode(repeat('00', 32), 'hex'),
+                 to_timestamp($1), 3000, 16, 16, 0, 1, 600000000000, FALSE)",
+    )
+    .bind(HEIGHT)
+    .bind(BLOCK)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO public.txs
+           (tx_hash, block_height, block_timestamp, block_position, size_bytes, version,
+            unlock_time, rct_type, num_inputs, num_outputs)
+         VALUES (decode(repeat($1, 32), 'hex'), $2, to_timestamp($2), 0, 1500, 2, 0, 6, 1, 1)",
+    )
+    .bind(TX)
+    .bind(HEIGHT)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO public.tx_inputs (tx_hash, tx_block_timestamp, idx, key_image, ring_size)
+         VALUES (decode(repeat($1, 32), 'hex'), to_timestamp($2), 0, decode(repeat($1, 32), 'hex'), 16)",
+    )
+    .bind(TX)
+    .bind(HEIGHT)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router()
+        .layer(axum::middleware::from_fn(api::util::conditional_get))
+        .with_state(state);
+    let tx_uri = format!("/api/v1/tx/{}", TX.repeat(32));
+
+    // Repeated to cover the cached path too.
+    let (status, etag) = head(&app, &tx_uri).await;
+    assert_eq!(status, StatusCode::OK);
+    let etag = etag.unwrap();
+    assert_eq!(
+        head(&app, &tx_uri).await,
+        (StatusCode::OK, Some(etag.clone()))
+    );
+    let unchanged = send(&app, Method::HEAD, &tx_uri, Some(&etag)).await;
+    assert_eq!(unchanged.status(), StatusCode::NOT_MODIFIED);
+
+    let (status, _) = head(&app, &format!("/api/v1/tx/{}", "0f".repeat(32))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = head(&app, "/api/v1/tx/xyz").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Height and hash name the same block, so they share an ETag.
+    let (status, by_height) = head(&app, &format!("/api/v1/block/{HEIGHT}")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, by_hash) = head(&app, &format!("/api/v1/block/{}", BLOCK.repeat(32))).await;
+    assert_eq!(by_height, by_hash);
+    let (status, _) = head(&app, &format!("/api/v1/block/{}", HEIGHT + 1)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Key image HEAD is the GET response without its body.
+    let ki_uri = format!("/api/v1/key_image/{}", TX.repeat(32));
+    let (status, ki_etag) = head(&app, &ki_uri).await;
+    assert_eq!(status, StatusCode::OK);
+    let get = send(&app, Method::GET, &ki_uri, None).await;
+    assert_eq!(
+        get.headers().get(header::ETAG).map(|v| v.to_str().unwrap()),
+        ki_etag.as_deref()
+    );
+    let (status, _) = head(&app, &format!("/api/v1/key_image/{}", "0f".repeat(32))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    cleanup(&pool).await;
+}
@@ -0,0 +1,97 @@
+use api::hex_param::BlockId;
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+mod common;
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, String, Value) {
+    let res = app.clone().oneshot(request).await.unwrap();
+    let status = res.status();
+    let content_type = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|v| v.to_str().unwrap().to_owned())
+        .unwrap_or_default();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        content_type,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+    )
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+fn post(uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn malformed_hex_is_rejected_with_problem_json() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = common::state(pool).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let short = "ab".repeat(31);
+    let not_hex = "zz".repeat(32);
+    for request in [
+        get(&format!("/api/v1/tx/{short}")),
+        get(&format!("/api/v1/tx/{not_hex}/rings")),
+        get(&format!("/api/v1/tx/{short}/context")),
+        get(&format!("/api/v1/key_image/{not_hex}")),
+        get("/api/v1/block/tip"),
+        get("/api/v1/block/xyz/analytics"),
+        post("/api/v1/blocks/batch", json!({ "ids": ["1", short] })),
+        post("/api/v1/txs/batch", json!({ "ids": [not_hex] })),
+    ] {
+        let uri = request.uri().to_string();
+        let (status, content_type, body) = send(&app, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(content_type, "application/problem+json", "{uri}");
+        assert_eq!(body["status"], 400, "{uri}");
+        assert_eq!(body["title"], "Invalid parameter", "{uri}");
+        assert!(
+            body["detail"].as_str().is_some_and(|d| !d.is_empty()),
+            "{uri}"
+        );
+        assert_eq!(body["error"], body["detail"], "{uri}");
+    }
+
+    let (_, _, body) = send(&app, get(&format!("/api/v1/tx/{short}"))).await;
+    assert_eq!(body["detail"], "`hash`: expected 64 hex characters, got 62");
+
+    // Case is normalised before lookup, so both spellings resolve alike.
+    let missing = "0F".repeat(32);
+    for uri in [
+        format!("/api/v1/tx/{missing}"),
+        format!("/api/v1/block/{missing}"),
+        format!("/api/v1/key_image/{missing}"),
+    ] {
+        let (status, content_type, _) = send(&app, get(&uri)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+        assert_eq!(content_type, "application/json", "{uri}");
+    }
+}
+
+#[test]
+fn all_digit_hash_is_not_a_height() {
+    let hash = format!("{}7", "0".repeat(63));
+    assert!(matches!(BlockId::parse(&hash), Ok(BlockId::Hash(h)) if h.as_str() == hash));
+    assert_eq!(BlockId::parse("007"), Ok(BlockId::Height(7)));
+}
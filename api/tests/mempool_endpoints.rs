@@ -0,0 +1,161 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+mod common;
+
+async fn get_json(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    let (status, _, body) = get_with_etag(app, uri).await;
+    (status, body)
+}
+
+async fn get_with_etag(app: &axum::Router, uri: &str) -> (StatusCode, String, Value) {
+    let res = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = res.status();
+    // Problem+json errors are not cached, so they carry no ETag.
+    let etag = res
+        .headers()
+        .get("etag")
+        .map(|v| v.to_str().unwrap().to_owned())
+        .unwrap_or_default();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, etag, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn mempool_pages_by_fee_rate() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let hashes = ["f1".repeat(32), "f2".repeat(32), "f3".repeat(32)];
+    for (i, hash) in hashes.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO public.mempool_txs (tx_hash, fee_rate, size_bytes)
+             VALUES (decode($1,'hex'), $2, 1500)
+             ON CONFLICT (tx_hash) DO UPDATE SET fee_rate = EXCLUDED.fee_rate",
+        )
+        .bind(hash)
+        .bind(rust_decimal::Decimal::from(3_000_000_000_i64 - i as i64))
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let (status, etag, first) =
+        get_with_etag(&app, "/api/v1/mempool?sort=fee_rate&limit=2&cursor=head").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(first["items"][0].get("age_seconds").is_none());
+
+    let (status, aged_etag, aged) = get_with_etag(
+        &app,
+        "/api/v1/mempool?sort=fee_rate&limit=2&cursor=head&age=true",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(aged["items"][0]["age_seconds"].as_i64().unwrap() >= 0);
+    assert_eq!(aged_etag, etag);
+    assert!(etag.starts_with("W/\""), "{etag}");
+
+    let items = first["items"].as_array().unwrap();
+    assert_eq!(items[0]["hash"], hashes[0].as_str());
+    assert_eq!(items[1]["hash"], hashes[1].as_str());
+    assert_eq!(items[0]["size_bytes"], 1500);
+    let cursor = first["next_cursor"].as_str().unwrap().to_owned();
+
+    let (status, second) = get_json(
+        &app,
+        &format!("/api/v1/mempool?sort=fee_rate&limit=2&cursor={cursor}"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(second["items"][0]["hash"], hashes[2].as_str());
+
+    let (status, _) = get_json(
+        &app,
+        &format!("/api/v1/mempool?sort=size&limit=2&cursor={cursor}"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Without a cursor the listing keeps its original bare-array shape.
+    let (status, list) = get_json(&app, "/api/v1/mempool?sort=fee_rate&limit=2").await;
+    assert_eq!(status, StatusCode::OK);
+    let list = list.as_array().unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0]["hash"], hashes[0].as_str());
+    assert_eq!(list[1]["hash"], hashes[1].as_str());
+
+    for hash in &hashes {
+        sqlx::query("DELETE FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')")
+            .bind(hash)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn mempool_ties_page_in_hash_order() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    // Inserted out of order with one size above anything else in the pool,
+    // so the pages below start with these, in hash order.
+    let hashes = ["e5", "e1", "e4", "e2", "e3"].map(|b| b.repeat(32));
+    for hash in &hashes {
+        sqlx::query(
+            "INSERT INTO public.mempool_txs (tx_hash, size_bytes)
+             VALUES (decode($1,'hex'), 2000000000)
+             ON CONFLICT (tx_hash) DO UPDATE SET size_bytes = EXCLUDED.size_bytes",
+        )
+        .bind(hash)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let mut seen = Vec::new();
+    let mut uri = "/api/v1/mempool?sort=size&limit=2&cursor=head".to_string();
+    while seen.len() < hashes.len() {
+        let (status, page) = get_json(&app, &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        for item in page["items"].as_array().unwrap() {
+            seen.push(item["hash"].as_str().unwrap().to_owned());
+        }
+        let Some(cursor) = page["next_cursor"].as_str() else {
+            break;
+        };
+        uri = format!("/api/v1/mempool?sort=size&limit=2&cursor={cursor}");
+    }
+    seen.truncate(hashes.len());
+    let mut expected = hashes.to_vec();
+    expected.sort();
+    assert_eq!(seen, expected);
+
+    for hash in &hashes {
+        sqlx::query("DELETE FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')")
+            .bind(hash)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,64 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+async fn mining_stats_list_newest_day_first() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    // Far-future days so they sort ahead of anything else in the table.
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let cleanup = || async {
+        sqlx::query("DELETE FROM public.mining_daily WHERE day >= '2999-12-30'")
+            .execute(&pool)
+            .await
+            .unwrap();
+    };
+    cleanup().await;
+    sqlx::query(
+        "INSERT INTO public.mining_daily (day, blocks, nonce_buckets, nonce_chi2,
+                                          avg_difficulty, implied_hashrate, mean_solve_secs,
+                                          residual_mean_abs_secs, residual_stddev_secs)
+         VALUES ('2999-12-31', 16, array_fill(1, ARRAY[16]), 0, 240000, 2000, 120, 4.5, 6),
+                ('2999-12-30', 1, array_fill(0, ARRAY[16]), 15, NULL, NULL, NULL, NULL, NULL)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let res = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/stats/mining?days=2")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let days = json["days"].as_array().unwrap();
+    assert_eq!(days.len(), 2);
+    assert_eq!(days[0]["day"], 32_503_593_600i64);
+    assert_eq!(days[0]["blocks"], 16);
+    assert_eq!(days[0]["nonce_buckets"], serde_json::json!(vec![1; 16]));
+    assert_eq!(days[0]["implied_hashrate"], 2000.0);
+    assert_eq!(days[0]["residual_mean_abs_secs"], 4.5);
+    assert_eq!(days[1]["day"], 32_503_507_200i64);
+    assert!(days[1]["implied_hashrate"].is_null());
+    assert!(json["computed_at"].is_i64());
+
+    cleanup().await;
+}
@@ -1,21 +1,32 @@
+use api::models::Describe;
+use bex_core::{BlockHash, TxHash};
+
 #[tokio::test]
 async fn dto_serializes() {
-    let b = api::models::BlockView {
+    let mut b = api::models::BlockView {
         height: 1,
-        hash: Some("ab".repeat(32)),
+        hash: BlockHash([0xab; 32]),
         ts: Some(0),
         size_bytes: 1234,
         major_version: 14,
         minor_version: 14,
         tx_count: 1,
         reward_nanos: 0,
+        confirmations: 1,
+        is_final: false,
+        prev_height: Some(0),
+        prev_hash: Some(BlockHash([0xef; 32])),
+        next_height: None,
+        next_hash: None,
+        reward_xmr: None,
     };
 
     let j = serde_json::to_string(&b).unwrap();
     assert!(j.contains("\"height\":1"));
+    assert!(j.contains(&format!("\"hash\":\"{}\"", "ab".repeat(32))));
 
-    let t = api::models::TxView {
-        hash: Some("cd".repeat(32)),
+    let mut t = api::models::TxView {
+        hash: TxHash([0xcd; 32]),
         block_height: Some(1),
         ts: Some(0),
         in_mempool: false,
@@ -29,7 +40,56 @@ async fn dto_serializes() {
         bp_plus: true,
         num_inputs: 2,
         num_outputs: 2,
+        first_seen: Some(0),
+        nonstandard: Some(false),
+        nonstandard_reasons: Some(vec![]),
+        fee_xmr: None,
     };
 
-    let _ = serde_json::to_string(&t).unwrap();
+    let j = serde_json::to_string(&t).unwrap();
+    assert!(!j.contains("fee_xmr"));
+
+    b.fill_xmr();
+    t.fill_xmr();
+    assert_eq!(t.fee_xmr.as_deref(), Some("0.000000000123"));
+
+    let mut b = serde_json::to_value(&b).unwrap();
+    let mut t = serde_json::to_value(&t).unwrap();
+    api::util::insert_age_seconds(&mut b, 60);
+    api::util::insert_age_seconds(&mut t, 60);
+    assert_eq!(b["age_seconds"], 60);
+
+    assert_documented(&b, api::models::BlockView::FIELDS);
+    assert_documented(&t, api::models::TxView::FIELDS);
+}
+
+fn assert_documented<T: serde::Serialize>(value: &T, fields: &[api::models::FieldDoc]) {
+    let json = serde_json::to_value(value).unwrap();
+    let mut keys: Vec<&str> = json
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    let mut documented: Vec<&str> = fields.iter().map(|f| f.name).collect();
+    keys.sort_unstable();
+    documented.sort_unstable();
+    assert_eq!(keys, documented);
+}
+
+#[test]
+fn data_dictionary_units_are_defined() {
+    let schema = api::models::data_dictionary();
+    for entity in &schema.entities {
+        for field in entity.fields {
+            if let Some(unit) = field.unit {
+                assert!(
+                    schema.units.contains_key(unit),
+                    "{}.{} uses undefined unit {unit}",
+                    entity.entity,
+                    field.name
+                );
+            }
+        }
+    }
 }
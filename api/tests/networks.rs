@@ -0,0 +1,121 @@
+use api::network::{multiplex, network_for_host, NetworkDeployment};
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    routing::get,
+    Router,
+};
+use tower::ServiceExt;
+
+#[test]
+fn deployments_parse_from_three_fields() {
+    let parsed: NetworkDeployment =
+        " mainnet|postgres://u@db/main?sslmode=require|redis://cache/1 "
+            .parse()
+            .unwrap();
+    assert_eq!(
+        parsed,
+        NetworkDeployment {
+            name: "mainnet".into(),
+            database_url: "postgres://u@db/main?sslmode=require".into(),
+            redis_url: "redis://cache/1".into(),
+        }
+    );
+    for bad in [
+        "mainnet",
+        "mainnet|postgres://db",
+        "mainnet||redis://cache/1",
+        "a|b|c|d",
+        "api|postgres://db|redis://cache/1",
+        "api-docs|postgres://db|redis://cache/1",
+        "readyz|postgres://db|redis://cache/1",
+        "Mainnet|postgres://db|redis://cache/1",
+        "main.net|postgres://db|redis://cache/1",
+        "-main|postgres://db|redis://cache/1",
+    ] {
+        assert!(bad.parse::<NetworkDeployment>().is_err(), "{bad:?}");
+    }
+}
+
+#[test]
+fn host_selects_by_first_label() {
+    let names = vec!["mainnet".to_string(), "testnet".to_string()];
+    let host = |value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_str(value).unwrap());
+        headers
+    };
+    assert_eq!(
+        network_for_host(&host("mainnet.example.org"), &names),
+        Some("mainnet")
+    );
+    assert_eq!(
+        network_for_host(&host("MAINNET:8081"), &names),
+        Some("mainnet")
+    );
+    assert_eq!(
+        network_for_host(&host("explorer.example.org"), &names),
+        None
+    );
+    assert_eq!(network_for_host(&host("example.mainnet.org"), &names), None);
+    assert_eq!(network_for_host(&HeaderMap::new(), &names), None);
+}
+
+fn answering(network: &'static str) -> Router {
+    Router::new().route("/api/v1/tip", get(move || async move { network }))
+}
+
+async fn served_by(app: &Router, uri: &str, host: Option<&str>) -> (StatusCode, String) {
+    let mut req = Request::builder().uri(uri);
+    if let Some(host) = host {
+        req = req.header(header::HOST, host);
+    }
+    let res = app
+        .clone()
+        .oneshot(req.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn requests_reach_the_named_network() {
+    let app = multiplex(
+        ("stagenet".into(), answering("stagenet")),
+        vec![("mainnet".into(), answering("mainnet"))],
+    );
+    let ok = |network: &str| (StatusCode::OK, network.to_string());
+
+    assert_eq!(served_by(&app, "/api/v1/tip", None).await, ok("stagenet"));
+    assert_eq!(
+        served_by(&app, "/stagenet/api/v1/tip", Some("mainnet.example.org")).await,
+        ok("stagenet")
+    );
+    assert_eq!(
+        served_by(&app, "/mainnet/api/v1/tip", None).await,
+        ok("mainnet")
+    );
+    assert_eq!(
+        served_by(&app, "/api/v1/tip", Some("mainnet.example.org")).await,
+        ok("mainnet")
+    );
+    // The path prefix wins over the host.
+    assert_eq!(
+        served_by(&app, "/mainnet/api/v1/tip", Some("stagenet.example.org")).await,
+        ok("mainnet")
+    );
+    assert_eq!(
+        served_by(&app, "/testnet/api/v1/tip", None).await.0,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn docs_name_the_network_prefix() {
+    let docs = api::docs::ApiDocs::load_at("/mainnet").unwrap();
+    let body = to_bytes(docs.json().into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["servers"], serde_json::json!([{ "url": "/mainnet" }]));
+}
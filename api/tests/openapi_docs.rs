@@ -0,0 +1,56 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    Router,
+};
+use tower::ServiceExt;
+
+mod common;
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, String, Vec<u8>) {
+    let res = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = res.status();
+    let content_type = res.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(res.headers().contains_key(header::ETAG));
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, content_type, body.to_vec())
+}
+
+#[tokio::test]
+async fn openapi_served_as_json_and_yaml_with_build_info() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = api::state::AppState {
+        docs: api::docs::ApiDocs::load().unwrap(),
+        ..common::state(pool).await
+    };
+    let app = api::routes::v1_router().with_state(state);
+
+    let (status, content_type, body) = get(&app, "/api-docs.json").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/json");
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["info"]["version"], api::docs::VERSION);
+    assert_eq!(json["info"]["x-git-sha"], api::docs::GIT_SHA);
+    assert!(json["paths"]["/api-docs.yaml"].is_object());
+
+    let (_, _, legacy) = get(&app, "/api-docs").await;
+    assert_eq!(legacy, body);
+
+    let (status, content_type, body) = get(&app, "/api-docs.yaml").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/yaml");
+    let yaml: serde_json::Value = serde_yaml::from_slice(&body).unwrap();
+    assert_eq!(yaml, json);
+}
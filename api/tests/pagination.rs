@@ -0,0 +1,148 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+/// Above any real chain, so these blocks are the head while the test runs.
+const HEIGHT: i64 = 940_000_000;
+/// `(hash byte, height)`: two siblings at `HEIGHT` on top of their parent.
+/// Each gets its own timestamp, which the blocks key includes.
+const BLOCKS: [(&str, i64); 3] = [("c0", HEIGHT - 1), ("c1", HEIGHT), ("c2", HEIGHT)];
+/// Same fee in the same block, so only the hash tells them apart.
+const TXS: [&str; 3] = ["d1", "d2", "d3"];
+const FEE: i64 = 3_000_000_000;
+
+async fn cleanup(pool: &PgPool) {
+    for byte in TXS {
+        sqlx::query("DELETE FROM public.txs WHERE tx_hash = decode(repeat($1, 32), 'hex')")
+            .bind(byte)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+    for (byte, _) in BLOCKS {
+        sqlx::query("DELETE FROM public.blocks WHERE hash = decode(repeat($1, 32), 'hex')")
+            .bind(byte)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let res = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+/// Pages through `base` from `cursor`, following `next_cursor`, and returns
+/// `field` of the first `max` rows.
+async fn page_through(
+    app: &Router,
+    base: &str,
+    cursor: Option<&str>,
+    field: &str,
+    max: usize,
+) -> Vec<Value> {
+    let mut seen = Vec::new();
+    let mut uri = match cursor {
+        Some(cursor) => format!("{base}&cursor={cursor}"),
+        None => base.to_owned(),
+    };
+    while seen.len() < max {
+        let (status, page) = get(app, &uri).await;
+        assert_eq!(status, StatusCode::OK, "{uri}: {page}");
+        seen.extend(
+            page["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item[field].clone()),
+        );
+        let Some(cursor) = page["next_cursor"].as_str() else {
+            break;
+        };
+        uri = format!("{base}&cursor={cursor}");
+    }
+    seen.truncate(max);
+    seen
+}
+
+#[tokio::test]
+async fn page_breaks_between_equal_keys_skip_nothing() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = PgPool::connect(&db).await.unwrap();
+    cleanup(&pool).await;
+    for (offset, (byte, height)) in BLOCKS.into_iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes,
+               major_version, minor_version, nonce, tx_count, reward_nanos, is_final)
+             VALUES ($1, decode(repeat($2, 32), 'hex'),
+                     decode(repeat(CASE WHEN $2 = 'c0' THEN '00' ELSE 'c0' END, 32), 'hex'),
+                     to_timestamp($1 + $3), 3000, 16, 16, 0, 1, 600000000000, FALSE)",
+        )
+        .bind(height)
+        .bind(byte)
+        .bind(offset as i64)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    for (position, byte) in TXS.into_iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO public.txs
+               (tx_hash, block_height, block_timestamp, block_position, fee_nanos, size_bytes,
+                version, unlock_time, rct_type, num_inputs, num_outputs)
+             VALUES (decode(repeat($1, 32), 'hex'), $2, to_timestamp($2), $3, $4, 1000, 2, 0, 6, 1, 2)",
+        )
+        .bind(byte)
+        .bind(HEIGHT)
+        .bind(position as i32)
+        .bind(FEE)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    // One block per page, so a page ends between the two siblings.
+    let blocks = page_through(&app, "/api/v1/blocks?limit=1", Some("head"), "hash", 3).await;
+    let ours: Vec<_> = blocks
+        .iter()
+        .map(|hash| hash.as_str().unwrap()[..2].to_owned())
+        .collect();
+    assert_eq!(ours, ["c2", "c1", "c0"]);
+
+    let txs = page_through(
+        &app,
+        &format!("/api/v1/txs/by-fee?min={FEE}&max={FEE}&from_height={HEIGHT}&to_height={HEIGHT}&limit=1"),
+        None,
+        "hash",
+        TXS.len() + 1,
+    )
+    .await;
+    let expected: Vec<_> = TXS
+        .iter()
+        .map(|byte| Value::from(byte.repeat(32)))
+        .collect();
+    assert_eq!(txs, expected);
+
+    cleanup(&pool).await;
+}
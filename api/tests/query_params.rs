@@ -0,0 +1,132 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+mod common;
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, String, Value) {
+    let res = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = res.status();
+    let content_type = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|v| v.to_str().unwrap().to_owned())
+        .unwrap_or_default();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        content_type,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn malformed_query_parameters_are_named_in_problem_json() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = common::state(pool).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    for (uri, name, expected) in [
+        (
+            "/api/v1/blocks?limit=ten",
+            "limit",
+            "an integer, clamped to 1..=200",
+        ),
+        ("/api/v1/blocks?xmr=yes", "xmr", "`true` or `false`"),
+        ("/api/v1/mempool?age=1", "age", "`true` or `false`"),
+        (
+            "/api/v1/stats/mining?days=-",
+            "days",
+            "an integer, clamped to 1..=365",
+        ),
+        (
+            "/api/v1/search",
+            "q",
+            "a block height, output index or 8 to 64 character hex hash prefix",
+        ),
+    ] {
+        let (status, content_type, body) = get(&app, uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(content_type, "application/problem+json", "{uri}");
+        assert_eq!(body["status"], 400, "{uri}");
+        assert_eq!(body["error"], body["detail"], "{uri}");
+        let param = &body["invalid_params"][0];
+        assert_eq!(param["name"], name, "{uri}");
+        assert_eq!(param["expected"], expected, "{uri}");
+        assert!(
+            body["detail"]
+                .as_str()
+                .unwrap()
+                .starts_with(&format!("`{name}` must be")),
+            "{uri}: {body}"
+        );
+    }
+
+    // Pool attribution does not exist, so the filter is refused, not ignored.
+    let (status, content_type, body) = get(&app, "/api/v1/blocks?pool=supportxmr").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(content_type, "application/problem+json");
+    assert!(body["detail"].as_str().unwrap().starts_with("`pool`"));
+}
+
+#[tokio::test]
+async fn handler_checks_name_the_parameter_in_problem_json() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = common::state(pool).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    for (uri, name, expected) in [
+        (
+            "/api/v1/mempool?sort=oldest",
+            "sort",
+            "one of last_seen, fee_rate, first_seen, size",
+        ),
+        (
+            "/api/v1/blocks?start=5&cursor=head",
+            "cursor",
+            "`head` or a `next_cursor` string",
+        ),
+        (
+            "/api/v1/txs/by-fee?min=-1",
+            "min",
+            "a non-negative fee in atomic units",
+        ),
+        (
+            "/api/v1/stats/top-blocks?by=fees",
+            "by",
+            "one of the orderings listed for the endpoint",
+        ),
+    ] {
+        let (status, content_type, body) = get(&app, uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(content_type, "application/problem+json", "{uri}");
+        let param = &body["invalid_params"][0];
+        assert_eq!(param["name"], name, "{uri}");
+        assert_eq!(param["expected"], expected, "{uri}");
+        assert!(
+            body["detail"]
+                .as_str()
+                .unwrap()
+                .starts_with(&format!("`{name}` must be")),
+            "{uri}: {body}"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+async fn quota_headers_and_limits_endpoint() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = api::state::AppState {
+        limiter: api::ratelimit::RateLimiter::new(3),
+        api_keys: api::usage::ApiKeys::new(["alice", "bob"]),
+        ..common::state(pool).await
+    };
+    let app = api::routes::v1_router()
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            api::ratelimit::enforce,
+        ))
+        .with_state(state);
+
+    let request = |key: &str| {
+        Request::builder()
+            .uri("/api/v1/limits")
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let header = |res: &axum::response::Response, name: &str| -> u64 {
+        res.headers()[name].to_str().unwrap().parse().unwrap()
+    };
+
+    let response = app.clone().oneshot(request("alice")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "ratelimit-limit"), 3);
+    assert_eq!(header(&response, "ratelimit-remaining"), 2);
+    assert!((1..=60).contains(&header(&response, "ratelimit-reset")));
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let quota: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(quota["limit"], 3);
+    assert_eq!(quota["used"], 1);
+    assert_eq!(quota["remaining"], 2);
+    assert_eq!(quota["window_secs"], 60);
+    assert_ne!(quota["caller"], "anonymous");
+
+    for remaining in [1, 0] {
+        let response = app.clone().oneshot(request("alice")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, "ratelimit-remaining"), remaining);
+    }
+    let response = app.clone().oneshot(request("alice")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header(&response, "ratelimit-remaining"), 0);
+    assert!(header(&response, "retry-after") >= 1);
+
+    // Quotas are per caller.
+    let response = app.clone().oneshot(request("bob")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "ratelimit-remaining"), 2);
+
+    // An unknown key buys no quota of its own.
+    for key in ["mallory", "mallory2"] {
+        let response = app.clone().oneshot(request(key)).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let quota: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(quota["caller"], "anonymous");
+    }
+    let response = app.clone().oneshot(request("mallory3")).await.unwrap();
+    assert_eq!(header(&response, "ratelimit-remaining"), 0);
+}
+
+#[test]
+fn callers_beyond_the_cap_share_the_overflow_window() {
+    let limiter = api::ratelimit::RateLimiter::new(2).with_max_callers(2);
+    assert_eq!(limiter.hit("ip:10.0.0.1").unwrap().caller, "ip:10.0.0.1");
+    assert_eq!(limiter.hit("ip:10.0.0.2").unwrap().caller, "ip:10.0.0.2");
+
+    let quota = limiter.hit("ip:10.0.0.3").unwrap();
+    assert_eq!(quota.caller, api::ratelimit::OVERFLOW);
+    assert_eq!(quota.remaining, 1);
+    assert_eq!(limiter.hit("ip:10.0.0.4").unwrap().remaining, 0);
+    assert!(limiter.hit("ip:10.0.0.5").is_err());
+
+    // Callers already tracked keep their own window.
+    assert_eq!(limiter.hit("ip:10.0.0.1").unwrap().remaining, 0);
+}
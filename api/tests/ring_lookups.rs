@@ -0,0 +1,168 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const GLOBAL_INDEX: i64 = 9_000_000_001;
+const SOURCE_TX: &str = "a1";
+/// Referencing txs with their heights and the inputs whose rings use the output.
+const USERS: [(&str, i64, &[i32]); 2] = [("b2", 900_000_010, &[0, 1]), ("c3", 900_000_020, &[0])];
+
+async fn get_json(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    let res = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn insert_tx(pool: &PgPool, byte: &str, height: i64) {
+    sqlx::query(
+        "INSERT INTO public.txs
+           (tx_hash, block_height, block_timestamp, size_bytes, version, unlock_time,
+            rct_type, num_inputs, num_outputs)
+         VALUES (decode(repeat($1, 32), 'hex'), $2, to_timestamp($2), 1500, 2, 0, 6, 1, 1)",
+    )
+    .bind(byte)
+    .bind(height)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn cleanup(pool: &PgPool) {
+    for (byte, _, _) in USERS {
+        sqlx::query("DELETE FROM public.txs WHERE tx_hash = decode(repeat($1, 32), 'hex')")
+            .bind(byte)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+    sqlx::query("DELETE FROM public.txs WHERE tx_hash = decode(repeat($1, 32), 'hex')")
+        .bind(SOURCE_TX)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn rings_by_output_pages_through_a_height_window() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = PgPool::connect(&db).await.unwrap();
+    cleanup(&pool).await;
+    insert_tx(&pool, SOURCE_TX, 900_000_000).await;
+    let output_id: i64 = sqlx::query_scalar(
+        "INSERT INTO public.outputs
+           (global_index, tx_hash, tx_block_timestamp, idx_in_tx, commitment, stealth_public_key)
+         VALUES ($1, decode(repeat($2, 32), 'hex'), to_timestamp(900000000), 0, '\\x00', '\\x00')
+         RETURNING output_id",
+    )
+    .bind(GLOBAL_INDEX)
+    .bind(SOURCE_TX)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    for (byte, height, inputs) in USERS {
+        insert_tx(&pool, byte, height).await;
+        for &input in inputs {
+            sqlx::query(
+                "INSERT INTO public.tx_inputs (tx_hash, tx_block_timestamp, idx, key_image, ring_size)
+                 VALUES (decode(repeat($1, 32), 'hex'), to_timestamp($2), $3, decode(repeat($1, 32), 'hex'), 16)",
+            )
+            .bind(byte)
+            .bind(height)
+            .bind(input)
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO public.rings (tx_hash, input_idx, ring_index, referenced_output_id)
+                 VALUES (decode(repeat($1, 32), 'hex'), $2, $3, $4)",
+            )
+            .bind(byte)
+            .bind(input)
+            .bind(input + 3)
+            .bind(output_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+    let base = format!("/api/v1/rings/by-output/{GLOBAL_INDEX}");
+
+    let (status, first) = get_json(&app, &format!("{base}?limit=2")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(first["global_index"], GLOBAL_INDEX);
+    let items = first["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["tx_hash"], "b2".repeat(32));
+    assert_eq!(items[0]["block_height"], 900_000_010);
+    assert_eq!(
+        (
+            items[0]["input_idx"].as_i64(),
+            items[0]["ring_index"].as_i64()
+        ),
+        (Some(0), Some(3))
+    );
+    assert_eq!(
+        (
+            items[1]["input_idx"].as_i64(),
+            items[1]["ring_index"].as_i64()
+        ),
+        (Some(1), Some(4))
+    );
+    let cursor = first["next_cursor"].as_str().unwrap();
+
+    let (status, second) = get_json(&app, &format!("{base}?limit=2&cursor={cursor}")).await;
+    assert_eq!(status, StatusCode::OK);
+    let items = second["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["tx_hash"], "c3".repeat(32));
+    assert!(second["next_cursor"].is_null());
+
+    let (status, window) = get_json(
+        &app,
+        &format!("{base}?from_height=900000015&to_height=900000030"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let items = window["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["block_height"], 900_000_020);
+
+    let (status, _) = get_json(
+        &app,
+        &format!("/api/v1/rings/by-output/{}", GLOBAL_INDEX + 1),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    // Cursors are tied to the output they were issued for.
+    let (status, _) = get_json(
+        &app,
+        &format!(
+            "/api/v1/rings/by-output/{}?cursor={cursor}",
+            GLOBAL_INDEX + 1
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get_json(&app, &format!("{base}?from_height=10&to_height=5")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    cleanup(&pool).await;
+}
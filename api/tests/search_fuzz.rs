@@ -1,13 +1,13 @@
 use axum::{
-    body::Body,
+    body::{to_bytes, Body},
     http::{Request, StatusCode},
 };
-use mini_redis::server;
 use rand::{distributions::Alphanumeric, Rng};
-use redis::aio::ConnectionManager;
-use tokio::{net::TcpListener, sync::oneshot};
+use serde_json::Value;
 use tower::ServiceExt;
 
+mod common;
+
 #[tokio::test]
 async fn fuzz_search_inputs() {
     let db = match std::env::var("DATABASE_URL") {
@@ -17,19 +17,7 @@ async fn fuzz_search_inputs() {
 
     let pool = sqlx::PgPool::connect(&db).await.unwrap();
 
-    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let addr = listener.local_addr().unwrap();
-    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
-    let server_task = tokio::spawn(async move {
-        let shutdown = async {
-            let _ = shutdown_rx.await;
-        };
-        let _ = server::run(listener, shutdown).await;
-    });
-
-    let client = redis::Client::open(format!("redis://{}", addr)).unwrap();
-    let cache = ConnectionManager::new(client).await.unwrap();
-    let state = api::state::AppState { db: pool, cache };
+    let state = common::state(pool).await;
     let app = api::routes::v1_router().with_state(state);
 
     for _ in 0..50 {
@@ -45,7 +33,62 @@ async fn fuzz_search_inputs() {
         let res = app.clone().oneshot(req).await.unwrap();
         assert_ne!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
+}
+
+#[tokio::test]
+async fn search_matches_hash_prefix() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = match sqlx::PgPool::connect(&db).await {
+        Ok(p) => p,
+        Err(_) => return,
+    };
+
+    let hash = match sqlx::query_scalar!(
+        "SELECT encode(tx_hash, 'hex') AS \"hash!\" FROM public.txs LIMIT 1"
+    )
+    .fetch_optional(&pool)
+    .await
+    {
+        Ok(Some(h)) => h,
+        _ => return,
+    };
+
+    let state = common::state(pool).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let prefix = hash[..12].to_ascii_uppercase();
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/search?q={prefix}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    // Another tx, block or key image may share the prefix; whichever wins, the
+    // value is a full hash that starts with it.
+    let value = json.get("value").and_then(Value::as_str).unwrap();
+    assert_eq!(value.len(), 64);
+    assert!(value.starts_with(&hash[..12]));
 
-    let _ = shutdown_tx.send(());
-    let _ = server_task.await;
+    let short = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/search?q={}", &hash[..4]))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_ne!(short.status(), StatusCode::OK);
 }
@@ -0,0 +1,116 @@
+use api::search::{parse, SearchGuard, Terms};
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use tower::ServiceExt;
+
+mod common;
+
+#[test]
+fn parse_rejects_what_cannot_match() {
+    assert_eq!(
+        parse(" 42 "),
+        Some(Terms {
+            prefix: None,
+            number: Some(42)
+        })
+    );
+    assert_eq!(
+        parse("ABCDEF01"),
+        Some(Terms {
+            prefix: Some("abcdef01".into()),
+            number: None
+        })
+    );
+    // Eight or more digits are both a number and a hex prefix.
+    assert_eq!(
+        parse("12345678"),
+        Some(Terms {
+            prefix: Some("12345678".into()),
+            number: Some(12345678)
+        })
+    );
+    for bad in [
+        "",
+        "abc",
+        "-1",
+        "+5",
+        "0x1234abcd",
+        "gggggggggg",
+        "' OR 1=1",
+    ] {
+        assert_eq!(parse(bad), None, "{bad:?}");
+    }
+    assert_eq!(parse(&"a".repeat(65)), None);
+}
+
+#[test]
+fn misses_are_remembered() {
+    let guard = SearchGuard::new(2);
+    assert!(!guard.known_miss("deadbeef"));
+    guard.record_miss("deadbeef");
+    assert!(guard.known_miss("deadbeef"));
+    for i in 0..10_000 {
+        guard.record_miss(&format!("{i:08x}"));
+    }
+    assert!(guard.known_miss(&format!("{:08x}", 9_999)));
+
+    assert!(guard.hit("ip:1").is_ok());
+    assert!(guard.hit("ip:1").is_ok());
+    assert!(guard.hit("ip:1").is_err());
+    assert!(guard.hit("ip:2").is_ok());
+
+    // Another network's guard spends the same quota but not the misses.
+    let other = guard.sharing_quota();
+    assert!(!other.known_miss("deadbeef"));
+    assert!(other.hit("ip:2").is_ok());
+    assert!(guard.hit("ip:2").is_err());
+}
+
+async fn status(app: &Router, q: &str) -> (StatusCode, Option<String>) {
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/search?q={q}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let retry_after = res
+        .headers()
+        .get(header::RETRY_AFTER)
+        .map(|v| v.to_str().unwrap().to_string());
+    (res.status(), retry_after)
+}
+
+#[tokio::test]
+async fn search_quota_applies_to_valid_queries_only() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = api::state::AppState {
+        search: SearchGuard::new(2),
+        ..common::state(pool).await
+    };
+    let app = api::routes::v1_router().with_state(state);
+
+    // Malformed queries are turned away without using the quota.
+    for _ in 0..5 {
+        assert_eq!(status(&app, "not-a-hash").await.0, StatusCode::BAD_REQUEST);
+    }
+    // No height or global index is this large, so both searches miss; the
+    // second is answered from the miss cache.
+    let unmatched = i64::MAX.to_string();
+    assert_eq!(status(&app, &unmatched).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(status(&app, &unmatched).await.0, StatusCode::NOT_FOUND);
+    let (code, retry_after) = status(&app, &unmatched).await;
+    assert_eq!(code, StatusCode::TOO_MANY_REQUESTS);
+    assert!(retry_after.is_some());
+}
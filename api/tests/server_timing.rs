@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use axum::{body::Body, http::Request};
+use tower::ServiceExt;
+
+mod common;
+
+fn phases(res: &axum::response::Response) -> HashMap<String, f64> {
+    res.headers()["server-timing"]
+        .to_str()
+        .unwrap()
+        .split(", ")
+        .map(|metric| {
+            let (name, dur) = metric.split_once(";dur=").unwrap();
+            (name.to_owned(), dur.parse().unwrap())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn server_timing_splits_db_cache_and_serialize() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let state = common::state(pool).await;
+    let app = api::routes::v1_router()
+        .layer(axum::middleware::from_fn(api::timing::server_timing))
+        .with_state(state.clone());
+    let request = || {
+        Request::builder()
+            .uri("/api/v1/tip")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // Whether a request is served from Postgres or Redis depends on the cache
+    // backend, so only the shape of the header is checked, on a miss and on a
+    // (possible) hit alike.
+    for _ in 0..2 {
+        let res = app.clone().oneshot(request()).await.unwrap();
+        let timing = phases(&res);
+        assert_eq!(timing.len(), 4, "{timing:?}");
+        for name in ["db", "cache", "serialize", "total"] {
+            let dur = timing.get(name).copied();
+            assert!(
+                dur.is_some_and(|d| d.is_finite() && d >= 0.0),
+                "{name}: {timing:?}"
+            );
+        }
+        for name in ["db", "cache", "serialize"] {
+            assert!(timing["total"] >= timing[name], "{timing:?}");
+        }
+    }
+
+    // Without the middleware there is no header.
+    let plain = api::routes::v1_router().with_state(state);
+    let res = plain.oneshot(request()).await.unwrap();
+    assert!(res.headers().get("server-timing").is_none());
+}
@@ -0,0 +1,91 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use api::util::{cached_or_flight, SingleFlight};
+use tokio::sync::Mutex;
+
+mod common;
+
+#[tokio::test]
+async fn concurrent_misses_run_one_query() {
+    let flights = SingleFlight::default();
+    let cache: Arc<Mutex<Option<u32>>> = Arc::default();
+    let queries = Arc::new(AtomicUsize::new(0));
+
+    let tasks: Vec<_> = (0..16)
+        .map(|_| {
+            let (flights, cache, queries) =
+                (flights.clone(), Arc::clone(&cache), Arc::clone(&queries));
+            tokio::spawn(async move {
+                let _flight = flights.acquire("block:42").await;
+                if let Some(hit) = *cache.lock().await {
+                    return hit;
+                }
+                queries.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                *cache.lock().await = Some(42);
+                42
+            })
+        })
+        .collect();
+    for task in tasks {
+        assert_eq!(task.await.unwrap(), 42);
+    }
+
+    assert_eq!(queries.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn distinct_keys_do_not_wait_on_each_other() {
+    let flights = SingleFlight::default();
+    let _a = flights.acquire("tx:aa").await;
+    tokio::time::timeout(Duration::from_secs(1), flights.acquire("tx:bb"))
+        .await
+        .expect("other key acquired while first is held");
+}
+
+#[tokio::test]
+async fn failures_are_shared_and_not_found_is_remembered() {
+    let cache = common::cache().await;
+    let flights = SingleFlight::default();
+
+    // Every outcome reaches the queued requests: none of them queries again.
+    for (key, code) in [("tx:broken", 500), ("tx:missing", 404)] {
+        let queries = Arc::new(AtomicUsize::new(0));
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let (cache, flights, queries) =
+                    (cache.clone(), flights.clone(), Arc::clone(&queries));
+                tokio::spawn(async move {
+                    match cached_or_flight(&cache, &flights, key, false).await {
+                        Ok(resp) => resp.status(),
+                        Err(flight) => {
+                            queries.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(10)).await;
+                            flight.fail(code, "failed").status()
+                        }
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().as_u16(), code, "{key}");
+        }
+        assert_eq!(queries.load(Ordering::SeqCst), 1, "{key}");
+    }
+
+    // A 404 keeps being answered from memory; other errors are retried.
+    let resp = cached_or_flight(&cache, &flights, "tx:missing", false)
+        .await
+        .ok()
+        .expect("404 remembered");
+    assert_eq!(resp.status().as_u16(), 404);
+    assert!(cached_or_flight(&cache, &flights, "tx:broken", false)
+        .await
+        .is_err());
+}
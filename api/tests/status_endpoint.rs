@@ -0,0 +1,76 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+async fn status_reports_pruned_dataset() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = sqlx::PgPool::connect(&db).await.unwrap();
+    let previous: Option<String> =
+        sqlx::query_scalar("SELECT value FROM public.dataset_metadata WHERE key = 'pruned'")
+            .fetch_optional(&pool)
+            .await
+            .unwrap();
+    sqlx::query(
+        "INSERT INTO public.dataset_metadata (key, value) VALUES ('pruned', 'true')
+         ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let tip: Option<i64> = sqlx::query_scalar("SELECT height FROM public.current_tip WHERE id = 1")
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+    let daemon_tip: Option<i64> = sqlx::query_scalar(
+        "SELECT value::bigint FROM public.dataset_metadata WHERE key = 'daemon_tip'",
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let res = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/status")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["pruned"], true);
+    assert_eq!(json["tip_height"].as_i64(), tip);
+    assert_eq!(json["daemon_tip_height"].as_i64(), daemon_tip);
+    assert!(json["daemon"].is_null());
+
+    match previous {
+        Some(value) => {
+            sqlx::query("UPDATE public.dataset_metadata SET value = $1 WHERE key = 'pruned'")
+                .bind(value)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        None => {
+            sqlx::query("DELETE FROM public.dataset_metadata WHERE key = 'pruned'")
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const HEIGHT: i64 = 910_000_000;
+const BLOCK: &str = "d5";
+/// A competing block at `HEIGHT` that the bundled tx is not in.
+const SIBLING: &str = "d6";
+const SOURCE_TX: &str = "d3";
+const BUNDLED_TX: &str = "d4";
+const GLOBAL_INDEX: i64 = 9_000_000_101;
+
+async fn insert_tx(pool: &PgPool, byte: &str, position: i32) {
+    sqlx::query(
+        "INSERT INTO public.txs
+           (tx_hash, block_height, block_timestamp, block_position, size_bytes, version,
+            unlock_time, rct_type, num_inputs, num_outputs)
+         VALUES (decode(repeat($1, 32), 'hex'), $2, to_timestamp($2), $3, 1500, 2, 0, 6, 1, 1)",
+    )
+    .bind(byte)
+    .bind(HEIGHT)
+    .bind(position)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn insert_output(pool: &PgPool, byte: &str, global_index: i64) -> i64 {
+    sqlx::query_scalar(
+        "INSERT INTO public.outputs
+           (global_index, tx_hash, tx_block_timestamp, idx_in_tx, commitment, stealth_public_key)
+         VALUES ($1, decode(repeat($2, 32), 'hex'), to_timestamp($3), 0, '\\xc0', '\\x5e')
+         RETURNING output_id",
+    )
+    .bind(global_index)
+    .bind(byte)
+    .bind(HEIGHT)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn cleanup(pool: &PgPool) {
+    for byte in [BUNDLED_TX, SOURCE_TX] {
+        sqlx::query("DELETE FROM public.txs WHERE tx_hash = decode(repeat($1, 32), 'hex')")
+            .bind(byte)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+    for byte in [BLOCK, SIBLING] {
+        sqlx::query("DELETE FROM public.blocks WHERE hash = decode(repeat($1, 32), 'hex')")
+            .bind(byte)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn tx_bundle_collects_block_io_rings_and_status() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = PgPool::connect(&db).await.unwrap();
+    cleanup(&pool).await;
+    for (offset, byte) in [BLOCK, SIBLING].into_iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes,
+               major_version, minor_version, nonce, tx_count, reward_nanos, is_final)
+             VALUES ($1, decode(repeat($2, 32), 'hex'), decode(repeat('00', 32), 'hex'),
+                     to_timestamp($1 + $3), 3000, 16, 16, 0, 2, 600000000000, TRUE)",
+        )
+        .bind(HEIGHT)
+        .bind(byte)
+        .bind(offset as i64)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    insert_tx(&pool, SOURCE_TX, 0).await;
+    let referenced = insert_output(&pool, SOURCE_TX, GLOBAL_INDEX).await;
+    insert_tx(&pool, BUNDLED_TX, 1).await;
+    insert_output(&pool, BUNDLED_TX, GLOBAL_INDEX + 1).await;
+    sqlx::query(
+        "INSERT INTO public.tx_inputs (tx_hash, tx_block_timestamp, idx, key_image, ring_size)
+         VALUES (decode(repeat($1, 32), 'hex'), to_timestamp($2), 0, decode(repeat($1, 32), 'hex'), 16)",
+    )
+    .bind(BUNDLED_TX)
+    .bind(HEIGHT)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO public.rings (tx_hash, input_idx, ring_index, referenced_output_id)
+         VALUES (decode(repeat($1, 32), 'hex'), 0, 7, $2)",
+    )
+    .bind(BUNDLED_TX)
+    .bind(referenced)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+    let hash = BUNDLED_TX.repeat(32);
+
+    // Second request is served from the cache and must look the same.
+    for uri in [
+        format!("/api/v1/tx/{}/bundle?xmr=true", hash.to_uppercase()),
+        format!("/api/v1/tx/{hash}/bundle?xmr=true"),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK, "{uri}");
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            format!("attachment; filename=\"tx-{hash}.json\"")
+        );
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+
+        assert!(json["generated_at"].as_i64().unwrap() > 0);
+        assert_eq!(json["tx"]["hash"], hash);
+        assert_eq!(json["block"]["height"], HEIGHT);
+        assert_eq!(json["block"]["hash"], BLOCK.repeat(32));
+        assert!(json["block"]["reward_xmr"].is_string());
+        assert_eq!(json["inputs"][0]["key_image"], hash);
+        assert_eq!(json["outputs"][0]["global_index"], GLOBAL_INDEX + 1);
+        let ring = &json["rings"][0];
+        assert_eq!(ring["input_idx"], 0);
+        assert_eq!(ring["members"][0]["ring_index"], 7);
+        assert_eq!(ring["members"][0]["global_index"], GLOBAL_INDEX);
+        assert_eq!(ring["members"][0]["tx_hash"], SOURCE_TX.repeat(32));
+        assert_eq!(ring["members"][0]["stealth_public_key"], "5e");
+        assert_eq!(json["status"]["state"], "final");
+        assert_eq!(json["status"]["position"], 1);
+        assert_eq!(
+            json["status"]["confirmations"],
+            json["block"]["confirmations"]
+        );
+    }
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/tx/{}/bundle", "00".repeat(32)))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert!(res.headers().get(header::CONTENT_DISPOSITION).is_none());
+
+    cleanup(&pool).await;
+}
@@ -2,12 +2,11 @@ use axum::{
     body::{to_bytes, Body},
     http::{Request, StatusCode},
 };
-use mini_redis::server;
-use redis::aio::ConnectionManager;
 use serde_json::Value;
-use tokio::{net::TcpListener, sync::oneshot};
 use tower::ServiceExt;
 
+mod common;
+
 #[tokio::test]
 async fn tx_endpoint_includes_inputs_outputs() {
     let db = match std::env::var("DATABASE_URL") {
@@ -52,22 +51,7 @@ LIMIT 1
     let expected_inputs = tx_row.num_inputs;
     let expected_outputs = tx_row.num_outputs;
 
-    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let addr = listener.local_addr().unwrap();
-    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
-    let server_task = tokio::spawn(async move {
-        let shutdown = async {
-            let _ = shutdown_rx.await;
-        };
-        let _ = server::run(listener, shutdown).await;
-    });
-
-    let client = redis::Client::open(format!("redis://{}", addr)).unwrap();
-    let cache = ConnectionManager::new(client).await.unwrap();
-    let state = api::state::AppState {
-        db: pool.clone(),
-        cache,
-    };
+    let state = common::state(pool.clone()).await;
     let app = api::routes::v1_router().with_state(state);
 
     let response = app
@@ -130,9 +114,6 @@ LIMIT 1
         .await
         .unwrap();
     assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
-
-    let _ = shutdown_tx.send(());
-    let _ = server_task.await;
 }
 
 #[tokio::test]
@@ -185,19 +166,7 @@ ORDER BY idx ASC
         _ => return,
     };
 
-    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let addr = listener.local_addr().unwrap();
-    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
-    let server_task = tokio::spawn(async move {
-        let shutdown = async {
-            let _ = shutdown_rx.await;
-        };
-        let _ = server::run(listener, shutdown).await;
-    });
-
-    let client = redis::Client::open(format!("redis://{}", addr)).unwrap();
-    let cache = ConnectionManager::new(client).await.unwrap();
-    let state = api::state::AppState { db: pool, cache };
+    let state = common::state(pool).await;
     let app = api::routes::v1_router().with_state(state);
 
     let response = app
@@ -241,7 +210,4 @@ ORDER BY idx ASC
         .await
         .unwrap();
     assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
-
-    let _ = shutdown_tx.send(());
-    let _ = server_task.await;
 }
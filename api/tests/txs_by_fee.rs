@@ -0,0 +1,100 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+mod common;
+
+const HEIGHT: i64 = 920_000_000;
+const TXS: [(&str, i64); 3] = [("e5", 10_000), ("e6", 5_000_000_000), ("e7", 7_000_000_000)];
+
+async fn cleanup(pool: &PgPool) {
+    for (byte, _) in TXS {
+        sqlx::query("DELETE FROM public.txs WHERE tx_hash = decode(repeat($1, 32), 'hex')")
+            .bind(byte)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let res = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn txs_by_fee_pages_through_a_fee_range() {
+    let db = match std::env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let pool = PgPool::connect(&db).await.unwrap();
+    cleanup(&pool).await;
+    for (position, (byte, fee)) in TXS.into_iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO public.txs
+               (tx_hash, block_height, block_timestamp, block_position, fee_nanos, size_bytes,
+                version, unlock_time, rct_type, num_inputs, num_outputs)
+             VALUES (decode(repeat($1, 32), 'hex'), $2, to_timestamp($2), $3, $4, 1000, 2, 0, 6, 1, 2)",
+        )
+        .bind(byte)
+        .bind(HEIGHT)
+        .bind(position as i32)
+        .bind(fee)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let state = common::state(pool.clone()).await;
+    let app = api::routes::v1_router().with_state(state);
+
+    let (status, first) = get(
+        &app,
+        &format!(
+            "/api/v1/txs/by-fee?min=1000000000&from_height={HEIGHT}&to_height={HEIGHT}&limit=1"
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(first["from_height"], HEIGHT);
+    assert_eq!(first["to_height"], HEIGHT);
+    let items = first["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["hash"], "e7".repeat(32));
+    assert_eq!(items[0]["fee_nanos"], 7_000_000_000i64);
+    assert_eq!(items[0]["block_position"], 2);
+    assert_eq!(items[0]["fee_per_byte"], 7_000_000.0);
+
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let (_, second) = get(
+        &app,
+        &format!(
+            "/api/v1/txs/by-fee?min=1000000000&from_height={HEIGHT}&to_height={HEIGHT}&limit=1&cursor={cursor}"
+        ),
+    )
+    .await;
+    let items = second["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["hash"], "e6".repeat(32));
+    assert!(second["next_cursor"].is_null());
+
+    let (status, _) = get(&app, "/api/v1/txs/by-fee?min=10&max=5").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/api/v1/txs/by-fee?from_height=0&to_height=10000").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    cleanup(&pool).await;
+}
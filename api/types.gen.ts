@@ -40,6 +40,54 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
+    "/readyz": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Readiness of Postgres, Redis and the daemon
+         * @description 503 when Postgres or Redis is unreachable. A configured daemon that is unreachable or too far from the ingested tip only degrades the proxy features, so it is reported as `degraded` with 200.
+         */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description Ready, possibly degraded */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ReadinessView"];
+                    };
+                };
+                /** @description Postgres or Redis unreachable */
+                503: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ReadinessView"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
     "/api/v1/blocks": {
         parameters: {
             query?: never;
@@ -47,12 +95,23 @@ export interface paths {
             path?: never;
             cookie?: never;
         };
-        /** List recent blocks or from start height */
+        /**
+         * List recent blocks or from start height
+         * @description Highest block first, blocks sharing a height by hash descending. With `cursor`, returns a `BlockPage` instead of a bare array. `cursor=head` anchors the listing at the current tip and each `next_cursor` continues strictly after the last block returned, so blocks arriving mid-listing do not shift the pages and a page break between blocks of one height skips neither.
+         */
         get: {
             parameters: {
                 query?: {
                     start?: number;
+                    /** @description `head`, or `next_cursor` from the previous page; excludes `start` */
+                    cursor?: string;
+                    /** @description Not supported: blocks are not attributed to mining pools, so any value is rejected with 400 rather than ignored */
+                    pool?: string;
                     limit?: number;
+                    /** @description Add fixed-point XMR strings (`*_xmr`) next to atomic-unit amounts */
+                    xmr?: boolean;
+                    /** @description Add `age_seconds`, computed per response; the weak ETag and cached payload do not depend on it */
+                    age?: boolean;
                 };
                 header?: never;
                 path?: never;
@@ -66,7 +125,26 @@ export interface paths {
                         [name: string]: unknown;
                     };
                     content: {
-                        "application/json": components["schemas"]["BlockView"][];
+                        "application/json": components["schemas"]["BlockView"][] | components["schemas"]["BlockPage"];
+                    };
+                };
+                /** @description Invalid cursor, or both `start` and `cursor` given (plain error), or a malformed query parameter or `pool` (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description A reorg replaced the cursor's last block; restart with `cursor=head` */
+                409: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
                     };
                 };
                 /** @description Database error */
@@ -98,7 +176,12 @@ export interface paths {
         /** Get block by height or hash */
         get: {
             parameters: {
-                query?: never;
+                query?: {
+                    /** @description Add fixed-point XMR strings (`*_xmr`) next to atomic-unit amounts */
+                    xmr?: boolean;
+                    /** @description Add `age_seconds`, computed per response; the weak ETag and cached payload do not depend on it */
+                    age?: boolean;
+                };
                 header?: never;
                 path: {
                     id: number | string;
@@ -116,6 +199,15 @@ export interface paths {
                         "application/json": components["schemas"]["BlockView"];
                     };
                 };
+                /** @description id is neither a height nor a block hash, or a malformed query parameter */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
                 /** @description Block not found */
                 404: {
                     headers: {
@@ -140,24 +232,78 @@ export interface paths {
         post?: never;
         delete?: never;
         options?: never;
-        head?: never;
+        /**
+         * Check that a block exists
+         * @description Answers like `GET` without a body and without building the block view. The `ETag` covers the block's hash and finality rather than the full view, so it differs from the `GET` ETag; send it back in `If-None-Match` to get 304 until a reorg or finalization changes it.
+         */
+        head: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path: {
+                    id: number | string;
+                };
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description The block exists */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Unchanged since the ETag in `If-None-Match` */
+                304: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description id is neither a height nor a block hash */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Block not found */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+            };
+        };
         patch?: never;
         trace?: never;
     };
-    "/api/v1/tx/{hash}": {
+    "/api/v1/block/{id}/analytics": {
         parameters: {
             query?: never;
             header?: never;
             path?: never;
             cookie?: never;
         };
-        /** Get transaction by hash */
+        /**
+         * Per-block soft facts (fees, ring sizes, proof totals)
+         * @description Cached for a day once the block is final and its soft facts are computed, otherwise for 30 seconds.
+         */
         get: {
             parameters: {
                 query?: never;
                 header?: never;
                 path: {
-                    hash: string;
+                    id: number | string;
                 };
                 cookie?: never;
             };
@@ -169,19 +315,19 @@ export interface paths {
                         [name: string]: unknown;
                     };
                     content: {
-                        "application/json": components["schemas"]["TxDetailView"];
+                        "application/json": components["schemas"]["BlockAnalyticsView"];
                     };
                 };
-                /** @description Invalid transaction hash */
+                /** @description id is neither a height nor a block hash */
                 400: {
                     headers: {
                         [name: string]: unknown;
                     };
                     content: {
-                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
                     };
                 };
-                /** @description Transaction not found */
+                /** @description Block not found */
                 404: {
                     headers: {
                         [name: string]: unknown;
@@ -209,17 +355,22 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
-    "/api/v1/tx/{hash}/rings": {
+    "/api/v1/tx/{hash}": {
         parameters: {
             query?: never;
             header?: never;
             path?: never;
             cookie?: never;
         };
-        /** Get ring members for a transaction, grouped by input */
+        /** Get transaction by hash */
         get: {
             parameters: {
-                query?: never;
+                query?: {
+                    /** @description Add fixed-point XMR strings (`*_xmr`) next to atomic-unit amounts */
+                    xmr?: boolean;
+                    /** @description Add `age_seconds`, computed per response; the weak ETag and cached payload do not depend on it */
+                    age?: boolean;
+                };
                 header?: never;
                 path: {
                     hash: string;
@@ -234,11 +385,20 @@ export interface paths {
                         [name: string]: unknown;
                     };
                     content: {
-                        "application/json": components["schemas"]["RingSetView"][];
+                        "application/json": components["schemas"]["TxDetailView"];
                     };
                 };
-                /** @description Invalid transaction hash */
+                /** @description Invalid transaction hash, or a malformed query parameter */
                 400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Transaction not found */
+                404: {
                     headers: {
                         [name: string]: unknown;
                     };
@@ -261,23 +421,82 @@ export interface paths {
         post?: never;
         delete?: never;
         options?: never;
-        head?: never;
+        /**
+         * Check that a transaction exists
+         * @description Answers like `GET` without a body and without reading inputs or outputs, for pollers waiting on a transaction to appear. The `ETag` covers whether the transaction is in the pool or mined and at which height, so it differs from the `GET` ETag; send it back in `If-None-Match` to get 304 until that changes.
+         */
+        head: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path: {
+                    hash: string;
+                };
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description The transaction is in the pool or a block */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Unchanged since the ETag in `If-None-Match` */
+                304: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Invalid transaction hash */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Transaction not found */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+            };
+        };
         patch?: never;
         trace?: never;
     };
-    "/api/v1/mempool": {
+    "/api/v1/tx/{hash}/context": {
         parameters: {
             query?: never;
             header?: never;
             path?: never;
             cookie?: never;
         };
-        /** List mempool transactions */
+        /**
+         * Get the chain context of a transaction
+         * @description Containing block, position within it, confirmations and finality, plus any blocks the transaction was reorged out of while that history is retained (see `--orphaned-tx-ttl-secs`), most recently orphaned first.
+         */
         get: {
             parameters: {
-                query?: never;
+                query?: {
+                    /** @description Add fixed-point XMR strings (`*_xmr`) next to atomic-unit amounts */
+                    xmr?: boolean;
+                };
                 header?: never;
-                path?: never;
+                path: {
+                    hash: string;
+                };
                 cookie?: never;
             };
             requestBody?: never;
@@ -288,7 +507,25 @@ export interface paths {
                         [name: string]: unknown;
                     };
                     content: {
-                        "application/json": components["schemas"]["MempoolView"][];
+                        "application/json": components["schemas"]["TxContextView"];
+                    };
+                };
+                /** @description Invalid transaction hash, or a malformed query parameter */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Transaction not found */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
                     };
                 };
                 /** @description Database error */
@@ -310,20 +547,26 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
-    "/api/v1/key_image/{hex}": {
+    "/api/v1/tx/{hash}/bundle": {
         parameters: {
             query?: never;
             header?: never;
             path?: never;
             cookie?: never;
         };
-        /** Lookup by key image */
+        /**
+         * Download a proof-of-inclusion bundle for a transaction
+         * @description One JSON document with the transaction, its containing block, inputs, outputs, rings resolved to the outputs they reference, and the confirmation status, all read from a single database snapshot. Served with `Content-Disposition: attachment` for archiving or attaching to support tickets.
+         */
         get: {
             parameters: {
-                query?: never;
+                query?: {
+                    /** @description Add fixed-point XMR strings (`*_xmr`) next to atomic-unit amounts */
+                    xmr?: boolean;
+                };
                 header?: never;
                 path: {
-                    hex: string;
+                    hash: string;
                 };
                 cookie?: never;
             };
@@ -335,19 +578,19 @@ export interface paths {
                         [name: string]: unknown;
                     };
                     content: {
-                        "application/json": components["schemas"]["KeyImageView"];
+                        "application/json": components["schemas"]["TxBundleView"];
                     };
                 };
-                /** @description Invalid key image */
+                /** @description Invalid transaction hash, or a malformed query parameter */
                 400: {
                     headers: {
                         [name: string]: unknown;
                     };
                     content: {
-                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
                     };
                 };
-                /** @description Key image not found */
+                /** @description Transaction not found */
                 404: {
                     headers: {
                         [name: string]: unknown;
@@ -375,21 +618,30 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
-    "/api/v1/search": {
+    "/api/v1/rings/by-output/{global_index}": {
         parameters: {
             query?: never;
             header?: never;
             path?: never;
             cookie?: never;
         };
-        /** Smart search for height/hash/key image/global index */
+        /**
+         * Find txs whose rings reference an output
+         * @description Ring member slots pointing at the output with this global index, oldest referencing block first (then by tx hash and input index), optionally limited to a height window of the referencing txs.
+         */
         get: {
             parameters: {
-                query: {
-                    q: string;
+                query?: {
+                    from_height?: number;
+                    to_height?: number;
+                    /** @description `next_cursor` from the previous page */
+                    cursor?: string;
+                    limit?: number;
                 };
                 header?: never;
-                path?: never;
+                path: {
+                    global_index: number;
+                };
                 cookie?: never;
             };
             requestBody?: never;
@@ -400,10 +652,20 @@ export interface paths {
                         [name: string]: unknown;
                     };
                     content: {
-                        "application/json": components["schemas"]["SearchResult"];
+                        "application/json": components["schemas"]["RingUsePage"];
                     };
                 };
-                /** @description No match */
+                /** @description Invalid height window or cursor (plain error), or a malformed query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description No output with this global index */
                 404: {
                     headers: {
                         [name: string]: unknown;
@@ -431,19 +693,21 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
-    "/api-docs": {
+    "/api/v1/tx/{hash}/rings": {
         parameters: {
             query?: never;
             header?: never;
             path?: never;
             cookie?: never;
         };
-        /** Retrieve OpenAPI specification */
+        /** Get ring members for a transaction, grouped by input */
         get: {
             parameters: {
                 query?: never;
                 header?: never;
-                path?: never;
+                path: {
+                    hash: string;
+                };
                 cookie?: never;
             };
             requestBody?: never;
@@ -454,7 +718,25 @@ export interface paths {
                         [name: string]: unknown;
                     };
                     content: {
-                        "application/json": Record<string, never>;
+                        "application/json": components["schemas"]["RingSetView"][];
+                    };
+                };
+                /** @description Invalid transaction hash */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
                     };
                 };
             };
@@ -467,13 +749,1251 @@ export interface paths {
         patch?: never;
         trace?: never;
     };
-}
-export type webhooks = Record<string, never>;
-export interface components {
-    schemas: {
-        ErrorResponse: {
-            error: string;
-        };
+    "/api/v1/mempool": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * List mempool transactions
+         * @description Sorted by `last_seen` (newest first), `fee_rate` (highest first), `first_seen` (oldest first) or `size` (largest first), ties broken by tx hash ascending. Rows without a fee rate or size sort last. With `cursor`, returns a `MempoolPage` instead of a bare array; `cursor=head` starts at the first row and each `next_cursor` continues after the last row returned.
+         */
+        get: {
+            parameters: {
+                query?: {
+                    sort?: "last_seen" | "fee_rate" | "first_seen" | "size";
+                    /** @description `head`, or `next_cursor` from the previous page with the same `sort` */
+                    cursor?: string;
+                    /** @description Defaults to 1000 for the bare array and 100 per page with `cursor` */
+                    limit?: number;
+                    /** @description Add `age_seconds`, computed per response; the weak ETag and cached payload do not depend on it */
+                    age?: boolean;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["MempoolView"][] | components["schemas"]["MempoolPage"];
+                    };
+                };
+                /** @description Unknown sort or cursor issued for a different sort (plain error), or a malformed query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/tip": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /** Current canonical chain tip as recorded by the ingestor */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["TipView"];
+                    };
+                };
+                /** @description No tip recorded yet */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/status": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Properties of the ingested dataset
+         * @description `pruned` is true once any transactions were ingested in pruned mode (no range proofs or signatures); their `bp_plus` and `proof_type` are derived from the RingCT type alone.
+         */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["StatusView"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/fees/base": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Daemon base fee and observed fee rates
+         * @description The daemon's `get_fee_estimate` as last polled by the ingestor, next to percentiles of the fee rates paid by pool txs and by txs in recent blocks. Cached for 10 seconds.
+         */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["BaseFeeView"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/stats/top-blocks": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Busiest blocks by transaction count or size
+         * @description Rankings rebuilt periodically by the ingestor's analytics worker; `computed_at` is the time of the last rebuild (null before the first).
+         */
+        get: {
+            parameters: {
+                query?: {
+                    by?: "tx_count" | "size";
+                    limit?: number;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["TopBlocksView"];
+                    };
+                };
+                /** @description Unknown ranking (plain error), or a malformed query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/stats/mining": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Daily nonce distribution and solve-time residuals
+         * @description Per-day statistics rebuilt periodically by the ingestor's analytics worker, newest day first. `nonce_buckets` counts blocks by the top four bits of the nonce and `nonce_chi2` tests them against uniform; the residuals compare each block's solve time with what its difficulty predicts at the day's implied hashrate.
+         */
+        get: {
+            parameters: {
+                query?: {
+                    days?: number;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["MiningStatsView"];
+                    };
+                };
+                /** @description Malformed query parameter */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/stats/top-outputs": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Outputs referenced by the most rings
+         * @description Rankings rebuilt periodically by the ingestor's analytics worker; `computed_at` is the time of the last rebuild (null before the first).
+         */
+        get: {
+            parameters: {
+                query?: {
+                    by?: "ring_references";
+                    limit?: number;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["TopOutputsView"];
+                    };
+                };
+                /** @description Unknown ranking (plain error), or a malformed query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/outputs/rct-offsets": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Cumulative RingCT output counts per block
+         * @description Amount-0 output distribution in the shape of one entry of the daemon's `get_output_distribution` result, so wallets can pick decoys without a daemon connection. With `cumulative=false` the distribution holds per-block counts and `base` the total below `start_height`.
+         */
+        get: {
+            parameters: {
+                query?: {
+                    from_height?: number;
+                    /** @description Defaults to the highest ingested height */
+                    to_height?: number;
+                    cumulative?: boolean;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["RctOffsetsView"];
+                    };
+                };
+                /** @description Invalid or oversized range (at most 100000 heights) (plain error), or a malformed query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description No output counts ingested yet */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Range not fully ingested from genesis */
+                409: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/blocks/batch": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        get?: never;
+        put?: never;
+        /**
+         * Get several blocks by height or hash
+         * @description Up to 100 heights and/or hashes per request. Items follow the request order, one per id; ids matching no block have `found: false` and a null `block`.
+         */
+        post: {
+            parameters: {
+                query?: {
+                    /** @description Add fixed-point XMR strings (`*_xmr`) next to atomic-unit amounts */
+                    xmr?: boolean;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody: {
+                content: {
+                    "application/json": components["schemas"]["BatchRequest"];
+                };
+            };
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["BlockBatchPage"];
+                    };
+                };
+                /** @description Empty, oversized or malformed request (plain error), or an invalid id or query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/txs/by-fee": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Mined transactions within a fee range
+         * @description Txs whose total fee (atomic units) lies in `min..=max`, mined in `from_height..=to_height`, highest fee first, then newest block, then tx hash ascending. The window spans at most 10000 blocks; a missing bound is filled in from the other one, or from the tip when both are missing. Txs without a recorded fee are never matched.
+         */
+        get: {
+            parameters: {
+                query?: {
+                    min?: number;
+                    max?: number;
+                    from_height?: number;
+                    to_height?: number;
+                    cursor?: string;
+                    limit?: number;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["TxFeePage"];
+                    };
+                };
+                /** @description Invalid range, window or cursor (plain error), or a malformed query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/txs/batch": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        get?: never;
+        put?: never;
+        /**
+         * Get several transactions by hash
+         * @description Up to 100 hashes per request. Items follow the request order, one per id; hashes matching no tx have `found: false` and a null `tx`. Inputs and outputs are not included; use `/api/v1/tx/{hash}` for those.
+         */
+        post: {
+            parameters: {
+                query?: {
+                    /** @description Add fixed-point XMR strings (`*_xmr`) next to atomic-unit amounts */
+                    xmr?: boolean;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody: {
+                content: {
+                    "application/json": components["schemas"]["BatchRequest"];
+                };
+            };
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["TxBatchPage"];
+                    };
+                };
+                /** @description Empty, oversized or malformed request (plain error), or an invalid id or query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/key_image/{hex}": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /** Lookup by key image */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path: {
+                    hex: string;
+                };
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["KeyImageView"];
+                    };
+                };
+                /** @description Invalid key image */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Key image not found */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        /**
+         * Check that a key image has been spent
+         * @description The `GET` response without its body; the `ETag` is the same.
+         */
+        head: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path: {
+                    hex: string;
+                };
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description The key image is spent */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Unchanged since the ETag in `If-None-Match` */
+                304: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Invalid key image */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Key image not found */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content?: never;
+                };
+            };
+        };
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/search": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Smart search for height/hash/key image/global index
+         * @description Numbers match a block height, then an output global index. Hex strings of 8 to 64 characters match a tx hash, block hash or key image by prefix; the matched value is always the full hash. Anything else is rejected with 400. Searches have their own per-IP quota (`SEARCH_RATE_LIMIT_PER_MINUTE`), and a miss is answered from memory for a few seconds.
+         */
+        get: {
+            parameters: {
+                query: {
+                    q: string;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["SearchResult"];
+                    };
+                };
+                /** @description Query is not a number or a hex prefix (plain error), or a malformed query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Search quota exhausted; see `Retry-After` */
+                429: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description No match */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/meta/schema": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /** Data dictionary describing response entities, units and encodings */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["SchemaView"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/limits": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * The caller's current request quota and usage
+         * @description Every response carries `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers with the same numbers. Callers over quota get 429 with a `Retry-After` header until the window resets.
+         */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["QuotaView"];
+                    };
+                };
+                /** @description Quota exhausted */
+                429: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/admin/usage": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Request counts and error rates per API key and route
+         * @description Callers are identified by a hash prefix of their `X-API-Key` header when it is one of `API_KEYS`; every other request counts as `anonymous`. Counters are flushed from Redis once per closed minute (see `USAGE_FLUSH_SECS`), so the current minute is not included, and kept for `USAGE_RETENTION_DAYS`.
+         */
+        get: {
+            parameters: {
+                query?: {
+                    /** @description Epoch seconds; defaults to 24 hours ago */
+                    since?: number;
+                    granularity?: "minute" | "hour" | "day";
+                    /** @description Restrict to one key id */
+                    key?: string;
+                    /** @description Restrict to one route template, e.g. `/api/v1/tx/:hash` */
+                    route?: string;
+                };
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["UsageView"][];
+                    };
+                };
+                /** @description Invalid granularity (plain error), or a malformed query parameter (problem) */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                        "application/problem+json": components["schemas"]["Problem"];
+                    };
+                };
+                /** @description Missing or invalid bearer token */
+                401: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Admin API disabled (ADMIN_TOKEN unset) */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api/v1/admin/reingest/{height}": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        get?: never;
+        put?: never;
+        /**
+         * Queue a single height for re-ingestion by the ingestor
+         * @description Idempotent while the height is queued or running; finished requests are queued again.
+         */
+        post: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path: {
+                    height: number;
+                };
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description Queued */
+                202: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ReingestView"];
+                    };
+                };
+                /** @description Invalid height */
+                400: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Missing or invalid bearer token */
+                401: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Admin API disabled (ADMIN_TOKEN unset) */
+                404: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+                /** @description Database error */
+                500: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": components["schemas"]["ErrorResponse"];
+                    };
+                };
+            };
+        };
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api-docs": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /**
+         * Retrieve OpenAPI specification
+         * @description Same document as `/api-docs.json`. `info.version` is the API build's crate version and `info.x-git-sha` the commit it was built from.
+         */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": Record<string, never>;
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api-docs.json": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /** Retrieve OpenAPI specification as JSON */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/json": Record<string, never>;
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+    "/api-docs.yaml": {
+        parameters: {
+            query?: never;
+            header?: never;
+            path?: never;
+            cookie?: never;
+        };
+        /** Retrieve OpenAPI specification as YAML */
+        get: {
+            parameters: {
+                query?: never;
+                header?: never;
+                path?: never;
+                cookie?: never;
+            };
+            requestBody?: never;
+            responses: {
+                /** @description OK */
+                200: {
+                    headers: {
+                        [name: string]: unknown;
+                    };
+                    content: {
+                        "application/yaml": string;
+                    };
+                };
+            };
+        };
+        put?: never;
+        post?: never;
+        delete?: never;
+        options?: never;
+        head?: never;
+        patch?: never;
+        trace?: never;
+    };
+}
+export type webhooks = Record<string, never>;
+export interface components {
+    schemas: {
+        ErrorResponse: {
+            error: string;
+        };
+        /** @description RFC 9457 problem details, returned for malformed hex parameters (hashes, key images, stealth keys) and query parameters that do not parse. `error` repeats `detail` for clients of `ErrorResponse`. */
+        Problem: {
+            type: string;
+            title: string;
+            status: number;
+            detail: string;
+            error: string;
+            /** @description The query parameter that failed to parse */
+            invalid_params?: ({
+                name: string | null;
+                reason: string;
+                /** @description The type and range the parameter accepts */
+                expected?: string;
+            })[];
+        };
         HealthResponse: {
             /** @enum {string} */
             status: "ok";
@@ -481,7 +2001,7 @@ export interface components {
         BlockView: {
             /** Format: int64 */
             height: number;
-            hash?: string | null;
+            hash: string;
             /** Format: int64 */
             ts?: number | null;
             size_bytes: number;
@@ -490,9 +2010,77 @@ export interface components {
             tx_count: number;
             /** Format: int64 */
             reward_nanos: number;
+            /**
+             * @description Computed from the current tip at query time
+             * Format: int64
+             */
+            confirmations: number;
+            is_final: boolean;
+            /** Format: int64 */
+            prev_height?: number | null;
+            prev_hash?: string | null;
+            /**
+             * @description Null at the tip; set only when the child links to this block by hash
+             * Format: int64
+             */
+            next_height?: number | null;
+            next_hash?: string | null;
+            /** @description reward_nanos as fixed-point XMR; only present with `?xmr=true` */
+            reward_xmr?: string;
+            /**
+             * @description Seconds since `ts` when served; only present with `?age=true`
+             * Format: int64
+             */
+            age_seconds?: number;
+        };
+        BlockAnalyticsView: {
+            /** Format: int64 */
+            height: number;
+            hash: string;
+            /** Format: int64 */
+            ts?: number | null;
+            is_final: boolean;
+            /** @description Soft facts not yet computed; the aggregates below are null */
+            analytics_pending: boolean;
+            /** Format: int64 */
+            total_fee_nanos?: number | null;
+            avg_ring_size?: string | null;
+            /** @description Atomic units per byte */
+            median_fee_rate?: string | null;
+            /** Format: int64 */
+            bp_total_bytes?: number | null;
+            clsag_count?: number | null;
+            /** @description Txs breaking a rule of the block's hard fork; null if any tx was ingested before the check existed */
+            nonstandard_count?: number | null;
+        };
+        OrphanedBlockView: {
+            /** Format: int64 */
+            height: number;
+            hash: string;
+            /** Format: int64 */
+            orphaned_at?: number | null;
+        };
+        TxContextView: {
+            hash: string;
+            in_mempool: boolean;
+            block?: components["schemas"]["BlockView"] | null;
+            /** @description Index within the block's tx_hashes, miner tx excluded */
+            position?: number | null;
+            /**
+             * @description 0 while unconfirmed
+             * Format: int64
+             */
+            confirmations: number;
+            is_final: boolean;
+            /**
+             * @description Last time a reorg detached the transaction from a block
+             * Format: int64
+             */
+            orphaned_at?: number | null;
+            orphaned_blocks: components["schemas"]["OrphanedBlockView"][];
         };
         TxView: {
-            hash?: string | null;
+            hash: string;
             /** Format: int64 */
             block_height?: number | null;
             /** Format: int64 */
@@ -504,12 +2092,28 @@ export interface components {
             version: number;
             /** Format: int64 */
             unlock_time: number;
+            /** @description JSON object with the raw extra as hex under `extra` and/or its decoded tags under `tags`, depending on the ingestor's `--tx-extra-retention` */
             extra_json?: string | null;
             rct_type: number;
             proof_type?: string | null;
             bp_plus: boolean;
             num_inputs: number;
             num_outputs: number;
+            /**
+             * @description First mempool sighting (epoch seconds); null if never seen unconfirmed
+             * Format: int64
+             */
+            first_seen?: number | null;
+            /** @description Breaks a rule (tx version, rct type, ring size) of the hard fork active at its height; null while unconfirmed or unchecked */
+            nonstandard?: boolean | null;
+            nonstandard_reasons?: ("version" | "rct_type" | "ring_size")[] | null;
+            /** @description fee_nanos as fixed-point XMR; only present with `?xmr=true` */
+            fee_xmr?: string;
+            /**
+             * @description Seconds since `ts` (`first_seen` while unconfirmed) when served; only present with `?age=true`
+             * Format: int64
+             */
+            age_seconds?: number;
         };
         InputView: {
             idx: number;
@@ -527,6 +2131,27 @@ export interface components {
             spent_by_key_image?: string | null;
             spent_in_tx?: string | null;
         };
+        BatchRequest: {
+            ids: string[];
+        };
+        BlockBatchItem: {
+            /** @description The requested height or hash (lowercased) */
+            id: string;
+            found: boolean;
+            block: components["schemas"]["BlockView"] | null;
+        };
+        BlockBatchPage: {
+            items: components["schemas"]["BlockBatchItem"][];
+        };
+        TxBatchItem: {
+            /** @description The requested hash (lowercased) */
+            id: string;
+            found: boolean;
+            tx: components["schemas"]["TxView"] | null;
+        };
+        TxBatchPage: {
+            items: components["schemas"]["TxBatchItem"][];
+        };
         TxDetailView: components["schemas"]["TxView"] & {
             inputs: components["schemas"]["InputView"][];
             outputs: components["schemas"]["OutputView"][];
@@ -540,21 +2165,353 @@ export interface components {
             input_idx: number;
             members: components["schemas"]["RingMemberView"][];
         };
+        /** @description Ring member with the output it references (null when unresolved) */
+        ResolvedRingMemberView: {
+            ring_index: number;
+            /** Format: int64 */
+            global_index: number | null;
+            tx_hash: string | null;
+            stealth_public_key: string | null;
+            commitment: string | null;
+        };
+        ResolvedRingSetView: {
+            input_idx: number;
+            members: components["schemas"]["ResolvedRingMemberView"][];
+        };
+        BundleStatus: {
+            /**
+             * @description `detached` when the transaction is neither in a block nor in the mempool (e.g. reorged out and since evicted)
+             * @enum {string}
+             */
+            state: "mempool" | "confirmed" | "final" | "detached";
+            /** Format: int64 */
+            confirmations: number;
+            position: number | null;
+            /** Format: int64 */
+            tip_height: number | null;
+        };
+        TxBundleView: {
+            /**
+             * @description Unix time of the database snapshot
+             * Format: int64
+             */
+            generated_at: number;
+            tx: components["schemas"]["TxView"];
+            block: components["schemas"]["BlockView"] | null;
+            inputs: components["schemas"]["InputView"][];
+            outputs: components["schemas"]["OutputView"][];
+            rings: components["schemas"]["ResolvedRingSetView"][];
+            status: components["schemas"]["BundleStatus"];
+        };
+        RingUseView: {
+            tx_hash: string;
+            /** Format: int64 */
+            block_height: number;
+            input_idx: number;
+            /** @description Position of the output within that input's ring */
+            ring_index: number;
+        };
+        RingUsePage: {
+            /** Format: int64 */
+            global_index: number;
+            items: components["schemas"]["RingUseView"][];
+            /** @description Opaque; pass back as `cursor` with the same window */
+            next_cursor: string | null;
+        };
         MempoolView: {
-            hash?: string | null;
+            hash: string;
             /** Format: int64 */
             first_seen?: number | null;
             /** Format: int64 */
             last_seen?: number | null;
             fee_rate?: string | null;
             relayed_by?: string | null;
+            size_bytes?: number | null;
+            relayed?: boolean | null;
+            do_not_relay?: boolean | null;
+            double_spend_seen?: boolean | null;
+            /**
+             * @description Seconds since `first_seen` when served; only present with `?age=true`
+             * Format: int64
+             */
+            age_seconds?: number;
+        };
+        BlockPage: {
+            items: components["schemas"]["BlockView"][];
+            /**
+             * @description Chain tip when the listing started; null when no blocks are stored
+             * Format: int64
+             */
+            anchor_height: number | null;
+            /** @description Opaque; pass back as `cursor` to continue */
+            next_cursor: string | null;
+        };
+        MempoolPage: {
+            items: components["schemas"]["MempoolView"][];
+            /** @description Opaque; pass back as `cursor` with the same `sort` */
+            next_cursor: string | null;
+        };
+        TxFeeView: {
+            hash: string;
+            /** Format: int64 */
+            block_height: number;
+            /** Format: int32 */
+            block_position: number | null;
+            /** Format: int64 */
+            fee_nanos: number;
+            /** Format: int32 */
+            size_bytes: number;
+            fee_per_byte: number | null;
+        };
+        TxFeePage: {
+            items: components["schemas"]["TxFeeView"][];
+            /** Format: int64 */
+            from_height: number;
+            /** Format: int64 */
+            to_height: number;
+            /** @description Opaque; pass back as `cursor` with the same filters */
+            next_cursor: string | null;
         };
         KeyImageView: {
-            key_image?: string | null;
-            spending_tx?: string | null;
+            key_image: string;
+            spending_tx: string;
             /** Format: int64 */
             block_height?: number | null;
         };
+        TipView: {
+            /** Format: int64 */
+            height: number;
+            hash: string;
+            prev_hash: string;
+            /** Format: int64 */
+            updated_at?: number | null;
+        };
+        /** @description Fee rates in atomic units per byte; percentiles are null for an empty sample. */
+        FeePercentiles: {
+            /** Format: int64 */
+            sample_size: number;
+            p10: number | null;
+            p25: number | null;
+            p50: number | null;
+            p75: number | null;
+            p90: number | null;
+        };
+        DaemonFeeView: {
+            /**
+             * @description Consensus base fee in atomic units per byte
+             * Format: int64
+             */
+            fee_per_byte: number;
+            /** @description Per-priority fees, lowest first; empty on daemons that omit them */
+            fees: number[];
+            /**
+             * @description Wallets round fees up to a multiple of this
+             * Format: int64
+             */
+            quantization_mask: number;
+            /**
+             * @description When the ingestor last polled the daemon (epoch seconds)
+             * Format: int64
+             */
+            updated_at: number | null;
+        };
+        BaseFeeView: {
+            /** @description Null until the ingestor has polled `get_fee_estimate` */
+            daemon: components["schemas"]["DaemonFeeView"] | null;
+            mempool: components["schemas"]["FeePercentiles"];
+            recent_blocks: components["schemas"]["FeePercentiles"];
+            /**
+             * @description Ingested blocks sampled for `recent_blocks` (miner txs excluded)
+             * Format: int64
+             */
+            window_blocks: number;
+        };
+        StatusView: {
+            pruned: boolean;
+            /** Format: int64 */
+            tip_height: number | null;
+            /**
+             * @description Daemon tip at the ingestor's last poll. Runs ahead of `tip_height` by the blocks the ingestor holds back with `--tip-confirmations`.
+             * Format: int64
+             */
+            daemon_tip_height: number | null;
+            /** @description Last probe of the daemon behind the API's proxy features; null when `DAEMON_URL` is unset. */
+            daemon: components["schemas"]["DaemonHealthView"] | null;
+        };
+        DaemonHealthView: {
+            reachable: boolean;
+            /** @description Reachable and within `max_lag` blocks of `db_height` */
+            healthy: boolean;
+            /** Format: int64 */
+            height: number | null;
+            /** Format: int64 */
+            db_height: number | null;
+            /**
+             * @description `height` minus `db_height`
+             * Format: int64
+             */
+            lag: number | null;
+            /** Format: int64 */
+            max_lag: number;
+            /**
+             * @description Unix time of the last probe; null before the first
+             * Format: int64
+             */
+            checked_at: number | null;
+            error: string | null;
+        };
+        ReadinessView: {
+            /** @enum {string} */
+            status: "ok" | "degraded" | "unavailable";
+            database: boolean;
+            cache: boolean;
+            daemon: components["schemas"]["DaemonHealthView"] | null;
+        };
+        RctOffsetsView: {
+            /**
+             * @description Always 0 (RingCT outputs)
+             * Format: int64
+             */
+            amount: number;
+            /** Format: int64 */
+            start_height: number;
+            /** Format: int64 */
+            base: number;
+            distribution: number[];
+        };
+        TopBlockEntry: {
+            rank: number;
+            /** Format: int64 */
+            height: number;
+            hash: string;
+            /**
+             * @description Transaction count or size in bytes, depending on `by`
+             * Format: int64
+             */
+            value: number;
+        };
+        MiningDayView: {
+            /**
+             * @description Unix time of the start of the UTC day
+             * Format: int64
+             */
+            day: number;
+            /** Format: int32 */
+            blocks: number;
+            nonce_buckets: number[];
+            nonce_chi2: number;
+            avg_difficulty: number | null;
+            implied_hashrate: number | null;
+            mean_solve_secs: number | null;
+            residual_mean_abs_secs: number | null;
+            residual_stddev_secs: number | null;
+        };
+        MiningStatsView: {
+            /** Format: int64 */
+            computed_at: number | null;
+            days: components["schemas"]["MiningDayView"][];
+        };
+        TopBlocksView: {
+            by: string;
+            /** Format: int64 */
+            computed_at: number | null;
+            entries: components["schemas"]["TopBlockEntry"][];
+        };
+        TopOutputEntry: {
+            rank: number;
+            /** Format: int64 */
+            global_index: number | null;
+            tx_hash: string;
+            /**
+             * @description Number of ring members referencing the output
+             * Format: int64
+             */
+            value: number;
+        };
+        TopOutputsView: {
+            by: string;
+            /** Format: int64 */
+            computed_at: number | null;
+            entries: components["schemas"]["TopOutputEntry"][];
+        };
+        FieldDoc: {
+            name: string;
+            /** @enum {string} */
+            type: "integer" | "string" | "boolean" | "number";
+            nullable: boolean;
+            /** @description Key into SchemaView.units */
+            unit?: string;
+            /** @enum {string} */
+            encoding?: "hex" | "unix_epoch" | "json" | "decimal";
+            description: string;
+        };
+        EntityDoc: {
+            entity: string;
+            fields: components["schemas"]["FieldDoc"][];
+        };
+        SchemaView: {
+            units: {
+                [key: string]: string;
+            };
+            entities: components["schemas"]["EntityDoc"][];
+        };
+        ReingestView: {
+            /** Format: int64 */
+            height: number;
+            /** @enum {string} */
+            status: "queued" | "running" | "done" | "failed";
+            /** Format: int64 */
+            requested_at?: number | null;
+        };
+        UsageView: {
+            /**
+             * @description Start of the bucket (epoch seconds)
+             * Format: int64
+             */
+            bucket: number;
+            /** @description Hash prefix of the caller's X-API-Key if it is one of `API_KEYS`, otherwise `anonymous` */
+            api_key: string;
+            route: string;
+            /** Format: int64 */
+            requests: number;
+            /**
+             * @description Responses with a 4xx or 5xx status
+             * Format: int64
+             */
+            errors: number;
+            /**
+             * @description Responses with status 429
+             * Format: int64
+             */
+            rate_limited: number;
+            error_rate: number;
+        };
+        QuotaView: {
+            /** @description Hash prefix of the caller's X-API-Key if it is one of `API_KEYS`, `ip:<address>` for other callers, `overflow` while too many callers are tracked, or `anonymous` */
+            caller: string;
+            /**
+             * @description Requests allowed per window (`RateLimit-Limit`)
+             * Format: int64
+             */
+            limit: number;
+            /**
+             * @description Requests counted in the current window, including this one
+             * Format: int64
+             */
+            used: number;
+            /**
+             * @description Requests left in the current window (`RateLimit-Remaining`)
+             * Format: int64
+             */
+            remaining: number;
+            /** Format: int64 */
+            window_secs: number;
+            /**
+             * @description Seconds until the window resets (`RateLimit-Reset`)
+             * Format: int64
+             */
+            reset_secs: number;
+        };
         SearchResult: {
             /** @enum {string} */
             kind: "block" | "tx" | "key_image" | "height" | "global_index";
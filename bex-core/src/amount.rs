@@ -0,0 +1,63 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Atomic units (piconero) in one XMR.
+pub const ATOMIC_PER_XMR: u64 = 1_000_000_000_000;
+const XMR_DECIMALS: usize = 12;
+
+/// An amount in atomic units. Serializes as the bare integer, matching the
+/// `*_nanos` fields of the API.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Atomic(pub u64);
+
+impl Atomic {
+    /// Fixed-point XMR string with all twelve decimals, e.g. `"0.000030000000"`.
+    pub fn to_xmr_string(self) -> String {
+        format!(
+            "{}.{:0width$}",
+            self.0 / ATOMIC_PER_XMR,
+            self.0 % ATOMIC_PER_XMR,
+            width = XMR_DECIMALS
+        )
+    }
+}
+
+impl From<u64> for Atomic {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl TryFrom<i64> for Atomic {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        u64::try_from(value).map(Self)
+    }
+}
+
+impl fmt::Display for Atomic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_fixed_point_xmr() {
+        assert_eq!(Atomic(0).to_xmr_string(), "0.000000000000");
+        assert_eq!(Atomic(30_000_000).to_xmr_string(), "0.000030000000");
+        assert_eq!(
+            Atomic(600_000_000_000 + ATOMIC_PER_XMR).to_xmr_string(),
+            "1.600000000000"
+        );
+        assert!(Atomic::try_from(-1_i64).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Fixed 32-byte identifiers. They parse from and serialize to lowercase hex,
+//! so a truncated or malformed hash is rejected where it enters the system
+//! instead of being padded or stored as-is.
+
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::hex::{decode_fixed, HexError};
+
+macro_rules! hash32 {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+        pub struct $name(pub [u8; 32]);
+
+        impl $name {
+            pub fn from_hex(value: &str) -> Result<Self, HexError> {
+                decode_fixed(value).map(Self)
+            }
+
+            pub fn as_bytes(&self) -> &[u8; 32] {
+                &self.0
+            }
+
+            pub fn to_hex(&self) -> String {
+                crate::hex::encode(self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = HexError;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                Self::from_hex(value)
+            }
+        }
+
+        impl TryFrom<&[u8]> for $name {
+            type Error = HexError;
+
+            fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+                <[u8; 32]>::try_from(value).map(Self).map_err(|_| HexError::Length {
+                    expected: 64,
+                    actual: value.len() * 2,
+                })
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.to_hex())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, concat!(stringify!($name), "({})"), self)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.to_hex())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+                Self::from_hex(&value).map_err(de::Error::custom)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl sqlx::Type<sqlx::Postgres> for $name {
+            fn type_info() -> sqlx::postgres::PgTypeInfo {
+                <Vec<u8> as sqlx::Type<sqlx::Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+                <Vec<u8> as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl sqlx::postgres::PgHasArrayType for $name {
+            fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+                <Vec<u8> as sqlx::postgres::PgHasArrayType>::array_type_info()
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'q> sqlx::Encode<'q, sqlx::Postgres> for $name {
+            fn encode_by_ref(
+                &self,
+                buf: &mut sqlx::postgres::PgArgumentBuffer,
+            ) -> sqlx::encode::IsNull {
+                <&[u8] as sqlx::Encode<sqlx::Postgres>>::encode(self.0.as_slice(), buf)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'r> sqlx::Decode<'r, sqlx::Postgres> for $name {
+            fn decode(
+                value: sqlx::postgres::PgValueRef<'r>,
+            ) -> Result<Self, sqlx::error::BoxDynError> {
+                let bytes = <&[u8] as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+                Ok(Self::try_from(bytes)?)
+            }
+        }
+    };
+}
+
+hash32!(
+    /// Block id (`blocks.hash`, `prev_hash`).
+    BlockHash
+);
+hash32!(
+    /// Transaction id (`txs.tx_hash`).
+    TxHash
+);
+hash32!(
+    /// Key image of a spent output (`tx_inputs.key_image`).
+    KeyImage
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_roundtrips_as_hex_and_rejects_bad_length() {
+        let hex = "ab".repeat(32);
+        let hash: BlockHash = serde_json::from_value(serde_json::json!(hex)).unwrap();
+        assert_eq!(hash.0, [0xab; 32]);
+        assert_eq!(serde_json::to_value(hash).unwrap(), serde_json::json!(hex));
+
+        assert!(serde_json::from_value::<TxHash>(serde_json::json!("ab")).is_err());
+        assert!(TxHash::try_from(&[0u8; 31][..]).is_err());
+        assert_eq!(KeyImage::from_hex(&"AB".repeat(32)).unwrap().0, [0xab; 32]);
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+use crate::BlockHash;
+
+/// Block header as reported by monerod (`get_block_header_by_height`,
+/// `get_block_headers_range`, `get_block`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub hash: BlockHash,
+    pub height: u64,
+    pub timestamp: u64,
+    pub prev_hash: BlockHash,
+    pub major_version: u32,
+    pub minor_version: u32,
+    pub nonce: u64,
+    pub reward: u64,
+    #[serde(default, alias = "block_size")]
+    pub size: u64,
+    /// Low 64 bits of the block's difficulty; `0` when the daemon omits it.
+    #[serde(default)]
+    pub difficulty: u64,
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+/// Hex-encoded length of a 32-byte hash, key image or key.
+pub const HASH_HEX_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HexError {
+    Length { expected: usize, actual: usize },
+    InvalidDigit,
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::Length { expected, actual } => {
+                write!(f, "expected {expected} hex characters, got {actual}")
+            }
+            HexError::InvalidDigit => write!(f, "invalid hex digit"),
+        }
+    }
+}
+
+impl std::error::Error for HexError {}
+
+/// True when `value` is exactly `bytes` bytes of hex, in either case.
+pub fn is_hex_of_len(value: &str, bytes: usize) -> bool {
+    value.len() == bytes * 2 && value.bytes().all(|c| c.is_ascii_hexdigit())
+}
+
+pub fn is_hex_64(value: &str) -> bool {
+    is_hex_of_len(value, HASH_HEX_LEN / 2)
+}
+
+/// Decodes exactly `N` bytes of hex, rejecting short or long input instead of
+/// padding it.
+pub fn decode_fixed<const N: usize>(value: &str) -> Result<[u8; N], HexError> {
+    if value.len() != N * 2 {
+        return Err(HexError::Length {
+            expected: N * 2,
+            actual: value.len(),
+        });
+    }
+    let mut out = [0u8; N];
+    hex::decode_to_slice(value, &mut out).map_err(|_| HexError::InvalidDigit)?;
+    Ok(out)
+}
+
+pub fn encode(bytes: impl AsRef<[u8]>) -> String {
+    hex::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_fixed_rejects_wrong_length() {
+        assert_eq!(decode_fixed::<2>("abcd"), Ok([0xab, 0xcd]));
+        assert_eq!(
+            decode_fixed::<2>("abc"),
+            Err(HexError::Length {
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_eq!(decode_fixed::<2>("zzzz"), Err(HexError::InvalidDigit));
+        assert!(is_hex_64(&"Ab".repeat(32)));
+        assert!(!is_hex_64(&"ab".repeat(31)));
+    }
+}
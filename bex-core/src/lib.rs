@@ -0,0 +1,16 @@
+//! Domain types shared by the ingestor, the API and anything else that speaks
+//! the explorer's data model (event consumers, client SDKs), plus the startup
+//! diagnostics both binaries report through, the daemon's JSON-RPC envelope
+//! and the signing scheme for outbound event payloads.
+
+pub mod amount;
+pub mod hash;
+pub mod header;
+pub mod hex;
+pub mod preflight;
+pub mod rpc;
+pub mod webhook;
+
+pub use amount::Atomic;
+pub use hash::{BlockHash, KeyImage, TxHash};
+pub use header::BlockHeader;
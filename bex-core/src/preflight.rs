@@ -0,0 +1,197 @@
+use std::{fmt, time::Duration};
+
+/// Outcome of the startup checks a binary runs before doing any work. Every
+/// check is recorded so operators see all problems in one report instead of
+/// the first error surfacing deep inside a worker.
+pub struct Report {
+    component: &'static str,
+    checks: Vec<Check>,
+}
+
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+impl Report {
+    pub fn new(component: &'static str) -> Self {
+        Self {
+            component,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Records a check; `Ok` carries a short detail, `Err` the reason it failed.
+    /// Returns whether it passed so dependent checks can be skipped.
+    pub fn check(&mut self, name: impl Into<String>, outcome: Result<String, String>) -> bool {
+        let (status, detail) = match outcome {
+            Ok(detail) => (Status::Ok, detail),
+            Err(reason) => (Status::Failed, reason),
+        };
+        self.checks.push(Check {
+            name: name.into(),
+            status,
+            detail,
+        });
+        status == Status::Ok
+    }
+
+    /// Records a check that was not attempted because a prerequisite failed.
+    pub fn skip(&mut self, name: impl Into<String>, reason: impl Into<String>) {
+        self.checks.push(Check {
+            name: name.into(),
+            status: Status::Skipped,
+            detail: reason.into(),
+        });
+    }
+
+    pub fn failures(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|c| c.status == Status::Failed)
+            .count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failures = self.failures();
+        if failures == 0 {
+            writeln!(f, "{} startup checks passed", self.component)?;
+        } else {
+            writeln!(
+                f,
+                "{} startup checks failed ({failures} of {})",
+                self.component,
+                self.checks.len()
+            )?;
+        }
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        for check in &self.checks {
+            let label = match check.status {
+                Status::Ok => "ok",
+                Status::Failed => "FAIL",
+                Status::Skipped => "skip",
+            };
+            writeln!(f, "  {label:<4}  {:<width$}  {}", check.name, check.detail)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// How long a reachability check may take before it counts as failed.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs a reachability check for [`Report::check`], failing it when `fut`
+/// errors or takes longer than [`PROBE_TIMEOUT`].
+#[cfg(feature = "probe")]
+pub async fn probe<F>(fut: F) -> Result<String, String>
+where
+    F: std::future::Future<Output = anyhow::Result<String>>,
+{
+    match tokio::time::timeout(PROBE_TIMEOUT, fut).await {
+        Ok(Ok(detail)) => Ok(detail),
+        Ok(Err(err)) => Err(format!("{err:#}")),
+        Err(_) => Err(format!("no answer within {}s", PROBE_TIMEOUT.as_secs())),
+    }
+}
+
+/// Network names the explorer understands.
+pub const NETWORKS: &[&str] = &["mainnet", "stagenet", "testnet", "devnet"];
+
+pub fn network(name: &str) -> Result<String, String> {
+    if NETWORKS.contains(&name) {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "unknown network {name:?}, expected one of {}",
+            NETWORKS.join(", ")
+        ))
+    }
+}
+
+/// Checks that the daemon's `get_info` `nettype` is the chain `network`
+/// names. `devnet` runs against a `--regtest` daemon, which reports
+/// `fakechain`.
+pub fn daemon_network(network: &str, nettype: &str) -> Result<String, String> {
+    let expected = match network {
+        "devnet" => "fakechain",
+        other => other,
+    };
+    if nettype == expected {
+        Ok(format!("daemon is on {nettype}"))
+    } else {
+        Err(format!(
+            "daemon is on {nettype}, but NETWORK is {network} (expects {expected})"
+        ))
+    }
+}
+
+/// Checks that `url` starts with one of `schemes` followed by `://` and a
+/// non-empty remainder. Connectivity is checked separately.
+pub fn url_scheme(url: &str, schemes: &[&str]) -> Result<String, String> {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Err("not a URL (missing scheme://)".to_string());
+    };
+    if !schemes.contains(&scheme) {
+        return Err(format!(
+            "scheme {scheme:?} not supported, expected one of {}",
+            schemes.join(", ")
+        ));
+    }
+    if rest.is_empty() {
+        return Err("missing host".to_string());
+    }
+    Ok(format!("{scheme} URL"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_every_check() {
+        let mut report = Report::new("ingestor");
+        assert!(report.check("DATABASE_URL", url_scheme("postgres://db/x", &["postgres"])));
+        assert!(!report.check("XMR_RPC_URL", url_scheme("localhost:18081", &["http"])));
+        report.skip("daemon", "XMR_RPC_URL invalid");
+
+        assert_eq!(report.failures(), 1);
+        let text = report.to_string();
+        assert!(text.starts_with("ingestor startup checks failed (1 of 3)"));
+        assert!(text.contains("ok    DATABASE_URL"));
+        assert!(text.contains("FAIL  XMR_RPC_URL   not a URL"));
+        assert!(text.contains("skip  daemon"));
+    }
+
+    #[test]
+    fn daemon_network_must_match() {
+        assert!(daemon_network("mainnet", "mainnet").is_ok());
+        assert!(daemon_network("devnet", "fakechain").is_ok());
+        assert_eq!(
+            daemon_network("stagenet", "mainnet").unwrap_err(),
+            "daemon is on mainnet, but NETWORK is stagenet (expects stagenet)"
+        );
+    }
+
+    #[test]
+    fn url_scheme_rejects_unknown_schemes() {
+        assert!(url_scheme("redis://cache:6379", &["redis", "rediss"]).is_ok());
+        assert!(url_scheme("http://cache:6379", &["redis"]).is_err());
+        assert!(url_scheme("tcp://", &["tcp"]).is_err());
+    }
+}
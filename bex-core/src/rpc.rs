@@ -0,0 +1,123 @@
+//! monerod's JSON-RPC envelope (`/json_rpc`), shared by the ingestor's
+//! daemon client and the API's daemon probe. Sending the request is left to
+//! the caller's HTTP client.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct Request<'a, P> {
+    jsonrpc: &'a str,
+    id: u64,
+    method: &'a str,
+    params: P,
+}
+
+impl<'a, P: Serialize> Request<'a, P> {
+    pub fn new(method: &'a str, params: P) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id: 1,
+            method,
+            params,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+// Plain struct rather than an untagged enum: serde buffers untagged input
+// into an intermediate tree, doubling the footprint of large results.
+#[derive(Debug, Deserialize)]
+pub struct Response<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+impl<T> Response<T> {
+    pub fn into_result(self) -> Result<T, CallError> {
+        match self {
+            Response {
+                result: Some(result),
+                ..
+            } => Ok(result),
+            Response {
+                error: Some(error), ..
+            } => Err(CallError::Rpc(error)),
+            Response { .. } => Err(CallError::Empty),
+        }
+    }
+}
+
+/// A response that carried no result. Displays without the method name,
+/// which callers prefix.
+#[derive(Debug)]
+pub enum CallError {
+    Rpc(RpcError),
+    Empty,
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Rpc(error) => write!(f, "error {}: {}", error.code, error.message),
+            CallError::Empty => f.write_str("returned neither result nor error"),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// `get_block_count`: the chain length, one more than the tip height.
+#[derive(Debug, Deserialize)]
+pub struct GetBlockCountResult {
+    pub count: u64,
+    pub status: String,
+}
+
+/// `get_info`, reduced to what startup checks read. `nettype` is `mainnet`,
+/// `stagenet`, `testnet`, or `fakechain` for a `--regtest` daemon.
+#[derive(Debug, Deserialize)]
+pub struct GetInfoResult {
+    pub height: u64,
+    pub nettype: String,
+    pub status: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope_round_trip() {
+        let request = serde_json::to_value(Request::new("get_block_count", ())).unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "get_block_count", "params": null})
+        );
+
+        let ok: Response<GetBlockCountResult> = serde_json::from_str(
+            r#"{"id":1,"jsonrpc":"2.0","result":{"count":993163,"status":"OK"}}"#,
+        )
+        .unwrap();
+        assert_eq!(ok.into_result().unwrap().count, 993163);
+
+        let err: Response<GetBlockCountResult> = serde_json::from_str(
+            r#"{"id":1,"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            err.into_result().unwrap_err().to_string(),
+            "error -32601: Method not found"
+        );
+
+        let empty: Response<GetBlockCountResult> =
+            serde_json::from_str(r#"{"id":1,"jsonrpc":"2.0"}"#).unwrap();
+        assert!(matches!(empty.into_result(), Err(CallError::Empty)));
+    }
+}
@@ -0,0 +1,199 @@
+//! Signing and verification of outbound event payloads.
+//!
+//! Each delivery carries three headers: [`TIMESTAMP_HEADER`] (unix seconds),
+//! [`SEQUENCE_HEADER`] (per-subscription counter, strictly increasing) and
+//! [`SIGNATURE_HEADER`] (`v1=` followed by the hex HMAC-SHA256 of
+//! `"{timestamp}.{sequence}.{body}"` keyed with the subscription secret).
+//! Senders use [`sign`]; consumers keep one [`Verifier`] per subscription.
+
+use std::fmt;
+
+use hmac::{digest::InvalidLength, Hmac, Mac};
+use sha2::Sha256;
+
+pub const TIMESTAMP_HEADER: &str = "x-bex-timestamp";
+pub const SEQUENCE_HEADER: &str = "x-bex-sequence";
+pub const SIGNATURE_HEADER: &str = "x-bex-signature";
+
+const SCHEME: &str = "v1=";
+
+/// Deliveries older (or newer) than this are rejected by default.
+pub const DEFAULT_TOLERANCE_SECS: i64 = 300;
+
+/// Value for [`SIGNATURE_HEADER`].
+pub fn sign(
+    secret: &[u8],
+    timestamp: i64,
+    sequence: u64,
+    body: &[u8],
+) -> Result<String, InvalidLength> {
+    let mac = mac(secret, timestamp, sequence, body)?;
+    Ok(format!(
+        "{SCHEME}{}",
+        crate::hex::encode(mac.finalize().into_bytes())
+    ))
+}
+
+fn mac(
+    secret: &[u8],
+    timestamp: i64,
+    sequence: u64,
+    body: &[u8],
+) -> Result<Hmac<Sha256>, InvalidLength> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret)?;
+    mac.update(format!("{timestamp}.{sequence}.").as_bytes());
+    mac.update(body);
+    Ok(mac)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    /// Header missing the `v1=` scheme or not hex.
+    Malformed,
+    /// The subscription secret was rejected as an HMAC key.
+    InvalidKey,
+    BadSignature,
+    /// Timestamp further than the tolerance from the consumer's clock.
+    Stale {
+        age_secs: i64,
+    },
+    /// Sequence not above the last accepted one: a replay or reordering.
+    Replayed {
+        sequence: u64,
+        last: u64,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Malformed => write!(f, "malformed signature header"),
+            VerifyError::InvalidKey => write!(f, "secret is not a valid HMAC key"),
+            VerifyError::BadSignature => write!(f, "signature does not match payload"),
+            VerifyError::Stale { age_secs } => {
+                write!(f, "timestamp is {age_secs}s away from local clock")
+            }
+            VerifyError::Replayed { sequence, last } => {
+                write!(f, "sequence {sequence} not above last accepted {last}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Consumer-side state for one subscription: the shared secret, the accepted
+/// clock skew and the highest sequence seen so far.
+pub struct Verifier {
+    secret: Vec<u8>,
+    tolerance_secs: i64,
+    last_sequence: Option<u64>,
+}
+
+impl Verifier {
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+            tolerance_secs: DEFAULT_TOLERANCE_SECS,
+            last_sequence: None,
+        }
+    }
+
+    pub fn with_tolerance_secs(mut self, secs: i64) -> Self {
+        self.tolerance_secs = secs;
+        self
+    }
+
+    /// Resumes replay protection from a sequence persisted by the consumer.
+    pub fn with_last_sequence(mut self, sequence: u64) -> Self {
+        self.last_sequence = Some(sequence);
+        self
+    }
+
+    pub fn last_sequence(&self) -> Option<u64> {
+        self.last_sequence
+    }
+
+    /// Checks one delivery against `now` (unix seconds). The sequence is only
+    /// recorded once the signature and timestamp are valid.
+    pub fn verify(
+        &mut self,
+        timestamp: i64,
+        sequence: u64,
+        signature: &str,
+        body: &[u8],
+        now: i64,
+    ) -> Result<(), VerifyError> {
+        let expected = signature
+            .strip_prefix(SCHEME)
+            .and_then(|hex| crate::hex::decode_fixed::<32>(hex).ok())
+            .ok_or(VerifyError::Malformed)?;
+        mac(&self.secret, timestamp, sequence, body)
+            .map_err(|_| VerifyError::InvalidKey)?
+            .verify_slice(&expected)
+            .map_err(|_| VerifyError::BadSignature)?;
+
+        let age_secs = now - timestamp;
+        if age_secs.abs() > self.tolerance_secs {
+            return Err(VerifyError::Stale { age_secs });
+        }
+        if let Some(last) = self.last_sequence {
+            if sequence <= last {
+                return Err(VerifyError::Replayed { sequence, last });
+            }
+        }
+        self.last_sequence = Some(sequence);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_payload_verifies_once() {
+        let body = br#"{"type":"block","height":10}"#;
+        let sig = sign(b"secret", 1_000, 7, body).unwrap();
+        assert!(sig.starts_with("v1="));
+
+        let mut verifier = Verifier::new("secret");
+        assert_eq!(verifier.verify(1_000, 7, &sig, body, 1_010), Ok(()));
+        assert_eq!(
+            verifier.verify(1_000, 7, &sig, body, 1_010),
+            Err(VerifyError::Replayed {
+                sequence: 7,
+                last: 7
+            })
+        );
+    }
+
+    #[test]
+    fn tampering_and_old_deliveries_are_rejected() {
+        let body = b"{}";
+        let sig = sign(b"secret", 1_000, 1, body).unwrap();
+        let mut verifier = Verifier::new("secret");
+
+        assert_eq!(
+            verifier.verify(1_000, 1, &sig, b"{ }", 1_000),
+            Err(VerifyError::BadSignature)
+        );
+        assert_eq!(
+            verifier.verify(1_000, 2, &sig, body, 1_000),
+            Err(VerifyError::BadSignature)
+        );
+        assert_eq!(
+            Verifier::new("other").verify(1_000, 1, &sig, body, 1_000),
+            Err(VerifyError::BadSignature)
+        );
+        assert_eq!(
+            verifier.verify(1_000, 1, &sig, body, 2_000),
+            Err(VerifyError::Stale { age_secs: 1_000 })
+        );
+        assert_eq!(
+            verifier.verify(1_000, 1, "deadbeef", body, 1_000),
+            Err(VerifyError::Malformed)
+        );
+        assert_eq!(verifier.last_sequence(), None);
+    }
+}
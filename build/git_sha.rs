@@ -0,0 +1,31 @@
+//! Build script of both binaries: exposes the commit they are built from as
+//! `GIT_SHA`, for the API's OpenAPI document and the ingestor's startup
+//! banner and `build_info` metric. An explicit `GIT_SHA` (e.g. a Docker
+//! build arg) wins; otherwise it is read from git, and `unknown` outside a
+//! checkout.
+
+use std::{path::Path, process::Command};
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    for path in ["../.git/HEAD", "../.git/refs/heads"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={path}");
+        }
+    }
+
+    let sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| {
+            let out = Command::new("git")
+                .args(["rev-parse", "--short=12", "HEAD"])
+                .output()
+                .ok()?;
+            out.status
+                .success()
+                .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_SHA={sha}");
+}
@@ -0,0 +1,18 @@
+-- migrate:up
+CREATE TABLE IF NOT EXISTS public.current_tip (
+  id         INTEGER     PRIMARY KEY DEFAULT 1 CHECK (id=1),
+  height     BIGINT      NOT NULL,
+  hash       BYTEA       NOT NULL,
+  prev_hash  BYTEA       NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Seed from the highest recorded tip so existing deployments expose a tip immediately
+INSERT INTO public.current_tip (id, height, hash, prev_hash)
+SELECT 1, height, hash, prev_hash FROM public.chain_tips
+ORDER BY height DESC
+LIMIT 1
+ON CONFLICT (id) DO NOTHING;
+
+-- migrate:down
+DROP TABLE IF EXISTS public.current_tip;
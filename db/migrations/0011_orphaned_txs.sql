@@ -0,0 +1,21 @@
+-- migrate:up
+-- Set when a reorg detaches a tx from its block; cleared when it reconfirms.
+ALTER TABLE public.txs ADD COLUMN IF NOT EXISTS orphaned_at TIMESTAMPTZ NULL;
+CREATE INDEX IF NOT EXISTS idx_txs_orphaned_at ON public.txs (orphaned_at) WHERE orphaned_at IS NOT NULL;
+
+-- block_timestamp is part of the partition key and cannot be NULL; unconfirmed
+-- txs use the column default ('infinity') instead.
+ALTER TABLE public.txs DROP CONSTRAINT IF EXISTS chk_mempool_flag;
+ALTER TABLE public.txs
+  ADD CONSTRAINT chk_mempool_flag
+  CHECK ((in_mempool = TRUE AND block_height IS NULL AND block_timestamp = 'infinity')
+         OR (in_mempool = FALSE));
+
+-- migrate:down
+ALTER TABLE public.txs DROP CONSTRAINT IF EXISTS chk_mempool_flag;
+ALTER TABLE public.txs
+  ADD CONSTRAINT chk_mempool_flag
+  CHECK ((in_mempool = TRUE AND block_height IS NULL AND block_timestamp IS NULL)
+         OR (in_mempool = FALSE));
+DROP INDEX IF EXISTS idx_txs_orphaned_at;
+ALTER TABLE public.txs DROP COLUMN IF EXISTS orphaned_at;
@@ -0,0 +1,18 @@
+-- migrate:up
+-- Operator-requested re-ingestion of single heights, filled by the API and
+-- drained by the ingestor.
+CREATE TABLE IF NOT EXISTS public.reingest_requests (
+  height       BIGINT      PRIMARY KEY,
+  status       TEXT        NOT NULL DEFAULT 'queued'
+               CHECK (status IN ('queued', 'running', 'done', 'failed')),
+  attempts     INTEGER     NOT NULL DEFAULT 0,
+  last_error   TEXT        NULL,
+  requested_by TEXT        NULL,
+  requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_reingest_requests_queued
+  ON public.reingest_requests (requested_at) WHERE status = 'queued';
+
+-- migrate:down
+DROP TABLE IF EXISTS public.reingest_requests;
@@ -0,0 +1,6 @@
+-- migrate:up
+-- Earliest mempool sighting, carried over from mempool_txs when the tx is included.
+ALTER TABLE public.txs ADD COLUMN IF NOT EXISTS first_seen TIMESTAMPTZ NULL;
+
+-- migrate:down
+ALTER TABLE public.txs DROP COLUMN IF EXISTS first_seen;
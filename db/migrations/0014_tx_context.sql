@@ -0,0 +1,19 @@
+-- migrate:up
+-- Index of the tx within its block's tx_hashes (miner tx excluded).
+ALTER TABLE public.txs ADD COLUMN IF NOT EXISTS block_position INTEGER NULL;
+
+-- Blocks a tx was detached from by reorg healing, kept for support lookups and
+-- purged together with orphaned txs.
+CREATE TABLE IF NOT EXISTS public.orphaned_tx_blocks (
+  tx_hash      BYTEA       NOT NULL,
+  block_height BIGINT      NOT NULL,
+  block_hash   BYTEA       NOT NULL,
+  orphaned_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (tx_hash, block_hash)
+);
+CREATE INDEX IF NOT EXISTS idx_orphaned_tx_blocks_orphaned_at
+  ON public.orphaned_tx_blocks (orphaned_at);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.orphaned_tx_blocks;
+ALTER TABLE public.txs DROP COLUMN IF EXISTS block_position;
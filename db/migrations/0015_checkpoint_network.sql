@@ -0,0 +1,18 @@
+-- migrate:up
+-- One checkpoint row per network. The pre-existing id=1 row keeps a NULL
+-- network until the first ingestor run claims it.
+ALTER TABLE ingestor_checkpoint ADD COLUMN IF NOT EXISTS network TEXT NULL;
+ALTER TABLE ingestor_checkpoint DROP CONSTRAINT IF EXISTS ingestor_checkpoint_pkey;
+ALTER TABLE ingestor_checkpoint DROP COLUMN IF EXISTS id;
+ALTER TABLE ingestor_checkpoint
+  ADD CONSTRAINT uq_ingestor_checkpoint_network UNIQUE (network);
+
+-- migrate:down
+DELETE FROM ingestor_checkpoint
+WHERE network IS DISTINCT FROM (
+  SELECT network FROM ingestor_checkpoint ORDER BY updated_at DESC LIMIT 1
+);
+ALTER TABLE ingestor_checkpoint DROP CONSTRAINT IF EXISTS uq_ingestor_checkpoint_network;
+ALTER TABLE ingestor_checkpoint DROP COLUMN IF EXISTS network;
+ALTER TABLE ingestor_checkpoint
+  ADD COLUMN id INTEGER PRIMARY KEY DEFAULT 1 CHECK (id=1);
@@ -0,0 +1,12 @@
+-- migrate:up
+-- RingCT outputs created per block with the running total, mirroring the
+-- daemon's get_output_distribution for amount 0. cumulative stays NULL until
+-- every height below it has been ingested.
+CREATE TABLE IF NOT EXISTS public.rct_output_counts (
+  height      BIGINT  PRIMARY KEY,
+  rct_outputs INTEGER NOT NULL,
+  cumulative  BIGINT  NULL
+);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.rct_output_counts;
@@ -0,0 +1,17 @@
+-- migrate:up
+-- Marker written before a block's persistence transaction opens and flipped to
+-- complete inside it, so a crash leaves an in_progress row the ingestor
+-- re-processes on its next start.
+CREATE TABLE IF NOT EXISTS public.block_ingest_state (
+  height       BIGINT      PRIMARY KEY,
+  hash         BYTEA       NOT NULL,
+  state        TEXT        NOT NULL
+               CHECK (state IN ('in_progress', 'complete')),
+  started_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  completed_at TIMESTAMPTZ NULL
+);
+CREATE INDEX IF NOT EXISTS idx_block_ingest_state_in_progress
+  ON public.block_ingest_state (height) WHERE state = 'in_progress';
+
+-- migrate:down
+DROP TABLE IF EXISTS public.block_ingest_state;
@@ -0,0 +1,18 @@
+-- migrate:up
+-- Per-minute API request counters, accumulated in Redis by the API and
+-- flushed here. api_key is a hash prefix of the caller's X-API-Key, or
+-- 'anonymous'; raw keys are never stored.
+CREATE TABLE IF NOT EXISTS public.api_usage (
+  bucket       TIMESTAMPTZ NOT NULL,
+  api_key      TEXT        NOT NULL,
+  route        TEXT        NOT NULL,
+  requests     BIGINT      NOT NULL DEFAULT 0,
+  errors       BIGINT      NOT NULL DEFAULT 0,
+  rate_limited BIGINT      NOT NULL DEFAULT 0,
+  PRIMARY KEY (bucket, api_key, route)
+);
+CREATE INDEX IF NOT EXISTS idx_api_usage_key_bucket
+  ON public.api_usage (api_key, bucket);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.api_usage;
@@ -0,0 +1,15 @@
+-- migrate:up
+-- Hex-prefix lookups for /api/v1/search. text_pattern_ops lets `LIKE 'ab12%'`
+-- use the index regardless of the database collation; full hashes hit the
+-- same indexes, so exact matches no longer scan every partition.
+CREATE INDEX IF NOT EXISTS idx_txs_tx_hash_hex
+  ON public.txs (encode(tx_hash, 'hex') text_pattern_ops);
+CREATE INDEX IF NOT EXISTS idx_blocks_hash_hex
+  ON public.blocks (encode(hash, 'hex') text_pattern_ops);
+CREATE INDEX IF NOT EXISTS idx_tx_inputs_key_image_hex
+  ON public.tx_inputs (encode(key_image, 'hex') text_pattern_ops);
+
+-- migrate:down
+DROP INDEX IF EXISTS public.idx_tx_inputs_key_image_hex;
+DROP INDEX IF EXISTS public.idx_blocks_hash_hex;
+DROP INDEX IF EXISTS public.idx_txs_tx_hash_hex;
@@ -0,0 +1,28 @@
+-- migrate:up
+-- Record tables rebuilt periodically by the analytics worker and served by
+-- /api/v1/stats/top-*. Entries keep the block hash so rows left behind by a
+-- reorg can be filtered out until the next rebuild.
+CREATE TABLE IF NOT EXISTS public.top_blocks (
+  metric       TEXT        NOT NULL CHECK (metric IN ('tx_count', 'size')),
+  rank         INTEGER     NOT NULL,
+  height       BIGINT      NOT NULL,
+  hash         BYTEA       NOT NULL,
+  value        BIGINT      NOT NULL,
+  computed_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (metric, rank)
+);
+
+CREATE TABLE IF NOT EXISTS public.top_outputs (
+  metric       TEXT        NOT NULL CHECK (metric IN ('ring_references')),
+  rank         INTEGER     NOT NULL,
+  output_id    BIGINT      NOT NULL,
+  global_index BIGINT      NULL,
+  tx_hash      BYTEA       NOT NULL,
+  value        BIGINT      NOT NULL,
+  computed_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (metric, rank)
+);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.top_outputs;
+DROP TABLE IF EXISTS public.top_blocks;
@@ -0,0 +1,7 @@
+-- migrate:up
+-- Serialized size of pool txs, for sorting /api/v1/mempool by size. NULL until
+-- the ingestor has reported it.
+ALTER TABLE public.mempool_txs ADD COLUMN IF NOT EXISTS size_bytes INTEGER NULL;
+
+-- migrate:down
+ALTER TABLE public.mempool_txs DROP COLUMN IF EXISTS size_bytes;
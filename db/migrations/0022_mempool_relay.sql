@@ -0,0 +1,13 @@
+-- migrate:up
+-- Relay bookkeeping reported by the daemon's get_transaction_pool. NULL for
+-- rows seen only through get_transaction_pool_hashes or requeued by a reorg.
+ALTER TABLE public.mempool_txs
+  ADD COLUMN IF NOT EXISTS relayed           BOOLEAN NULL,
+  ADD COLUMN IF NOT EXISTS do_not_relay      BOOLEAN NULL,
+  ADD COLUMN IF NOT EXISTS double_spend_seen BOOLEAN NULL;
+
+-- migrate:down
+ALTER TABLE public.mempool_txs
+  DROP COLUMN IF EXISTS double_spend_seen,
+  DROP COLUMN IF EXISTS do_not_relay,
+  DROP COLUMN IF EXISTS relayed;
@@ -0,0 +1,25 @@
+-- migrate:up
+-- Coins emitted per block (reward minus fees) with the running supply, used
+-- to check each block's reward against the emission curve. cumulative stays
+-- NULL until every height below it has been ingested.
+CREATE TABLE IF NOT EXISTS public.block_emission (
+  height      BIGINT  PRIMARY KEY,
+  base_reward BIGINT  NOT NULL,
+  cumulative  NUMERIC NULL
+);
+
+-- Blocks whose emitted amount differs from the emission schedule by more
+-- than the block size penalty can explain.
+CREATE TABLE IF NOT EXISTS public.emission_anomalies (
+  height        BIGINT      PRIMARY KEY,
+  block_hash    BYTEA       NOT NULL,
+  expected_base BIGINT      NOT NULL,
+  reported_base BIGINT      NOT NULL,
+  fees          BIGINT      NOT NULL,
+  deviation     BIGINT      NOT NULL,
+  detected_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.emission_anomalies;
+DROP TABLE IF EXISTS public.block_emission;
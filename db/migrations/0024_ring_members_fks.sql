@@ -0,0 +1,16 @@
+-- migrate:up
+-- ring_members was the only link table without foreign keys. Added NOT VALID
+-- so this migration takes its lock only briefly; existing rows are checked by
+-- 0038, in a transaction of its own whose validation scan does not block
+-- writes. The ingestor's --defer-constraints mode re-creates these NOT VALID
+-- together with the other txs/inputs/outputs/rings keys.
+ALTER TABLE public.ring_members
+  ADD CONSTRAINT fk_ring_members_tx_input FOREIGN KEY (tx_hash, input_idx)
+    REFERENCES public.tx_inputs (tx_hash, idx) ON DELETE CASCADE NOT VALID,
+  ADD CONSTRAINT fk_ring_members_output FOREIGN KEY (output_id)
+    REFERENCES public.outputs (output_id) ON DELETE RESTRICT NOT VALID;
+
+-- migrate:down
+ALTER TABLE public.ring_members
+  DROP CONSTRAINT IF EXISTS fk_ring_members_output,
+  DROP CONSTRAINT IF EXISTS fk_ring_members_tx_input;
@@ -0,0 +1,19 @@
+-- migrate:up
+-- Hard-fork rules (tx version, rct type, ring size) each tx breaks at the
+-- height it was mined. An empty array means compliant; NULL means unchecked,
+-- as for rows ingested before this column existed or still in the pool.
+ALTER TABLE public.txs
+  ADD COLUMN IF NOT EXISTS nonstandard BOOLEAN NULL,
+  ADD COLUMN IF NOT EXISTS nonstandard_reasons TEXT[] NULL;
+
+CREATE INDEX IF NOT EXISTS idx_txs_nonstandard
+  ON public.txs (block_height) WHERE nonstandard;
+
+ALTER TABLE public.soft_facts ADD COLUMN IF NOT EXISTS nonstandard_count INTEGER NULL;
+
+-- migrate:down
+ALTER TABLE public.soft_facts DROP COLUMN IF EXISTS nonstandard_count;
+DROP INDEX IF EXISTS public.idx_txs_nonstandard;
+ALTER TABLE public.txs
+  DROP COLUMN IF EXISTS nonstandard_reasons,
+  DROP COLUMN IF EXISTS nonstandard;
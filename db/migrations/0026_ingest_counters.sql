@@ -0,0 +1,11 @@
+-- migrate:up
+-- Cumulative ingestion counters, saved by the ingestor on an interval and on
+-- exit so Prometheus counters can resume from them after a restart.
+CREATE TABLE IF NOT EXISTS public.ingest_counters (
+  name       TEXT        PRIMARY KEY,
+  value      BIGINT      NOT NULL DEFAULT 0,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.ingest_counters;
@@ -0,0 +1,8 @@
+-- migrate:up
+-- Reverse ring lookups (which txs used an output as a decoy or real spend)
+-- for GET /api/v1/rings/by-output/{global_index}.
+CREATE INDEX IF NOT EXISTS idx_rings_referenced_output_id
+  ON public.rings (referenced_output_id);
+
+-- migrate:down
+DROP INDEX IF EXISTS idx_rings_referenced_output_id;
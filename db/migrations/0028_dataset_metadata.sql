@@ -0,0 +1,11 @@
+-- migrate:up
+-- Facts about the ingested dataset as a whole, surfaced by GET /api/v1/status.
+-- `pruned` turns 'true' once any block was ingested with --prune and stays so.
+CREATE TABLE IF NOT EXISTS public.dataset_metadata (
+  key        TEXT        PRIMARY KEY,
+  value      TEXT        NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.dataset_metadata;
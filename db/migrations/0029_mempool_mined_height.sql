@@ -0,0 +1,12 @@
+-- migrate:up
+-- Height of the daemon block that mined a pool tx while that block is held
+-- back by the ingestor's --tip-confirmations. Such txs are hidden from the
+-- mempool views but keep their row (and first_seen) until the block is
+-- ingested; a reorg that returns them to the pool clears the column.
+-- `daemon_tip` in dataset_metadata is the daemon's raw tip at the last poll.
+ALTER TABLE public.mempool_txs
+  ADD COLUMN IF NOT EXISTS mined_height BIGINT NULL;
+
+-- migrate:down
+ALTER TABLE public.mempool_txs
+  DROP COLUMN IF EXISTS mined_height;
@@ -0,0 +1,14 @@
+-- migrate:up
+-- The daemon's get_fee_estimate at the ingestor's last poll, served with
+-- explorer-derived fee percentiles by GET /api/v1/fees/base. Single row.
+-- Fees are atomic units per byte; `fees` holds the daemon's priority tiers.
+CREATE TABLE IF NOT EXISTS public.fee_estimate (
+  id                SMALLINT    PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+  fee_per_byte      BIGINT      NOT NULL,
+  fees              BIGINT[]    NOT NULL,
+  quantization_mask BIGINT      NOT NULL,
+  updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.fee_estimate;
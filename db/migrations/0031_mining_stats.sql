@@ -0,0 +1,33 @@
+-- migrate:up
+-- Block difficulty as reported in the header (low 64 bits). NULL for blocks
+-- ingested before this column existed.
+ALTER TABLE public.blocks
+  ADD COLUMN IF NOT EXISTS difficulty BIGINT NULL;
+
+-- Per-day mining statistics (UTC), rebuilt by the ingestor's analytics worker
+-- and served by GET /api/v1/stats/mining.
+--   nonce_buckets: block counts by the top four bits of the 32-bit nonce; a
+--     miner population scanning the nonce space evenly fills them uniformly.
+--   nonce_chi2: chi-squared statistic of those buckets against uniform
+--     (15 degrees of freedom; values far above ~30 are unlikely by chance).
+--   solve-time residuals: each block's time since its parent minus the time
+--     its difficulty predicts at the day's implied hashrate,
+--     SUM(difficulty) / SUM(solve time). They average to zero by construction,
+--     so their mean absolute value and spread are kept.
+CREATE TABLE IF NOT EXISTS public.mining_daily (
+  day                    DATE             PRIMARY KEY,
+  blocks                 INTEGER          NOT NULL,
+  nonce_buckets          INTEGER[]        NOT NULL,
+  nonce_chi2             DOUBLE PRECISION NOT NULL,
+  avg_difficulty         DOUBLE PRECISION NULL,
+  implied_hashrate       DOUBLE PRECISION NULL,
+  mean_solve_secs        DOUBLE PRECISION NULL,
+  residual_mean_abs_secs DOUBLE PRECISION NULL,
+  residual_stddev_secs   DOUBLE PRECISION NULL,
+  computed_at            TIMESTAMPTZ      NOT NULL DEFAULT NOW()
+);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.mining_daily;
+ALTER TABLE public.blocks
+  DROP COLUMN IF EXISTS difficulty;
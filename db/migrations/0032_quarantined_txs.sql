@@ -0,0 +1,18 @@
+-- migrate:up
+-- Block txs the codec could not parse. The rest of the block is persisted
+-- without them; `ingestor reprocess-quarantined` re-parses `raw_json` after a
+-- codec fix and queues re-ingestion of the blocks that now parse. Rows are
+-- removed once their tx is persisted.
+CREATE TABLE IF NOT EXISTS public.quarantined_txs (
+  tx_hash        BYTEA       PRIMARY KEY,
+  block_height   BIGINT      NOT NULL,
+  block_hash     BYTEA       NOT NULL,
+  raw_json       TEXT        NOT NULL,
+  error          TEXT        NOT NULL,
+  quarantined_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_quarantined_txs_height
+  ON public.quarantined_txs (block_height);
+
+-- migrate:down
+DROP TABLE IF EXISTS public.quarantined_txs;
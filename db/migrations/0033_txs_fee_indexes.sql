@@ -0,0 +1,14 @@
+-- migrate:up
+-- Serve GET /api/v1/txs/by-fee. The first index walks a fee range in the
+-- endpoint's order (fee, then height, descending) across any block window;
+-- the second covers narrow windows holding many txs.
+CREATE INDEX IF NOT EXISTS idx_txs_fee_height
+  ON public.txs (fee_nanos DESC, block_height DESC, tx_hash)
+  WHERE block_height IS NOT NULL AND fee_nanos IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_txs_height_fee
+  ON public.txs (block_height, fee_nanos)
+  WHERE fee_nanos IS NOT NULL;
+
+-- migrate:down
+DROP INDEX IF EXISTS public.idx_txs_height_fee;
+DROP INDEX IF EXISTS public.idx_txs_fee_height;
@@ -0,0 +1,16 @@
+-- migrate:up
+-- Split each block's reward into what the protocol allowed and what the miner
+-- took. subsidy is the emission schedule's base reward before any size
+-- penalty (NULL until every lower height is ingested), fees the sum of the
+-- block's tx fees, and claimed the sum of the miner tx's outputs. A miner
+-- that took less than allowed burned subsidy + fees - claimed.
+ALTER TABLE public.block_emission
+  ADD COLUMN IF NOT EXISTS subsidy BIGINT NULL,
+  ADD COLUMN IF NOT EXISTS fees    BIGINT NULL,
+  ADD COLUMN IF NOT EXISTS claimed BIGINT NULL;
+
+-- migrate:down
+ALTER TABLE public.block_emission
+  DROP COLUMN IF EXISTS claimed,
+  DROP COLUMN IF EXISTS fees,
+  DROP COLUMN IF EXISTS subsidy;
@@ -0,0 +1,29 @@
+-- migrate:up
+-- The block as the API serves it, defined once for every endpoint that
+-- returns one. Confirmations count from the tip even before the ingestor
+-- refreshes the stored column, and prev/next only resolve when linked by
+-- hash, so a block left over from a half-healed reorg never points at the
+-- wrong neighbour.
+CREATE OR REPLACE VIEW public.block_views AS
+SELECT b.height,
+       b.hash,
+       b.block_timestamp,
+       extract(epoch from b.block_timestamp)::bigint AS ts,
+       b.size_bytes,
+       b.major_version,
+       b.minor_version,
+       b.tx_count,
+       b.reward_nanos,
+       GREATEST(b.confirmations::bigint,
+                COALESCE((SELECT t.height FROM public.current_tip t WHERE t.id = 1) - b.height + 1, 0)) AS confirmations,
+       b.is_final,
+       p.height AS prev_height,
+       p.hash AS prev_hash,
+       n.height AS next_height,
+       n.hash AS next_hash
+FROM public.blocks b
+LEFT JOIN public.blocks p ON p.height = b.height - 1 AND p.hash = b.prev_hash
+LEFT JOIN public.blocks n ON n.height = b.height + 1 AND n.prev_hash = b.hash;
+
+-- migrate:down
+DROP VIEW IF EXISTS public.block_views;
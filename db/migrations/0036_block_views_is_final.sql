@@ -0,0 +1,50 @@
+-- migrate:up
+-- `is_final` derived from the tip like `confirmations`, so it never waits
+-- for the ingestor's refresh to flip the stored column. The window is the
+-- one the ingestor last ran with; until one has recorded it, the stored
+-- column is served.
+CREATE OR REPLACE VIEW public.block_views AS
+SELECT b.height,
+       b.hash,
+       b.block_timestamp,
+       extract(epoch from b.block_timestamp)::bigint AS ts,
+       b.size_bytes,
+       b.major_version,
+       b.minor_version,
+       b.tx_count,
+       b.reward_nanos,
+       GREATEST(b.confirmations::bigint,
+                COALESCE((SELECT t.height FROM public.current_tip t WHERE t.id = 1) - b.height + 1, 0)) AS confirmations,
+       COALESCE(b.height <= (SELECT t.height FROM public.current_tip t WHERE t.id = 1)
+                            - (SELECT m.value::bigint FROM public.dataset_metadata m
+                               WHERE m.key = 'finality_window'),
+                b.is_final) AS is_final,
+       p.height AS prev_height,
+       p.hash AS prev_hash,
+       n.height AS next_height,
+       n.hash AS next_hash
+FROM public.blocks b
+LEFT JOIN public.blocks p ON p.height = b.height - 1 AND p.hash = b.prev_hash
+LEFT JOIN public.blocks n ON n.height = b.height + 1 AND n.prev_hash = b.hash;
+
+-- migrate:down
+CREATE OR REPLACE VIEW public.block_views AS
+SELECT b.height,
+       b.hash,
+       b.block_timestamp,
+       extract(epoch from b.block_timestamp)::bigint AS ts,
+       b.size_bytes,
+       b.major_version,
+       b.minor_version,
+       b.tx_count,
+       b.reward_nanos,
+       GREATEST(b.confirmations::bigint,
+                COALESCE((SELECT t.height FROM public.current_tip t WHERE t.id = 1) - b.height + 1, 0)) AS confirmations,
+       b.is_final,
+       p.height AS prev_height,
+       p.hash AS prev_hash,
+       n.height AS next_height,
+       n.hash AS next_hash
+FROM public.blocks b
+LEFT JOIN public.blocks p ON p.height = b.height - 1 AND p.hash = b.prev_hash
+LEFT JOIN public.blocks n ON n.height = b.height + 1 AND n.prev_hash = b.hash;
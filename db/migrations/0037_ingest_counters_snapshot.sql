@@ -0,0 +1,9 @@
+-- migrate:up
+-- `ingest_counters` now holds a snapshot of every exporter counter, keyed by
+-- series (`name{label="value"}`). Unlabelled series keep their bare name, so
+-- the rows already stored (`ingested_blocks_total` among them) resume as is.
+COMMENT ON TABLE public.ingest_counters IS
+  'Exporter counter snapshot, keyed by series as rendered by Prometheus.';
+
+-- migrate:down
+COMMENT ON TABLE public.ingest_counters IS NULL;
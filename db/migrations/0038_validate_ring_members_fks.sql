@@ -0,0 +1,10 @@
+-- migrate:up
+-- Checks the rows stored before 0024 added the ring_members keys. VALIDATE
+-- only takes a SHARE UPDATE EXCLUSIVE lock, so writes continue during the
+-- scan.
+ALTER TABLE public.ring_members VALIDATE CONSTRAINT fk_ring_members_tx_input;
+ALTER TABLE public.ring_members VALIDATE CONSTRAINT fk_ring_members_output;
+
+-- migrate:down
+-- A validated key cannot be marked NOT VALID again; 0024's down drops it.
+SELECT 1;
@@ -0,0 +1,17 @@
+-- migrate:up
+-- Marks the miner tx of each block. It is only valid in its own block, so
+-- reorg healing and re-ingestion drop it instead of returning it to the
+-- mempool.
+ALTER TABLE public.txs ADD COLUMN IF NOT EXISTS is_coinbase BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Every non-coinbase v2 tx is RingCT, so a confirmed v2 tx with rct_type 0 is
+-- a miner tx. v1-era blocks are far below any reorg depth and stay unmarked.
+UPDATE public.txs
+SET is_coinbase = TRUE
+WHERE block_height IS NOT NULL
+  AND version = 2
+  AND rct_type = 0
+  AND NOT is_coinbase;
+
+-- migrate:down
+ALTER TABLE public.txs DROP COLUMN IF EXISTS is_coinbase;
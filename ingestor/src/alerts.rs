@@ -0,0 +1,577 @@
+//! Built-in alerting for deployments without Prometheus and Alertmanager. A
+//! background task evaluates a few threshold rules every `ALERT_INTERVAL_SECS`
+//! and notifies the configured sinks when an alert starts or stops firing.
+//!
+//! Rules read the ingestion lag (daemon tip minus checkpoint) and counters the
+//! pipeline bumps through the `record_*` functions; those counters cover one
+//! evaluation interval and restart from zero after each evaluation. A rule
+//! whose signal is unknown for an interval (lag not measurable, too few daemon
+//! requests to judge) keeps its previous state instead of resolving.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bex_core::webhook;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{checkpoint::Checkpoint, rpc::MoneroRpc};
+
+/// Fewer daemon requests than this per interval are too few to judge an
+/// error rate by.
+const MIN_RPC_SAMPLE: u64 = 20;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Sink {
+    /// One line per notification on stderr.
+    Stderr,
+    /// `alert_firing{alert}` gauge, 1 while firing.
+    Metrics,
+    /// JSON POST to `ALERT_WEBHOOK_URL`, signed with `ALERT_WEBHOOK_SECRET`
+    /// when set.
+    Webhook,
+}
+
+struct Signals {
+    rpc_requests: AtomicU64,
+    rpc_errors: AtomicU64,
+    reorg_depth: AtomicU64,
+    mempool_failures: AtomicU64,
+}
+
+static SIGNALS: Signals = Signals {
+    rpc_requests: AtomicU64::new(0),
+    rpc_errors: AtomicU64::new(0),
+    reorg_depth: AtomicU64::new(0),
+    mempool_failures: AtomicU64::new(0),
+};
+
+pub fn record_rpc_request() {
+    SIGNALS.rpc_requests.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_rpc_error() {
+    SIGNALS.rpc_errors.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Keeps the deepest reorg healed during the interval.
+pub fn record_reorg(depth: u64) {
+    SIGNALS.reorg_depth.fetch_max(depth, Ordering::Relaxed);
+}
+
+pub fn record_mempool_failure() {
+    SIGNALS.mempool_failures.fetch_add(1, Ordering::Relaxed);
+}
+
+/// What happened during one evaluation interval.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Window {
+    /// Blocks the checkpoint trails the daemon tip; `None` if unknown.
+    pub lag: Option<u64>,
+    pub reorg_depth: u64,
+    pub rpc_requests: u64,
+    pub rpc_errors: u64,
+    pub mempool_failures: u64,
+}
+
+impl Window {
+    /// Drains the counters recorded since the last call.
+    fn take(lag: Option<u64>) -> Self {
+        Self {
+            lag,
+            reorg_depth: SIGNALS.reorg_depth.swap(0, Ordering::Relaxed),
+            rpc_requests: SIGNALS.rpc_requests.swap(0, Ordering::Relaxed),
+            rpc_errors: SIGNALS.rpc_errors.swap(0, Ordering::Relaxed),
+            mempool_failures: SIGNALS.mempool_failures.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alert {
+    IngestLag,
+    ReorgDepth,
+    RpcErrorRate,
+    MempoolReconciliation,
+}
+
+impl Alert {
+    pub fn name(self) -> &'static str {
+        match self {
+            Alert::IngestLag => "ingest_lag",
+            Alert::ReorgDepth => "reorg_depth",
+            Alert::RpcErrorRate => "rpc_error_rate",
+            Alert::MempoolReconciliation => "mempool_reconciliation",
+        }
+    }
+
+    fn describe(self, value: f64, threshold: f64) -> String {
+        match self {
+            Alert::IngestLag => {
+                format!("ingestion is {value} blocks behind the daemon (threshold {threshold})")
+            }
+            Alert::ReorgDepth => {
+                format!("healed a reorg {value} blocks deep (threshold {threshold})")
+            }
+            Alert::RpcErrorRate => {
+                format!("{value:.1}% of daemon requests failed (threshold {threshold}%)")
+            }
+            Alert::MempoolReconciliation => {
+                format!("{value} mempool reconciliations failed (threshold {threshold})")
+            }
+        }
+    }
+}
+
+/// Thresholds; each rule fires when its value exceeds the threshold, and a
+/// zero threshold disables it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    pub lag_blocks: u64,
+    pub reorg_depth: u64,
+    pub rpc_error_percent: f64,
+    pub mempool_failures: u64,
+}
+
+impl Rules {
+    /// Firing alerts with their observed value and threshold.
+    pub fn evaluate(&self, window: &Window) -> BTreeMap<Alert, (f64, f64)> {
+        let mut firing = BTreeMap::new();
+        let mut check = |alert, value: f64, threshold: f64| {
+            if threshold > 0.0 && value > threshold {
+                firing.insert(alert, (value, threshold));
+            }
+        };
+        if let Some(lag) = window.lag {
+            check(Alert::IngestLag, lag as f64, self.lag_blocks as f64);
+        }
+        check(
+            Alert::ReorgDepth,
+            window.reorg_depth as f64,
+            self.reorg_depth as f64,
+        );
+        if window.rpc_requests >= MIN_RPC_SAMPLE {
+            let percent = window.rpc_errors as f64 * 100.0 / window.rpc_requests as f64;
+            check(Alert::RpcErrorRate, percent, self.rpc_error_percent);
+        }
+        check(
+            Alert::MempoolReconciliation,
+            window.mempool_failures as f64,
+            self.mempool_failures as f64,
+        );
+        firing
+    }
+
+    /// Alerts `window` says nothing about, which [`Rules::evaluate`] can
+    /// neither fire nor clear.
+    pub fn unknown(&self, window: &Window) -> BTreeSet<Alert> {
+        let mut unknown = BTreeSet::new();
+        if window.lag.is_none() {
+            unknown.insert(Alert::IngestLag);
+        }
+        if window.rpc_requests < MIN_RPC_SAMPLE {
+            unknown.insert(Alert::RpcErrorRate);
+        }
+        unknown
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    Firing,
+    Resolved,
+}
+
+/// Body of a webhook call and of each stderr line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub alert: Alert,
+    pub state: State,
+    pub value: f64,
+    pub threshold: f64,
+    pub message: String,
+    pub at: u64,
+}
+
+/// The webhook sink. With a secret, every call carries the
+/// [`bex_core::webhook`] timestamp, sequence and signature headers so the
+/// receiver can authenticate it and drop replays.
+struct Webhook {
+    client: reqwest::Client,
+    url: String,
+    secret: Option<Vec<u8>>,
+    /// Seeded from the clock in milliseconds so it keeps increasing across
+    /// restarts.
+    sequence: AtomicU64,
+}
+
+impl Webhook {
+    fn new(url: String, secret: Option<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .expect("reqwest client");
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        Self {
+            client,
+            url,
+            secret: secret.map(String::into_bytes),
+            sequence: AtomicU64::new(seed),
+        }
+    }
+
+    /// Signature headers for `body` sent at `timestamp`, empty without a
+    /// secret.
+    fn headers(&self, timestamp: i64, body: &[u8]) -> Vec<(&'static str, String)> {
+        let Some(secret) = &self.secret else {
+            return Vec::new();
+        };
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed) + 1;
+        let signature = match webhook::sign(secret, timestamp, sequence, body) {
+            Ok(signature) => signature,
+            Err(err) => {
+                warn!(error = %err, "alert webhook secret rejected; sending unsigned");
+                return Vec::new();
+            }
+        };
+        vec![
+            (webhook::TIMESTAMP_HEADER, timestamp.to_string()),
+            (webhook::SEQUENCE_HEADER, sequence.to_string()),
+            (webhook::SIGNATURE_HEADER, signature),
+        ]
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), reqwest::Error> {
+        let body = serde_json::to_vec(notification).expect("notification serializes");
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        for (name, value) in self.headers(timestamp, &body) {
+            request = request.header(name, value);
+        }
+        request
+            .body(body)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map(drop)
+    }
+}
+
+pub struct Alerter {
+    rules: Rules,
+    sinks: Vec<Sink>,
+    webhook: Option<Webhook>,
+    active: BTreeMap<Alert, (f64, f64)>,
+}
+
+impl Alerter {
+    pub fn new(
+        rules: Rules,
+        sinks: Vec<Sink>,
+        webhook_url: Option<String>,
+        webhook_secret: Option<String>,
+    ) -> Self {
+        let webhook = webhook_url
+            .filter(|_| sinks.contains(&Sink::Webhook))
+            .map(|url| Webhook::new(url, webhook_secret));
+        Self {
+            rules,
+            sinks,
+            webhook,
+            active: BTreeMap::new(),
+        }
+    }
+
+    /// Evaluates `window` and returns a notification for every alert that
+    /// started or stopped firing since the previous evaluation. Alerts whose
+    /// signal is unknown stay as they were.
+    pub fn transitions(&mut self, window: &Window) -> Vec<Notification> {
+        let mut firing = self.rules.evaluate(window);
+        for alert in self.rules.unknown(window) {
+            if let Some(&previous) = self.active.get(&alert) {
+                firing.insert(alert, previous);
+            }
+        }
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let mut out = Vec::new();
+        for (&alert, &(value, threshold)) in &firing {
+            if !self.active.contains_key(&alert) {
+                out.push(Notification {
+                    alert,
+                    state: State::Firing,
+                    value,
+                    threshold,
+                    message: alert.describe(value, threshold),
+                    at,
+                });
+            }
+        }
+        for (&alert, &(value, threshold)) in &self.active {
+            if !firing.contains_key(&alert) {
+                out.push(Notification {
+                    alert,
+                    state: State::Resolved,
+                    value,
+                    threshold,
+                    message: format!("resolved: {}", alert.describe(value, threshold)),
+                    at,
+                });
+            }
+        }
+        self.active = firing;
+        out
+    }
+
+    async fn dispatch(&self, notifications: &[Notification]) {
+        if self.sinks.contains(&Sink::Metrics) {
+            for alert in [
+                Alert::IngestLag,
+                Alert::ReorgDepth,
+                Alert::RpcErrorRate,
+                Alert::MempoolReconciliation,
+            ] {
+                let firing = if self.active.contains_key(&alert) {
+                    1.0
+                } else {
+                    0.0
+                };
+                metrics::gauge!("alert_firing", "alert" => alert.name()).set(firing);
+            }
+        }
+        for notification in notifications {
+            if self.sinks.contains(&Sink::Stderr) {
+                eprintln!(
+                    "ALERT {} {}: {}",
+                    notification.alert.name(),
+                    match notification.state {
+                        State::Firing => "firing",
+                        State::Resolved => "resolved",
+                    },
+                    notification.message
+                );
+            }
+            if let Some(webhook) = &self.webhook {
+                if let Err(err) = webhook.send(notification).await {
+                    warn!(alert = notification.alert.name(), error = ?err, "alert webhook failed");
+                    metrics::counter!("alert_webhook_failures_total").increment(1);
+                }
+            }
+        }
+    }
+}
+
+/// Evaluates the rules every `interval` for the life of the process.
+pub fn spawn(
+    mut alerter: Alerter,
+    rpc: Arc<dyn MoneroRpc>,
+    checkpoint: Arc<Checkpoint>,
+    interval: Duration,
+) {
+    info!(sinks = ?alerter.sinks, "alerting enabled");
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let lag = match (rpc.get_block_count().await, checkpoint.get().await) {
+                (Ok(count), Ok(ingested)) => {
+                    let tip = count.count.saturating_sub(1);
+                    Some(tip.saturating_sub(u64::try_from(ingested).unwrap_or(0)))
+                }
+                _ => None,
+            };
+            let window = Window::take(lag);
+            let notifications = alerter.transitions(&window);
+            alerter.dispatch(&notifications).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> Rules {
+        Rules {
+            lag_blocks: 10,
+            reorg_depth: 2,
+            rpc_error_percent: 5.0,
+            mempool_failures: 3,
+        }
+    }
+
+    #[test]
+    fn rules_fire_above_their_thresholds() {
+        let quiet = Window {
+            lag: Some(10),
+            reorg_depth: 2,
+            rpc_requests: 100,
+            rpc_errors: 5,
+            mempool_failures: 3,
+        };
+        assert!(rules().evaluate(&quiet).is_empty());
+
+        let noisy = Window {
+            lag: Some(11),
+            reorg_depth: 3,
+            rpc_requests: 100,
+            rpc_errors: 6,
+            mempool_failures: 4,
+        };
+        let firing = rules().evaluate(&noisy);
+        assert_eq!(
+            firing.keys().copied().collect::<Vec<_>>(),
+            vec![
+                Alert::IngestLag,
+                Alert::ReorgDepth,
+                Alert::RpcErrorRate,
+                Alert::MempoolReconciliation
+            ]
+        );
+        assert_eq!(firing[&Alert::RpcErrorRate], (6.0, 5.0));
+    }
+
+    #[test]
+    fn small_rpc_samples_and_zero_thresholds_never_fire() {
+        let window = Window {
+            rpc_requests: MIN_RPC_SAMPLE - 1,
+            rpc_errors: MIN_RPC_SAMPLE - 1,
+            lag: Some(1_000),
+            ..Window::default()
+        };
+        let disabled = Rules {
+            lag_blocks: 0,
+            ..rules()
+        };
+        assert!(disabled.evaluate(&window).is_empty());
+    }
+
+    #[test]
+    fn notifications_only_on_transitions() {
+        let mut alerter = Alerter::new(rules(), vec![Sink::Stderr], None, None);
+        let lagging = Window {
+            lag: Some(50),
+            ..Window::default()
+        };
+
+        let fired = alerter.transitions(&lagging);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].alert, Alert::IngestLag);
+        assert_eq!(fired[0].state, State::Firing);
+        assert_eq!(
+            fired[0].message,
+            "ingestion is 50 blocks behind the daemon (threshold 10)"
+        );
+
+        assert!(alerter.transitions(&lagging).is_empty());
+
+        let caught_up = Window {
+            lag: Some(0),
+            ..Window::default()
+        };
+        let resolved = alerter.transitions(&caught_up);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].state, State::Resolved);
+        assert!(alerter.transitions(&caught_up).is_empty());
+    }
+
+    #[test]
+    fn unknown_signals_keep_the_previous_state() {
+        let mut alerter = Alerter::new(rules(), vec![Sink::Stderr], None, None);
+        let failing = Window {
+            lag: Some(50),
+            rpc_requests: 100,
+            rpc_errors: 50,
+            ..Window::default()
+        };
+        assert_eq!(alerter.transitions(&failing).len(), 2);
+
+        // Neither the lag nor the error rate can be measured: both stay firing.
+        let blind = Window {
+            lag: None,
+            rpc_requests: MIN_RPC_SAMPLE - 1,
+            ..Window::default()
+        };
+        assert!(alerter.transitions(&blind).is_empty());
+        assert!(alerter.active.contains_key(&Alert::IngestLag));
+        assert!(alerter.active.contains_key(&Alert::RpcErrorRate));
+
+        let healthy = Window {
+            lag: Some(0),
+            rpc_requests: 100,
+            ..Window::default()
+        };
+        let resolved = alerter.transitions(&healthy);
+        assert_eq!(resolved.len(), 2);
+        assert!(resolved.iter().all(|n| n.state == State::Resolved));
+
+        // An unknown signal never starts an alert either.
+        assert!(alerter.transitions(&blind).is_empty());
+        assert!(alerter.active.is_empty());
+    }
+
+    #[test]
+    fn webhook_payload_is_flat_json() {
+        let notification = Notification {
+            alert: Alert::ReorgDepth,
+            state: State::Firing,
+            value: 4.0,
+            threshold: 2.0,
+            message: Alert::ReorgDepth.describe(4.0, 2.0),
+            at: 1_700_000_000,
+        };
+        assert_eq!(
+            serde_json::to_value(&notification).unwrap(),
+            serde_json::json!({
+                "alert": "reorg_depth",
+                "state": "firing",
+                "value": 4.0,
+                "threshold": 2.0,
+                "message": "healed a reorg 4 blocks deep (threshold 2)",
+                "at": 1_700_000_000u64,
+            })
+        );
+    }
+
+    #[test]
+    fn webhook_calls_are_signed_in_sequence() {
+        let unsigned = Webhook::new("http://hook".into(), None);
+        assert!(unsigned.headers(1_000, b"{}").is_empty());
+
+        let hook = Webhook::new("http://hook".into(), Some("secret".into()));
+        let mut verifier = webhook::Verifier::new("secret");
+        for body in [&b"{\"a\":1}"[..], b"{\"a\":2}"] {
+            let headers: BTreeMap<_, _> = hook.headers(1_000, body).into_iter().collect();
+            assert_eq!(headers[webhook::TIMESTAMP_HEADER], "1000");
+            let sequence = headers[webhook::SEQUENCE_HEADER].parse().unwrap();
+            assert_eq!(
+                verifier.verify(
+                    1_000,
+                    sequence,
+                    &headers[webhook::SIGNATURE_HEADER],
+                    body,
+                    1_000
+                ),
+                Ok(())
+            );
+        }
+    }
+}
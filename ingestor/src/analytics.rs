@@ -1,5 +1,327 @@
-use anyhow::Result;
-use sqlx::Row;
+use std::{
+    ops::RangeInclusive,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use bex_core::BlockHash;
+use sqlx::{postgres::PgPoolOptions, Executor, PgPool, Row};
+use tokio::{sync::Notify, task::JoinHandle};
+use tracing::{debug, warn};
+
+use crate::rpc::MoneroRpc;
+
+const IDLE_POLL: Duration = Duration::from_secs(30);
+const PENDING_BATCH: i64 = 100;
+/// Entries kept per leaderboard; the API serves at most this many.
+pub const LEADERBOARD_SIZE: i64 = 100;
+/// Ranking outputs aggregates every ring member, which takes far longer than
+/// the per-block statement timeout allows.
+const LEADERBOARD_STATEMENT_TIMEOUT: &str = "300s";
+
+/// Opens a dedicated single-connection pool for soft-facts aggregation. Every
+/// statement on it is bounded by `statement_timeout_ms` so a pathological block
+/// cannot stall ingestion or hold locks indefinitely.
+pub async fn connect(db_url: &str, statement_timeout_ms: u64) -> Result<PgPool> {
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .after_connect(move |conn, _meta| {
+            Box::pin(async move {
+                conn.execute(format!("SET statement_timeout = {statement_timeout_ms}").as_str())
+                    .await?;
+                Ok(())
+            })
+        })
+        .connect(db_url)
+        .await?;
+    Ok(pool)
+}
+
+/// Computes soft facts for blocks flagged `analytics_pending`, one block per
+/// transaction. Failures (including statement timeouts) are logged and the
+/// block stays pending for a later pass or `analytics-backfill`.
+pub async fn process_pending(db: &PgPool, batch: i64) -> Result<u64> {
+    let mut done = 0u64;
+    let mut after = -1i64;
+    loop {
+        let heights: Vec<i64> = sqlx::query_scalar(
+            "SELECT height FROM public.blocks
+             WHERE analytics_pending = TRUE AND height > $1
+             ORDER BY height ASC LIMIT $2",
+        )
+        .bind(after)
+        .bind(batch)
+        .fetch_all(db)
+        .await?;
+        let Some(&last) = heights.last() else {
+            break;
+        };
+        after = last;
+
+        for h in heights {
+            let mut tx = db.begin().await?;
+            match super::store::Store::upsert_soft_facts_for_block(&mut tx, h).await {
+                Ok(()) => {
+                    tx.commit().await?;
+                    done += 1;
+                }
+                Err(err) => {
+                    tx.rollback().await.ok();
+                    let reason = if is_statement_timeout(&err) {
+                        "timeout"
+                    } else {
+                        "error"
+                    };
+                    warn!(height = h, reason, error = ?err, "soft facts computation failed");
+                    metrics::counter!("analytics_failures_total", "reason" => reason).increment(1);
+                }
+            }
+        }
+    }
+    Ok(done)
+}
+
+fn is_statement_timeout(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<sqlx::Error>()
+        .and_then(|e| e.as_database_error())
+        .and_then(|e| e.code())
+        .is_some_and(|code| code == "57014")
+}
+
+/// Runs [`process_pending`] whenever persistence signals `wake`, and on an
+/// idle interval to pick up blocks left pending by earlier failures. The
+/// leaderboards and daily mining stats are rebuilt at most once per
+/// `leaderboard_every`.
+pub fn spawn_worker(db: PgPool, wake: Arc<Notify>, leaderboard_every: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut leaderboards_at: Option<Instant> = None;
+        loop {
+            tokio::select! {
+                _ = wake.notified() => {}
+                _ = tokio::time::sleep(IDLE_POLL) => {}
+            }
+            match process_pending(&db, PENDING_BATCH).await {
+                Ok(0) => {}
+                Ok(processed) => debug!(processed, "soft facts computed"),
+                Err(err) => warn!(error = ?err, "analytics worker pass failed"),
+            }
+            if leaderboards_at.is_none_or(|at| at.elapsed() >= leaderboard_every) {
+                leaderboards_at = Some(Instant::now());
+                if let Err(err) = refresh_leaderboards(&db, LEADERBOARD_SIZE).await {
+                    warn!(error = ?err, "leaderboard refresh failed");
+                    metrics::counter!("analytics_failures_total", "reason" => "leaderboards")
+                        .increment(1);
+                }
+                if let Err(err) = refresh_mining_stats(&db, None).await {
+                    warn!(error = ?err, "mining stats refresh failed");
+                    metrics::counter!("analytics_failures_total", "reason" => "mining")
+                        .increment(1);
+                }
+            }
+        }
+    })
+}
+
+/// Rebuilds `top_blocks` and `top_outputs` in one transaction so readers see
+/// either the previous or the new rankings, never a mix.
+pub async fn refresh_leaderboards(db: &PgPool, size: i64) -> Result<()> {
+    let mut tx = db.begin().await?;
+    tx.execute(format!("SET LOCAL statement_timeout = '{LEADERBOARD_STATEMENT_TIMEOUT}'").as_str())
+        .await?;
+
+    sqlx::query("DELETE FROM public.top_blocks")
+        .execute(&mut *tx)
+        .await?;
+    for (metric, column) in [("tx_count", "tx_count"), ("size", "size_bytes")] {
+        sqlx::query(&format!(
+            "INSERT INTO public.top_blocks (metric, rank, height, hash, value)
+             SELECT $1, ROW_NUMBER() OVER (ORDER BY {column} DESC, height ASC),
+                    height, hash, {column}
+             FROM public.blocks
+             ORDER BY {column} DESC, height ASC
+             LIMIT $2"
+        ))
+        .bind(metric)
+        .bind(size)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query("DELETE FROM public.top_outputs")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO public.top_outputs (metric, rank, output_id, global_index, tx_hash, value)
+         SELECT 'ring_references',
+                ROW_NUMBER() OVER (ORDER BY r.refs DESC, r.referenced_output_id ASC),
+                o.output_id, o.global_index, o.tx_hash, r.refs
+         FROM (
+           SELECT referenced_output_id, COUNT(*) AS refs
+           FROM public.rings
+           GROUP BY referenced_output_id
+           ORDER BY refs DESC, referenced_output_id ASC
+           LIMIT $1
+         ) r
+         JOIN public.outputs o ON o.output_id = r.referenced_output_id",
+    )
+    .bind(size)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Rebuilds `mining_daily` for every UTC day from `since` (epoch seconds)
+/// on, or from the last computed day when `None`, since only the newest day
+/// is still filling. Returns the number of days written.
+pub async fn refresh_mining_stats(db: &PgPool, since: Option<i64>) -> Result<u64> {
+    let mut tx = db.begin().await?;
+    tx.execute(format!("SET LOCAL statement_timeout = '{LEADERBOARD_STATEMENT_TIMEOUT}'").as_str())
+        .await?;
+    let written = sqlx::query(
+        r#"
+WITH since AS (
+  SELECT COALESCE(
+    (to_timestamp($1) AT TIME ZONE 'UTC')::date,
+    (SELECT MAX(day) FROM public.mining_daily),
+    '-infinity'::date
+  ) AS day
+), per_block AS (
+  SELECT (b.block_timestamp AT TIME ZONE 'UTC')::date AS day,
+         b.nonce,
+         b.difficulty::double precision AS difficulty,
+         extract(epoch from b.block_timestamp - p.block_timestamp)::double precision AS solve_secs
+  FROM public.blocks b
+  LEFT JOIN public.blocks p ON p.hash = b.prev_hash AND p.height = b.height - 1
+  WHERE b.block_timestamp >= (SELECT day FROM since)::timestamp AT TIME ZONE 'UTC'
+), days AS (
+  SELECT day,
+         COUNT(*)::int AS blocks,
+         AVG(difficulty) AS avg_difficulty,
+         AVG(solve_secs) AS mean_solve_secs,
+         SUM(difficulty) FILTER (WHERE solve_secs IS NOT NULL)
+           / NULLIF(SUM(solve_secs) FILTER (WHERE difficulty IS NOT NULL), 0) AS hashrate
+  FROM per_block
+  GROUP BY day
+), buckets AS (
+  SELECT d.day, array_agg(COALESCE(c.n, 0) ORDER BY g.bucket) AS nonce_buckets
+  FROM days d
+  CROSS JOIN generate_series(0, 15) AS g(bucket)
+  LEFT JOIN (
+    SELECT day, ((nonce >> 28) & 15)::int AS bucket, COUNT(*)::int AS n
+    FROM per_block
+    GROUP BY 1, 2
+  ) c ON c.day = d.day AND c.bucket = g.bucket
+  GROUP BY d.day
+), residuals AS (
+  SELECT p.day,
+         AVG(abs(p.solve_secs - p.difficulty / d.hashrate)) AS mean_abs,
+         STDDEV_SAMP(p.solve_secs - p.difficulty / d.hashrate) AS stddev
+  FROM per_block p
+  JOIN days d ON d.day = p.day
+  WHERE d.hashrate > 0 AND p.solve_secs IS NOT NULL AND p.difficulty IS NOT NULL
+  GROUP BY p.day
+)
+INSERT INTO public.mining_daily
+  (day, blocks, nonce_buckets, nonce_chi2, avg_difficulty, implied_hashrate,
+   mean_solve_secs, residual_mean_abs_secs, residual_stddev_secs, computed_at)
+SELECT d.day, d.blocks, bk.nonce_buckets,
+       (SELECT SUM((n - d.blocks / 16.0) ^ 2) / (d.blocks / 16.0)
+          FROM unnest(bk.nonce_buckets) AS n)::double precision,
+       d.avg_difficulty, d.hashrate, d.mean_solve_secs, r.mean_abs, r.stddev, NOW()
+FROM days d
+JOIN buckets bk ON bk.day = d.day
+LEFT JOIN residuals r ON r.day = d.day
+ON CONFLICT (day) DO UPDATE
+SET blocks = EXCLUDED.blocks,
+    nonce_buckets = EXCLUDED.nonce_buckets,
+    nonce_chi2 = EXCLUDED.nonce_chi2,
+    avg_difficulty = EXCLUDED.avg_difficulty,
+    implied_hashrate = EXCLUDED.implied_hashrate,
+    mean_solve_secs = EXCLUDED.mean_solve_secs,
+    residual_mean_abs_secs = EXCLUDED.residual_mean_abs_secs,
+    residual_stddev_secs = EXCLUDED.residual_stddev_secs,
+    computed_at = NOW()
+"#,
+    )
+    .bind(since.map(|ts| ts as f64))
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+    Ok(written)
+}
+
+/// Fills `blocks.difficulty` for blocks in `heights` stored before the column
+/// existed, from the daemon's headers of up to `batch` heights at a time. A row is
+/// only updated when its hash is the daemon's block at that height, so
+/// orphaned rows stay NULL. Returns the number of blocks filled.
+pub async fn backfill_difficulty(
+    db: &PgPool,
+    rpc: &dyn MoneroRpc,
+    headers_range: bool,
+    heights: RangeInclusive<i64>,
+    batch: i64,
+) -> Result<u64> {
+    let mut filled = 0;
+    let mut after = heights.start() - 1;
+    loop {
+        let missing: Vec<i64> = sqlx::query_scalar(
+            "SELECT DISTINCT height FROM public.blocks
+             WHERE difficulty IS NULL AND height > $1 AND height <= $2
+             ORDER BY height LIMIT $3",
+        )
+        .bind(after)
+        .bind(heights.end())
+        .bind(batch)
+        .fetch_all(db)
+        .await?;
+        let (Some(&first), Some(&last)) = (missing.first(), missing.last()) else {
+            break;
+        };
+
+        let headers = if headers_range {
+            rpc.get_block_headers_range(first as u64, last as u64)
+                .await
+                .with_context(|| format!("fetch header range {first}..={last}"))?
+        } else {
+            let mut headers = Vec::with_capacity(missing.len());
+            for &height in &missing {
+                let header = rpc
+                    .get_block_header_by_height(height as u64)
+                    .await
+                    .with_context(|| format!("fetch header {height}"))?;
+                headers.push(header.block_header);
+            }
+            headers
+        };
+        let (hashes, difficulties): (Vec<BlockHash>, Vec<i64>) = headers
+            .into_iter()
+            .filter(|header| header.difficulty > 0)
+            .map(|header| {
+                let difficulty = i64::try_from(header.difficulty).unwrap_or(i64::MAX);
+                (header.hash, difficulty)
+            })
+            .unzip();
+        filled += sqlx::query(
+            "UPDATE public.blocks b SET difficulty = d.difficulty
+             FROM unnest($1::bytea[], $2::bigint[]) AS d(hash, difficulty)
+             WHERE b.hash = d.hash AND b.height BETWEEN $3 AND $4 AND b.difficulty IS NULL",
+        )
+        .bind(&hashes)
+        .bind(&difficulties)
+        .bind(first)
+        .bind(last)
+        .execute(db)
+        .await?
+        .rows_affected();
+        debug!(first, last, filled, "difficulty backfill progress");
+        after = last;
+    }
+    Ok(filled)
+}
 
 pub async fn backfill(db: &sqlx::PgPool, batch: i64) -> Result<i64> {
     let mut done = 0i64;
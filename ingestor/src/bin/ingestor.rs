@@ -1,22 +1,40 @@
-use std::{convert::TryFrom, env, net::SocketAddr, sync::Arc};
+use std::{convert::TryFrom, env, net::SocketAddr, sync::Arc, time::Duration};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Args as ClapArgs, Parser, Subcommand};
 use ingestor::{
+    alerts::{self, Alerter},
     analytics,
+    banner::StartupConfig,
+    capabilities::{self, LiveCapabilities},
     checkpoint::Checkpoint,
     cli::RunArgs,
+    confirmations::{self, ChainPosition},
+    constraints, counters, daemon_tip,
+    events::Events,
+    fees,
+    health::Readiness,
     limits,
+    memory::{self, MemoryBudget},
     mempool::MempoolWatcher,
     pipeline::{self, PipelineCfg},
-    rpc::{MoneroRpc, Rpc},
+    prefetch::{self, Prefetcher},
+    preflight, quarantine, reingest, replay,
+    rpc::{Capabilities, MoneroRpc, Rpc},
     store::Store,
-    work_block, work_persist, work_sched, work_tx,
+    trace, work_block, work_persist, work_sched, work_tx,
 };
-use tokio::sync::Mutex;
-use tracing::{error, info};
+use metrics_exporter_prometheus::PrometheusHandle;
+use tokio::sync::{watch, Mutex, Notify};
+use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
 
+/// First wait between startup capability probes; doubles per attempt.
+const CAPS_PROBE_BACKOFF: Duration = Duration::from_secs(1);
+const MEMORY_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Headers per bulk request; only used while bulk header fetches are available.
+const HEADER_BATCH: u64 = 200;
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -26,8 +44,20 @@ struct Cli {
 
 #[derive(Subcommand, Debug)]
 enum Cmd {
-    Run(RunArgs),
+    Run(Box<RunArgs>),
     AnalyticsBackfill(BackfillArgs),
+    /// Fills the header difficulty of blocks stored before it was recorded,
+    /// then recomputes every mining day.
+    BackfillDifficulty(DifficultyArgs),
+    /// Re-parses quarantined txs and queues re-ingestion of each block
+    /// holding one that now parses; run after a codec fix.
+    ReprocessQuarantined(ReprocessArgs),
+    /// Runs one block through fetch and parse without persisting it and
+    /// prints the derived fields, stage timings and validation results as JSON.
+    TraceBlock(TraceArgs),
+    /// Drives the pipeline through a recorded reorg against a scratch
+    /// database and checks the stored chain ends up on the new branch.
+    ReplayReorg(ReplayArgs),
 }
 
 #[derive(ClapArgs, Debug)]
@@ -38,35 +68,147 @@ struct BackfillArgs {
     batch: i64,
 }
 
+#[derive(ClapArgs, Debug)]
+struct DifficultyArgs {
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+    #[arg(
+        long,
+        env = "XMR_RPC_URL",
+        default_value = "http://127.0.0.1:38081/json_rpc"
+    )]
+    rpc_url: String,
+    #[arg(long, default_value_t = 0)]
+    from_height: i64,
+    #[arg(long, default_value_t = i64::MAX)]
+    to_height: i64,
+    /// Heights per header request.
+    #[arg(long, default_value_t = HEADER_BATCH as i64)]
+    batch: i64,
+}
+
+#[derive(ClapArgs, Debug)]
+struct ReprocessArgs {
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+    /// Reports what would be queued without queueing it.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+struct TraceArgs {
+    #[arg(long)]
+    height: u64,
+    #[arg(
+        long,
+        env = "XMR_RPC_URL",
+        default_value = "http://127.0.0.1:38081/json_rpc"
+    )]
+    rpc_url: String,
+    #[arg(
+        long,
+        env = "DATABASE_URL",
+        help = "Compares against stored rows and checks emission when set"
+    )]
+    database_url: Option<String>,
+}
+
+#[derive(ClapArgs, Debug)]
+struct ReplayArgs {
+    /// JSON fixture with the `chain` and the `reorg` branch replacing its top.
+    #[arg(long)]
+    fixture: std::path::PathBuf,
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+    #[arg(long, env = "NETWORK", default_value = "stagenet")]
+    network: String,
+    #[arg(long, env = "MAX_REORG_DEPTH", default_value_t = 30)]
+    max_reorg_depth: u64,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("ingestor=info"));
 
+    if env::var("INGEST_CONCURRENCY").is_err() {
+        if let Ok(val) = env::var("CONCURRENCY") {
+            env::set_var("INGEST_CONCURRENCY", val);
+        }
+    }
+
+    let cli = Cli::parse();
+
+    // trace-block and replay-reorg print a report on stdout, so logs go to
+    // stderr there.
+    if matches!(cli.command, Cmd::TraceBlock(_) | Cmd::ReplayReorg(_)) {
+        tracing_subscriber::fmt()
+            .with_env_filter(env_filter)
+            .with_target(false)
+            .with_writer(std::io::stderr)
+            .init();
+        return match cli.command {
+            Cmd::TraceBlock(args) => trace_block(args).await,
+            Cmd::ReplayReorg(args) => replay_reorg(args).await,
+            _ => unreachable!(),
+        };
+    }
     tracing_subscriber::fmt()
         .with_env_filter(env_filter)
         .with_target(false)
         .init();
 
+    if let Cmd::Run(args) = &cli.command {
+        let report = preflight::check_run(args).await;
+        if report.failures() > 0 {
+            bail!("{report}");
+        }
+        info!("{report}");
+    }
+
     let builder = metrics_exporter_prometheus::PrometheusBuilder::new();
     let recorder = builder
         .install_recorder()
         .context("install prometheus recorder")?;
-    let metrics_addr: SocketAddr = "0.0.0.0:9898"
+    let metrics_addr: SocketAddr = preflight::METRICS_ADDR
         .parse()
         .context("parse metrics listen address")?;
+    let readiness = Arc::new(Readiness::default());
     tokio::spawn({
         let handle = recorder.clone();
+        let readiness = Arc::clone(&readiness);
         async move {
-            use axum::{routing::get, Router};
+            use axum::{http::StatusCode, routing::get, Json, Router};
             let route_handle = handle.clone();
-            let app = Router::new().route(
-                "/metrics",
-                get(move || {
-                    let handle = route_handle.clone();
-                    async move { handle.render() }
-                }),
-            );
+            let app = Router::new()
+                .route(
+                    "/metrics",
+                    get(move || {
+                        let handle = route_handle.clone();
+                        async move { handle.render() }
+                    }),
+                )
+                .route(
+                    "/readyz",
+                    get(move || {
+                        let readiness = Arc::clone(&readiness);
+                        async move {
+                            match readiness.not_ready_reason() {
+                                None => {
+                                    (StatusCode::OK, Json(serde_json::json!({"status": "ready"})))
+                                }
+                                Some(reason) => (
+                                    StatusCode::SERVICE_UNAVAILABLE,
+                                    Json(serde_json::json!({
+                                        "status": "not_ready",
+                                        "reason": reason,
+                                    })),
+                                ),
+                            }
+                        }
+                    }),
+                );
             match tokio::net::TcpListener::bind(metrics_addr).await {
                 Ok(listener) => {
                     if let Err(err) = axum::serve(listener, app.into_make_service()).await {
@@ -80,17 +222,14 @@ async fn main() -> Result<()> {
         }
     });
 
-    if env::var("INGEST_CONCURRENCY").is_err() {
-        if let Ok(val) = env::var("CONCURRENCY") {
-            env::set_var("INGEST_CONCURRENCY", val);
-        }
-    }
-
-    let cli = Cli::parse();
-
     match cli.command {
-        Cmd::Run(args) => run(args).await,
+        Cmd::Run(args) => run(*args, readiness, recorder).await,
         Cmd::AnalyticsBackfill(args) => analytics_backfill(args).await,
+        Cmd::BackfillDifficulty(args) => backfill_difficulty(args).await,
+        Cmd::ReprocessQuarantined(args) => reprocess_quarantined(args).await,
+        Cmd::TraceBlock(_) | Cmd::ReplayReorg(_) => {
+            unreachable!("handled before the exporter starts")
+        }
     }
 }
 
@@ -100,33 +239,236 @@ async fn analytics_backfill(args: BackfillArgs) -> Result<()> {
         .await
         .context("failed to connect to postgres")?;
     let processed = analytics::backfill(store.pool(), args.batch).await?;
+    analytics::refresh_leaderboards(store.pool(), analytics::LEADERBOARD_SIZE)
+        .await
+        .context("refresh leaderboards")?;
+    analytics::refresh_mining_stats(store.pool(), Some(0))
+        .await
+        .context("refresh mining stats")?;
     info!(processed, "analytics backfill complete");
     Ok(())
 }
 
-async fn run(args: RunArgs) -> Result<()> {
-    let limiter = Arc::new(limits::make_limiter(args.rpc_rps, args.bootstrap));
-    let conc = limits::eff_concurrency(args.ingest_concurrency, args.bootstrap);
-    let block_workers = conc.max(1).min(4);
-    let tx_workers = conc.max(1);
-    let do_analytics = !args.bootstrap;
+async fn backfill_difficulty(args: DifficultyArgs) -> Result<()> {
+    info!("connecting to database");
+    let store = Store::connect(&args.database_url)
+        .await
+        .context("failed to connect to postgres")?;
+    let rpc = Rpc::new(&args.rpc_url);
+    let headers_range = rpc.probe_caps().await.headers_range;
+    let filled = analytics::backfill_difficulty(
+        store.pool(),
+        &rpc,
+        headers_range,
+        args.from_height..=args.to_height,
+        args.batch.max(1),
+    )
+    .await?;
+    analytics::refresh_mining_stats(store.pool(), Some(0))
+        .await
+        .context("refresh mining stats")?;
+    info!(filled, "difficulty backfill complete");
+    Ok(())
+}
 
+async fn reprocess_quarantined(args: ReprocessArgs) -> Result<()> {
     info!("connecting to database");
     let store = Store::connect(&args.database_url)
         .await
         .context("failed to connect to postgres")?;
-    let checkpoint = Arc::new(Checkpoint::new(store.pool().clone()));
-    let rpc: Arc<dyn MoneroRpc> = Arc::new(Rpc::new(&args.rpc_url));
-    let caps = rpc.probe_caps().await;
+    let summary = quarantine::reprocess(&store, args.dry_run).await?;
     info!(
-        headers_range = caps.headers_range,
-        blocks_by_height_bin = caps.blocks_by_height_bin,
-        "rpc capabilities probed",
+        checked = summary.checked,
+        parsed = summary.parsed,
+        heights = ?summary.heights,
+        "quarantined txs reprocessed; the running ingestor re-ingests the queued heights"
     );
+    Ok(())
+}
 
-    let header_batch = if caps.headers_range { 200 } else { 1 };
+async fn replay_reorg(args: ReplayArgs) -> Result<()> {
+    let fixture = replay::Fixture::load(&args.fixture)?;
+    let store = Store::connect(&args.database_url)
+        .await
+        .context("failed to connect to postgres")?;
+    let report = replay::replay(&store, &args.network, &fixture, args.max_reorg_depth).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    if !report.failures.is_empty() {
+        bail!(
+            "replayed chain differs from the reorg branch in {} places",
+            report.failures.len()
+        );
+    }
+    Ok(())
+}
+
+async fn trace_block(args: TraceArgs) -> Result<()> {
+    let height = i64::try_from(args.height).context("height overflow")?;
+    let store = match &args.database_url {
+        Some(url) => Some(
+            Store::connect(url)
+                .await
+                .context("failed to connect to postgres")?,
+        ),
+        None => None,
+    };
+    let rpc: Arc<dyn MoneroRpc> = Arc::new(Rpc::new(&args.rpc_url));
+    let limiter = Arc::new(limits::make_limiter(10, false));
+    let report = trace::trace_block(rpc, limiter, store.as_ref(), height).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    let failures = report.failures();
+    if failures > 0 {
+        bail!("{failures} validation checks failed for block {height}");
+    }
+    Ok(())
+}
+
+async fn run(args: RunArgs, readiness: Arc<Readiness>, recorder: PrometheusHandle) -> Result<()> {
+    let startup = StartupConfig::new(&args, HEADER_BATCH);
+    startup.announce();
+    let limiter = Arc::new(limits::make_limiter(args.rpc_rps, args.bootstrap));
+    let conc = startup.concurrency;
+    let block_workers = startup.block_workers;
+    let tx_workers = startup.tx_workers;
+    let do_analytics = startup.analytics;
+    let prepare_pool = work_persist::PreparePool::new(startup.prepare_workers);
+
+    info!("connecting to database");
+    let store = Store::connect(&args.database_url)
+        .await
+        .context("failed to connect to postgres")?;
+    let checkpoint = Arc::new(Checkpoint::new(store.pool().clone(), args.network.clone()));
+    checkpoint
+        .claim()
+        .await
+        .context("claim ingestor checkpoint")?;
+    work_persist::resume_incomplete(&store, &checkpoint)
+        .await
+        .context("resume interrupted blocks")?;
+    counters::restore(&store)
+        .await
+        .context("restore ingest counters")?;
+    let counter_snapshots = counters::spawn(store.clone(), recorder.clone());
+    if args.defer_constraints {
+        let deferred = constraints::defer(store.pool())
+            .await
+            .context("re-create foreign keys NOT VALID for initial sync")?;
+        info!(
+            deferred,
+            "foreign keys left unvalidated until ingestion reaches the tip"
+        );
+    } else {
+        let pending = constraints::pending(store.pool())
+            .await
+            .context("inspect foreign keys")?;
+        if !pending.is_empty() {
+            warn!(
+                ?pending,
+                "foreign keys missing or unvalidated, restoring in background"
+            );
+            let pool = store.pool().clone();
+            tokio::spawn(async move {
+                match constraints::restore(&pool).await {
+                    Ok(()) => info!("foreign keys restored"),
+                    Err(err) => warn!(error = ?err, "foreign key restore failed"),
+                }
+            });
+        }
+    }
+    let pruned = store
+        .record_pruned(args.prune)
+        .await
+        .context("record pruned dataset")?;
+    if pruned && !args.prune {
+        warn!("dataset already holds pruned transactions; proof analytics stay partial");
+    }
+    store
+        .record_finality_window(args.finality_window)
+        .await
+        .context("record finality window")?;
+    let rpc: Arc<dyn MoneroRpc> = Arc::new(
+        Rpc::new(&args.rpc_url)
+            .with_max_response_bytes(args.rpc_max_response_bytes)
+            .with_prune(args.prune),
+    );
+    let caps = match capabilities::probe_with_retry(
+        rpc.as_ref(),
+        args.caps_probe_attempts,
+        CAPS_PROBE_BACKOFF,
+    )
+    .await
+    {
+        Some(caps) => {
+            info!(
+                headers_range = caps.headers_range,
+                blocks_by_height_bin = caps.blocks_by_height_bin,
+                "rpc capabilities probed",
+            );
+            caps
+        }
+        None => {
+            warn!(
+                attempts = args.caps_probe_attempts,
+                "daemon unreachable while probing capabilities; using single requests until a re-probe succeeds"
+            );
+            Capabilities::default()
+        }
+    };
+    let caps = Arc::new(LiveCapabilities::new(caps));
+    if args.caps_reprobe_secs > 0 {
+        capabilities::spawn_reprobe(
+            Arc::clone(&rpc),
+            Arc::clone(&caps),
+            Duration::from_secs(args.caps_reprobe_secs),
+        );
+    }
+
+    let header_batch = HEADER_BATCH;
 
-    MempoolWatcher::new(&args.zmq_url, Arc::clone(&rpc), store.clone()).spawn();
+    let analytics_wake = if do_analytics {
+        let analytics_pool =
+            analytics::connect(&args.database_url, args.analytics_statement_timeout_ms)
+                .await
+                .context("failed to open analytics connection")?;
+        let wake = Arc::new(Notify::new());
+        analytics::spawn_worker(
+            analytics_pool,
+            Arc::clone(&wake),
+            Duration::from_secs(args.leaderboard_refresh_secs.max(1)),
+        );
+        Some(wake)
+    } else {
+        None
+    };
+
+    let events = match &args.events_redis_url {
+        Some(url) => Events::connect(url).await?,
+        None => Events::default(),
+    };
+
+    MempoolWatcher::new(
+        &args.zmq_url,
+        Arc::clone(&rpc),
+        store.clone(),
+        args.orphaned_tx_ttl_secs,
+        Duration::from_secs(args.mempool_full_refresh_secs),
+        events.clone(),
+    )
+    .spawn();
+
+    if !args.alert_sinks.is_empty() {
+        alerts::spawn(
+            Alerter::new(
+                args.alert_rules(),
+                args.alert_sinks.clone(),
+                args.alert_webhook_url.clone(),
+                args.alert_webhook_secret.clone(),
+            ),
+            Arc::clone(&rpc),
+            Arc::clone(&checkpoint),
+            Duration::from_secs(args.alert_interval_secs.max(1)),
+        );
+    }
 
     let start_height = match args.start_height {
         Some(start) => Some(i64::try_from(start).context("start height overflow")?),
@@ -141,6 +483,56 @@ async fn run(args: RunArgs) -> Result<()> {
     let (tx_sched, rx_sched, tx_block, rx_block, tx_tx, rx_tx) =
         pipeline::make_channels(&pipeline_cfg);
 
+    if args.fee_estimate_refresh_secs > 0 {
+        fees::spawn_poller(
+            store.clone(),
+            Arc::clone(&rpc),
+            limiter.clone(),
+            Duration::from_secs(args.fee_estimate_refresh_secs),
+        );
+    }
+
+    let (daemon_tip_tx, daemon_tip_rx) = watch::channel(0);
+    daemon_tip::spawn_tracker(
+        store.clone(),
+        Arc::clone(&rpc),
+        limiter.clone(),
+        args.tip_confirmations,
+        daemon_tip_rx,
+    );
+    let memory = if args.memory_budget_mb > 0 {
+        let budget = MemoryBudget::new(args.memory_budget_mb.saturating_mul(1024 * 1024));
+        memory::spawn_monitor(budget.clone(), MEMORY_SAMPLE_INTERVAL);
+        info!(budget_mb = args.memory_budget_mb, "memory budget enabled");
+        budget
+    } else {
+        MemoryBudget::default()
+    };
+    let prefetch = (args.prefetch_window > 0).then(|| {
+        let cache = Prefetcher::new();
+        prefetch::spawn(
+            Arc::clone(&cache),
+            Arc::clone(&rpc),
+            limiter.clone(),
+            Arc::clone(&caps),
+            prefetch::Settings {
+                window: args.prefetch_window,
+                blocks: args.prefetch_blocks,
+                concurrency: args.prefetch_concurrency,
+                header_batch,
+                tip_confirmations: args.tip_confirmations,
+            },
+            memory.clone(),
+            daemon_tip_tx.subscribe(),
+        );
+        info!(
+            window = args.prefetch_window,
+            blocks = args.prefetch_blocks,
+            concurrency = args.prefetch_concurrency,
+            "prefetching ahead of block workers"
+        );
+        cache
+    });
     let sched_cfg = work_sched::Config {
         checkpoint: checkpoint.clone(),
         rpc: Arc::clone(&rpc),
@@ -148,8 +540,12 @@ async fn run(args: RunArgs) -> Result<()> {
         start_height,
         limit: args.limit,
         finality_window: args.finality_window,
-        caps,
+        caps: Arc::clone(&caps),
         header_batch,
+        readiness: Arc::clone(&readiness),
+        tip_confirmations: args.tip_confirmations,
+        daemon_tip: daemon_tip_tx,
+        memory: memory.clone(),
     };
 
     let scheduler = tokio::spawn(async move { work_sched::run(tx_sched, sched_cfg, None).await });
@@ -159,9 +555,12 @@ async fn run(args: RunArgs) -> Result<()> {
         rpc: Arc::clone(&rpc),
         limiter: limiter.clone(),
         store: store.clone(),
-        finality_window: args.finality_window,
+        max_reorg_depth: args.effective_max_reorg_depth(),
         caps,
         header_batch,
+        events: events.clone(),
+        prefetch,
+        memory: memory.clone(),
     };
     let mut block_handles = Vec::with_capacity(block_workers);
     for _ in 0..block_workers {
@@ -179,6 +578,7 @@ async fn run(args: RunArgs) -> Result<()> {
         rpc: Arc::clone(&rpc),
         limiter: limiter.clone(),
         concurrency: conc,
+        memory,
     };
     let mut tx_handles = Vec::with_capacity(tx_workers);
     for _ in 0..tx_workers {
@@ -191,11 +591,53 @@ async fn run(args: RunArgs) -> Result<()> {
     }
     drop(tx_tx);
 
+    let (position_tx, position_rx) = watch::channel(ChainPosition::default());
+    confirmations::spawn_refresher(
+        store.clone(),
+        events.clone(),
+        args.finality_window,
+        Duration::from_secs(args.confirmations_refresh_secs.max(1)),
+        position_rx,
+    );
+    let constraint_restorer = args.defer_constraints.then(|| {
+        constraints::spawn_restore_when_synced(
+            store.pool().clone(),
+            checkpoint.clone(),
+            position_tx.subscribe(),
+            args.finality_window,
+        )
+    });
+
+    reingest::spawn(reingest::Config {
+        block: block_cfg,
+        tx: tx_cfg,
+        persist: work_persist::Config {
+            store: store.clone(),
+            checkpoint: checkpoint.clone(),
+            finality_window: args.finality_window,
+            do_analytics,
+            chain_tips_retention: args.chain_tips_retention,
+            analytics_wake: analytics_wake.clone(),
+            position_tx: None,
+            prepare_pool: prepare_pool.clone(),
+            events: Events::default(),
+            extra_retention: args.tx_extra_retention,
+        },
+        checkpoint: checkpoint.clone(),
+        poll_interval: Duration::from_secs(args.reingest_poll_secs.max(1)),
+    });
+
     let persist_cfg = work_persist::Config {
         store: store.clone(),
         checkpoint: checkpoint.clone(),
         finality_window: args.finality_window,
         do_analytics,
+        chain_tips_retention: args.chain_tips_retention,
+        analytics_wake,
+        position_tx: Some(position_tx),
+        prepare_pool,
+        events,
+        extra_retention: args.tx_extra_retention,
     };
     let persister = tokio::spawn(async move { work_persist::run(rx_tx, persist_cfg, None).await });
 
@@ -212,6 +654,20 @@ async fn run(args: RunArgs) -> Result<()> {
         return Err(err);
     }
 
+    if let Some(restorer) = constraint_restorer {
+        if !restorer.is_finished() {
+            restorer.abort();
+            info!("restoring deferred foreign keys");
+            constraints::restore(store.pool())
+                .await
+                .context("restore foreign keys")?;
+        }
+    }
+
+    counter_snapshots.abort();
+    counters::snapshot(&store, &recorder)
+        .await
+        .context("save ingest counters")?;
     info!("backfill complete");
     Ok(())
 }
@@ -0,0 +1,282 @@
+//! Local parsing of the block blob `get_block` returns next to its `json`
+//! rendering. Only the parts the pipeline consumes are decoded: the header,
+//! the miner tx (re-rendered in the daemon's JSON shape so `prepare_tx`
+//! handles both sources alike) and the list of tx hashes.
+
+use anyhow::{bail, ensure, Context, Result};
+use bex_core::{BlockHash, BlockHeader, TxHash};
+use serde_json::{json, Value};
+
+/// Input tag of the coinbase input (`txin_gen`).
+const TXIN_GEN: u8 = 0xff;
+/// Output target tags: `txout_to_key` and, from v15, `txout_to_tagged_key`.
+const TXOUT_TO_KEY: u8 = 0x02;
+const TXOUT_TO_TAGGED_KEY: u8 = 0x03;
+
+#[derive(Debug)]
+pub struct ParsedBlock {
+    pub major_version: u32,
+    pub minor_version: u32,
+    pub timestamp: u64,
+    pub prev_hash: BlockHash,
+    pub nonce: u64,
+    pub miner_tx: Value,
+    pub tx_hashes: Vec<TxHash>,
+}
+
+impl ParsedBlock {
+    /// Fails when the blob disagrees with the header the daemon reported for
+    /// the same block.
+    pub fn check_header(&self, header: &BlockHeader) -> Result<()> {
+        ensure!(
+            self.prev_hash == header.prev_hash,
+            "prev_hash {} != header {}",
+            self.prev_hash,
+            header.prev_hash
+        );
+        ensure!(
+            (self.major_version, self.minor_version)
+                == (header.major_version, header.minor_version),
+            "version {}.{} != header {}.{}",
+            self.major_version,
+            self.minor_version,
+            header.major_version,
+            header.minor_version
+        );
+        ensure!(
+            self.timestamp == header.timestamp,
+            "timestamp {} != header {}",
+            self.timestamp,
+            header.timestamp
+        );
+        ensure!(
+            self.nonce == header.nonce,
+            "nonce {} != header {}",
+            self.nonce,
+            header.nonce
+        );
+        Ok(())
+    }
+}
+
+pub fn parse_block_hex(blob: &str) -> Result<ParsedBlock> {
+    let bytes = hex::decode(blob).context("block blob is not hex")?;
+    parse_block(&bytes)
+}
+
+pub fn parse_block(bytes: &[u8]) -> Result<ParsedBlock> {
+    let mut r = Reader { bytes, pos: 0 };
+    let major_version = u32::try_from(r.varint()?).context("major_version overflow")?;
+    let minor_version = u32::try_from(r.varint()?).context("minor_version overflow")?;
+    let timestamp = r.varint()?;
+    let prev_hash = BlockHash(r.array()?);
+    let nonce = u64::from(u32::from_le_bytes(r.array()?));
+    let miner_tx = miner_tx(&mut r).context("miner tx")?;
+
+    let count = r.varint()?;
+    let mut tx_hashes = Vec::new();
+    for _ in 0..count {
+        tx_hashes.push(TxHash(r.array()?));
+    }
+    ensure!(
+        r.pos == bytes.len(),
+        "{} trailing bytes after tx hashes",
+        bytes.len() - r.pos
+    );
+
+    Ok(ParsedBlock {
+        major_version,
+        minor_version,
+        timestamp,
+        prev_hash,
+        nonce,
+        miner_tx,
+        tx_hashes,
+    })
+}
+
+fn miner_tx(r: &mut Reader<'_>) -> Result<Value> {
+    let version = r.varint()?;
+    let unlock_time = r.varint()?;
+
+    let mut vin = Vec::new();
+    for _ in 0..r.varint()? {
+        match r.byte()? {
+            TXIN_GEN => vin.push(json!({ "gen": { "height": r.varint()? } })),
+            tag => bail!("unexpected input tag {tag:#04x}"),
+        }
+    }
+
+    let mut vout = Vec::new();
+    for _ in 0..r.varint()? {
+        let amount = r.varint()?;
+        let target = match r.byte()? {
+            TXOUT_TO_KEY => json!({ "key": hex::encode(r.array::<32>()?) }),
+            TXOUT_TO_TAGGED_KEY => {
+                let key = hex::encode(r.array::<32>()?);
+                let view_tag = hex::encode([r.byte()?]);
+                json!({ "tagged_key": { "key": key, "view_tag": view_tag } })
+            }
+            tag => bail!("unexpected output tag {tag:#04x}"),
+        };
+        vout.push(json!({ "amount": amount, "target": target }));
+    }
+
+    let extra_len = usize::try_from(r.varint()?).context("extra length overflow")?;
+    let extra = r.take(extra_len)?.to_vec();
+
+    let mut tx = json!({
+        "version": version,
+        "unlock_time": unlock_time,
+        "vin": vin,
+        "vout": vout,
+        "extra": extra,
+    });
+    if version >= 2 {
+        // Coinbase outputs carry plain amounts, so only the type is present.
+        let rct_type = r.byte()?;
+        ensure!(rct_type == 0, "miner tx has rct type {rct_type}");
+        tx["rct_signatures"] = json!({ "type": 0 });
+    }
+    Ok(tx)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.bytes.len())
+            .with_context(|| format!("blob truncated at byte {}", self.pos))?;
+        let out = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("take returns N bytes"))
+    }
+
+    /// Little-endian base-128 varint, as written by `write_varint`.
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            let bits = u64::from(b & 0x7f);
+            ensure!(shift < 63 || bits <= 1, "varint overflow");
+            value |= bits << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("varint overflow")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut v: u64, out: &mut Vec<u8>) {
+        while v >= 0x80 {
+            out.push((v as u8 & 0x7f) | 0x80);
+            v >>= 7;
+        }
+        out.push(v as u8);
+    }
+
+    fn block_blob(tx_hashes: &[[u8; 32]]) -> Vec<u8> {
+        let mut b = Vec::new();
+        varint(16, &mut b);
+        varint(16, &mut b);
+        varint(1_700_000_000, &mut b);
+        b.extend([0x11; 32]);
+        b.extend(0xdead_beefu32.to_le_bytes());
+        // miner tx: v2, unlock 60 blocks after height 3_000_000
+        varint(2, &mut b);
+        varint(3_000_060, &mut b);
+        varint(1, &mut b);
+        b.push(TXIN_GEN);
+        varint(3_000_000, &mut b);
+        varint(1, &mut b);
+        varint(600_000_000_000, &mut b);
+        b.push(TXOUT_TO_TAGGED_KEY);
+        b.extend([0x22; 32]);
+        b.push(0x7a);
+        varint(2, &mut b);
+        b.extend([0x02, 0x00]);
+        b.push(0);
+        varint(tx_hashes.len() as u64, &mut b);
+        for h in tx_hashes {
+            b.extend(h);
+        }
+        b
+    }
+
+    #[test]
+    fn parses_header_miner_tx_and_hashes() {
+        let parsed = parse_block(&block_blob(&[[0x33; 32], [0x44; 32]])).expect("parse");
+        assert_eq!((parsed.major_version, parsed.minor_version), (16, 16));
+        assert_eq!(parsed.timestamp, 1_700_000_000);
+        assert_eq!(parsed.prev_hash, BlockHash([0x11; 32]));
+        assert_eq!(parsed.nonce, 0xdead_beef);
+        assert_eq!(
+            parsed.tx_hashes,
+            vec![TxHash([0x33; 32]), TxHash([0x44; 32])]
+        );
+        assert_eq!(
+            parsed.miner_tx,
+            json!({
+                "version": 2,
+                "unlock_time": 3_000_060,
+                "vin": [{ "gen": { "height": 3_000_000 } }],
+                "vout": [{
+                    "amount": 600_000_000_000u64,
+                    "target": { "tagged_key": { "key": "22".repeat(32), "view_tag": "7a" } }
+                }],
+                "extra": [2, 0],
+                "rct_signatures": { "type": 0 },
+            })
+        );
+        let tx = crate::codec::parse_tx_json(&parsed.miner_tx.to_string()).expect("tx json");
+        assert_eq!(tx.extra, "0200");
+    }
+
+    #[test]
+    fn rejects_truncated_and_trailing_bytes() {
+        let blob = block_blob(&[[0x33; 32]]);
+        assert!(parse_block(&blob[..blob.len() - 1]).is_err());
+        let mut long = blob.clone();
+        long.push(0);
+        assert!(parse_block(&long).is_err());
+    }
+
+    #[test]
+    fn header_mismatch_is_reported() {
+        let parsed = parse_block(&block_blob(&[])).expect("parse");
+        let mut header = BlockHeader {
+            hash: BlockHash([0; 32]),
+            height: 3_000_000,
+            timestamp: 1_700_000_000,
+            prev_hash: BlockHash([0x11; 32]),
+            major_version: 16,
+            minor_version: 16,
+            nonce: 0xdead_beef,
+            reward: 600_000_000_000,
+            size: 0,
+            difficulty: 0,
+        };
+        parsed.check_header(&header).expect("matching header");
+        header.nonce += 1;
+        assert!(parsed.check_header(&header).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Daemon capabilities shared by the workers that choose between bulk and
+//! per-item RPC paths. They are probed at startup with retries, so a daemon
+//! that is briefly down does not disable bulk paths for the whole run, and
+//! re-probed periodically so they recover after a daemon restart.
+
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::rpc::{Capabilities, MoneroRpc};
+
+/// Longest wait between startup probe attempts.
+const MAX_PROBE_BACKOFF: Duration = Duration::from_secs(30);
+
+pub struct LiveCapabilities {
+    current: RwLock<Capabilities>,
+}
+
+impl LiveCapabilities {
+    pub fn new(caps: Capabilities) -> Self {
+        record(caps);
+        Self {
+            current: RwLock::new(caps),
+        }
+    }
+
+    pub fn get(&self) -> Capabilities {
+        *self.current.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replaces the capabilities, returning the previous ones.
+    pub fn set(&self, caps: Capabilities) -> Capabilities {
+        record(caps);
+        std::mem::replace(
+            &mut *self.current.write().unwrap_or_else(|e| e.into_inner()),
+            caps,
+        )
+    }
+
+    /// Turns off bulk header fetches after one failed at runtime; the next
+    /// re-probe turns them back on if the daemon supports them again.
+    pub fn disable_headers_range(&self) {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        current.headers_range = false;
+        record(*current);
+    }
+}
+
+fn record(caps: Capabilities) {
+    let flag = |on: bool| if on { 1.0 } else { 0.0 };
+    metrics::gauge!("rpc_capability", "capability" => "headers_range")
+        .set(flag(caps.headers_range));
+    metrics::gauge!("rpc_capability", "capability" => "blocks_by_height_bin")
+        .set(flag(caps.blocks_by_height_bin));
+}
+
+/// Probes once the daemon answers `get_block_count`, retrying up to
+/// `attempts` times with exponential backoff from `backoff`. Probing an
+/// unreachable daemon would report every capability as missing, so `None`
+/// means the daemon never answered.
+pub async fn probe_with_retry(
+    rpc: &dyn MoneroRpc,
+    attempts: u32,
+    mut backoff: Duration,
+) -> Option<Capabilities> {
+    for attempt in 1..=attempts.max(1) {
+        match rpc.get_block_count().await {
+            Ok(_) => {
+                metrics::counter!("rpc_capability_probes_total", "outcome" => "ok").increment(1);
+                return Some(rpc.probe_caps().await);
+            }
+            Err(err) => {
+                metrics::counter!("rpc_capability_probes_total", "outcome" => "unreachable")
+                    .increment(1);
+                if attempt < attempts {
+                    warn!(
+                        attempt,
+                        attempts,
+                        retry_in_ms = backoff.as_millis() as u64,
+                        error = ?err,
+                        "daemon unreachable, retrying capability probe"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_PROBE_BACKOFF);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Re-probes every `interval` while the daemon is reachable and applies any
+/// change.
+pub fn spawn_reprobe(
+    rpc: Arc<dyn MoneroRpc>,
+    live: Arc<LiveCapabilities>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let Some(caps) = probe_with_retry(rpc.as_ref(), 1, interval).await else {
+                continue;
+            };
+            let previous = live.set(caps);
+            if previous != caps {
+                info!(
+                    headers_range = caps.headers_range,
+                    blocks_by_height_bin = caps.blocks_by_height_bin,
+                    "rpc capabilities changed"
+                );
+            }
+        }
+    })
+}
@@ -1,9 +1,12 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use sqlx::{PgPool, Row};
 
+/// Ingestion progress for one network. A database only ever tracks a single
+/// network; writes are refused once another network owns a checkpoint row.
 #[derive(Clone)]
 pub struct Checkpoint {
     pool: PgPool,
+    network: String,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -13,15 +16,54 @@ pub struct CheckpointState {
 }
 
 impl Checkpoint {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, network: impl Into<String>) -> Self {
+        Self {
+            pool,
+            network: network.into(),
+        }
+    }
+
+    pub fn network(&self) -> &str {
+        &self.network
+    }
+
+    /// Adopts the unowned row left by older schemas and fails if the database
+    /// already holds a checkpoint for a different network.
+    pub async fn claim(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+UPDATE ingestor_checkpoint SET network = $1
+WHERE network IS NULL
+  AND NOT EXISTS (SELECT 1 FROM ingestor_checkpoint WHERE network = $1)
+"#,
+        )
+        .bind(&self.network)
+        .execute(&self.pool)
+        .await?;
+        self.ensure_single_network().await
+    }
+
+    async fn ensure_single_network(&self) -> Result<()> {
+        let other: Option<String> = sqlx::query_scalar(
+            "SELECT network FROM ingestor_checkpoint WHERE network <> $1 LIMIT 1",
+        )
+        .bind(&self.network)
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(other) = other {
+            bail!(
+                "database already tracks network {other}; refusing to ingest {}",
+                self.network
+            );
+        }
+        Ok(())
     }
 
     pub async fn get_state(&self) -> Result<CheckpointState> {
         let rec = sqlx::query(
-            "SELECT last_height, finalized_height FROM ingestor_checkpoint WHERE id=$1",
+            "SELECT last_height, finalized_height FROM ingestor_checkpoint WHERE network = $1",
         )
-        .bind(1i32)
+        .bind(&self.network)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -46,31 +88,31 @@ impl Checkpoint {
         Ok(self.get_state().await?.ingested_height)
     }
 
+    /// Advances the checkpoint; it never moves back here, since blocks can
+    /// commit slightly out of order. Only reorg healing rewinds it.
     pub async fn set(&self, ingested_height: i64, finalized_height: i64) -> Result<()> {
-        sqlx::query(
+        let res = sqlx::query(
             r#"
-INSERT INTO ingestor_checkpoint (id, last_height, updated_at)
-VALUES (1, $1, NOW())
-ON CONFLICT (id)
-DO UPDATE SET last_height = EXCLUDED.last_height,
+INSERT INTO ingestor_checkpoint (network, last_height, finalized_height, updated_at)
+SELECT $1, $2, $3, NOW()
+WHERE NOT EXISTS (
+  SELECT 1 FROM ingestor_checkpoint WHERE network <> $1
+)
+ON CONFLICT (network)
+DO UPDATE SET last_height = GREATEST(ingestor_checkpoint.last_height, EXCLUDED.last_height),
+              finalized_height = GREATEST(ingestor_checkpoint.finalized_height, EXCLUDED.finalized_height),
               updated_at = NOW()
 "#,
         )
+        .bind(&self.network)
         .bind(ingested_height)
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
-            r#"
-UPDATE ingestor_checkpoint
-SET finalized_height = $1,
-    updated_at = NOW()
-WHERE id = 1
-"#,
-        )
         .bind(finalized_height)
         .execute(&self.pool)
         .await?;
+        if res.rows_affected() == 0 {
+            self.ensure_single_network().await?;
+            bail!("checkpoint write for {} was not applied", self.network);
+        }
         Ok(())
     }
 }
@@ -78,47 +120,31 @@ WHERE id = 1
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::TestDb;
     use anyhow::Result;
-    use sqlx::{postgres::PgPoolOptions, Executor, PgPool};
-
-    static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("../db/migrations");
-
-    async fn setup_pool() -> Result<Option<PgPool>> {
-        let url =
-            match std::env::var("TEST_DATABASE_URL").or_else(|_| std::env::var("DATABASE_URL")) {
-                Ok(url) => url,
-                Err(_) => return Ok(None),
-            };
-
-        let pool = match PgPoolOptions::new().max_connections(1).connect(&url).await {
-            Ok(pool) => pool,
-            Err(err) => {
-                eprintln!("skipping checkpoint test: failed to connect to {url}: {err}");
-                return Ok(None);
-            }
-        };
+    use sqlx::Executor;
 
-        if let Err(err) = MIGRATOR.run(&pool).await {
-            eprintln!("skipping checkpoint test: failed to run migrations: {err}");
-            return Ok(None);
-        }
-
-        Ok(Some(pool))
+    async fn setup_pool() -> Result<Option<TestDb>> {
+        TestDb::start().await
     }
 
     #[tokio::test]
     async fn checkpoint_roundtrip() -> Result<()> {
-        let Some(pool) = setup_pool().await? else {
-            eprintln!("checkpoint_roundtrip skipped (set TEST_DATABASE_URL to run)");
+        let Some(db) = setup_pool().await? else {
+            eprintln!(
+                "checkpoint_roundtrip skipped (set TEST_DATABASE_URL or enable testcontainers)"
+            );
             return Ok(());
         };
+        let pool = db.pool.clone();
 
         if let Err(err) = pool.execute("DELETE FROM ingestor_checkpoint").await {
             eprintln!("skipping checkpoint test: cleanup failed: {err}");
             return Ok(());
         }
 
-        let checkpoint = Checkpoint::new(pool.clone());
+        let checkpoint = Checkpoint::new(pool.clone(), "stagenet");
+        checkpoint.claim().await?;
         let initial = checkpoint.get_state().await?;
         assert_eq!(initial.ingested_height, 0);
         assert_eq!(initial.finalized_height, 0);
@@ -133,6 +159,11 @@ mod tests {
         assert_eq!(end.ingested_height, 1337);
         assert_eq!(end.finalized_height, 1300);
 
+        let other = Checkpoint::new(pool.clone(), "mainnet");
+        assert!(other.claim().await.is_err());
+        assert!(other.set(1, 0).await.is_err());
+        assert_eq!(checkpoint.get().await?, 1337);
+
         Ok(())
     }
 }
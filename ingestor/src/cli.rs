@@ -1,5 +1,10 @@
 use clap::Args as ClapArgs;
 
+use crate::{
+    alerts::{Rules, Sink},
+    codec::ExtraRetention,
+};
+
 #[derive(ClapArgs, Debug)]
 pub struct RunArgs {
     #[arg(long, env = "DATABASE_URL")]
@@ -10,8 +15,78 @@ pub struct RunArgs {
         default_value = "http://127.0.0.1:38081/json_rpc"
     )]
     pub rpc_url: String,
+    #[arg(
+        long,
+        env = "NETWORK",
+        default_value = "stagenet",
+        help = "Network name keying the ingestor checkpoint"
+    )]
+    pub network: String,
     #[arg(long, env = "FINALITY_WINDOW", default_value_t = 30)]
     pub finality_window: u64,
+    #[arg(
+        long,
+        env = "MAX_REORG_DEPTH",
+        help = "Maximum depth walked back when healing a reorg (defaults to the finality window)"
+    )]
+    pub max_reorg_depth: Option<u64>,
+    #[arg(
+        long,
+        env = "CHAIN_TIPS_RETENTION",
+        default_value_t = 1000,
+        help = "Number of recent heights kept in chain_tips (0 disables pruning)"
+    )]
+    pub chain_tips_retention: u64,
+    #[arg(
+        long,
+        env = "TX_EXTRA_RETENTION",
+        value_enum,
+        default_value_t = ExtraRetention::Raw,
+        help = "What txs.extra keeps of each tx's extra field: raw hex, decoded tags, or both"
+    )]
+    pub tx_extra_retention: ExtraRetention,
+    #[arg(
+        long,
+        env = "ORPHANED_TX_TTL_SECS",
+        default_value_t = 86_400,
+        help = "Seconds a reorged-out tx may stay unconfirmed and absent from the pool before it is purged (0 disables)"
+    )]
+    pub orphaned_tx_ttl_secs: u64,
+    #[arg(
+        long,
+        env = "MEMPOOL_FULL_REFRESH_SECS",
+        default_value_t = 5,
+        help = "Longest gap between full pool reconciliations when ZMQ is quiet (jittered by 10%)"
+    )]
+    pub mempool_full_refresh_secs: u64,
+    #[arg(
+        long,
+        env = "ANALYTICS_STATEMENT_TIMEOUT_MS",
+        default_value_t = 5_000,
+        help = "Statement timeout for soft-facts aggregation on the dedicated analytics connection"
+    )]
+    pub analytics_statement_timeout_ms: u64,
+    #[arg(
+        long,
+        env = "LEADERBOARD_REFRESH_SECS",
+        default_value_t = 600,
+        help = "Minimum interval between rebuilds of the block and output leaderboards"
+    )]
+    pub leaderboard_refresh_secs: u64,
+    #[arg(
+        long,
+        env = "CONFIRMATIONS_REFRESH_SECS",
+        default_value_t = 5,
+        help = "Seconds between batched confirmation refreshes over the finality window"
+    )]
+    pub confirmations_refresh_secs: u64,
+    #[arg(
+        long,
+        env = "REINGEST_POLL_SECS",
+        default_value_t = 10,
+        help = "Seconds between polls of the operator re-ingestion queue"
+    )]
+    pub reingest_poll_secs: u64,
     #[arg(
         long = "ingest-concurrency",
         env = "INGEST_CONCURRENCY",
@@ -25,6 +100,33 @@ pub struct RunArgs {
         default_value_t = 10
     )]
     pub rpc_rps: u32,
+    #[arg(
+        long,
+        env = "PREPARE_WORKERS",
+        help = "Blocking threads parsing a block's transactions in parallel (default: CPU count)"
+    )]
+    pub prepare_workers: Option<usize>,
+    #[arg(
+        long,
+        env = "RPC_MAX_RESPONSE_BYTES",
+        default_value_t = crate::rpc::DEFAULT_MAX_RESPONSE_BYTES,
+        help = "Largest daemon response body accepted before the request fails"
+    )]
+    pub rpc_max_response_bytes: usize,
+    #[arg(
+        long,
+        env = "CAPS_PROBE_ATTEMPTS",
+        default_value_t = 5,
+        help = "Startup attempts to reach the daemon before probing its capabilities (1s backoff, doubling)"
+    )]
+    pub caps_probe_attempts: u32,
+    #[arg(
+        long,
+        env = "CAPS_REPROBE_SECS",
+        default_value_t = 300,
+        help = "Seconds between capability re-probes so bulk paths recover after daemon restarts (0 disables)"
+    )]
+    pub caps_reprobe_secs: u64,
     #[arg(
         long,
         env = "BOOTSTRAP",
@@ -32,6 +134,62 @@ pub struct RunArgs {
         help = "Bootstrap mode relaxes limits & disables analytics, for fastest initial sync"
     )]
     pub bootstrap: bool,
+    #[arg(
+        long,
+        env = "PRUNE",
+        default_value_t = false,
+        help = "Fetch pruned transactions (no range proofs or signatures); proof analytics fall back to rct_type"
+    )]
+    pub prune: bool,
+    #[arg(
+        long,
+        env = "DEFER_CONSTRAINTS",
+        default_value_t = false,
+        help = "Re-create tx/input/output/ring foreign keys NOT VALID, skipping the check of existing rows, and validate them once ingestion reaches the tip; new rows are still checked"
+    )]
+    pub defer_constraints: bool,
+    #[arg(
+        long,
+        env = "TIP_CONFIRMATIONS",
+        default_value_t = 0,
+        help = "Leave the newest N daemon blocks unscheduled until they are buried N deep (1 ingests up to tip-1)"
+    )]
+    pub tip_confirmations: u64,
+    #[arg(
+        long,
+        env = "FEE_ESTIMATE_REFRESH_SECS",
+        default_value_t = 60,
+        help = "Seconds between polls of the daemon's get_fee_estimate for /api/v1/fees/base (0 disables)"
+    )]
+    pub fee_estimate_refresh_secs: u64,
+    #[arg(
+        long,
+        env = "PREFETCH_WINDOW",
+        default_value_t = 0,
+        help = "Headers to fetch ahead of the block workers in a dedicated task (0 disables)"
+    )]
+    pub prefetch_window: u64,
+    #[arg(
+        long,
+        env = "PREFETCH_BLOCKS",
+        default_value_t = false,
+        help = "Prefetch block JSON as well as headers"
+    )]
+    pub prefetch_blocks: bool,
+    #[arg(
+        long,
+        env = "PREFETCH_CONCURRENCY",
+        default_value_t = 8,
+        help = "Daemon requests the prefetch task keeps in flight"
+    )]
+    pub prefetch_concurrency: usize,
+    #[arg(
+        long,
+        env = "MEMORY_BUDGET_MB",
+        default_value_t = 0,
+        help = "Resident memory to stay under, in MiB; ingestion is throttled as it nears it (0 disables)"
+    )]
+    pub memory_budget_mb: u64,
     #[arg(long, env = "START_HEIGHT")]
     pub start_height: Option<u64>,
     #[arg(long, env = "LIMIT", help = "Optional limit of blocks to sync")]
@@ -43,4 +201,87 @@ pub struct RunArgs {
         help = "Monero ZMQ publisher providing raw_tx/raw_block topics"
     )]
     pub zmq_url: String,
+    #[arg(
+        long,
+        env = "EVENTS_REDIS_URL",
+        help = "Redis to publish new_block/new_mempool_tx/reorg events to (unset disables)"
+    )]
+    pub events_redis_url: Option<String>,
+    #[arg(
+        long,
+        env = "ALERT_SINKS",
+        value_enum,
+        value_delimiter = ',',
+        help = "Where threshold alerts go: any of stderr,metrics,webhook (unset disables alerting)"
+    )]
+    pub alert_sinks: Vec<Sink>,
+    #[arg(
+        long,
+        env = "ALERT_WEBHOOK_URL",
+        help = "URL the webhook sink POSTs each alert to as JSON"
+    )]
+    pub alert_webhook_url: Option<String>,
+    #[arg(
+        long,
+        env = "ALERT_WEBHOOK_SECRET",
+        hide_env_values = true,
+        help = "Shared secret the webhook sink signs each call with (x-bex-signature)"
+    )]
+    pub alert_webhook_secret: Option<String>,
+    #[arg(
+        long,
+        env = "ALERT_INTERVAL_SECS",
+        default_value_t = 60,
+        help = "Seconds between alert rule evaluations"
+    )]
+    pub alert_interval_secs: u64,
+    #[arg(
+        long,
+        env = "ALERT_LAG_BLOCKS",
+        default_value_t = 20,
+        help = "Alert when ingestion trails the daemon tip by more blocks than this (0 disables)"
+    )]
+    pub alert_lag_blocks: u64,
+    #[arg(
+        long,
+        env = "ALERT_REORG_DEPTH",
+        default_value_t = 2,
+        help = "Alert when a healed reorg is deeper than this (0 disables)"
+    )]
+    pub alert_reorg_depth: u64,
+    #[arg(
+        long,
+        env = "ALERT_RPC_ERROR_PERCENT",
+        default_value_t = 5.0,
+        help = "Alert when more than this percentage of daemon requests fail in an interval (0 disables)"
+    )]
+    pub alert_rpc_error_percent: f64,
+    #[arg(
+        long,
+        env = "ALERT_MEMPOOL_FAILURES",
+        default_value_t = 3,
+        help = "Alert when more mempool reconciliations than this fail in an interval (0 disables)"
+    )]
+    pub alert_mempool_failures: u64,
+}
+
+impl RunArgs {
+    pub fn effective_max_reorg_depth(&self) -> u64 {
+        self.max_reorg_depth.unwrap_or(self.finality_window)
+    }
+
+    pub fn alert_rules(&self) -> Rules {
+        Rules {
+            lag_blocks: self.alert_lag_blocks,
+            reorg_depth: self.alert_reorg_depth,
+            rpc_error_percent: self.alert_rpc_error_percent,
+            mempool_failures: self.alert_mempool_failures,
+        }
+    }
+
+    pub fn effective_prepare_workers(&self) -> usize {
+        self.prepare_workers.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        })
+    }
 }
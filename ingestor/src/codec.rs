@@ -1,5 +1,7 @@
+use std::ops::RangeInclusive;
+
 use anyhow::Result;
-use serde::{de::Error as DeError, Deserialize, Deserializer};
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Deserialize)]
 pub struct TxJson {
@@ -16,7 +18,7 @@ pub struct TxJson {
     pub unlock_time: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct TxAnalysis {
     pub version: u64,
     pub num_inputs: usize,
@@ -27,7 +29,7 @@ pub struct TxAnalysis {
     pub tx_extra_tags: Vec<TxExtraTag>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum TxExtraTag {
     PubKey(String),
     Nonce(Vec<u8>),
@@ -35,6 +37,42 @@ pub enum TxExtraTag {
     Unknown(u8, usize),
 }
 
+impl TxExtraTag {
+    /// Stored form under `txs.extra.tags`. A nonce keeps only its length and
+    /// which kind of payment id it holds, never the id itself.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            TxExtraTag::PubKey(key) => serde_json::json!({ "type": "pub_key", "key": key }),
+            TxExtraTag::Nonce(bytes) => {
+                let payment_id = match (bytes.first(), bytes.len()) {
+                    (Some(0x00), 33) => Some("plain"),
+                    (Some(0x01), 9) => Some("encrypted"),
+                    _ => None,
+                };
+                serde_json::json!({ "type": "nonce", "len": bytes.len(), "payment_id": payment_id })
+            }
+            TxExtraTag::AdditionalPubKeys(count) => {
+                serde_json::json!({ "type": "additional_pub_keys", "count": count })
+            }
+            TxExtraTag::Unknown(tag, len) => {
+                serde_json::json!({ "type": "unknown", "tag": tag, "len": len })
+            }
+        }
+    }
+}
+
+/// What `txs.extra` keeps of a tx's extra field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExtraRetention {
+    /// The raw bytes as hex, under `extra`.
+    #[default]
+    Raw,
+    /// Only the decoded tags, under `tags`.
+    Decoded,
+    /// Both.
+    Both,
+}
+
 fn extra_as_hex<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
@@ -61,6 +99,140 @@ where
     }
 }
 
+/// Atomic units the emission curve converges towards (`MONEY_SUPPLY`).
+const MONEY_SUPPLY: u64 = u64::MAX;
+/// Tail emission floor per minute of block target.
+const FINAL_SUBSIDY_PER_MINUTE: u64 = 300_000_000_000;
+
+/// Base reward (fees excluded, before any size penalty) of the block mined
+/// after `already_generated` atomic units were emitted. Blocks are 60s apart
+/// before hard fork 2 and 120s after.
+pub fn expected_base_reward(already_generated: u64, major_version: u32) -> u64 {
+    let target_minutes: u64 = if major_version >= 2 { 2 } else { 1 };
+    let speed_factor = 20 - (target_minutes - 1);
+    let base = (MONEY_SUPPLY - already_generated) >> speed_factor;
+    base.max(FINAL_SUBSIDY_PER_MINUTE * target_minutes)
+}
+
+/// Atomic units a miner tx pays out: the sum of its cleartext output
+/// amounts. `None` when an output hides its amount or the sum overflows.
+pub fn coinbase_amount(miner_tx: &TxJson) -> Option<u64> {
+    miner_tx.vout.iter().try_fold(0u64, |sum, out| {
+        sum.checked_add(out.get("amount")?.as_u64()?)
+    })
+}
+
+/// Size below which a block never pays the size penalty.
+pub fn full_reward_zone(major_version: u32) -> u64 {
+    match major_version {
+        0 | 1 => 20_000,
+        2..=4 => 60_000,
+        _ => 300_000,
+    }
+}
+
+/// `reported_base - expected` when the difference is anomalous: any excess,
+/// or a shortfall on a block too small to have been penalised.
+pub fn emission_deviation(
+    reported_base: u64,
+    expected: u64,
+    block_size: u64,
+    major_version: u32,
+) -> Option<i64> {
+    let deviation = i128::from(reported_base) - i128::from(expected);
+    let anomalous =
+        deviation > 0 || (deviation < 0 && block_size <= full_reward_zone(major_version));
+    anomalous.then(|| {
+        i64::try_from(deviation).unwrap_or(if deviation > 0 { i64::MAX } else { i64::MIN })
+    })
+}
+
+/// What a non-coinbase tx must satisfy under one hard fork.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkRules {
+    pub min_version: u64,
+    /// Accepted `rct_signatures.type` values; `0` stands for v1 txs.
+    pub rct_types: &'static [i64],
+    pub ring_sizes: RangeInclusive<usize>,
+}
+
+/// Mainnet consensus rules for txs in blocks of `major_version`: v1 txs end
+/// at fork 6, each proof type (Bulletproofs at 8, Bulletproofs2 at 10, CLSAG
+/// at 13, Bulletproofs+ at 15) becomes mandatory one fork after its
+/// introduction, and rings grow to 3, 5, 7, then exactly 11 and 16.
+pub fn fork_rules(major_version: u32) -> ForkRules {
+    let (min_version, rct_types): (u64, &'static [i64]) = match major_version {
+        0..=3 => (1, &[0]),
+        4..=5 => (1, &[0, 1, 2]),
+        6..=7 => (2, &[1, 2]),
+        8 => (2, &[1, 2, 3]),
+        9 => (2, &[3]),
+        10 => (2, &[3, 4]),
+        11..=12 => (2, &[4]),
+        13 => (2, &[4, 5]),
+        14 => (2, &[5]),
+        15 => (2, &[5, 6]),
+        _ => (2, &[6]),
+    };
+    let ring_sizes = match major_version {
+        0..=1 => 1..=usize::MAX,
+        2..=5 => 3..=usize::MAX,
+        6 => 5..=usize::MAX,
+        7 => 7..=usize::MAX,
+        8..=14 => 11..=11,
+        _ => 16..=16,
+    };
+    ForkRules {
+        min_version,
+        rct_types,
+        ring_sizes,
+    }
+}
+
+/// Names of the [`fork_rules`] `tx` breaks in a block of `major_version`:
+/// `version`, `rct_type` and/or `ring_size`. Coinbase txs follow separate
+/// rules and are never flagged.
+pub fn fork_violations(tx: &TxJson, major_version: u32) -> Vec<&'static str> {
+    if tx.vin.iter().any(|v| v.get("gen").is_some()) {
+        return Vec::new();
+    }
+    let rules = fork_rules(major_version);
+    let rct_type = tx
+        .rct_signatures
+        .get("type")
+        .and_then(serde_json::Value::as_i64)
+        .unwrap_or_default();
+
+    let mut violations = Vec::new();
+    if tx.version < rules.min_version {
+        violations.push("version");
+    }
+    if !rules.rct_types.contains(&rct_type) {
+        violations.push("rct_type");
+    }
+    if extract_ring_sizes(&tx.vin)
+        .iter()
+        .any(|size| !rules.ring_sizes.contains(size))
+    {
+        violations.push("ring_size");
+    }
+    violations
+}
+
+/// `rct_signatures.type` of Bulletproofs+ txs (`RCTTypeBulletproofPlus`).
+pub const RCT_TYPE_BULLETPROOF_PLUS: i64 = 6;
+
+/// Whether `tx` is a RingCT tx fetched with `prune`: the daemon leaves out
+/// `rctsig_prunable`, so range proofs and signatures cannot be inspected.
+pub fn is_pruned(tx: &TxJson) -> bool {
+    let rct_type = tx
+        .rct_signatures
+        .get("type")
+        .and_then(serde_json::Value::as_i64)
+        .unwrap_or_default();
+    rct_type > 0 && tx.rctsig_prunable.is_null()
+}
+
 pub fn parse_tx_json(json_str: &str) -> Result<TxJson> {
     Ok(serde_json::from_str::<TxJson>(json_str)?)
 }
@@ -113,6 +285,22 @@ fn estimate_bp(prunable: &serde_json::Value) -> Result<(bool, usize)> {
     Ok((true, 0))
 }
 
+/// The `txs.extra` value for extra bytes `hex_str` under `retention`. Bytes
+/// that are not hex decode to no tags.
+pub fn extra_column(hex_str: &str, retention: ExtraRetention) -> serde_json::Value {
+    let mut column = serde_json::Map::new();
+    if retention != ExtraRetention::Decoded {
+        column.insert("extra".into(), hex_str.into());
+    }
+    if retention != ExtraRetention::Raw {
+        let tags = parse_tx_extra(hex_str)
+            .map(|tags| tags.iter().map(TxExtraTag::to_json).collect())
+            .unwrap_or_default();
+        column.insert("tags".into(), serde_json::Value::Array(tags));
+    }
+    column.into()
+}
+
 pub fn parse_tx_extra(hex_str: &str) -> Result<Vec<TxExtraTag>> {
     let bytes = hex::decode(hex_str)?;
     let mut tags = Vec::new();
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::warn;
+
+use crate::{
+    events::{Event, Events},
+    store::Store,
+};
+
+/// Blocks below the finality window that are still refreshed, so blocks that
+/// just crossed into finality get their final counts written.
+const WINDOW_EXTRA: i64 = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChainPosition {
+    pub tip_height: i64,
+    pub finalized_height: i64,
+}
+
+/// Rewrites `confirmations`/`is_final` for the blocks inside the finality
+/// window relative to `pos` and finalizes any older block left behind,
+/// publishing a `finalized` event for each block that turned final; the
+/// publisher derives the per-tx events from it. Blocks already final when
+/// persisted, as during catch-up, publish nothing.
+pub async fn refresh(
+    store: &Store,
+    finality_window: u64,
+    pos: ChainPosition,
+    events: &Events,
+) -> Result<()> {
+    let finality_i64 = i64::try_from(finality_window).unwrap_or(i64::MAX / 2);
+    let span = finality_i64.max(1) + WINDOW_EXTRA;
+    let start_height = (pos.tip_height - span).max(0);
+    let finalized = store
+        .refresh_confirmations(start_height, pos.tip_height, pos.finalized_height)
+        .await
+        .context("refresh confirmation window")?;
+    if finalized.is_empty() || !events.enabled() {
+        return Ok(());
+    }
+
+    let heights: Vec<i64> = finalized.iter().map(|(height, _)| *height).collect();
+    let txs = store
+        .block_tx_hashes(&heights)
+        .await
+        .context("list txs of finalized blocks")?;
+    for (height, hash) in finalized {
+        let tx_hashes: Vec<_> = txs
+            .iter()
+            .filter(|(tx_height, _)| *tx_height == height)
+            .map(|(_, tx)| *tx)
+            .collect();
+        events.publish(Event::Finalized {
+            height,
+            hash,
+            tx_hashes,
+        });
+    }
+    Ok(())
+}
+
+/// Refreshes confirmations at most once per `interval`, and only when the
+/// persister has published a new chain position since the last pass.
+pub fn spawn_refresher(
+    store: Store,
+    events: Events,
+    finality_window: u64,
+    interval: Duration,
+    mut rx: watch::Receiver<ChainPosition>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match rx.has_changed() {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(_) => break,
+            }
+            let pos = *rx.borrow_and_update();
+            if let Err(err) = refresh(&store, finality_window, pos, &events).await {
+                warn!(error = ?err, tip_height = pos.tip_height, "confirmation refresh failed");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestDb;
+    use bex_core::{BlockHash, TxHash};
+
+    #[tokio::test]
+    async fn publishes_blocks_and_txs_turning_final() -> Result<()> {
+        let Some(db) = TestDb::start().await? else {
+            eprintln!("skipping publishes_blocks_and_txs_turning_final: no database available");
+            return Ok(());
+        };
+        let store = Store::connect(&db.url).await?;
+        let first = 7_960_000i64;
+        let tx = TxHash([0xd1; 32]);
+        let cleanup = || async {
+            sqlx::query("DELETE FROM public.txs WHERE block_height BETWEEN $1 AND $1 + 2")
+                .bind(first)
+                .execute(store.pool())
+                .await?;
+            sqlx::query("DELETE FROM public.blocks WHERE height BETWEEN $1 - 40 AND $1 + 2")
+                .bind(first)
+                .execute(store.pool())
+                .await?;
+            anyhow::Ok(())
+        };
+        cleanup().await?;
+
+        let block_hash = |height: i64| BlockHash([(height - first) as u8 + 0xa0; 32]);
+        // Left behind below the refreshed span, e.g. across a restart.
+        let stale = first - 40;
+        sqlx::query(
+            "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos, is_final)
+             VALUES ($1, $2, $2, to_timestamp(0), 100, 16, 16, 0, 0, 0, false)",
+        )
+        .bind(stale)
+        .bind(BlockHash([0x9f; 32]))
+        .execute(store.pool())
+        .await?;
+        for height in first..=first + 2 {
+            // The oldest block was already final when persisted.
+            sqlx::query(
+                "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos, is_final)
+                 VALUES ($1, $2, $2, to_timestamp(0), 100, 16, 16, 0, 1, 0, $3)",
+            )
+            .bind(height)
+            .bind(block_hash(height))
+            .bind(height == first)
+            .execute(store.pool())
+            .await?;
+        }
+        sqlx::query(
+            "INSERT INTO public.txs
+               (tx_hash, block_height, block_timestamp, block_position, fee_nanos, size_bytes,
+                version, unlock_time, rct_type, num_inputs, num_outputs)
+             VALUES ($1, $2, to_timestamp(0), 0, 0, 1000, 2, 0, 6, 1, 2)",
+        )
+        .bind(tx)
+        .bind(first + 1)
+        .execute(store.pool())
+        .await?;
+
+        let (events, mut rx) = Events::channel();
+        let pos = ChainPosition {
+            tip_height: first + 2,
+            finalized_height: first + 1,
+        };
+        refresh(&store, 1, pos, &events).await?;
+        refresh(&store, 1, pos, &events).await?;
+        drop(events);
+        let mut published = Vec::new();
+        while let Some(event) = rx.recv().await {
+            // The catch-all also finalizes whatever other tests left pending.
+            if matches!(event, Event::Finalized { height, .. } if height >= stale) {
+                published.push(event);
+            }
+        }
+        assert_eq!(
+            published,
+            vec![
+                Event::Finalized {
+                    height: stale,
+                    hash: BlockHash([0x9f; 32]),
+                    tx_hashes: Vec::new(),
+                },
+                Event::Finalized {
+                    height: first + 1,
+                    hash: block_hash(first + 1),
+                    tx_hashes: vec![tx],
+                },
+            ]
+        );
+
+        cleanup().await
+    }
+}
@@ -0,0 +1,205 @@
+//! Foreign keys between txs, inputs, outputs and rings. `--defer-constraints`
+//! re-creates them `NOT VALID`, which skips checking them against the rows
+//! already stored, and [`restore`] validates them against those rows once
+//! ingestion has caught up. A `NOT VALID` key is still checked on every new
+//! insert and still cascades, so row writes are no faster; moving a tx out of
+//! the mempool, requeueing a reorged block and purging orphans keep re-keying
+//! or removing child rows throughout.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use sqlx::{Executor, PgPool};
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{info, warn};
+
+use crate::{checkpoint::Checkpoint, confirmations::ChainPosition};
+
+struct ForeignKey {
+    name: &'static str,
+    table: &'static str,
+    definition: &'static str,
+}
+
+const FOREIGN_KEYS: &[ForeignKey] = &[
+    ForeignKey {
+        name: "fk_tx_inputs_tx",
+        table: "public.tx_inputs",
+        definition: "FOREIGN KEY (tx_block_timestamp, tx_hash) \
+                     REFERENCES public.txs(block_timestamp, tx_hash) \
+                     ON DELETE CASCADE ON UPDATE CASCADE",
+    },
+    ForeignKey {
+        name: "fk_outputs_tx",
+        table: "public.outputs",
+        definition: "FOREIGN KEY (tx_block_timestamp, tx_hash) \
+                     REFERENCES public.txs(block_timestamp, tx_hash) \
+                     ON DELETE CASCADE ON UPDATE CASCADE",
+    },
+    ForeignKey {
+        name: "fk_rings_tx_input",
+        table: "public.rings",
+        definition: "FOREIGN KEY (tx_hash, input_idx) \
+                     REFERENCES public.tx_inputs (tx_hash, idx) ON DELETE CASCADE",
+    },
+    ForeignKey {
+        name: "fk_rings_output",
+        table: "public.rings",
+        definition: "FOREIGN KEY (referenced_output_id) \
+                     REFERENCES public.outputs (output_id) ON DELETE RESTRICT",
+    },
+    ForeignKey {
+        name: "fk_ring_members_tx_input",
+        table: "public.ring_members",
+        definition: "FOREIGN KEY (tx_hash, input_idx) \
+                     REFERENCES public.tx_inputs (tx_hash, idx) ON DELETE CASCADE",
+    },
+    ForeignKey {
+        name: "fk_ring_members_output",
+        table: "public.ring_members",
+        definition: "FOREIGN KEY (output_id) \
+                     REFERENCES public.outputs (output_id) ON DELETE RESTRICT",
+    },
+];
+
+/// How often the restore task compares the checkpoint with the daemon tip.
+const SYNC_POLL: Duration = Duration::from_secs(30);
+
+/// `(name, validated)` for the managed keys that exist. Partition clones of
+/// a key (`conparentid <> 0`) are left to Postgres.
+async fn existing(db: &PgPool) -> Result<Vec<(String, bool)>> {
+    let names: Vec<&str> = FOREIGN_KEYS.iter().map(|fk| fk.name).collect();
+    Ok(sqlx::query_as(
+        "SELECT conname::text, convalidated FROM pg_constraint
+         WHERE contype = 'f' AND conparentid = 0 AND conname = ANY($1)",
+    )
+    .bind(&names)
+    .fetch_all(db)
+    .await?)
+}
+
+/// Managed keys that are missing or not yet validated.
+pub async fn pending(db: &PgPool) -> Result<Vec<&'static str>> {
+    let existing = existing(db).await?;
+    let pending: Vec<&'static str> = FOREIGN_KEYS
+        .iter()
+        .filter(|fk| {
+            !existing
+                .iter()
+                .any(|(name, validated)| name == fk.name && *validated)
+        })
+        .map(|fk| fk.name)
+        .collect();
+    metrics::gauge!("deferred_constraints").set(pending.len() as f64);
+    Ok(pending)
+}
+
+/// Re-creates every managed key `NOT VALID`, adding any that are missing,
+/// and returns how many were re-created. Keys that are already `NOT VALID`
+/// are left alone.
+pub async fn defer(db: &PgPool) -> Result<usize> {
+    let existing = existing(db).await?;
+    let mut tx = db.begin().await?;
+    let mut deferred = 0;
+    for fk in FOREIGN_KEYS {
+        match existing.iter().find(|(name, _)| name == fk.name) {
+            Some((_, false)) => continue,
+            Some((_, true)) => {
+                tx.execute(
+                    format!("ALTER TABLE {} DROP CONSTRAINT {}", fk.table, fk.name).as_str(),
+                )
+                .await
+                .with_context(|| format!("drop {}", fk.name))?;
+            }
+            None => {}
+        }
+        tx.execute(
+            format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} {} NOT VALID",
+                fk.table, fk.name, fk.definition
+            )
+            .as_str(),
+        )
+        .await
+        .with_context(|| format!("add {}", fk.name))?;
+        deferred += 1;
+    }
+    tx.commit().await?;
+    pending(db).await?;
+    Ok(deferred)
+}
+
+/// Re-creates missing keys as `NOT VALID` (an older run may have dropped
+/// them), then validates each key that is not yet valid. Validation fails if
+/// backfilled rows violate a key; the key then stays `NOT VALID` (still
+/// enforced for new rows) and the error names it. Deferring only saves this
+/// scan of existing rows: new inserts were checked against the keys all along.
+pub async fn restore(db: &PgPool) -> Result<()> {
+    let existing = existing(db).await?;
+    let mut tx = db.begin().await?;
+    for fk in FOREIGN_KEYS {
+        if !existing.iter().any(|(name, _)| name == fk.name) {
+            tx.execute(
+                format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} {} NOT VALID",
+                    fk.table, fk.name, fk.definition
+                )
+                .as_str(),
+            )
+            .await
+            .with_context(|| format!("add {}", fk.name))?;
+        }
+    }
+    tx.commit().await?;
+
+    for fk in FOREIGN_KEYS {
+        if existing
+            .iter()
+            .any(|(name, validated)| name == fk.name && *validated)
+        {
+            continue;
+        }
+        db.execute(format!("ALTER TABLE {} VALIDATE CONSTRAINT {}", fk.table, fk.name).as_str())
+            .await
+            .with_context(|| format!("validate {}", fk.name))?;
+        info!(constraint = fk.name, "foreign key validated");
+    }
+    pending(db).await?;
+    Ok(())
+}
+
+/// Restores the keys once the ingested height is within `finality_window`
+/// of the daemon tip reported through `position`.
+pub fn spawn_restore_when_synced(
+    db: PgPool,
+    checkpoint: Arc<Checkpoint>,
+    mut position: watch::Receiver<ChainPosition>,
+    finality_window: u64,
+) -> JoinHandle<()> {
+    let window = i64::try_from(finality_window).unwrap_or(i64::MAX / 2);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(SYNC_POLL).await;
+            let tip = position.borrow_and_update().tip_height;
+            let ingested = match checkpoint.get().await {
+                Ok(h) => h,
+                Err(err) => {
+                    warn!(error = ?err, "checkpoint read failed");
+                    continue;
+                }
+            };
+            if tip == 0 || ingested + window < tip {
+                continue;
+            }
+            info!(
+                ingested,
+                tip, "initial sync caught up, restoring foreign keys"
+            );
+            match restore(&db).await {
+                Ok(()) => info!("foreign keys restored"),
+                Err(err) => warn!(error = ?err, "foreign key restore failed"),
+            }
+            return;
+        }
+    })
+}
@@ -0,0 +1,141 @@
+//! Keeps the exporter's counters across restarts. Every counter series is
+//! copied into `ingest_counters` on an interval and once more on exit, and
+//! started from the stored value on the next run, so long backfills do not
+//! show their totals dropping to zero.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use metrics::Label;
+use metrics_exporter_prometheus::PrometheusHandle;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::store::Store;
+
+/// How often counters are written out. At most this much counting is lost
+/// when the process is killed.
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Starts every stored counter series at its saved value. Call once before
+/// anything increments them.
+pub async fn restore(store: &Store) -> Result<()> {
+    for (series, value) in store
+        .ingest_counters()
+        .await
+        .context("read ingest counters")?
+    {
+        let Some((name, labels)) = parse_series(&series) else {
+            warn!(series, "skipping unreadable stored counter");
+            continue;
+        };
+        metrics::counter!(name, labels).absolute(u64::try_from(value).unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// Writes the current counter values to `ingest_counters`.
+pub async fn snapshot(store: &Store, handle: &PrometheusHandle) -> Result<()> {
+    let series = counter_series(&handle.render());
+    store
+        .save_ingest_counters(&series)
+        .await
+        .context("save ingest counters")
+}
+
+/// Snapshots the counters every [`SNAPSHOT_INTERVAL`].
+pub fn spawn(store: Store, handle: PrometheusHandle) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(SNAPSHOT_INTERVAL).await;
+            if let Err(err) = snapshot(&store, &handle).await {
+                warn!(error = ?err, "counter snapshot failed");
+            }
+        }
+    })
+}
+
+/// Counter series of a Prometheus text exposition, keyed as rendered
+/// (`name{label="value",...}`).
+pub fn counter_series(rendered: &str) -> Vec<(String, i64)> {
+    let mut counter = None;
+    let mut series = Vec::new();
+    for line in rendered.lines() {
+        if let Some(decl) = line.strip_prefix("# TYPE ") {
+            counter = decl
+                .strip_suffix(" counter")
+                .map(|name| name.trim().to_owned());
+            continue;
+        }
+        let Some(name) = &counter else { continue };
+        let Some((key, value)) = line.rsplit_once(' ') else {
+            continue;
+        };
+        if !key.starts_with(name.as_str()) {
+            continue;
+        }
+        if let Ok(value) = value.parse::<f64>() {
+            series.push((key.to_owned(), value as i64));
+        }
+    }
+    series
+}
+
+/// Splits a series key from [`counter_series`] into its name and labels.
+fn parse_series(series: &str) -> Option<(String, Vec<Label>)> {
+    let Some((name, rest)) = series.split_once('{') else {
+        return Some((series.to_owned(), Vec::new()));
+    };
+    let mut rest = rest.strip_suffix('}')?;
+    let mut labels = Vec::new();
+    while !rest.is_empty() {
+        let (key, after) = rest.split_once("=\"")?;
+        let mut value = String::new();
+        let mut chars = after.char_indices();
+        let end = loop {
+            match chars.next()? {
+                (_, '\\') => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    c => value.push(c),
+                },
+                (i, '"') => break i,
+                (_, c) => value.push(c),
+            }
+        };
+        labels.push(Label::new(key.to_owned(), value));
+        rest = after[end + 1..].trim_start_matches(',');
+    }
+    Some((name.to_owned(), labels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    #[test]
+    fn restored_series_render_as_saved() {
+        let saved = vec![
+            ("block_decode_total{source=\"blob\"}".to_owned(), 41),
+            ("memory_throttle_total".to_owned(), 3),
+            (
+                "rpc_errors_total{method=\"get_block\"}".to_owned(),
+                1_000_000,
+            ),
+        ];
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            for (series, value) in &saved {
+                let (name, labels) = parse_series(series).unwrap();
+                metrics::counter!(name, labels).absolute(*value as u64);
+            }
+            metrics::histogram!("block_process_ms").record(5.0);
+            metrics::gauge!("queue_depth").set(7.0);
+        });
+
+        let mut series = counter_series(&handle.render());
+        series.sort();
+        assert_eq!(series, saved);
+    }
+}
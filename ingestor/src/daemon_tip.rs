@@ -0,0 +1,83 @@
+//! Follows the daemon's raw tip, which the scheduler publishes on every poll.
+//! With `--tip-confirmations` the newest blocks are not ingested until they
+//! are buried that deep, so this task stands in for the persister on those
+//! blocks: it hides the pool txs they mined and records the raw tip for
+//! `GET /api/v1/status`.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use governor::DefaultDirectRateLimiter;
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::warn;
+
+use crate::{pipeline::SchedMsg, rpc::MoneroRpc, store::Store, work_block::fetch_block_detached};
+
+/// Heights the scheduler is holding back below raw tip `tip`.
+pub fn held_back(tip: u64, tip_confirmations: u64) -> std::ops::RangeInclusive<u64> {
+    tip.checked_sub(tip_confirmations)
+        .map_or(0, |ingestable| ingestable + 1)..=tip
+}
+
+/// Records `tip` and marks the pool txs mined in the held-back blocks. Every
+/// held block is re-read, so a reorg among them is picked up at the next tip.
+pub async fn track(
+    store: &Store,
+    rpc: &dyn MoneroRpc,
+    limiter: &Arc<DefaultDirectRateLimiter>,
+    tip_confirmations: u64,
+    tip: u64,
+) -> Result<()> {
+    let tip_i64 = i64::try_from(tip).context("tip height overflow")?;
+    store
+        .record_daemon_tip(tip_i64)
+        .await
+        .context("record daemon tip")?;
+    metrics::gauge!("daemon_tip_height").set(tip as f64);
+    for height in held_back(tip, tip_confirmations) {
+        let msg = SchedMsg {
+            height: i64::try_from(height).context("height overflow")?,
+            tip_height: tip_i64,
+            finalized_height: 0,
+            started: std::time::Instant::now(),
+        };
+        let block = fetch_block_detached(rpc, limiter, &msg)
+            .await
+            .with_context(|| format!("fetch held-back block {height}"))?;
+        store
+            .mark_mempool_mined(block.height, &block.tx_hashes)
+            .await
+            .with_context(|| format!("mark txs mined at {height}"))?;
+    }
+    Ok(())
+}
+
+pub fn spawn_tracker(
+    store: Store,
+    rpc: Arc<dyn MoneroRpc>,
+    limiter: Arc<DefaultDirectRateLimiter>,
+    tip_confirmations: u64,
+    mut rx: watch::Receiver<u64>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while rx.changed().await.is_ok() {
+            let tip = *rx.borrow_and_update();
+            if let Err(err) = track(&store, rpc.as_ref(), &limiter, tip_confirmations, tip).await {
+                warn!(error = ?err, tip, "daemon tip tracking failed");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_back_the_newest_blocks() {
+        assert!(held_back(100, 0).is_empty());
+        assert_eq!(held_back(100, 1), 100..=100);
+        assert_eq!(held_back(100, 3), 98..=100);
+        assert_eq!(held_back(1, 5), 0..=1);
+    }
+}
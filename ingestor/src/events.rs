@@ -0,0 +1,315 @@
+//! Realtime notifications on Redis pub/sub, the transport behind the API's
+//! live feeds. Enabled by `EVENTS_REDIS_URL`; each event is a JSON object
+//! published on its own channel. Publishing is fire-and-forget: events are
+//! queued to a background task, and dropped (and counted) rather than
+//! stalling ingestion when Redis is slow or down.
+//!
+//! Per-tx finality events are not queued. The publisher derives them from
+//! each `finalized` event and only for tx channels that have a subscriber
+//! (`PUBSUB NUMSUB`), so a block of a thousand txs nobody waits for costs one
+//! round trip instead of a thousand publishes.
+
+use std::borrow::Cow;
+
+use anyhow::{Context, Result};
+use bex_core::{BlockHash, TxHash};
+use redis::aio::ConnectionManager;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+pub const NEW_BLOCK_CHANNEL: &str = "bex:new_block";
+pub const NEW_MEMPOOL_TX_CHANNEL: &str = "bex:new_mempool_tx";
+pub const REORG_CHANNEL: &str = "bex:reorg";
+pub const FINALIZED_CHANNEL: &str = "bex:finalized";
+/// Prefix of the per-tx channels, `bex:tx_finalized:<tx hash>`.
+pub const TX_FINALIZED_CHANNEL: &str = "bex:tx_finalized";
+
+/// Events waiting for the publisher; further events are dropped.
+const QUEUE: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    NewBlock {
+        height: u64,
+        hash: BlockHash,
+        prev_hash: BlockHash,
+        timestamp: u64,
+        tx_count: usize,
+    },
+    NewMempoolTx {
+        hash: String,
+        fee: u64,
+        size_bytes: u64,
+    },
+    /// Stored blocks from `fork_height` up were rolled back; `depth` is how
+    /// many of them the new chain replaced.
+    Reorg { fork_height: i64, depth: i64 },
+    /// The block at `height` became final, i.e. fell out of the finality
+    /// window below the tip.
+    Finalized {
+        height: i64,
+        hash: BlockHash,
+        tx_hashes: Vec<TxHash>,
+    },
+    /// A tx of a block that just became final, published on a channel of
+    /// its own so a consumer subscribes to exactly the txs it waits for.
+    /// Only sent while that channel has a subscriber.
+    TxFinalized {
+        hash: TxHash,
+        height: i64,
+        block_hash: BlockHash,
+    },
+}
+
+impl Event {
+    pub fn channel(&self) -> &'static str {
+        match self {
+            Event::NewBlock { .. } => NEW_BLOCK_CHANNEL,
+            Event::NewMempoolTx { .. } => NEW_MEMPOOL_TX_CHANNEL,
+            Event::Reorg { .. } => REORG_CHANNEL,
+            Event::Finalized { .. } => FINALIZED_CHANNEL,
+            Event::TxFinalized { .. } => TX_FINALIZED_CHANNEL,
+        }
+    }
+
+    /// Channel the event is published on. Only per-tx events extend
+    /// [`Event::channel`], which stays the metrics label.
+    pub fn topic(&self) -> Cow<'static, str> {
+        match self {
+            Event::TxFinalized { hash, .. } => Cow::Owned(format!("{TX_FINALIZED_CHANNEL}:{hash}")),
+            event => Cow::Borrowed(event.channel()),
+        }
+    }
+}
+
+/// Handle for publishing events; the default one discards them.
+#[derive(Clone, Default)]
+pub struct Events {
+    queue: Option<mpsc::Sender<Event>>,
+}
+
+impl Events {
+    /// Connects to Redis and spawns the publisher task.
+    pub async fn connect(redis_url: &str) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("parse EVENTS_REDIS_URL")?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .context("connect events redis")?;
+        let (queue, rx) = mpsc::channel(QUEUE);
+        tokio::spawn(publish_loop(conn, rx));
+        info!("publishing realtime events to redis");
+        Ok(Self { queue: Some(queue) })
+    }
+
+    /// Channel-backed handle for tests; events land on the returned receiver.
+    pub fn channel() -> (Self, mpsc::Receiver<Event>) {
+        let (queue, rx) = mpsc::channel(QUEUE);
+        (Self { queue: Some(queue) }, rx)
+    }
+
+    /// False for the default handle, so callers can skip building events.
+    pub fn enabled(&self) -> bool {
+        self.queue.is_some()
+    }
+
+    pub fn publish(&self, event: Event) {
+        let Some(queue) = &self.queue else {
+            return;
+        };
+        if let Err(err) = queue.try_send(event) {
+            let channel = match &err {
+                mpsc::error::TrySendError::Full(event)
+                | mpsc::error::TrySendError::Closed(event) => event.channel(),
+            };
+            metrics::counter!("events_dropped_total", "channel" => channel).increment(1);
+        }
+    }
+}
+
+async fn publish_loop(mut conn: ConnectionManager, mut rx: mpsc::Receiver<Event>) {
+    while let Some(event) = rx.recv().await {
+        publish_one(&mut conn, &event).await;
+        if let Event::Finalized { .. } = &event {
+            match watched_txs(&mut conn, &event).await {
+                Ok(txs) => {
+                    for tx in txs {
+                        publish_one(&mut conn, &tx).await;
+                    }
+                }
+                Err(err) => {
+                    warn!(error = ?err, "failed to list watched tx channels");
+                    metrics::counter!("events_dropped_total", "channel" => TX_FINALIZED_CHANNEL)
+                        .increment(1);
+                }
+            }
+        }
+    }
+}
+
+async fn publish_one(conn: &mut ConnectionManager, event: &Event) {
+    let channel = event.channel();
+    let payload = match serde_json::to_string(event) {
+        Ok(payload) => payload,
+        Err(err) => {
+            warn!(channel, error = ?err, "failed to encode event");
+            return;
+        }
+    };
+    match redis::cmd("PUBLISH")
+        .arg(event.topic().as_ref())
+        .arg(payload)
+        .query_async::<_, i64>(conn)
+        .await
+    {
+        Ok(_) => metrics::counter!("events_published_total", "channel" => channel).increment(1),
+        Err(err) => {
+            warn!(channel, error = ?err, "failed to publish event");
+            metrics::counter!("events_dropped_total", "channel" => channel).increment(1);
+        }
+    }
+}
+
+/// The `tx_finalized` events of a `finalized` event whose channels have a
+/// subscriber right now.
+async fn watched_txs(conn: &mut ConnectionManager, finalized: &Event) -> Result<Vec<Event>> {
+    let txs = tx_finalized(finalized);
+    if txs.is_empty() {
+        return Ok(txs);
+    }
+    let mut numsub = redis::cmd("PUBSUB");
+    numsub.arg("NUMSUB");
+    for tx in &txs {
+        numsub.arg(tx.topic().as_ref());
+    }
+    let counts: Vec<(String, i64)> = numsub.query_async(conn).await?;
+    Ok(subscribed(txs, &counts))
+}
+
+/// One `tx_finalized` event per tx of a `finalized` event.
+fn tx_finalized(finalized: &Event) -> Vec<Event> {
+    let Event::Finalized {
+        height,
+        hash,
+        tx_hashes,
+    } = finalized
+    else {
+        return Vec::new();
+    };
+    tx_hashes
+        .iter()
+        .map(|tx| Event::TxFinalized {
+            hash: *tx,
+            height: *height,
+            block_hash: *hash,
+        })
+        .collect()
+}
+
+/// Keeps the events whose topic has subscribers in a `PUBSUB NUMSUB` reply.
+fn subscribed(events: Vec<Event>, counts: &[(String, i64)]) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| {
+            counts
+                .iter()
+                .any(|(topic, n)| *n > 0 && *topic == event.topic())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn events_are_tagged_json_on_their_own_channel() {
+        let block = Event::NewBlock {
+            height: 7,
+            hash: BlockHash([0xab; 32]),
+            prev_hash: BlockHash([0xcd; 32]),
+            timestamp: 1_700_000_000,
+            tx_count: 3,
+        };
+        assert_eq!(block.channel(), NEW_BLOCK_CHANNEL);
+        assert_eq!(
+            serde_json::to_value(&block).unwrap(),
+            json!({
+                "type": "new_block",
+                "height": 7,
+                "hash": "ab".repeat(32),
+                "prev_hash": "cd".repeat(32),
+                "timestamp": 1_700_000_000,
+                "tx_count": 3,
+            })
+        );
+        let reorg = Event::Reorg {
+            fork_height: 100,
+            depth: 2,
+        };
+        assert_eq!(reorg.channel(), REORG_CHANNEL);
+        assert_eq!(
+            serde_json::to_value(&reorg).unwrap(),
+            json!({ "type": "reorg", "fork_height": 100, "depth": 2 })
+        );
+        let tx = Event::TxFinalized {
+            hash: TxHash([0x01; 32]),
+            height: 100,
+            block_hash: BlockHash([0xab; 32]),
+        };
+        assert_eq!(tx.channel(), TX_FINALIZED_CHANNEL);
+        assert_eq!(tx.topic(), format!("bex:tx_finalized:{}", "01".repeat(32)));
+        assert_eq!(reorg.topic(), REORG_CHANNEL);
+    }
+
+    #[test]
+    fn tx_events_go_only_to_subscribed_channels() {
+        let finalized = Event::Finalized {
+            height: 100,
+            hash: BlockHash([0xab; 32]),
+            tx_hashes: vec![TxHash([0x01; 32]), TxHash([0x02; 32])],
+        };
+        let txs = tx_finalized(&finalized);
+        assert_eq!(txs.len(), 2);
+        let counts = vec![
+            (txs[0].topic().into_owned(), 0),
+            (txs[1].topic().into_owned(), 2),
+        ];
+        assert_eq!(
+            subscribed(txs, &counts),
+            vec![Event::TxFinalized {
+                hash: TxHash([0x02; 32]),
+                height: 100,
+                block_hash: BlockHash([0xab; 32]),
+            }]
+        );
+        assert!(tx_finalized(&Event::Reorg {
+            fork_height: 1,
+            depth: 1
+        })
+        .is_empty());
+    }
+
+    #[tokio::test]
+    async fn full_queue_drops_instead_of_blocking() {
+        let (events, mut rx) = Events::channel();
+        for depth in 0..QUEUE as i64 + 5 {
+            events.publish(Event::Reorg {
+                fork_height: 1,
+                depth,
+            });
+        }
+        Events::default().publish(Event::Reorg {
+            fork_height: 1,
+            depth: 0,
+        });
+        drop(events);
+        let mut received = 0;
+        while rx.recv().await.is_some() {
+            received += 1;
+        }
+        assert_eq!(received, QUEUE);
+    }
+}
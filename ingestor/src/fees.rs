@@ -0,0 +1,49 @@
+//! Polls the daemon's dynamic base fee into `fee_estimate`, which the API
+//! serves next to fee percentiles derived from stored txs. The API never
+//! talks to the daemon itself.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use governor::DefaultDirectRateLimiter;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::{rpc::MoneroRpc, store::Store};
+
+pub async fn refresh(
+    store: &Store,
+    rpc: &dyn MoneroRpc,
+    limiter: &Arc<DefaultDirectRateLimiter>,
+) -> Result<()> {
+    limiter.until_ready().await;
+    let estimate = rpc.get_fee_estimate().await.context("get_fee_estimate")?;
+    if estimate.status != "OK" {
+        bail!("get_fee_estimate status {}", estimate.status);
+    }
+    store
+        .record_fee_estimate(&estimate)
+        .await
+        .context("record fee estimate")?;
+    metrics::gauge!("fee_estimate_per_byte").set(estimate.fee as f64);
+    Ok(())
+}
+
+/// Refreshes the estimate every `interval`, starting immediately.
+pub fn spawn_poller(
+    store: Store,
+    rpc: Arc<dyn MoneroRpc>,
+    limiter: Arc<DefaultDirectRateLimiter>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(err) = refresh(&store, rpc.as_ref(), &limiter).await {
+                warn!(error = ?err, "fee estimate refresh failed");
+            }
+        }
+    })
+}
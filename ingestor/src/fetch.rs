@@ -1,4 +1,4 @@
-use crate::rpc::MoneroRpc;
+use crate::rpc::{MoneroRpc, ResponseTooLarge};
 
 pub async fn fetch_txs_adaptive(
     rpc: &(impl MoneroRpc + ?Sized),
@@ -12,7 +12,15 @@ pub async fn fetch_txs_adaptive(
     while i < hashes.len() {
         limiter.until_ready().await;
         let end = (i + chunk).min(hashes.len());
-        let res = rpc.get_transactions(&hashes[i..end]).await?;
+        let res = match rpc.get_transactions(&hashes[i..end]).await {
+            Ok(res) => res,
+            // Oversized batches are split down to single txs before giving up.
+            Err(err) if err.is::<ResponseTooLarge>() && end - i > 1 => {
+                chunk = ((end - i) / 2).max(1);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
         if !res.missed_tx.is_empty() {
             chunk = (chunk / 2).max(10);
             continue;
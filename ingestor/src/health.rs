@@ -0,0 +1,47 @@
+use std::sync::RwLock;
+
+/// Conditions that make the ingestor unfit to serve as a source of truth,
+/// reported on `/readyz` next to `/metrics`.
+#[derive(Default)]
+pub struct Readiness {
+    daemon_regression: RwLock<Option<String>>,
+}
+
+impl Readiness {
+    /// Records (or clears, with `None`) a daemon whose tip fell behind the
+    /// stored chain by more than the finality window.
+    pub fn set_daemon_regression(&self, detail: Option<String>) {
+        let active = detail.is_some();
+        *self
+            .daemon_regression
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = detail;
+        metrics::gauge!("daemon_height_regressed").set(if active { 1.0 } else { 0.0 });
+    }
+
+    /// `None` when ready, otherwise why not.
+    pub fn not_ready_reason(&self) -> Option<String> {
+        self.daemon_regression
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Readiness;
+
+    #[test]
+    fn regression_toggles_readiness() {
+        let readiness = Readiness::default();
+        assert_eq!(readiness.not_ready_reason(), None);
+        readiness.set_daemon_regression(Some("daemon tip 10 behind".into()));
+        assert_eq!(
+            readiness.not_ready_reason().as_deref(),
+            Some("daemon tip 10 behind")
+        );
+        readiness.set_daemon_regression(None);
+        assert_eq!(readiness.not_ready_reason(), None);
+    }
+}
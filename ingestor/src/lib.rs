@@ -1,14 +1,36 @@
+pub mod alerts;
 pub mod analytics;
+pub mod banner;
+pub mod blob;
+pub mod capabilities;
 pub mod checkpoint;
 pub mod cli;
 pub mod codec;
+pub mod confirmations;
+pub mod constraints;
+pub mod counters;
+pub mod daemon_tip;
+pub mod events;
+pub mod fees;
 pub mod fetch;
+pub mod health;
 pub mod limits;
+pub mod memory;
 pub mod mempool;
 pub mod pipeline;
+pub mod prefetch;
+pub mod preflight;
+pub mod quarantine;
+pub mod reingest;
 pub mod reorg;
+pub mod replay;
 pub mod rpc;
+pub mod schema;
 pub mod store;
+#[cfg(any(test, feature = "testing"))]
+#[doc(hidden)]
+pub mod testing;
+pub mod trace;
 pub mod work_block;
 pub mod work_persist;
 pub mod work_sched;
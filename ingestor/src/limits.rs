@@ -2,14 +2,18 @@ use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
 use std::num::NonZeroU32;
 
 pub fn make_limiter(rps: u32, bootstrap: bool) -> DefaultDirectRateLimiter {
+    RateLimiter::direct(Quota::per_second(
+        NonZeroU32::new(eff_rps(rps, bootstrap)).expect("quota denominator must be non-zero"),
+    ))
+}
+
+pub fn eff_rps(rps: u32, bootstrap: bool) -> u32 {
     let eff = if bootstrap {
         ((rps as f32) * 2.5).ceil() as u32
     } else {
         rps
     };
-    RateLimiter::direct(Quota::per_second(
-        NonZeroU32::new(eff.max(1)).expect("quota denominator must be non-zero"),
-    ))
+    eff.max(1)
 }
 
 pub fn eff_concurrency(base: usize, bootstrap: bool) -> usize {
@@ -0,0 +1,183 @@
+//! Memory budget for small hosts. A monitor samples the process's resident
+//! set size and, once it nears `--memory-budget-mb`, throttles ingestion: the
+//! scheduler stops queueing heights, header ranges and tx batches shrink and
+//! the prefetch task idles. The bounded pipeline channels then drain, and
+//! full speed resumes once RSS is back under the low watermark.
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{info, warn};
+
+/// Share of the budget at which ingestion is throttled.
+const HIGH_WATERMARK: f64 = 0.9;
+/// Share of the budget RSS must fall below before throttling ends.
+const LOW_WATERMARK: f64 = 0.75;
+/// Batch sizes are divided by this while throttled.
+const THROTTLED_BATCH_DIVISOR: u64 = 4;
+
+/// Shared throttle state; the default has no budget and never throttles.
+#[derive(Clone, Default)]
+pub struct MemoryBudget {
+    inner: Option<Arc<Inner>>,
+}
+
+struct Inner {
+    limit_bytes: u64,
+    throttled: watch::Sender<bool>,
+}
+
+impl MemoryBudget {
+    pub fn new(limit_bytes: u64) -> Self {
+        metrics::gauge!("memory_budget_bytes").set(limit_bytes as f64);
+        metrics::gauge!("memory_throttled").set(0.0);
+        Self {
+            inner: Some(Arc::new(Inner {
+                limit_bytes,
+                throttled: watch::channel(false).0,
+            })),
+        }
+    }
+
+    pub fn throttled(&self) -> bool {
+        self.inner
+            .as_ref()
+            .is_some_and(|inner| *inner.throttled.borrow())
+    }
+
+    /// Applies an RSS sample, throttling above the high watermark and
+    /// releasing below the low one. Returns whether ingestion is throttled.
+    pub fn observe(&self, rss_bytes: u64) -> bool {
+        let Some(inner) = &self.inner else {
+            return false;
+        };
+        let limit = inner.limit_bytes as f64;
+        let rss = rss_bytes as f64;
+        inner.throttled.send_if_modified(|throttled| {
+            let next = if *throttled {
+                rss >= limit * LOW_WATERMARK
+            } else {
+                rss >= limit * HIGH_WATERMARK
+            };
+            if next == *throttled {
+                return false;
+            }
+            if next {
+                warn!(
+                    rss_bytes,
+                    budget_bytes = inner.limit_bytes,
+                    "memory budget nearly exhausted, throttling ingestion"
+                );
+                metrics::counter!("memory_throttle_total").increment(1);
+            } else {
+                info!(
+                    rss_bytes,
+                    budget_bytes = inner.limit_bytes,
+                    "memory back under budget, resuming full speed"
+                );
+            }
+            metrics::gauge!("memory_throttled").set(if next { 1.0 } else { 0.0 });
+            *throttled = next;
+            true
+        });
+        self.throttled()
+    }
+
+    /// `configured` while unthrottled, a fraction of it (at least 1) while
+    /// throttled.
+    pub fn scale(&self, configured: u64) -> u64 {
+        if self.throttled() {
+            (configured / THROTTLED_BATCH_DIVISOR).max(1)
+        } else {
+            configured
+        }
+    }
+
+    /// Returns once ingestion is no longer throttled.
+    pub async fn wait_unthrottled(&self) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        let mut rx = inner.throttled.subscribe();
+        // The sender lives in `inner`, so the channel cannot close here.
+        let _ = rx.wait_for(|throttled| !*throttled).await;
+    }
+}
+
+/// The process's resident set size, from `/proc/self/status`. `None` where
+/// procfs is unavailable.
+pub fn resident_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// Samples RSS every `interval` and feeds it to `budget`.
+pub fn spawn_monitor(budget: MemoryBudget, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if resident_bytes().is_none() {
+            warn!("cannot read resident set size; memory budget not enforced");
+            return;
+        }
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Some(rss) = resident_bytes() {
+                metrics::gauge!("process_resident_bytes").set(rss as f64);
+                budget.observe(rss);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttles_between_watermarks() {
+        let budget = MemoryBudget::new(1000);
+        assert!(!budget.observe(800));
+        assert_eq!(budget.scale(200), 200);
+        assert!(budget.observe(900));
+        assert_eq!(budget.scale(200), 50);
+        assert_eq!(budget.scale(2), 1);
+        // Stays throttled until RSS is under the low watermark.
+        assert!(budget.observe(800));
+        assert!(!budget.observe(749));
+        assert_eq!(budget.scale(200), 200);
+
+        let unlimited = MemoryBudget::default();
+        assert!(!unlimited.observe(u64::MAX));
+        assert_eq!(unlimited.scale(200), 200);
+    }
+
+    #[tokio::test]
+    async fn waiters_resume_when_released() {
+        let budget = MemoryBudget::new(1000);
+        budget.observe(950);
+        let waiter = tokio::spawn({
+            let budget = budget.clone();
+            async move { budget.wait_unthrottled().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        budget.observe(100);
+        waiter.await.unwrap();
+    }
+
+    #[test]
+    fn reads_resident_set_size() {
+        if cfg!(target_os = "linux") {
+            assert!(resident_bytes().is_some_and(|rss| rss > 0));
+        }
+    }
+}
@@ -1,27 +1,57 @@
-use std::{str, sync::Arc, thread, time::Duration};
+use std::{
+    collections::HashSet,
+    str,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Result};
+use rand::Rng;
 use tokio::runtime::Handle;
 use tracing::{debug, error, info, warn};
 
-use crate::{rpc::MoneroRpc, store::Store};
+use crate::{
+    alerts,
+    events::{Event, Events},
+    rpc::MoneroRpc,
+    store::Store,
+};
 
 const RAW_TX: &str = "raw_tx";
 const RAW_BLOCK: &str = "raw_block";
-const RECEIVE_TIMEOUT_MS: i32 = 5_000;
+const MEMPOOL_UPSERT_BATCH: usize = 5_000;
+/// Pool tx bodies requested per `get_transactions` call.
+const POOL_FETCH_BATCH: usize = 100;
 
 pub struct MempoolWatcher {
     zmq_addr: String,
     rpc: Arc<dyn MoneroRpc>,
     store: Store,
+    orphan_ttl_secs: u64,
+    full_refresh: Duration,
+    events: Events,
+    /// Pool contents at the last successful refresh, for churn metrics.
+    known: Option<HashSet<String>>,
 }
 
 impl MempoolWatcher {
-    pub fn new<S: Into<String>>(zmq_addr: S, rpc: Arc<dyn MoneroRpc>, store: Store) -> Self {
+    pub fn new<S: Into<String>>(
+        zmq_addr: S,
+        rpc: Arc<dyn MoneroRpc>,
+        store: Store,
+        orphan_ttl_secs: u64,
+        full_refresh: Duration,
+        events: Events,
+    ) -> Self {
         Self {
             zmq_addr: zmq_addr.into(),
             rpc,
             store,
+            orphan_ttl_secs,
+            full_refresh,
+            events,
+            known: None,
         }
     }
 
@@ -37,10 +67,13 @@ impl MempoolWatcher {
             .expect("spawn mempool watcher");
     }
 
-    fn run(self, handle: Handle) -> Result<()> {
+    /// Every refresh reconciles the whole pool: it lists the pool's hashes and
+    /// fetches bodies only for txs it has not seen. ZMQ notifications trigger one
+    /// immediately; otherwise one runs when `full_refresh` (jittered) has
+    /// passed since the last.
+    fn run(mut self, handle: Handle) -> Result<()> {
         let ctx = zmq::Context::new();
         let sub = ctx.socket(zmq::SUB).context("create ZMQ SUB socket")?;
-        sub.set_rcvtimeo(RECEIVE_TIMEOUT_MS)?;
         sub.connect(&self.zmq_addr)
             .with_context(|| format!("connect zmq {}", self.zmq_addr))?;
         sub.set_subscribe(RAW_TX.as_bytes())?;
@@ -48,11 +81,18 @@ impl MempoolWatcher {
 
         info!(addr = %self.zmq_addr, "subscribed to mempool topics");
 
-        if let Err(err) = handle.block_on(self.refresh_from_pool()) {
-            warn!(error = ?err, "initial mempool refresh failed");
-        }
+        self.reconcile(&handle, "startup");
+        let mut next_refresh = Instant::now() + jittered(self.full_refresh);
 
         loop {
+            let wait = next_refresh.saturating_duration_since(Instant::now());
+            if wait.is_zero() {
+                self.reconcile(&handle, "scheduled");
+                next_refresh = Instant::now() + jittered(self.full_refresh);
+                continue;
+            }
+            sub.set_rcvtimeo(i32::try_from(wait.as_millis()).unwrap_or(i32::MAX).max(1))?;
+
             match sub.recv_multipart(0) {
                 Ok(frames) => {
                     let topic = frames
@@ -62,21 +102,15 @@ impl MempoolWatcher {
 
                     if matches!(topic, RAW_TX | RAW_BLOCK) {
                         debug!(%topic, "refreshing mempool");
-                        if let Err(err) = handle.block_on(self.refresh_from_pool()) {
-                            warn!(topic = %topic, error = ?err, "mempool refresh failed");
-                        }
+                        self.reconcile(&handle, "zmq");
+                        next_refresh = Instant::now() + jittered(self.full_refresh);
                     } else {
                         debug!(%topic, "ignored zmq topic");
                     }
                 }
+                // Receive timeout: the deadline check above runs the refresh.
+                Err(zmq::Error::EAGAIN) => {}
                 Err(err) => {
-                    if err == zmq::Error::EAGAIN {
-                        if let Err(err) = handle.block_on(self.refresh_from_pool()) {
-                            debug!(error = ?err, "periodic mempool refresh failed");
-                        }
-                        continue;
-                    }
-
                     warn!(error = ?err, "zmq receive error");
                     thread::sleep(Duration::from_secs(1));
                 }
@@ -84,32 +118,175 @@ impl MempoolWatcher {
         }
     }
 
-    async fn refresh_from_pool(&self) -> Result<()> {
+    fn reconcile(&mut self, handle: &Handle, trigger: &'static str) {
+        let started = Instant::now();
+        let res = handle.block_on(self.refresh_from_pool());
+        metrics::histogram!("mempool_refresh_seconds", "trigger" => trigger)
+            .record(started.elapsed().as_secs_f64());
+        if let Err(err) = res {
+            warn!(trigger, error = ?err, "mempool refresh failed");
+            metrics::counter!("mempool_refresh_failures_total", "trigger" => trigger).increment(1);
+            alerts::record_mempool_failure();
+        }
+    }
+
+    async fn refresh_from_pool(&mut self) -> Result<()> {
         let hashes = self
             .rpc
             .get_transaction_pool_hashes()
             .await
             .context("get_transaction_pool_hashes")?;
 
-        if hashes.is_empty() {
-            return Ok(());
+        let (seen, unseen): (Vec<String>, Vec<String>) = hashes
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .partition(|hash| {
+                self.known
+                    .as_ref()
+                    .is_some_and(|known| known.contains(hash))
+            });
+        let mut arrived = Vec::with_capacity(unseen.len());
+        for batch in unseen.chunks(POOL_FETCH_BATCH) {
+            arrived.extend(
+                self.rpc
+                    .get_pool_transactions(batch)
+                    .await
+                    .context("get_transactions")?,
+            );
         }
 
         let mut tx = self.store.pool().begin().await?;
-        for hash in hashes {
-            sqlx::query(
-                r#"
-INSERT INTO public.mempool_txs (tx_hash)
-VALUES (decode($1, 'hex'))
-ON CONFLICT (tx_hash) DO UPDATE SET last_seen = NOW()
-"#,
-            )
-            .bind(&hash)
-            .execute(&mut *tx)
-            .await?;
+        for batch in seen.chunks(MEMPOOL_UPSERT_BATCH) {
+            Store::upsert_mempool_hashes(&mut tx, batch)
+                .await
+                .context("upsert mempool hashes")?;
+        }
+        for batch in arrived.chunks(MEMPOOL_UPSERT_BATCH) {
+            Store::upsert_mempool_pool(&mut tx, batch)
+                .await
+                .context("upsert mempool entries")?;
+        }
+
+        if self.orphan_ttl_secs > 0 {
+            let ttl = i64::try_from(self.orphan_ttl_secs).unwrap_or(i64::MAX);
+            let purged = Store::purge_orphaned_txs(&mut tx, ttl)
+                .await
+                .context("purge orphaned txs")?;
+            if purged > 0 {
+                info!(purged, "purged orphaned txs that never reconfirmed");
+                metrics::counter!("orphaned_txs_purged_total").increment(purged);
+            }
         }
         tx.commit().await?;
 
+        // The first refresh only learns the pool; later ones announce arrivals.
+        if self.known.is_some() {
+            for entry in &arrived {
+                self.events.publish(Event::NewMempoolTx {
+                    hash: entry.id_hash.clone(),
+                    fee: entry.fee,
+                    size_bytes: entry.blob_size,
+                });
+            }
+        }
+
+        // Unseen txs that left the pool before their bodies were fetched do
+        // not count as present.
+        let current: HashSet<String> = seen
+            .into_iter()
+            .chain(arrived.into_iter().map(|e| e.id_hash))
+            .collect();
+        if let Some(known) = &self.known {
+            let added = current.difference(known).count() as u64;
+            let removed = known.difference(&current).count() as u64;
+            metrics::counter!("mempool_churn_total", "direction" => "added").increment(added);
+            metrics::counter!("mempool_churn_total", "direction" => "removed").increment(removed);
+        }
+        metrics::gauge!("mempool_pool_size").set(current.len() as f64);
+        self.known = Some(current);
+
+        Ok(())
+    }
+}
+
+/// Spreads scheduled refreshes by up to 10% either way so several ingestors
+/// sharing a daemon do not poll it in lockstep.
+fn jittered(base: Duration) -> Duration {
+    base.mul_f64(rand::thread_rng().gen_range(0.9..=1.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rpc::PoolTx, testing::MockRpc};
+
+    use super::*;
+
+    fn pool_tx(byte: u8) -> PoolTx {
+        PoolTx {
+            id_hash: format!("{byte:02x}").repeat(32),
+            blob_size: 1_000,
+            fee: 20_000_000,
+            relayed: true,
+            do_not_relay: None,
+            double_spend_seen: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn refresh_fetches_only_new_pool_txs() -> Result<()> {
+        let Some(db) = crate::testing::TestDb::start().await? else {
+            eprintln!("skipping refresh_fetches_only_new_pool_txs: no database available");
+            return Ok(());
+        };
+        let hashes: Vec<String> = [0xd1, 0xd2, 0xd3]
+            .into_iter()
+            .map(|byte| pool_tx(byte).id_hash)
+            .collect();
+        let cleanup = || async {
+            sqlx::query("DELETE FROM public.mempool_txs WHERE encode(tx_hash, 'hex') = ANY($1)")
+                .bind(&hashes)
+                .execute(&db.pool)
+                .await
+        };
+        cleanup().await?;
+
+        let rpc = Arc::new(MockRpc::new(0));
+        let mut watcher = MempoolWatcher::new(
+            "tcp://127.0.0.1:1",
+            rpc.clone(),
+            Store::connect(&db.url).await?,
+            0,
+            Duration::from_secs(5),
+            Events::default(),
+        );
+
+        rpc.set_pool(vec![pool_tx(0xd1), pool_tx(0xd2)]);
+        watcher.refresh_from_pool().await?;
+        let mut fetched = rpc.take_pool_fetches();
+        fetched.sort();
+        assert_eq!(fetched, hashes[..2]);
+
+        rpc.set_pool(vec![pool_tx(0xd2), pool_tx(0xd3)]);
+        watcher.refresh_from_pool().await?;
+        assert_eq!(rpc.take_pool_fetches(), hashes[2..]);
+
+        let stored: Vec<(String, Option<i32>)> = sqlx::query_as(
+            "SELECT encode(tx_hash, 'hex'), size_bytes FROM public.mempool_txs
+             WHERE encode(tx_hash, 'hex') = ANY($1) ORDER BY tx_hash",
+        )
+        .bind(&hashes)
+        .fetch_all(&db.pool)
+        .await?;
+        assert_eq!(
+            stored,
+            hashes
+                .iter()
+                .map(|hash| (hash.clone(), Some(1_000)))
+                .collect::<Vec<_>>()
+        );
+
+        cleanup().await?;
         Ok(())
     }
 }
@@ -1,7 +1,13 @@
-use std::time::Instant;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
 
 use tokio::sync::{mpsc, oneshot};
 
+use bex_core::{BlockHash, TxHash};
+
 use crate::rpc::BlockHeader;
 
 pub type Shutdown = oneshot::Receiver<()>;
@@ -15,28 +21,28 @@ pub struct SchedMsg {
 
 pub struct BlockMsg {
     pub height: i64,
-    pub hash: String,
-    pub tx_hashes: Vec<String>,
+    pub hash: BlockHash,
+    pub tx_hashes: Vec<TxHash>,
     pub ts: i64,
     pub tip_height: i64,
     pub finalized_height: i64,
     pub header: BlockHeader,
     pub miner_tx_json: Option<String>,
-    pub miner_tx_hash: Option<String>,
+    pub miner_tx_hash: Option<TxHash>,
     pub started: Instant,
 }
 
 pub struct TxMsg {
     pub height: i64,
-    pub block_hash: String,
+    pub block_hash: BlockHash,
     pub tx_jsons: Vec<String>,
     pub ts: i64,
     pub tip_height: i64,
     pub finalized_height: i64,
     pub header: BlockHeader,
     pub miner_tx_json: Option<String>,
-    pub miner_tx_hash: Option<String>,
-    pub ordered_tx_hashes: Vec<String>,
+    pub miner_tx_hash: Option<TxHash>,
+    pub ordered_tx_hashes: Vec<TxHash>,
     pub started: Instant,
 }
 
@@ -59,6 +65,14 @@ pub fn make_channels(
     let (s1, r1) = mpsc::channel(cfg.sched_buffer);
     let (s2, r2) = mpsc::channel(cfg.block_workers * 4);
     let (s3, r3) = mpsc::channel(cfg.tx_workers * 4);
+    for (queue, capacity) in [
+        ("sched", s1.max_capacity()),
+        ("block", s2.max_capacity()),
+        ("tx", s3.max_capacity()),
+    ] {
+        metrics::gauge!("queue_capacity", "queue" => queue).set(capacity as f64);
+        metrics::gauge!("queue_depth", "queue" => queue).set(0.0);
+    }
     (s1, r1, s2, r2, s3, r3)
 }
 
@@ -71,3 +85,86 @@ pub fn record_queue_depth_receiver<T>(queue: &'static str, receiver: &mpsc::Rece
     let depth = receiver.max_capacity().saturating_sub(receiver.capacity());
     metrics::gauge!("queue_depth", "queue" => queue).set(depth as f64);
 }
+
+/// What a pipeline worker is currently doing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkerState {
+    /// Processing a message (RPC, parsing, SQL).
+    Busy = 0,
+    /// Waiting for the upstream queue to deliver work.
+    RecvWait = 1,
+    /// Blocked because the downstream queue is full.
+    SendWait = 2,
+}
+
+impl WorkerState {
+    fn label(self) -> &'static str {
+        match self {
+            WorkerState::Busy => "busy",
+            WorkerState::RecvWait => "recv_wait",
+            WorkerState::SendWait => "send_wait",
+        }
+    }
+}
+
+/// Per-worker time accounting, exported as
+/// `worker_time_us_total{stage,worker,state}` (microseconds) plus a
+/// `worker_state` gauge holding the current [`WorkerState`] discriminant.
+pub struct WorkerMetrics {
+    stage: &'static str,
+    worker: String,
+    state: WorkerState,
+    since: Instant,
+}
+
+impl WorkerMetrics {
+    /// Registers a worker of `stage`; workers are numbered per stage in
+    /// registration order.
+    pub fn register(stage: &'static str) -> Self {
+        static NEXT_IDS: OnceLock<Mutex<HashMap<&'static str, usize>>> = OnceLock::new();
+        let id = {
+            let mut ids = NEXT_IDS
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let next = ids.entry(stage).or_insert(0);
+            let id = *next;
+            *next += 1;
+            id
+        };
+        let metrics = Self {
+            stage,
+            worker: id.to_string(),
+            state: WorkerState::Busy,
+            since: Instant::now(),
+        };
+        metrics.publish_state();
+        metrics
+    }
+
+    pub fn enter(&mut self, state: WorkerState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.since).as_micros();
+        metrics::counter!(
+            "worker_time_us_total",
+            "stage" => self.stage,
+            "worker" => self.worker.clone(),
+            "state" => self.state.label()
+        )
+        .increment(u64::try_from(elapsed).unwrap_or(u64::MAX));
+        self.state = state;
+        self.since = now;
+        self.publish_state();
+    }
+
+    fn publish_state(&self) {
+        metrics::gauge!("worker_state", "stage" => self.stage, "worker" => self.worker.clone())
+            .set(self.state as u8 as f64);
+    }
+}
+
+impl Drop for WorkerMetrics {
+    fn drop(&mut self) {
+        self.enter(self.state);
+    }
+}
@@ -0,0 +1,345 @@
+//! Look-ahead header (and optionally block) fetching for the block workers.
+//! Each worker otherwise fetches its height only once the scheduler hands it
+//! over, so a slow daemon costs a full round trip per block. With
+//! `--prefetch-window` a dedicated task keeps the next heights above the
+//! workers' position warmed in a shared cache, issuing requests concurrently
+//! so a daemon with high latency but good parallelism stays busy.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use futures::{stream, StreamExt};
+use governor::DefaultDirectRateLimiter;
+use tokio::{
+    sync::{watch, Notify},
+    task::JoinHandle,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    capabilities::LiveCapabilities,
+    memory::MemoryBudget,
+    rpc::{BlockHeader, GetBlockResult, MoneroRpc},
+};
+
+/// How long the task idles when the window is full and nothing wakes it.
+const IDLE_WAIT: Duration = Duration::from_secs(2);
+
+type Entry = (BlockHeader, Option<GetBlockResult>);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    /// Heights kept warmed above the next one the workers need.
+    pub window: u64,
+    /// Also fetch each block's JSON, not just its header.
+    pub blocks: bool,
+    /// Requests in flight at once.
+    pub concurrency: usize,
+    /// Headers per range request while bulk headers are available.
+    pub header_batch: u64,
+    pub tip_confirmations: u64,
+}
+
+/// Heights fetched ahead of the block workers. The workers `take` from it
+/// and fall back to fetching themselves on a miss.
+#[derive(Default)]
+pub struct Prefetcher {
+    inner: Mutex<Inner>,
+    advanced: Notify,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: BTreeMap<u64, Entry>,
+    /// Lowest height the workers may still ask for; `None` until the first.
+    next: Option<u64>,
+    /// Bumped by `clear` so fetches started before it are discarded.
+    epoch: u64,
+}
+
+impl Prefetcher {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Removes the cached entry for `height` and moves the window past it.
+    pub fn take(&self, height: u64) -> Option<Entry> {
+        let entry = {
+            let mut inner = self.inner.lock().expect("prefetch lock");
+            let entry = inner.entries.remove(&height);
+            let next = inner.next.map_or(height + 1, |next| next.max(height + 1));
+            inner.next = Some(next);
+            // Workers run slightly out of order, so only evict what no
+            // worker can still be waiting for.
+            inner.entries.retain(|&h, _| h >= height.saturating_sub(64));
+            metrics::gauge!("prefetch_cached_blocks").set(inner.entries.len() as f64);
+            entry
+        };
+        let result = if entry.is_some() { "hit" } else { "miss" };
+        metrics::counter!("prefetch_lookups_total", "result" => result).increment(1);
+        self.advanced.notify_one();
+        entry
+    }
+
+    /// Drops everything cached, e.g. after a reorg replaced the headers.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().expect("prefetch lock");
+        inner.entries.clear();
+        inner.epoch += 1;
+        metrics::gauge!("prefetch_cached_blocks").set(0.0);
+    }
+
+    /// Heights in `next..next + window` capped at `ingestable` that are not
+    /// cached yet, with the epoch to hand back to `insert`.
+    fn missing(&self, window: u64, ingestable: u64) -> (u64, Vec<u64>) {
+        let inner = self.inner.lock().expect("prefetch lock");
+        let Some(next) = inner.next else {
+            return (inner.epoch, Vec::new());
+        };
+        let end = next
+            .saturating_add(window)
+            .min(ingestable.saturating_add(1));
+        let heights = (next..end)
+            .filter(|h| !inner.entries.contains_key(h))
+            .collect();
+        (inner.epoch, heights)
+    }
+
+    fn insert(&self, epoch: u64, entries: Vec<Entry>) {
+        let mut inner = self.inner.lock().expect("prefetch lock");
+        if inner.epoch != epoch {
+            return;
+        }
+        // A worker may have overtaken the task and fetched these itself.
+        let floor = inner.next.unwrap_or(0);
+        for (header, block) in entries {
+            if header.height >= floor {
+                inner.entries.insert(header.height, (header, block));
+            }
+        }
+        metrics::gauge!("prefetch_cached_blocks").set(inner.entries.len() as f64);
+    }
+}
+
+/// Keeps the window ahead of the workers filled as the daemon tip, which the
+/// scheduler publishes on `tip`, moves.
+pub fn spawn(
+    cache: Arc<Prefetcher>,
+    rpc: Arc<dyn MoneroRpc>,
+    limiter: Arc<DefaultDirectRateLimiter>,
+    caps: Arc<LiveCapabilities>,
+    settings: Settings,
+    memory: MemoryBudget,
+    mut tip: watch::Receiver<u64>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            // Nothing is fetched ahead near the memory budget.
+            memory.wait_unthrottled().await;
+            let ingestable = tip.borrow().checked_sub(settings.tip_confirmations);
+            let (epoch, missing) = ingestable
+                .map(|ingestable| cache.missing(settings.window, ingestable))
+                .unwrap_or_default();
+            if missing.is_empty() {
+                tokio::select! {
+                    changed = tip.changed() => if changed.is_err() { break },
+                    _ = cache.advanced.notified() => {}
+                    _ = tokio::time::sleep(IDLE_WAIT) => {}
+                }
+                continue;
+            }
+            match fill(rpc.as_ref(), &limiter, &caps, &settings, &missing).await {
+                Ok(entries) => {
+                    debug!(fetched = entries.len(), "prefetched blocks");
+                    cache.insert(epoch, entries);
+                }
+                Err(err) => {
+                    warn!(error = ?err, from = missing[0], "prefetch failed");
+                    tokio::time::sleep(IDLE_WAIT).await;
+                }
+            }
+        }
+    })
+}
+
+/// Fetches `heights` (ascending), keeping whatever succeeded even when
+/// some requests failed; the workers fetch the rest themselves.
+async fn fill(
+    rpc: &dyn MoneroRpc,
+    limiter: &Arc<DefaultDirectRateLimiter>,
+    caps: &LiveCapabilities,
+    settings: &Settings,
+    heights: &[u64],
+) -> Result<Vec<Entry>> {
+    let concurrency = settings.concurrency.max(1);
+    let mut entries = Vec::with_capacity(heights.len());
+    let mut first_err = None;
+    if caps.get().headers_range {
+        let chunks = contiguous_chunks(heights, settings.header_batch.max(1));
+        let mut ranges = stream::iter(chunks)
+            .map(|(start, end)| async move {
+                limiter.until_ready().await;
+                rpc.get_block_headers_range(start, end)
+                    .await
+                    .with_context(|| format!("fetch header range {start}..={end}"))
+            })
+            .buffer_unordered(concurrency);
+        let mut headers = Vec::new();
+        while let Some(res) = ranges.next().await {
+            match res {
+                Ok(batch) => headers.extend(batch),
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        if settings.blocks {
+            let mut blocks = stream::iter(headers)
+                .map(|header| async move {
+                    limiter.until_ready().await;
+                    let block = rpc.get_block(&header.hash, false).await;
+                    (header, block)
+                })
+                .buffer_unordered(concurrency);
+            while let Some((header, block)) = blocks.next().await {
+                match block {
+                    Ok(block) => entries.push((header, Some(block))),
+                    Err(err) => {
+                        first_err.get_or_insert(err);
+                        entries.push((header, None));
+                    }
+                }
+            }
+        } else {
+            entries.extend(headers.into_iter().map(|header| (header, None)));
+        }
+    } else {
+        let mut singles = stream::iter(heights.iter().copied())
+            .map(|height| async move { fetch_single(rpc, limiter, settings.blocks, height).await })
+            .buffer_unordered(concurrency);
+        while let Some(res) = singles.next().await {
+            match res {
+                Ok(entry) => entries.push(entry),
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+    }
+    match first_err {
+        Some(err) if entries.is_empty() => Err(err),
+        _ => Ok(entries),
+    }
+}
+
+async fn fetch_single(
+    rpc: &dyn MoneroRpc,
+    limiter: &Arc<DefaultDirectRateLimiter>,
+    blocks: bool,
+    height: u64,
+) -> Result<Entry> {
+    if blocks {
+        limiter.until_ready().await;
+        let res = rpc
+            .get_block_by_height(height)
+            .await
+            .with_context(|| format!("fetch block {height}"))?;
+        let header = match res.header() {
+            Some(header) => header,
+            None => fetch_header(rpc, limiter, height).await?,
+        };
+        return Ok((header.clone(), res.with_header(header)));
+    }
+    Ok((fetch_header(rpc, limiter, height).await?, None))
+}
+
+async fn fetch_header(
+    rpc: &dyn MoneroRpc,
+    limiter: &Arc<DefaultDirectRateLimiter>,
+    height: u64,
+) -> Result<BlockHeader> {
+    limiter.until_ready().await;
+    Ok(rpc
+        .get_block_header_by_height(height)
+        .await
+        .with_context(|| format!("fetch header {height}"))?
+        .block_header)
+}
+
+/// Splits ascending `heights` into inclusive ranges of consecutive heights,
+/// none longer than `batch`.
+fn contiguous_chunks(heights: &[u64], batch: u64) -> Vec<(u64, u64)> {
+    let mut chunks: Vec<(u64, u64)> = Vec::new();
+    for &height in heights {
+        match chunks.last_mut() {
+            Some((start, end)) if *end + 1 == height && height - *start < batch => *end = height,
+            _ => chunks.push((height, height)),
+        }
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bex_core::BlockHash;
+
+    fn header(height: u64) -> BlockHeader {
+        serde_json::from_value(serde_json::json!({
+            "hash": format!("{:064x}", height + 1),
+            "height": height,
+            "timestamp": height * 60,
+            "prev_hash": format!("{:064x}", height),
+            "major_version": 1,
+            "minor_version": 1,
+            "nonce": 0,
+            "reward": 0,
+            "block_size": 1,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn chunks_split_on_gaps_and_batch_size() {
+        assert_eq!(
+            contiguous_chunks(&[1, 2, 3, 5, 6, 7, 8, 9], 3),
+            vec![(1, 3), (5, 7), (8, 9)]
+        );
+        assert!(contiguous_chunks(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn window_follows_the_workers() {
+        let cache = Prefetcher::default();
+        // Nothing is fetched before a worker reports its position.
+        assert!(cache.missing(4, 100).1.is_empty());
+
+        assert!(cache.take(10).is_none());
+        let (epoch, missing) = cache.missing(4, 100);
+        assert_eq!(missing, vec![11, 12, 13, 14]);
+        assert_eq!(cache.missing(4, 12).1, vec![11, 12]);
+
+        cache.insert(
+            epoch,
+            vec![(header(9), None), (header(11), None), (header(12), None)],
+        );
+        assert_eq!(cache.missing(4, 100).1, vec![13, 14]);
+        let (hit, block) = cache.take(11).expect("prefetched");
+        assert_eq!(
+            hit.hash,
+            BlockHash::from_hex(&format!("{:064x}", 12)).unwrap()
+        );
+        assert!(block.is_none());
+        // Below the workers' position, so never cached.
+        assert!(cache.take(9).is_none());
+
+        // Fetched before a reorg cleared the cache, so discarded.
+        cache.clear();
+        cache.insert(epoch, vec![(header(13), None)]);
+        assert_eq!(cache.missing(4, 100).1, vec![12, 13, 14, 15]);
+    }
+}
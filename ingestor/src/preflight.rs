@@ -0,0 +1,198 @@
+//! Startup validation for `ingestor run`: flag values, URL formats, the
+//! metrics port, the alert sinks, reachability of Postgres, the daemon and its ZMQ publisher,
+//! whether the daemon runs the configured network, and whether the schema is
+//! migrated, reported together before any worker starts.
+
+use std::{env, net::TcpListener, str::FromStr};
+
+use bex_core::preflight::{self, probe, url_scheme, Report};
+use sqlx::{postgres::PgConnectOptions, PgPool};
+
+use crate::{alerts::Sink, cli::RunArgs, rpc::Rpc, schema};
+
+/// Where the Prometheus exporter listens.
+pub const METRICS_ADDR: &str = "0.0.0.0:9898";
+
+/// Smallest `--rpc-max-response-bytes` that still fits a full block.
+const MIN_RESPONSE_BYTES: usize = 1024 * 1024;
+
+pub async fn check_run(args: &RunArgs) -> Report {
+    let mut report = Report::new("ingestor");
+
+    report.check("flags", check_flags(args));
+    let network_ok = report.check("NETWORK", preflight::network(&args.network));
+    report.check(
+        "metrics port",
+        TcpListener::bind(METRICS_ADDR)
+            .map(|_| format!("{METRICS_ADDR} available"))
+            .map_err(|err| format!("cannot bind {METRICS_ADDR}: {err}")),
+    );
+
+    let db_url_ok = report.check(
+        "DATABASE_URL",
+        url_scheme(&args.database_url, &["postgres", "postgresql"]).and_then(|detail| {
+            PgConnectOptions::from_str(&args.database_url)
+                .map(|_| detail)
+                .map_err(|err| err.to_string())
+        }),
+    );
+    if db_url_ok {
+        let connected = report.check(
+            "postgres",
+            probe(async {
+                let pool = PgPool::connect(&args.database_url).await?;
+                sqlx::query("SELECT 1").execute(&pool).await?;
+                pool.close().await;
+                Ok("connected".to_string())
+            })
+            .await,
+        );
+        if connected {
+            report.check(
+                "schema",
+                probe(async {
+                    let pool = PgPool::connect(&args.database_url).await?;
+                    let drift = schema::check(&pool).await;
+                    pool.close().await;
+                    let drift = drift?;
+                    if drift.is_blocking() {
+                        anyhow::bail!("{drift}");
+                    }
+                    Ok(drift.to_string())
+                })
+                .await,
+            );
+        } else {
+            report.skip("schema", "postgres unreachable");
+        }
+    } else {
+        report.skip("postgres", "DATABASE_URL invalid");
+        report.skip("schema", "DATABASE_URL invalid");
+    }
+
+    if report.check("XMR_RPC_URL", url_scheme(&args.rpc_url, &["http", "https"])) {
+        let mut nettype = None;
+        report.check(
+            "daemon",
+            probe(async {
+                let info = Rpc::new(&args.rpc_url).get_info().await?;
+                let detail = format!("reachable, {} blocks", info.height);
+                nettype = Some(info.nettype);
+                Ok(detail)
+            })
+            .await,
+        );
+        match nettype {
+            Some(nettype) if network_ok => {
+                report.check(
+                    "daemon network",
+                    preflight::daemon_network(&args.network, &nettype),
+                );
+            }
+            Some(_) => report.skip("daemon network", "NETWORK invalid"),
+            None => report.skip("daemon network", "daemon unreachable"),
+        }
+    } else {
+        report.skip("daemon", "XMR_RPC_URL invalid");
+        report.skip("daemon network", "XMR_RPC_URL invalid");
+    }
+
+    if report.check("XMR_ZMQ_URL", url_scheme(&args.zmq_url, &["tcp", "ipc"])) {
+        match args.zmq_url.strip_prefix("tcp://") {
+            Some(addr) => {
+                report.check(
+                    "zmq",
+                    probe(async {
+                        tokio::net::TcpStream::connect(addr).await?;
+                        Ok(format!("{addr} accepts connections"))
+                    })
+                    .await,
+                );
+            }
+            None => report.skip("zmq", "only tcp endpoints are probed"),
+        }
+    } else {
+        report.skip("zmq", "XMR_ZMQ_URL invalid");
+    }
+
+    if let Some(redis_url) = &args.events_redis_url {
+        if report.check(
+            "EVENTS_REDIS_URL",
+            url_scheme(redis_url, &["redis", "rediss", "unix"]),
+        ) {
+            report.check(
+                "events redis",
+                probe(async {
+                    let client = redis::Client::open(redis_url.as_str())?;
+                    let mut conn = client.get_multiplexed_async_connection().await?;
+                    redis::cmd("PING").query_async::<_, ()>(&mut conn).await?;
+                    Ok("connected".to_string())
+                })
+                .await,
+            );
+        } else {
+            report.skip("events redis", "EVENTS_REDIS_URL invalid");
+        }
+    }
+
+    if args.alert_sinks.contains(&Sink::Webhook) {
+        let url = args.alert_webhook_url.as_deref().unwrap_or_default();
+        report.check("ALERT_WEBHOOK_URL", url_scheme(url, &["http", "https"]));
+    }
+
+    report
+}
+
+fn check_flags(args: &RunArgs) -> Result<String, String> {
+    let mut problems = Vec::new();
+    if args.ingest_concurrency == 0 {
+        problems.push("--ingest-concurrency must be at least 1".to_string());
+    }
+    if args.prepare_workers == Some(0) {
+        problems.push("--prepare-workers must be at least 1".to_string());
+    }
+    if args.mempool_full_refresh_secs == 0 {
+        problems.push("--mempool-full-refresh-secs must be at least 1".to_string());
+    }
+    if args.caps_probe_attempts == 0 {
+        problems.push("--caps-probe-attempts must be at least 1".to_string());
+    }
+    if args.rpc_rps == 0 {
+        problems.push("--rpc-requests-per-second must be at least 1".to_string());
+    }
+    if args.rpc_max_response_bytes < MIN_RESPONSE_BYTES {
+        problems.push(format!(
+            "--rpc-max-response-bytes must be at least {MIN_RESPONSE_BYTES}"
+        ));
+    }
+    if !args.alert_sinks.is_empty() && args.alert_interval_secs == 0 {
+        problems.push("--alert-interval-secs must be at least 1".to_string());
+    }
+    if args.alert_webhook_url.is_some() && !args.alert_sinks.contains(&Sink::Webhook) {
+        problems.push(
+            "--alert-webhook-url is set but the webhook sink is not in --alert-sinks".to_string(),
+        );
+    }
+    if args.alert_webhook_secret.is_some() && args.alert_webhook_url.is_none() {
+        problems.push("--alert-webhook-secret is set without --alert-webhook-url".to_string());
+    }
+    if args.limit == Some(0) {
+        problems.push("--limit 0 would not sync any block".to_string());
+    }
+    // main() copies the legacy CONCURRENCY into INGEST_CONCURRENCY only when
+    // the latter is unset, so differing values mean one is silently ignored.
+    if let (Ok(legacy), Ok(current)) = (env::var("CONCURRENCY"), env::var("INGEST_CONCURRENCY")) {
+        if legacy != current {
+            problems.push(
+                "CONCURRENCY and INGEST_CONCURRENCY are mutually exclusive; unset CONCURRENCY"
+                    .to_string(),
+            );
+        }
+    }
+
+    if problems.is_empty() {
+        Ok("valid".to_string())
+    } else {
+        Err(problems.join("; "))
+    }
+}
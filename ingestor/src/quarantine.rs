@@ -0,0 +1,161 @@
+//! Second chance for txs set aside in `quarantined_txs`. After a codec fix,
+//! `ingestor reprocess-quarantined` re-parses their stored JSON and queues
+//! re-ingestion of each block holding one that now parses, so the running
+//! ingestor persists them with the rest of their block.
+
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+use tracing::warn;
+
+use crate::{codec::ExtraRetention, store::Store, work_persist::prepare_tx};
+
+/// Recorded as `requested_by` on the re-ingestion requests this queues.
+pub const REQUESTED_BY: &str = "reprocess-quarantined";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub checked: usize,
+    pub parsed: usize,
+    /// Heights queued for re-ingestion, or that would be with `dry_run`.
+    pub heights: Vec<i64>,
+}
+
+pub async fn reprocess(store: &Store, dry_run: bool) -> Result<Summary> {
+    let rows = store
+        .quarantined_txs()
+        .await
+        .context("read quarantined txs")?;
+    let mut summary = Summary {
+        checked: rows.len(),
+        ..Summary::default()
+    };
+    let mut heights = BTreeSet::new();
+    for (hash, height, major_version, raw_json) in rows {
+        let major_version = u32::try_from(major_version).ok();
+        match prepare_tx(
+            &raw_json,
+            Some(hash),
+            major_version,
+            true,
+            ExtraRetention::default(),
+        ) {
+            Ok(_) => {
+                summary.parsed += 1;
+                heights.insert(height);
+            }
+            Err(err) => warn!(height, tx = %hash, error = %format!("{err:#}"), "still unparseable"),
+        }
+    }
+    summary.heights = heights.into_iter().collect();
+
+    if !dry_run {
+        for &height in &summary.heights {
+            store
+                .enqueue_reingest(height, REQUESTED_BY)
+                .await
+                .with_context(|| format!("queue re-ingestion of {height}"))?;
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestDb;
+    use bex_core::{BlockHash, TxHash};
+
+    #[tokio::test]
+    async fn queues_blocks_whose_quarantined_txs_now_parse() -> Result<()> {
+        let Some(db) = TestDb::start().await? else {
+            eprintln!(
+                "skipping queues_blocks_whose_quarantined_txs_now_parse: no database available"
+            );
+            return Ok(());
+        };
+        let store = Store::connect(&db.url).await?;
+        let (fixed, broken, stale) = (7_950_000i64, 7_950_001i64, 7_950_002i64);
+        let cleanup = || async {
+            sqlx::query(
+                "DELETE FROM public.quarantined_txs WHERE block_height BETWEEN $1 AND $1 + 2",
+            )
+            .bind(fixed)
+            .execute(store.pool())
+            .await?;
+            sqlx::query("DELETE FROM public.reingest_requests WHERE height BETWEEN $1 AND $1 + 2")
+                .bind(fixed)
+                .execute(store.pool())
+                .await?;
+            sqlx::query("DELETE FROM public.blocks WHERE height BETWEEN $1 AND $1 + 2")
+                .bind(fixed)
+                .execute(store.pool())
+                .await?;
+            anyhow::Ok(())
+        };
+        cleanup().await?;
+
+        let block_hash = |height: i64| BlockHash([height as u8; 32]);
+        for height in [fixed, broken] {
+            sqlx::query(
+                "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos)
+                 VALUES ($1, $2, $2, to_timestamp(0), 100, 16, 16, 0, 2, 0)",
+            )
+            .bind(height)
+            .bind(block_hash(height))
+            .execute(store.pool())
+            .await?;
+        }
+        let parseable = r#"{"version": 1, "unlock_time": 0, "vin": [], "vout": [], "extra": []}"#;
+        let quarantined = [
+            (fixed, TxHash([0xc1; 32]), parseable),
+            (broken, TxHash([0xc2; 32]), r#"{"version": "two"}"#),
+            // Its block was reorged out, so it is dropped, not requeued.
+            (stale, TxHash([0xc3; 32]), parseable),
+        ];
+        let mut tx = store.begin_block().await?;
+        for (height, hash, raw) in quarantined {
+            Store::quarantine_txs(
+                &mut tx,
+                height,
+                &block_hash(height),
+                &[hash],
+                &[raw.to_string()],
+                &["parse tx json".to_string()],
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        // Other tests share the table, so only this test's heights are checked.
+        let preview = reprocess(&store, true).await?;
+        assert!(preview.heights.contains(&fixed));
+        assert!(!preview.heights.contains(&broken));
+        assert!(!preview.heights.contains(&stale));
+        let queued: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM public.reingest_requests WHERE height BETWEEN $1 AND $1 + 2",
+        )
+        .bind(fixed)
+        .fetch_one(store.pool())
+        .await?;
+        assert_eq!(queued, 0);
+
+        reprocess(&store, false).await?;
+        let queued: Vec<(i64, Option<String>)> = sqlx::query_as(
+            "SELECT height, requested_by FROM public.reingest_requests WHERE height BETWEEN $1 AND $1 + 2",
+        )
+        .bind(fixed)
+        .fetch_all(store.pool())
+        .await?;
+        assert_eq!(queued, vec![(fixed, Some(REQUESTED_BY.to_string()))]);
+        let remaining: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM public.quarantined_txs WHERE block_height BETWEEN $1 AND $1 + 2",
+        )
+        .bind(fixed)
+        .fetch_one(store.pool())
+        .await?;
+        assert_eq!(remaining, 2);
+
+        cleanup().await
+    }
+}
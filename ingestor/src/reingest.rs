@@ -0,0 +1,82 @@
+use std::{sync::Arc, time::Duration, time::Instant};
+
+use anyhow::{anyhow, Context, Result};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::{checkpoint::Checkpoint, pipeline::SchedMsg, work_block, work_persist, work_tx};
+
+/// Drains `public.reingest_requests`, which operators fill through the API's
+/// `POST /api/v1/admin/reingest/{height}`.
+pub struct Config {
+    pub block: work_block::Config,
+    pub tx: work_tx::Config,
+    pub persist: work_persist::Config,
+    pub checkpoint: Arc<Checkpoint>,
+    pub poll_interval: Duration,
+}
+
+pub fn spawn(cfg: Config) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match run_once(&cfg).await {
+                Ok(Some(_)) => continue,
+                Ok(None) => {}
+                Err(err) => warn!(error = ?err, "reingest poll failed"),
+            }
+            tokio::time::sleep(cfg.poll_interval).await;
+        }
+    })
+}
+
+/// Claims and processes one request. Returns the height handled, if any.
+pub async fn run_once(cfg: &Config) -> Result<Option<i64>> {
+    let store = &cfg.persist.store;
+    let Some(height) = store
+        .claim_reingest_request()
+        .await
+        .context("claim reingest request")?
+    else {
+        return Ok(None);
+    };
+
+    info!(height, "re-ingesting block on operator request");
+    match reingest_height(cfg, height).await {
+        Ok(()) => {
+            store.finish_reingest_request(height, None).await?;
+            metrics::counter!("reingest_requests_total", "outcome" => "done").increment(1);
+        }
+        Err(err) => {
+            warn!(height, error = ?err, "re-ingestion failed");
+            store
+                .finish_reingest_request(height, Some(&format!("{err:#}")))
+                .await?;
+            metrics::counter!("reingest_requests_total", "outcome" => "failed").increment(1);
+        }
+    }
+    Ok(Some(height))
+}
+
+async fn reingest_height(cfg: &Config, height: i64) -> Result<()> {
+    let state = cfg
+        .checkpoint
+        .get_state()
+        .await
+        .context("read checkpoint")?;
+    if height > state.ingested_height {
+        return Err(anyhow!(
+            "height {height} is above the ingested height {}",
+            state.ingested_height
+        ));
+    }
+
+    let sched = SchedMsg {
+        height,
+        tip_height: state.ingested_height,
+        finalized_height: state.finalized_height,
+        started: Instant::now(),
+    };
+    let block = work_block::fetch_block(&cfg.block, &sched).await?;
+    let msg = work_tx::fetch_block_txs(&cfg.tx, block).await?;
+    work_persist::reingest_block(&cfg.persist, &msg).await
+}
@@ -1,14 +1,16 @@
 use anyhow::{anyhow, Context, Result};
-use hex::decode;
 
 use crate::{rpc::MoneroRpc, store::Store};
 
+/// Rolls the stored chain back to the last height whose hash still matches
+/// the daemon's and returns the first height that was removed. The checkpoint
+/// moves back with it, so a restart schedules the removed heights again.
 pub async fn heal_reorg(
     start_height: i64,
     store: &Store,
     rpc: &dyn MoneroRpc,
-    finality_window: i64,
-) -> Result<()> {
+    max_depth: i64,
+) -> Result<i64> {
     let mut h = start_height - 1;
     let mut steps = 0_i64;
 
@@ -18,10 +20,10 @@ pub async fn heal_reorg(
         .ok_or_else(|| anyhow!("no db block at height {}", h))?;
 
     loop {
-        if steps > finality_window {
+        if steps > max_depth {
             return Err(anyhow!(
-                "reorg exceeds FINALITY_WINDOW={} ({} steps)",
-                finality_window,
+                "reorg exceeds MAX_REORG_DEPTH={} ({} steps)",
+                max_depth,
                 steps
             ));
         }
@@ -30,10 +32,7 @@ pub async fn heal_reorg(
             .get_block_header_by_height(h as u64)
             .await
             .with_context(|| format!("fetch header at height {}", h))?;
-        let live_hash = decode(&live_hdr.block_header.hash)
-            .with_context(|| format!("decode live hash at height {}", h))?;
-
-        if live_hash == db_hash_at_h {
+        if live_hdr.block_header.hash == db_hash_at_h {
             break;
         }
 
@@ -72,12 +71,60 @@ pub async fn heal_reorg(
     .await
     .with_context(|| "delete chain tips".to_string())?;
 
+    sqlx::query!(
+        "DELETE FROM public.rct_output_counts WHERE height >= $1",
+        fork_height
+    )
+    .execute(&mut *tx)
+    .await
+    .with_context(|| "delete rct output counts".to_string())?;
+
+    sqlx::query!(
+        "DELETE FROM public.block_emission WHERE height >= $1",
+        fork_height
+    )
+    .execute(&mut *tx)
+    .await
+    .with_context(|| "delete block emission".to_string())?;
+
+    sqlx::query!(
+        "DELETE FROM public.emission_anomalies WHERE height >= $1",
+        fork_height
+    )
+    .execute(&mut *tx)
+    .await
+    .with_context(|| "delete emission anomalies".to_string())?;
+
+    sqlx::query!(
+        "DELETE FROM public.block_ingest_state WHERE height >= $1",
+        fork_height
+    )
+    .execute(&mut *tx)
+    .await
+    .with_context(|| "delete block ingest state".to_string())?;
+
     sqlx::query!("DELETE FROM public.blocks WHERE height >= $1", fork_height)
         .execute(&mut *tx)
         .await
         .with_context(|| "delete blocks".to_string())?;
 
+    Store::reset_current_tip(&mut tx, fork_height)
+        .await
+        .with_context(|| "reset current tip".to_string())?;
+
+    // A database tracks a single network, so there is at most one row.
+    sqlx::query!(
+        "UPDATE public.ingestor_checkpoint
+         SET last_height = LEAST(last_height, $1::BIGINT - 1),
+             finalized_height = LEAST(finalized_height, $1::BIGINT - 1),
+             updated_at = NOW()",
+        fork_height
+    )
+    .execute(&mut *tx)
+    .await
+    .with_context(|| "rewind checkpoint".to_string())?;
+
     tx.commit().await?;
 
-    Ok(())
+    Ok(fork_height)
 }
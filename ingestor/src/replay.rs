@@ -0,0 +1,541 @@
+//! Replays a captured reorg through the whole pipeline, healing included,
+//! against a scratch database. A `FixtureRpc` daemon first serves the
+//! fixture's chain, then switches to its competing branch, and ingestion
+//! carries on until the stored chain is the branch. Backs `replay-reorg` and
+//! the reorg regression test.
+
+use std::{
+    collections::HashSet,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use async_trait::async_trait;
+use bex_core::{BlockHash, TxHash};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, Mutex};
+
+use crate::{
+    capabilities::LiveCapabilities,
+    checkpoint::Checkpoint,
+    events::{Event, Events},
+    limits,
+    pipeline::{self, PipelineCfg},
+    rpc::{
+        BlockHeader, Capabilities, GetBlockCountResult, GetBlockHeaderByHeightResult,
+        GetBlockResult, GetTransactionsResult, MoneroRpc, PoolTx,
+    },
+    store::Store,
+    work_block, work_persist, work_sched, work_tx,
+};
+
+/// A chain and the branch that replaces its top, as JSON.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fixture {
+    /// Ingested first, in height order.
+    pub chain: Vec<FixtureBlock>,
+    pub reorg: FixtureReorg,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FixtureReorg {
+    /// First height the branch replaces.
+    pub fork_height: u64,
+    /// Must reach above the chain's tip, as a daemon only switches to a
+    /// longer branch.
+    pub blocks: Vec<FixtureBlock>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FixtureBlock {
+    pub height: u64,
+    pub hash: BlockHash,
+    pub prev_hash: BlockHash,
+    #[serde(default)]
+    pub txs: Vec<TxHash>,
+}
+
+impl FixtureBlock {
+    pub(crate) fn header(&self) -> BlockHeader {
+        BlockHeader {
+            hash: self.hash,
+            height: self.height,
+            timestamp: self.height * 100,
+            prev_hash: self.prev_hash,
+            major_version: 1,
+            minor_version: 1,
+            nonce: 0,
+            reward: 0,
+            size: 1,
+            difficulty: 0,
+        }
+    }
+
+    /// `get_block` JSON of a version 1 block with an empty miner tx.
+    pub(crate) fn block_json(&self) -> String {
+        serde_json::json!({
+            "miner_tx": {
+                "version": 1,
+                "extra": "",
+                "vin": [],
+                "vout": [],
+                "rct_signatures": {},
+                "rctsig_prunable": {},
+                "unlock_time": 0,
+            },
+            "tx_hashes": self.txs.iter().map(TxHash::to_hex).collect::<Vec<_>>(),
+        })
+        .to_string()
+    }
+
+    /// Derived from the block hash, so branches never share a miner tx.
+    pub(crate) fn miner_tx_hash(&self) -> String {
+        let mut miner_tx_hash = self.hash.to_hex();
+        miner_tx_hash.replace_range(..2, "ff");
+        miner_tx_hash
+    }
+
+    /// `get_transactions` JSON of an empty version 1 tx.
+    pub(crate) fn tx_json(hash: &str) -> String {
+        serde_json::json!({
+            "tx_hash": hash,
+            "version": 1,
+            "vin": [],
+            "vout": [],
+            "extra": "",
+            "rct_signatures": {},
+            "rctsig_prunable": {},
+            "unlock_time": 0,
+        })
+        .to_string()
+    }
+}
+
+impl Fixture {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        let fixture: Self = serde_json::from_slice(&raw)
+            .with_context(|| format!("parse fixture {}", path.display()))?;
+        fixture.validate()?;
+        Ok(fixture)
+    }
+
+    /// Checks both sides are linked runs of heights and that the branch
+    /// forks off the chain and outgrows it.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.chain.is_empty(), "fixture chain is empty");
+        ensure!(!self.reorg.blocks.is_empty(), "fixture reorg has no blocks");
+        linked(&self.chain).context("fixture chain")?;
+        linked(&self.reorg.blocks).context("fixture reorg")?;
+
+        let fork = self.reorg.fork_height;
+        ensure!(
+            fork > self.first_height() && fork <= self.chain_tip(),
+            "fork height {fork} must replace a chain block above the first"
+        );
+        let branch = &self.reorg.blocks[0];
+        ensure!(
+            branch.height == fork,
+            "reorg starts at {} instead of the fork height {fork}",
+            branch.height
+        );
+        let parent = &self.chain[(fork - 1 - self.first_height()) as usize];
+        ensure!(
+            branch.prev_hash == parent.hash,
+            "reorg block {fork} does not build on chain block {}",
+            parent.height
+        );
+        ensure!(
+            branch.hash != self.chain[(fork - self.first_height()) as usize].hash,
+            "reorg block {fork} is the chain's own block"
+        );
+        ensure!(
+            self.branch_tip() > self.chain_tip(),
+            "reorg tip {} must be above the chain tip {}",
+            self.branch_tip(),
+            self.chain_tip()
+        );
+        Ok(())
+    }
+
+    fn first_height(&self) -> u64 {
+        self.chain[0].height
+    }
+
+    fn chain_tip(&self) -> u64 {
+        self.chain[self.chain.len() - 1].height
+    }
+
+    fn branch_tip(&self) -> u64 {
+        self.reorg.blocks[self.reorg.blocks.len() - 1].height
+    }
+
+    /// The chain a daemon serves after the reorg.
+    fn expected(&self) -> impl Iterator<Item = &FixtureBlock> {
+        self.chain
+            .iter()
+            .filter(|b| b.height < self.reorg.fork_height)
+            .chain(&self.reorg.blocks)
+    }
+}
+
+fn linked(blocks: &[FixtureBlock]) -> Result<()> {
+    for pair in blocks.windows(2) {
+        ensure!(
+            pair[1].height == pair[0].height + 1 && pair[1].prev_hash == pair[0].hash,
+            "block {} does not follow block {}",
+            pair[1].height,
+            pair[0].height
+        );
+    }
+    Ok(())
+}
+
+/// What the replay left in the database; `failures` lists every way it
+/// differs from the branch.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// From the `reorg` event, `None` if no reorg was healed.
+    pub fork_height: Option<i64>,
+    pub depth: Option<i64>,
+    pub checkpoint: i64,
+    pub failures: Vec<String>,
+}
+
+/// Ingests the fixture's chain, switches the daemon to the branch, ingests up
+/// to the branch tip and compares the result with the branch. Refuses to run
+/// on a database that already has a checkpoint, which a live ingestor would
+/// share with the replay, or that holds blocks in the fixture's height range.
+pub async fn replay(
+    store: &Store,
+    network: &str,
+    fixture: &Fixture,
+    max_reorg_depth: u64,
+) -> Result<Report> {
+    fixture.validate()?;
+    let first = i64::try_from(fixture.first_height()).context("height overflow")?;
+    let tip = i64::try_from(fixture.branch_tip()).context("height overflow")?;
+    let tracked: Option<Option<String>> =
+        sqlx::query_scalar("SELECT network FROM public.ingestor_checkpoint LIMIT 1")
+            .fetch_optional(store.pool())
+            .await?;
+    if let Some(tracked) = tracked {
+        bail!(
+            "database already has a checkpoint (network {}); replay needs a scratch database",
+            tracked.as_deref().unwrap_or("unset")
+        );
+    }
+    let occupied: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM public.blocks WHERE height BETWEEN $1 AND $2)",
+    )
+    .bind(first)
+    .bind(tip)
+    .fetch_one(store.pool())
+    .await?;
+    if occupied {
+        bail!("database already holds blocks in {first}..={tip}; replay needs a scratch database");
+    }
+
+    let checkpoint = Arc::new(Checkpoint::new(store.pool().clone(), network));
+    checkpoint.claim().await?;
+    let rpc = Arc::new(FixtureRpc::new(fixture.chain.clone()));
+    let (events, mut published) = Events::channel();
+    let stages = Stages {
+        store,
+        checkpoint: &checkpoint,
+        rpc: Arc::clone(&rpc) as Arc<dyn MoneroRpc>,
+        max_reorg_depth,
+        events,
+    };
+
+    stages
+        .ingest(first, fixture.chain_tip() - fixture.first_height() + 1)
+        .await
+        .context("ingest chain")?;
+    rpc.replace_from(fixture.reorg.fork_height, fixture.reorg.blocks.clone());
+    stages
+        .ingest(
+            i64::try_from(fixture.chain_tip() + 1).context("height overflow")?,
+            fixture.branch_tip() - fixture.chain_tip(),
+        )
+        .await
+        .context("ingest branch")?;
+    drop(stages);
+
+    let mut report = Report {
+        fork_height: None,
+        depth: None,
+        checkpoint: checkpoint.get().await?,
+        failures: Vec::new(),
+    };
+    while let Ok(event) = published.try_recv() {
+        if let Event::Reorg { fork_height, depth } = event {
+            report.fork_height = Some(fork_height);
+            report.depth = Some(depth);
+        }
+    }
+    check(store, fixture, &mut report).await?;
+    Ok(report)
+}
+
+/// A daemon serving fixture blocks, each holding empty version 1 txs, with
+/// an empty mempool.
+struct FixtureRpc {
+    blocks: RwLock<Vec<FixtureBlock>>,
+}
+
+impl FixtureRpc {
+    fn new(blocks: Vec<FixtureBlock>) -> Self {
+        Self {
+            blocks: RwLock::new(blocks),
+        }
+    }
+
+    /// Drops every block from `height` up and serves `blocks` instead.
+    fn replace_from(&self, height: u64, blocks: Vec<FixtureBlock>) {
+        let mut chain = self.blocks.write().expect("fixture chain lock");
+        chain.retain(|b| b.height < height);
+        chain.extend(blocks);
+    }
+
+    fn find(&self, pred: impl Fn(&FixtureBlock) -> bool) -> Option<FixtureBlock> {
+        let chain = self.blocks.read().expect("fixture chain lock");
+        chain.iter().find(|b| pred(b)).cloned()
+    }
+}
+
+#[async_trait]
+impl MoneroRpc for FixtureRpc {
+    async fn get_block_headers_range(&self, start: u64, end: u64) -> Result<Vec<BlockHeader>> {
+        let chain = self.blocks.read().expect("fixture chain lock");
+        Ok(chain
+            .iter()
+            .filter(|b| b.height >= start && b.height <= end)
+            .map(FixtureBlock::header)
+            .collect())
+    }
+
+    async fn get_block_header_by_height(
+        &self,
+        height: u64,
+    ) -> Result<GetBlockHeaderByHeightResult> {
+        let block = self
+            .find(|b| b.height == height)
+            .context("missing block header")?;
+        Ok(GetBlockHeaderByHeightResult {
+            block_header: block.header(),
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_block(&self, hash: &BlockHash, _fill_pow: bool) -> Result<GetBlockResult> {
+        let block = self.find(|b| b.hash == *hash).context("missing block")?;
+        Ok(GetBlockResult {
+            block_header: block.header(),
+            json: Some(block.block_json()),
+            blob: None,
+            miner_tx_hash: Some(block.miner_tx_hash()),
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_transactions(&self, txs_hashes: &[String]) -> Result<GetTransactionsResult> {
+        let chain = self.blocks.read().expect("fixture chain lock");
+        let txs_as_json = txs_hashes
+            .iter()
+            .map(|hash| {
+                chain
+                    .iter()
+                    .any(|b| b.txs.iter().any(|tx| tx.to_hex() == *hash))
+                    .then(|| FixtureBlock::tx_json(hash))
+                    .context("missing tx json")
+            })
+            .collect::<Result<_>>()?;
+        Ok(GetTransactionsResult {
+            txs_as_json,
+            missed_tx: Vec::new(),
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_block_count(&self) -> Result<GetBlockCountResult> {
+        let chain = self.blocks.read().expect("fixture chain lock");
+        let tip = chain.iter().map(|b| b.height).max().unwrap_or_default();
+        Ok(GetBlockCountResult {
+            count: tip + 1,
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_transaction_pool_hashes(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_pool_transactions(&self, _txs_hashes: &[String]) -> Result<Vec<PoolTx>> {
+        Ok(Vec::new())
+    }
+
+    async fn probe_caps(&self) -> Capabilities {
+        Capabilities::default()
+    }
+}
+
+struct Stages<'a> {
+    store: &'a Store,
+    checkpoint: &'a Arc<Checkpoint>,
+    rpc: Arc<dyn MoneroRpc>,
+    max_reorg_depth: u64,
+    events: Events,
+}
+
+impl Stages<'_> {
+    /// Runs `count` heights from `start` through one worker per stage, so a
+    /// replay always takes the same path.
+    async fn ingest(&self, start: i64, count: u64) -> Result<()> {
+        let caps = Arc::new(LiveCapabilities::new(Capabilities::default()));
+        let limiter = Arc::new(limits::make_limiter(1000, false));
+        let (tx_sched, rx_sched, tx_block, rx_block, tx_tx, rx_tx) =
+            pipeline::make_channels(&PipelineCfg {
+                sched_buffer: 8,
+                block_workers: 1,
+                tx_workers: 1,
+            });
+
+        let sched = work_sched::run(
+            tx_sched,
+            work_sched::Config {
+                checkpoint: Arc::clone(self.checkpoint),
+                rpc: Arc::clone(&self.rpc),
+                limiter: Arc::clone(&limiter),
+                start_height: Some(start),
+                limit: Some(count),
+                finality_window: self.max_reorg_depth,
+                caps: Arc::clone(&caps),
+                header_batch: 1,
+                readiness: Arc::default(),
+                tip_confirmations: 0,
+                daemon_tip: watch::channel(0).0,
+                memory: Default::default(),
+            },
+            None,
+        );
+        let block = work_block::run(
+            Arc::new(Mutex::new(rx_sched)),
+            tx_block,
+            work_block::Config {
+                rpc: Arc::clone(&self.rpc),
+                limiter: Arc::clone(&limiter),
+                store: self.store.clone(),
+                max_reorg_depth: self.max_reorg_depth,
+                caps,
+                header_batch: 1,
+                events: self.events.clone(),
+                prefetch: None,
+                memory: Default::default(),
+            },
+            None,
+        );
+        let txs = work_tx::run(
+            Arc::new(Mutex::new(rx_block)),
+            tx_tx,
+            work_tx::Config {
+                rpc: Arc::clone(&self.rpc),
+                limiter,
+                concurrency: 1,
+                memory: Default::default(),
+            },
+            None,
+        );
+        let persist = work_persist::run(
+            rx_tx,
+            work_persist::Config {
+                store: self.store.clone(),
+                checkpoint: Arc::clone(self.checkpoint),
+                finality_window: self.max_reorg_depth,
+                do_analytics: false,
+                chain_tips_retention: 0,
+                analytics_wake: None,
+                position_tx: None,
+                prepare_pool: work_persist::PreparePool::new(1),
+                events: self.events.clone(),
+                extra_retention: Default::default(),
+            },
+            None,
+        );
+        tokio::try_join!(sched, block, txs, persist)?;
+        Ok(())
+    }
+}
+
+/// Compares the stored chain, its txs and the checkpoint with the branch.
+async fn check(store: &Store, fixture: &Fixture, report: &mut Report) -> Result<()> {
+    let first = i64::try_from(fixture.first_height()).context("height overflow")?;
+    let tip = i64::try_from(fixture.branch_tip()).context("height overflow")?;
+    let stored: Vec<(i64, Vec<u8>)> = sqlx::query_as(
+        "SELECT height, hash FROM public.blocks WHERE height BETWEEN $1 AND $2 ORDER BY height",
+    )
+    .bind(first)
+    .bind(tip)
+    .fetch_all(store.pool())
+    .await?;
+    let expected: Vec<&FixtureBlock> = fixture.expected().collect();
+    for block in &expected {
+        let height = block.height as i64;
+        match stored.iter().find(|(h, _)| *h == height) {
+            Some((_, hash)) if hash.as_slice() == block.hash.as_bytes() => {}
+            Some((_, hash)) => report.failures.push(format!(
+                "block {height} is {} instead of {}",
+                hex::encode(hash),
+                block.hash
+            )),
+            None => report.failures.push(format!("block {height} is missing")),
+        }
+    }
+    if report.checkpoint != tip {
+        report.failures.push(format!(
+            "checkpoint is {} instead of {tip}",
+            report.checkpoint
+        ));
+    }
+
+    let kept: HashSet<TxHash> = expected
+        .iter()
+        .flat_map(|b| b.txs.iter().copied())
+        .collect();
+    for block in &expected {
+        for tx in &block.txs {
+            let height: Option<Option<i64>> =
+                sqlx::query_scalar("SELECT block_height FROM public.txs WHERE tx_hash = $1")
+                    .bind(tx.as_bytes().as_slice())
+                    .fetch_optional(store.pool())
+                    .await?;
+            if height != Some(Some(block.height as i64)) {
+                report.failures.push(format!(
+                    "tx {tx} is at {height:?} instead of block {}",
+                    block.height
+                ));
+            }
+        }
+    }
+    let abandoned = fixture
+        .chain
+        .iter()
+        .filter(|b| b.height >= fixture.reorg.fork_height)
+        .flat_map(|b| b.txs.iter())
+        .filter(|tx| !kept.contains(*tx));
+    for tx in abandoned {
+        let in_pool: Option<bool> = sqlx::query_scalar(
+            "SELECT in_mempool AND block_height IS NULL FROM public.txs WHERE tx_hash = $1",
+        )
+        .bind(tx.as_bytes().as_slice())
+        .fetch_optional(store.pool())
+        .await?;
+        if in_pool != Some(true) {
+            report
+                .failures
+                .push(format!("abandoned tx {tx} is not back in the pool"));
+        }
+    }
+    Ok(())
+}
@@ -1,13 +1,168 @@
+use std::fmt;
+
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use bex_core::{rpc, BlockHash};
+use reqwest::{Client, Response};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub use bex_core::{
+    rpc::{GetBlockCountResult, GetInfoResult},
+    BlockHeader,
+};
+
+use crate::alerts;
+
+/// Default cap on a single daemon response body.
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
 
 fn record_rpc_error(method: &str) {
     metrics::counter!("rpc_errors_total", "method" => method.to_string()).increment(1);
+    alerts::record_rpc_error();
+}
+
+/// A daemon response body exceeded the configured size limit. Callers that
+/// batch requests can downcast to this and retry with a smaller batch.
+#[derive(Debug)]
+pub struct ResponseTooLarge {
+    pub method: String,
+    pub limit: usize,
+}
+
+impl fmt::Display for ResponseTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RPC {} response exceeds {} bytes",
+            self.method, self.limit
+        )
+    }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+impl std::error::Error for ResponseTooLarge {}
+
+/// Body chunks queued between the network and the decoder. Together with the
+/// decoded value this bounds what a response holds in memory.
+const DECODE_QUEUE_CHUNKS: usize = 8;
+
+fn too_large(method: &str, limit: usize) -> anyhow::Error {
+    record_rpc_error(method);
+    anyhow::Error::new(ResponseTooLarge {
+        method: method.to_string(),
+        limit,
+    })
+}
+
+/// Reads a response body chunk by chunk, failing as soon as it grows past
+/// `limit` instead of buffering whatever the daemon sends. Used for error
+/// bodies; results are decoded with [`decode_body`].
+async fn read_body(method: &str, mut res: Response, limit: usize) -> Result<Vec<u8>> {
+    let declared = res.content_length().unwrap_or(0);
+    if declared > limit as u64 {
+        return Err(too_large(method, limit));
+    }
+
+    let mut body = Vec::with_capacity(declared as usize);
+    while let Some(chunk) = res
+        .chunk()
+        .await
+        .inspect_err(|_| record_rpc_error(method))
+        .with_context(|| format!("RPC {} body read failed", method))?
+    {
+        if body.len() + chunk.len() > limit {
+            return Err(too_large(method, limit));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    metrics::histogram!("rpc_response_bytes", "method" => method.to_string())
+        .record(body.len() as f64);
+    Ok(body)
+}
+
+/// Decodes a JSON response body as it arrives, so only a few chunks are held
+/// besides the decoded value. Fails with [`ResponseTooLarge`] once the body
+/// grows past `limit`.
+async fn decode_body<T: DeserializeOwned + Send + 'static>(
+    method: &str,
+    mut res: Response,
+    limit: usize,
+) -> Result<T> {
+    if res.content_length().unwrap_or(0) > limit as u64 {
+        return Err(too_large(method, limit));
+    }
+
+    let (chunks, queued) = tokio::sync::mpsc::channel(DECODE_QUEUE_CHUNKS);
+    let decoder = tokio::task::spawn_blocking(move || {
+        serde_json::from_reader::<_, T>(ChunkReader {
+            queued,
+            current: None,
+            pos: 0,
+        })
+    });
+
+    let mut read = 0;
+    let streamed = async {
+        while let Some(chunk) = res
+            .chunk()
+            .await
+            .inspect_err(|_| record_rpc_error(method))
+            .with_context(|| format!("RPC {} body read failed", method))?
+        {
+            read += chunk.len();
+            if read > limit {
+                return Err(too_large(method, limit));
+            }
+            if chunks.send(chunk).await.is_err() {
+                // The decoder already gave up on the body.
+                break;
+            }
+        }
+        Ok(())
+    }
+    .await;
+    // Closing the queue ends the decoder's input, also after an early return.
+    drop(chunks);
+    let decoded = decoder.await.context("RPC decode task failed")?;
+    streamed?;
+
+    metrics::histogram!("rpc_response_bytes", "method" => method.to_string()).record(read as f64);
+    decoded
+        .inspect_err(|_| record_rpc_error(method))
+        .with_context(|| format!("RPC {} decode failed", method))
+}
+
+/// Blocking reader over the chunks [`decode_body`] queues from the network.
+struct ChunkReader<B> {
+    queued: tokio::sync::mpsc::Receiver<B>,
+    current: Option<B>,
+    pos: usize,
+}
+
+impl<B: AsRef<[u8]>> std::io::Read for ChunkReader<B> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            if let Some(chunk) = &self.current {
+                let rest = &chunk.as_ref()[self.pos..];
+                if !rest.is_empty() {
+                    let n = rest.len().min(buf.len());
+                    buf[..n].copy_from_slice(&rest[..n]);
+                    self.pos += n;
+                    return Ok(n);
+                }
+            }
+            match self.queued.blocking_recv() {
+                Some(chunk) => {
+                    self.current = Some(chunk);
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Capabilities {
     pub headers_range: bool,
     pub blocks_by_height_bin: bool,
@@ -20,7 +175,22 @@ pub trait MoneroRpc: Send + Sync {
 
     async fn get_block_headers_range(&self, start: u64, end: u64) -> Result<Vec<BlockHeader>>;
 
-    async fn get_block(&self, hash: &str, fill_pow: bool) -> Result<GetBlockResult>;
+    async fn get_block(&self, hash: &BlockHash, fill_pow: bool) -> Result<GetBlockResult>;
+
+    /// `get_block` addressed by height. The default takes the two-step path
+    /// (header by height, then block by hash) for daemons and mocks that
+    /// cannot do it in one call.
+    async fn get_block_by_height(&self, height: u64) -> Result<GetBlockByHeightResult> {
+        let header = self.get_block_header_by_height(height).await?.block_header;
+        let block = self.get_block(&header.hash, false).await?;
+        Ok(GetBlockByHeightResult {
+            block_header: serde_json::to_value(&header)?,
+            json: block.json,
+            blob: block.blob,
+            miner_tx_hash: block.miner_tx_hash,
+            status: block.status,
+        })
+    }
 
     async fn get_transactions(&self, txs_hashes: &[String]) -> Result<GetTransactionsResult>;
 
@@ -28,6 +198,16 @@ pub trait MoneroRpc: Send + Sync {
 
     async fn get_transaction_pool_hashes(&self) -> Result<Vec<String>>;
 
+    /// Bodies of the given pool txs, for what `get_transaction_pool_hashes`
+    /// listed. Txs that were mined or dropped in between are left out.
+    async fn get_pool_transactions(&self, txs_hashes: &[String]) -> Result<Vec<PoolTx>>;
+
+    /// The daemon's dynamic base fee. The default fails, for mocks that do
+    /// not serve it.
+    async fn get_fee_estimate(&self) -> Result<FeeEstimate> {
+        Err(anyhow!("get_fee_estimate not supported"))
+    }
+
     async fn probe_caps(&self) -> Capabilities;
 }
 
@@ -36,6 +216,8 @@ pub struct Rpc {
     base_json: String,
     base_rest: String,
     http: Client,
+    max_response_bytes: usize,
+    prune: bool,
 }
 
 impl Rpc {
@@ -51,91 +233,62 @@ impl Rpc {
             base_json,
             base_rest,
             http: Client::builder().build().expect("reqwest client"),
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            prune: false,
         }
     }
 
-    async fn raw_call<T: for<'de> Deserialize<'de>, P: Serialize>(
+    pub fn with_max_response_bytes(mut self, limit: usize) -> Self {
+        self.max_response_bytes = limit;
+        self
+    }
+
+    /// Requests pruned transactions (no range proofs or signatures) from
+    /// `get_transactions`.
+    pub fn with_prune(mut self, prune: bool) -> Self {
+        self.prune = prune;
+        self
+    }
+
+    async fn raw_call<T: DeserializeOwned + Send + 'static, P: Serialize>(
         &self,
         method: &str,
         params: P,
     ) -> Result<T> {
-        #[derive(Serialize)]
-        struct Req<'a, P> {
-            jsonrpc: &'a str,
-            id: u64,
-            method: &'a str,
-            params: P,
-        }
-
-        #[derive(Deserialize)]
-        struct RpcError {
-            code: i64,
-            message: String,
-        }
-
-        #[derive(Deserialize)]
-        #[serde(untagged)]
-        enum RpcResponse<T> {
-            Ok { result: T },
-            Err { error: RpcError },
-        }
-
-        let body = Req {
-            jsonrpc: "2.0",
-            id: 1,
-            method,
-            params,
-        };
+        let body = rpc::Request::new(method, params);
 
+        alerts::record_rpc_request();
         let res = self
             .http
             .post(&self.base_json)
             .json(&body)
             .send()
             .await
-            .map_err(|err| {
-                record_rpc_error(method);
-                err
-            })
+            .inspect_err(|_| record_rpc_error(method))
             .with_context(|| format!("RPC {} send failed", method))?;
 
         let status = res.status();
-        let v = res
-            .json::<serde_json::Value>()
-            .await
-            .map_err(|err| {
-                record_rpc_error(method);
-                err
-            })
-            .with_context(|| "RPC JSON decode failed".to_string())?;
-
         if !status.is_success() {
+            let body = read_body(method, res, self.max_response_bytes).await?;
             record_rpc_error(method);
-            anyhow::bail!("RPC {} HTTP {}: {}", method, status, v);
+            anyhow::bail!(
+                "RPC {} HTTP {}: {}",
+                method,
+                status,
+                String::from_utf8_lossy(&body)
+            );
         }
 
-        match serde_json::from_value::<RpcResponse<T>>(v)
+        decode_body::<rpc::Response<T>>(method, res, self.max_response_bytes)
+            .await?
+            .into_result()
             .map_err(|err| {
                 record_rpc_error(method);
-                err
+                anyhow!("RPC {} {}", method, err)
             })
-            .with_context(|| "RPC result decode failed")?
-        {
-            RpcResponse::Ok { result } => Ok(result),
-            RpcResponse::Err { error } => Err(anyhow!(
-                "RPC {} error {}: {}",
-                method,
-                error.code,
-                error.message
-            ))
-            .map_err(|err| {
-                record_rpc_error(method);
-                err
-            }),
-        }
     }
 
-    async fn call<T: for<'de> Deserialize<'de>, P: Serialize>(
+    async fn call<T: DeserializeOwned + Send + 'static, P: Serialize>(
         &self,
         method: &str,
         params: P,
@@ -212,17 +365,52 @@ impl Rpc {
         Ok(r.headers)
     }
 
-    pub async fn get_block(&self, hash: &str, fill_pow: bool) -> Result<GetBlockResult> {
+    pub async fn get_block(&self, hash: &BlockHash, fill_pow: bool) -> Result<GetBlockResult> {
         #[derive(Serialize)]
         struct P<'a> {
-            hash: &'a str,
+            hash: &'a BlockHash,
             fill_pow: bool,
         }
 
         self.call("get_block", P { hash, fill_pow }).await
     }
 
+    pub async fn get_block_by_height(&self, height: u64) -> Result<GetBlockByHeightResult> {
+        #[derive(Serialize)]
+        struct P {
+            height: u64,
+        }
+
+        self.call("get_block", P { height }).await
+    }
+
     pub async fn get_transactions(&self, txs_hashes: &[String]) -> Result<GetTransactionsResult> {
+        self.post_get_transactions(txs_hashes, self.prune).await
+    }
+
+    /// Pool txs fetched unpruned, so the blob size is known even with
+    /// `--prune`.
+    pub async fn get_pool_transactions(&self, txs_hashes: &[String]) -> Result<Vec<PoolTx>> {
+        #[derive(Deserialize)]
+        struct RestResponse {
+            #[serde(default)]
+            txs: Vec<PoolTxEntry>,
+        }
+
+        let body: RestResponse = self.post_get_transactions(txs_hashes, false).await?;
+        Ok(body
+            .txs
+            .into_iter()
+            .filter(|entry| entry.in_pool)
+            .map(PoolTx::from)
+            .collect())
+    }
+
+    async fn post_get_transactions<T: DeserializeOwned + Send + 'static>(
+        &self,
+        txs_hashes: &[String],
+        prune: bool,
+    ) -> Result<T> {
         #[derive(Serialize)]
         struct P<'a> {
             txs_hashes: &'a [String],
@@ -231,45 +419,46 @@ impl Rpc {
         }
 
         let url = format!("{}/get_transactions", self.base_rest);
+        alerts::record_rpc_request();
         let res = self
             .http
             .post(&url)
             .json(&P {
                 txs_hashes,
                 decode_as_json: true,
-                prune: false,
+                prune,
             })
             .send()
             .await
-            .map_err(|err| {
-                record_rpc_error("get_transactions");
-                err
-            })
+            .inspect_err(|_| record_rpc_error("get_transactions"))
             .with_context(|| "get_transactions send failed".to_string())?;
 
         let status = res.status();
         if !status.is_success() {
+            let body = read_body("get_transactions", res, self.max_response_bytes).await?;
             record_rpc_error("get_transactions");
-            let body = res
-                .text()
-                .await
-                .unwrap_or_else(|_| "<binary response>".to_string());
-            anyhow::bail!("get_transactions HTTP {}: {}", status, body);
+            anyhow::bail!(
+                "get_transactions HTTP {}: {}",
+                status,
+                String::from_utf8_lossy(&body)
+            );
         }
 
-        res.json::<GetTransactionsResult>()
-            .await
-            .map_err(|err| {
-                record_rpc_error("get_transactions");
-                err
-            })
-            .with_context(|| "get_transactions decode failed".to_string())
+        decode_body("get_transactions", res, self.max_response_bytes).await
     }
 
     pub async fn get_block_count(&self) -> Result<GetBlockCountResult> {
         self.call("get_block_count", ()).await
     }
 
+    pub async fn get_info(&self) -> Result<GetInfoResult> {
+        self.call("get_info", ()).await
+    }
+
+    pub async fn get_fee_estimate(&self) -> Result<FeeEstimate> {
+        self.call("get_fee_estimate", ()).await
+    }
+
     pub async fn get_transaction_pool_hashes(&self) -> Result<Vec<String>> {
         #[derive(Deserialize)]
         struct RestResponse {
@@ -279,26 +468,18 @@ impl Rpc {
         }
 
         let url = format!("{}/get_transaction_pool_hashes", self.base_rest);
+        alerts::record_rpc_request();
         let res = self
             .http
             .get(&url)
             .send()
             .await
-            .map_err(|err| {
-                record_rpc_error("get_transaction_pool_hashes");
-                err
-            })
+            .inspect_err(|_| record_rpc_error("get_transaction_pool_hashes"))
             .with_context(|| "get_transaction_pool_hashes send failed".to_string())?;
 
         let status = res.status();
-        let body = res
-            .json::<RestResponse>()
-            .await
-            .map_err(|err| {
-                record_rpc_error("get_transaction_pool_hashes");
-                err
-            })
-            .with_context(|| "get_transaction_pool_hashes decode failed".to_string())?;
+        let body: RestResponse =
+            decode_body("get_transaction_pool_hashes", res, self.max_response_bytes).await?;
 
         if !status.is_success() {
             record_rpc_error("get_transaction_pool_hashes");
@@ -334,10 +515,14 @@ impl MoneroRpc for Rpc {
         Rpc::get_block_header_by_height(self, height).await
     }
 
-    async fn get_block(&self, hash: &str, fill_pow: bool) -> Result<GetBlockResult> {
+    async fn get_block(&self, hash: &BlockHash, fill_pow: bool) -> Result<GetBlockResult> {
         Rpc::get_block(self, hash, fill_pow).await
     }
 
+    async fn get_block_by_height(&self, height: u64) -> Result<GetBlockByHeightResult> {
+        Rpc::get_block_by_height(self, height).await
+    }
+
     async fn get_transactions(&self, txs_hashes: &[String]) -> Result<GetTransactionsResult> {
         Rpc::get_transactions(self, txs_hashes).await
     }
@@ -350,6 +535,14 @@ impl MoneroRpc for Rpc {
         Rpc::get_transaction_pool_hashes(self).await
     }
 
+    async fn get_pool_transactions(&self, txs_hashes: &[String]) -> Result<Vec<PoolTx>> {
+        Rpc::get_pool_transactions(self, txs_hashes).await
+    }
+
+    async fn get_fee_estimate(&self) -> Result<FeeEstimate> {
+        Rpc::get_fee_estimate(self).await
+    }
+
     async fn probe_caps(&self) -> Capabilities {
         Rpc::probe_caps(self).await
     }
@@ -361,20 +554,6 @@ pub struct GetBlockHeaderByHeightResult {
     pub status: String,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-pub struct BlockHeader {
-    pub hash: String,
-    pub height: u64,
-    pub timestamp: u64,
-    pub prev_hash: String,
-    pub major_version: u32,
-    pub minor_version: u32,
-    pub nonce: u64,
-    pub reward: u64,
-    #[serde(default, alias = "block_size")]
-    pub size: u64,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct GetBlockResult {
     pub block_header: BlockHeader,
@@ -387,6 +566,51 @@ pub struct GetBlockResult {
     pub status: String,
 }
 
+/// [`GetBlockResult`] with the header left raw, so a daemon that omits
+/// header fields yields `None` from [`Self::header`] instead of a
+/// failed call.
+#[derive(Debug, Deserialize)]
+pub struct GetBlockByHeightResult {
+    #[serde(default)]
+    pub block_header: serde_json::Value,
+    #[serde(default)]
+    pub json: Option<String>,
+    #[serde(default)]
+    pub blob: Option<String>,
+    #[serde(default)]
+    pub miner_tx_hash: Option<String>,
+    pub status: String,
+}
+
+impl GetBlockByHeightResult {
+    /// The header, if it has every field [`BlockHeader`] needs.
+    pub fn header(&self) -> Option<BlockHeader> {
+        serde_json::from_value(self.block_header.clone()).ok()
+    }
+
+    /// The block under `block_header`: its own [`Self::header`], or one
+    /// fetched separately for the same height when that lacked fields. `None`
+    /// unless the response names the same hash or, naming none, carries a
+    /// blob that agrees with the header, since the daemon may have switched
+    /// blocks in between.
+    pub fn with_header(self, block_header: BlockHeader) -> Option<GetBlockResult> {
+        let same_block = match self.block_header.get("hash").and_then(|h| h.as_str()) {
+            Some(hash) => hash.eq_ignore_ascii_case(&block_header.hash.to_hex()),
+            None => self.blob.as_deref().is_some_and(|blob| {
+                crate::blob::parse_block_hex(blob)
+                    .is_ok_and(|parsed| parsed.check_header(&block_header).is_ok())
+            }),
+        };
+        same_block.then_some(GetBlockResult {
+            block_header,
+            json: self.json,
+            blob: self.blob,
+            miner_tx_hash: self.miner_tx_hash,
+            status: self.status,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetTransactionsResult {
     #[serde(default)]
@@ -396,9 +620,69 @@ pub struct GetTransactionsResult {
     pub status: String,
 }
 
+/// A pool tx with the daemon's relay bookkeeping. `get_transactions` does
+/// not report `do_not_relay`, so it is `None` for txs learned that way.
+#[derive(Debug, Clone)]
+pub struct PoolTx {
+    pub id_hash: String,
+    pub blob_size: u64,
+    pub fee: u64,
+    pub relayed: bool,
+    pub do_not_relay: Option<bool>,
+    pub double_spend_seen: bool,
+}
+
+/// One `txs` entry of `get_transactions`.
 #[derive(Debug, Deserialize)]
-pub struct GetBlockCountResult {
-    pub count: u64,
+struct PoolTxEntry {
+    tx_hash: String,
+    #[serde(default)]
+    as_hex: String,
+    #[serde(default)]
+    as_json: String,
+    #[serde(default)]
+    in_pool: bool,
+    #[serde(default)]
+    relayed: bool,
+    #[serde(default)]
+    double_spend_seen: bool,
+}
+
+impl From<PoolTxEntry> for PoolTx {
+    fn from(entry: PoolTxEntry) -> Self {
+        #[derive(Deserialize)]
+        struct Rct {
+            #[serde(rename = "txnFee", default)]
+            fee: u64,
+        }
+        #[derive(Deserialize)]
+        struct Body {
+            rct_signatures: Option<Rct>,
+        }
+
+        let fee = serde_json::from_str::<Body>(&entry.as_json)
+            .ok()
+            .and_then(|body| body.rct_signatures)
+            .map_or(0, |rct| rct.fee);
+        Self {
+            id_hash: entry.tx_hash,
+            blob_size: (entry.as_hex.len() / 2) as u64,
+            fee,
+            relayed: entry.relayed,
+            do_not_relay: None,
+            double_spend_seen: entry.double_spend_seen,
+        }
+    }
+}
+
+/// `get_fee_estimate`, in atomic units per byte. `fees` lists the four
+/// priority tiers on daemons that report them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FeeEstimate {
+    pub fee: u64,
+    #[serde(default)]
+    pub fees: Vec<u64>,
+    pub quantization_mask: u64,
     pub status: String,
 }
 
@@ -449,6 +733,50 @@ mod tests {
         mock.assert();
     }
 
+    #[tokio::test]
+    async fn pool_transactions_fetch_unpruned_bodies() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/get_transactions")
+                .json_body(json!({
+                    "txs_hashes": ["abcdef", "fedcba"],
+                    "decode_as_json": true,
+                    "prune": false,
+                }));
+            then.status(200).json_body(json!({
+                "status": "OK",
+                "txs": [{
+                    "tx_hash": "abcdef",
+                    "as_hex": "00".repeat(1500),
+                    "as_json": "{\"version\":2,\"rct_signatures\":{\"type\":6,\"txnFee\":30000000}}",
+                    "in_pool": true,
+                    "relayed": true,
+                    "double_spend_seen": true,
+                }, {
+                    "tx_hash": "fedcba",
+                    "as_hex": "00",
+                    "as_json": "{}",
+                    "in_pool": false,
+                }],
+            }));
+        });
+
+        let rpc = Rpc::new(format!("{}/json_rpc", server.url(""))).with_prune(true);
+        let pool = rpc
+            .get_pool_transactions(&["abcdef".to_string(), "fedcba".to_string()])
+            .await
+            .expect("pool success");
+
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool[0].id_hash, "abcdef");
+        assert_eq!(pool[0].blob_size, 1500);
+        assert_eq!(pool[0].fee, 30_000_000);
+        assert!(pool[0].relayed && pool[0].double_spend_seen);
+        assert_eq!(pool[0].do_not_relay, None);
+        mock.assert();
+    }
+
     #[tokio::test]
     async fn get_transactions_via_rest() {
         let server = MockServer::start();
@@ -482,6 +810,77 @@ mod tests {
         mock.assert();
     }
 
+    #[tokio::test]
+    async fn pruned_rpc_requests_pruned_transactions() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/get_transactions")
+                .json_body_partial(r#"{ "prune": true }"#);
+            then.status(200).json_body(json!({
+                "status": "OK",
+                "txs_as_json": [],
+                "missed_tx": ["deadbeef"],
+            }));
+        });
+
+        let rpc = Rpc::new(format!("{}/json_rpc", server.url(""))).with_prune(true);
+        let res = rpc
+            .get_transactions(&["deadbeef".to_string()])
+            .await
+            .expect("pruned get_transactions");
+
+        assert_eq!(res.missed_tx, vec!["deadbeef".to_string()]);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn oversized_response_is_rejected() {
+        let server = MockServer::start();
+        let _mock = server.mock(|when, then| {
+            when.method(POST).path("/get_transactions");
+            then.status(200).json_body(json!({
+                "status": "OK",
+                "txs_as_json": ["x".repeat(256)],
+                "missed_tx": [],
+            }));
+        });
+
+        let rpc = Rpc::new(format!("{}/json_rpc", server.url(""))).with_max_response_bytes(128);
+        let err = rpc
+            .get_transactions(&["deadbeef".to_string()])
+            .await
+            .unwrap_err();
+
+        assert!(err.is::<ResponseTooLarge>());
+    }
+
+    #[tokio::test]
+    async fn fee_estimate_reads_tiers_and_mask() {
+        let server = MockServer::start();
+        let rpc = Rpc::new(format!("{}/json_rpc", server.url("")));
+        let _mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/json_rpc")
+                .json_body_partial(r#"{"method": "get_fee_estimate"}"#);
+            then.status(200).json_body(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "status": "OK",
+                    "fee": 20000,
+                    "fees": [20000, 80000, 320000, 4000000],
+                    "quantization_mask": 10000,
+                },
+            }));
+        });
+
+        let estimate = rpc.get_fee_estimate().await.unwrap();
+        assert_eq!(estimate.fee, 20_000);
+        assert_eq!(estimate.fees, vec![20_000, 80_000, 320_000, 4_000_000]);
+        assert_eq!(estimate.quantization_mask, 10_000);
+    }
+
     #[tokio::test]
     async fn probe_caps_detects_range_and_bin() {
         let server = MockServer::start();
@@ -0,0 +1,204 @@
+//! Schema drift detection. The migrations in `db/migrations` are embedded at
+//! build time; preflight compares them with `_sqlx_migrations` so an ingestor
+//! started against an unmigrated (or hand-edited) database names the exact
+//! migrations to apply instead of failing on a missing column mid-pipeline.
+
+use std::{collections::BTreeMap, fmt};
+
+use anyhow::Result;
+use sqlx::{
+    migrate::{Migration, Migrator},
+    PgPool,
+};
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("../db/migrations");
+
+/// A row of `_sqlx_migrations`.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub checksum: Vec<u8>,
+    pub success: bool,
+}
+
+/// How the live schema differs from the embedded migrations.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Drift {
+    /// Embedded migrations the database has not applied.
+    pub pending: Vec<String>,
+    /// Applied migrations whose file has changed since.
+    pub modified: Vec<String>,
+    /// Migrations recorded as failed part-way.
+    pub failed: Vec<String>,
+    /// Applied versions this build does not know, from a newer release.
+    pub unknown: Vec<String>,
+}
+
+fn label(version: i64, description: &str) -> String {
+    format!("{version:04} {description}")
+}
+
+impl Drift {
+    pub fn compare(embedded: &[Migration], applied: &[AppliedMigration]) -> Self {
+        let applied: BTreeMap<i64, &AppliedMigration> =
+            applied.iter().map(|m| (m.version, m)).collect();
+        let mut drift = Drift::default();
+        for migration in embedded
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration())
+        {
+            let name = label(migration.version, &migration.description);
+            match applied.get(&migration.version) {
+                None => drift.pending.push(name),
+                Some(row) if !row.success => drift.failed.push(name),
+                Some(row) if *row.checksum != *migration.checksum => drift.modified.push(name),
+                Some(_) => {}
+            }
+        }
+        drift.unknown = applied
+            .values()
+            .filter(|row| !embedded.iter().any(|m| m.version == row.version))
+            .map(|row| label(row.version, &row.description))
+            .collect();
+        drift
+    }
+
+    /// Whether ingestion must not start. Unknown newer migrations are
+    /// reported but tolerated, since migrations only add to the schema.
+    pub fn is_blocking(&self) -> bool {
+        !(self.pending.is_empty() && self.modified.is_empty() && self.failed.is_empty())
+    }
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if !self.pending.is_empty() {
+            parts.push(format!(
+                "{} pending migration(s) ({}); run `make migrate`",
+                self.pending.len(),
+                self.pending.join(", ")
+            ));
+        }
+        if !self.failed.is_empty() {
+            parts.push(format!(
+                "migration(s) marked failed in _sqlx_migrations ({}); fix and rerun `make migrate`",
+                self.failed.join(", ")
+            ));
+        }
+        if !self.modified.is_empty() {
+            parts.push(format!(
+                "applied migration(s) changed since they ran ({}); restore the files or migrate a fresh database",
+                self.modified.join(", ")
+            ));
+        }
+        if !self.unknown.is_empty() {
+            parts.push(format!(
+                "database has migration(s) newer than this build ({})",
+                self.unknown.join(", ")
+            ));
+        }
+        if parts.is_empty() {
+            return f.write_str("up to date");
+        }
+        f.write_str(&parts.join("; "))
+    }
+}
+
+/// Compares the database behind `pool` with [`MIGRATOR`].
+pub async fn check(pool: &PgPool) -> Result<Drift> {
+    let has_table: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    let applied: Vec<AppliedMigration> = if has_table {
+        sqlx::query_as(
+            "SELECT version, description, checksum, success FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(pool)
+        .await?
+    } else {
+        Vec::new()
+    };
+    Ok(Drift::compare(MIGRATOR.migrations.as_ref(), &applied))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use sqlx::migrate::MigrationType;
+
+    use super::*;
+
+    fn migration(version: i64, sql: &'static str) -> Migration {
+        Migration::new(
+            version,
+            Cow::Owned(format!("step {version}")),
+            MigrationType::Simple,
+            Cow::Borrowed(sql),
+        )
+    }
+
+    fn applied(m: &Migration, success: bool) -> AppliedMigration {
+        AppliedMigration {
+            version: m.version,
+            description: m.description.to_string(),
+            checksum: m.checksum.to_vec(),
+            success,
+        }
+    }
+
+    #[test]
+    fn matching_history_is_up_to_date() {
+        let embedded = [migration(1, "a"), migration(2, "b")];
+        let rows: Vec<_> = embedded.iter().map(|m| applied(m, true)).collect();
+        let drift = Drift::compare(&embedded, &rows);
+        assert!(!drift.is_blocking());
+        assert_eq!(drift.to_string(), "up to date");
+    }
+
+    #[test]
+    fn drift_names_each_offending_migration() {
+        let embedded = [
+            migration(1, "a"),
+            migration(2, "b"),
+            migration(3, "c"),
+            migration(4, "d"),
+        ];
+        let mut edited = applied(&embedded[1], true);
+        edited.checksum = vec![0; 48];
+        let rows = vec![
+            applied(&embedded[0], true),
+            edited,
+            applied(&embedded[2], false),
+        ];
+        let drift = Drift::compare(&embedded, &rows);
+        assert!(drift.is_blocking());
+        assert_eq!(drift.pending, vec!["0004 step 4"]);
+        assert_eq!(drift.modified, vec!["0002 step 2"]);
+        assert_eq!(drift.failed, vec!["0003 step 3"]);
+        assert!(drift.to_string().contains("run `make migrate`"));
+    }
+
+    #[test]
+    fn newer_database_is_reported_but_not_blocking() {
+        let embedded = [migration(1, "a")];
+        let newer = migration(2, "b");
+        let rows = vec![applied(&embedded[0], true), applied(&newer, true)];
+        let drift = Drift::compare(&embedded, &rows);
+        assert!(!drift.is_blocking());
+        assert_eq!(drift.unknown, vec!["0002 step 2"]);
+    }
+
+    #[tokio::test]
+    async fn migrated_test_database_has_no_drift() -> Result<()> {
+        let Some(db) = crate::testing::TestDb::start().await? else {
+            eprintln!("skipping migrated_test_database_has_no_drift: no database available");
+            return Ok(());
+        };
+        let drift = check(&db.pool).await?;
+        assert!(!drift.is_blocking(), "{drift}");
+        Ok(())
+    }
+}
@@ -1,11 +1,44 @@
 use anyhow::Result;
+use bex_core::{BlockHash, KeyImage, TxHash};
 use sqlx::{postgres::PgQueryResult, PgPool, Postgres, Row, Transaction};
 
+use crate::rpc::{FeeEstimate, PoolTx};
+
+/// Result of writing a block or transaction row that may already exist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpsertOutcome {
+    Inserted,
+    Unchanged,
+    /// A previously unconfirmed row was promoted with block data.
+    Updated,
+    /// A confirmed row differed from the incoming data and was overwritten.
+    Conflict,
+}
+
 #[derive(Clone)]
 pub struct Store {
     pool: PgPool,
 }
 
+/// A block's recorded emission, as the schedule check reads it back.
+pub struct RecordedEmission {
+    pub height: i64,
+    pub hash: BlockHash,
+    pub size: u64,
+    pub major_version: u32,
+    pub base_reward: i64,
+    pub fees: i64,
+    /// Coins emitted before this block.
+    pub generated: u64,
+}
+
+/// A running supply read back as text. The daemon stops counting at
+/// MONEY_SUPPLY once tail emission starts, so larger totals saturate.
+fn supply_u64(text: &str) -> u64 {
+    text.parse::<u128>()
+        .map_or(u64::MAX, |n| n.min(u64::MAX as u128) as u64)
+}
+
 impl Store {
     pub async fn connect(db_url: &str) -> Result<Self> {
         let pool = PgPool::connect(db_url).await?;
@@ -20,11 +53,29 @@ impl Store {
         Ok(self.pool.begin().await?)
     }
 
+    /// Stores the header difficulty of the block at `height`, kept out of
+    /// [`Store::insert_block`]'s conflict check since older rows lack it.
+    pub async fn set_block_difficulty(
+        tx: &mut Transaction<'_, Postgres>,
+        height: i64,
+        difficulty: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE public.blocks SET difficulty = $2
+             WHERE height = $1 AND difficulty IS DISTINCT FROM $2",
+        )
+        .bind(height)
+        .bind(difficulty)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
     pub async fn insert_block(
         tx: &mut Transaction<'_, Postgres>,
         height: i64,
-        hash: &[u8],
-        prev_hash: &[u8],
+        hash: &BlockHash,
+        prev_hash: &BlockHash,
         ts: i64,
         size_bytes: i32,
         major: i32,
@@ -32,12 +83,13 @@ impl Store {
         nonce: i64,
         tx_count: i32,
         reward_nanos: i64,
-    ) -> Result<PgQueryResult> {
-        sqlx::query(
+    ) -> Result<UpsertOutcome> {
+        let differs: Option<bool> = sqlx::query_scalar(
             r#"
-INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos)
-VALUES ($1, $2, $3, to_timestamp($4), $5, $6, $7, $8, $9, $10)
-ON CONFLICT DO NOTHING
+SELECT (hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos)
+       IS DISTINCT FROM ($2::bytea, $3::bytea, to_timestamp($4), $5::int, $6::int, $7::int, $8::bigint, $9::int, $10::bigint)
+FROM public.blocks WHERE height = $1
+FOR UPDATE
 "#,
         )
         .bind(height)
@@ -50,14 +102,50 @@ ON CONFLICT DO NOTHING
         .bind(nonce)
         .bind(tx_count)
         .bind(reward_nanos)
-        .execute(&mut **tx)
-        .await
-        .map_err(Into::into)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let (sql, outcome) = match differs {
+            None => (
+                r#"
+INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos)
+VALUES ($1, $2, $3, to_timestamp($4), $5, $6, $7, $8, $9, $10)
+ON CONFLICT DO NOTHING
+"#,
+                UpsertOutcome::Inserted,
+            ),
+            Some(false) => return Ok(UpsertOutcome::Unchanged),
+            Some(true) => (
+                r#"
+UPDATE public.blocks
+SET hash = $2, prev_hash = $3, block_timestamp = to_timestamp($4), size_bytes = $5,
+    major_version = $6, minor_version = $7, nonce = $8, tx_count = $9, reward_nanos = $10,
+    analytics_pending = TRUE
+WHERE height = $1
+"#,
+                UpsertOutcome::Conflict,
+            ),
+        };
+
+        sqlx::query(sql)
+            .bind(height)
+            .bind(hash)
+            .bind(prev_hash)
+            .bind(ts)
+            .bind(size_bytes)
+            .bind(major)
+            .bind(minor)
+            .bind(nonce)
+            .bind(tx_count)
+            .bind(reward_nanos)
+            .execute(&mut **tx)
+            .await?;
+        Ok(outcome)
     }
 
     pub async fn insert_tx(
         tx: &mut Transaction<'_, Postgres>,
-        tx_hash: &[u8],
+        tx_hash: &TxHash,
         block_height: Option<i64>,
         block_ts: Option<i64>,
         in_mempool: bool,
@@ -71,13 +159,19 @@ ON CONFLICT DO NOTHING
         bp_plus: bool,
         num_inputs: i32,
         num_outputs: i32,
-    ) -> Result<PgQueryResult> {
-        sqlx::query(
+        nonstandard_reasons: Option<&[&str]>,
+    ) -> Result<UpsertOutcome> {
+        let existing = sqlx::query(
             r#"
-INSERT INTO public.txs
-(tx_hash, block_height, block_timestamp, in_mempool, fee_nanos, size_bytes, version, unlock_time, extra, rct_type, proof_type, bp_plus, num_inputs, num_outputs)
-VALUES ($1, $2, CASE WHEN $3 IS NULL THEN NULL ELSE to_timestamp($3) END, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
-ON CONFLICT DO NOTHING
+SELECT block_height IS NULL AS unconfirmed,
+       (block_height, block_timestamp, in_mempool, fee_nanos, size_bytes, version, unlock_time, extra,
+        rct_type, proof_type, bp_plus, num_inputs, num_outputs, COALESCE(nonstandard_reasons, $15::text[]))
+       IS DISTINCT FROM
+       ($2::bigint, CASE WHEN $3::bigint IS NULL THEN NULL ELSE to_timestamp($3) END, $4::boolean, $5::bigint,
+        $6::int, $7::int, $8::bigint, $9::jsonb, $10::int, $11::text, $12::boolean, $13::int, $14::int,
+        COALESCE($15::text[], nonstandard_reasons)) AS differs
+FROM public.txs WHERE tx_hash = $1
+FOR UPDATE
 "#,
         )
         .bind(tx_hash)
@@ -94,16 +188,72 @@ ON CONFLICT DO NOTHING
         .bind(bp_plus)
         .bind(num_inputs)
         .bind(num_outputs)
-        .execute(&mut **tx)
-        .await
-        .map_err(Into::into)
+        .bind(nonstandard_reasons)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let (sql, outcome) = match existing {
+            None => (
+                r#"
+INSERT INTO public.txs
+(tx_hash, block_height, block_timestamp, in_mempool, fee_nanos, size_bytes, version, unlock_time, extra, rct_type, proof_type, bp_plus, num_inputs, num_outputs,
+ nonstandard, nonstandard_reasons)
+VALUES ($1, $2, CASE WHEN $3 IS NULL THEN NULL ELSE to_timestamp($3) END, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14,
+        cardinality($15::text[]) > 0, $15)
+ON CONFLICT DO NOTHING
+"#,
+                UpsertOutcome::Inserted,
+            ),
+            Some(row) => {
+                if !row.try_get::<bool, _>("differs")? {
+                    return Ok(UpsertOutcome::Unchanged);
+                }
+                let outcome = if row.try_get::<bool, _>("unconfirmed")? {
+                    UpsertOutcome::Updated
+                } else {
+                    UpsertOutcome::Conflict
+                };
+                (
+                    r#"
+UPDATE public.txs
+SET block_height = $2, block_timestamp = CASE WHEN $3 IS NULL THEN NULL ELSE to_timestamp($3) END,
+    in_mempool = $4, fee_nanos = $5, size_bytes = $6, version = $7, unlock_time = $8, extra = $9,
+    rct_type = $10, proof_type = $11, bp_plus = $12, num_inputs = $13, num_outputs = $14,
+    nonstandard = COALESCE(cardinality($15::text[]) > 0, nonstandard),
+    nonstandard_reasons = COALESCE($15, nonstandard_reasons), orphaned_at = NULL
+WHERE tx_hash = $1
+"#,
+                    outcome,
+                )
+            }
+        };
+
+        sqlx::query(sql)
+            .bind(tx_hash)
+            .bind(block_height)
+            .bind(block_ts)
+            .bind(in_mempool)
+            .bind(fee_nanos)
+            .bind(size_bytes)
+            .bind(version)
+            .bind(unlock_time)
+            .bind(extra)
+            .bind(rct_type)
+            .bind(proof_type)
+            .bind(bp_plus)
+            .bind(num_inputs)
+            .bind(num_outputs)
+            .bind(nonstandard_reasons)
+            .execute(&mut **tx)
+            .await?;
+        Ok(outcome)
     }
 
     pub async fn insert_input(
         tx: &mut Transaction<'_, Postgres>,
-        tx_hash: &[u8],
+        tx_hash: &TxHash,
         idx: i32,
-        key_image: &[u8],
+        key_image: &KeyImage,
         ring_size: i32,
         pseudo_out: Option<&[u8]>,
     ) -> Result<PgQueryResult> {
@@ -126,7 +276,7 @@ ON CONFLICT (tx_hash, idx) DO NOTHING
 
     pub async fn insert_output(
         tx: &mut Transaction<'_, Postgres>,
-        tx_hash: &[u8],
+        tx_hash: &TxHash,
         idx_in_tx: i32,
         commitment: &[u8],
         amount: Option<i64>,
@@ -154,9 +304,10 @@ ON CONFLICT (tx_hash, idx_in_tx) DO NOTHING
     pub async fn record_tip(
         tx: &mut Transaction<'_, Postgres>,
         height: i64,
-        hash: &[u8],
-        prev_hash: &[u8],
+        hash: &BlockHash,
+        prev_hash: &BlockHash,
     ) -> Result<()> {
+        let (hash, prev_hash) = (hash.as_ref(), prev_hash.as_ref());
         sqlx::query!(
             r#"INSERT INTO public.chain_tips (height, hash, prev_hash) VALUES ($1,$2,$3)
                ON CONFLICT (height) DO UPDATE SET hash = EXCLUDED.hash, prev_hash = EXCLUDED.prev_hash"#,
@@ -166,6 +317,69 @@ ON CONFLICT (tx_hash, idx_in_tx) DO NOTHING
         )
         .execute(&mut **tx)
         .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO public.current_tip (id, height, hash, prev_hash, updated_at) VALUES (1,$1,$2,$3,NOW())
+               ON CONFLICT (id) DO UPDATE SET height = EXCLUDED.height, hash = EXCLUDED.hash,
+                 prev_hash = EXCLUDED.prev_hash, updated_at = NOW()"#,
+            height,
+            hash,
+            prev_hash
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn prune_chain_tips(
+        tx: &mut Transaction<'_, Postgres>,
+        keep_from_height: i64,
+    ) -> Result<u64> {
+        let res = sqlx::query!(
+            "DELETE FROM public.chain_tips WHERE height < $1",
+            keep_from_height
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Drops completed `block_ingest_state` markers below `keep_from_height`;
+    /// in-progress ones are kept until their block is persisted again.
+    pub async fn prune_block_ingest_state(
+        tx: &mut Transaction<'_, Postgres>,
+        keep_from_height: i64,
+    ) -> Result<u64> {
+        let res = sqlx::query!(
+            "DELETE FROM public.block_ingest_state WHERE height < $1 AND state = 'complete'",
+            keep_from_height
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    pub async fn reset_current_tip(
+        tx: &mut Transaction<'_, Postgres>,
+        fork_height: i64,
+    ) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM public.current_tip WHERE height >= $1",
+            fork_height
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO public.current_tip (id, height, hash, prev_hash, updated_at)
+               SELECT 1, height, hash, prev_hash, NOW() FROM public.blocks
+               WHERE height < $1 ORDER BY height DESC LIMIT 1
+               ON CONFLICT (id) DO UPDATE SET height = EXCLUDED.height, hash = EXCLUDED.hash,
+                 prev_hash = EXCLUDED.prev_hash, updated_at = NOW()"#,
+            fork_height
+        )
+        .execute(&mut **tx)
+        .await?;
         Ok(())
     }
 
@@ -180,6 +394,7 @@ WITH per_tx AS (
     COALESCE(fee_nanos,0) AS fee,
     NULLIF(size_bytes,0) AS size,
     num_inputs,
+    nonstandard,
     (CASE WHEN size_bytes>0 THEN COALESCE(fee_nanos,0)::numeric / size_bytes::numeric ELSE NULL END) AS fee_rate
   FROM public.txs WHERE block_height = $1
 ),
@@ -187,13 +402,17 @@ aggs AS (
   SELECT
     SUM(fee)::bigint AS total_fee,
     AVG(NULLIF(num_inputs,0))::double precision AS avg_inputs,
-    (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY fee_rate))::double precision AS median_fee_rate
+    (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY fee_rate))::double precision AS median_fee_rate,
+    -- Unknown while any tx predates the compliance check.
+    CASE WHEN bool_and(nonstandard IS NOT NULL) IS NOT FALSE
+         THEN (COUNT(*) FILTER (WHERE nonstandard))::int END AS nonstandard_count
   FROM per_tx
 )
 SELECT
   COALESCE(total_fee,0)::bigint AS total_fee,
   COALESCE(avg_inputs,0::double precision) AS avg_inputs,
-  COALESCE(median_fee_rate,0::double precision) AS median_fee_rate
+  COALESCE(median_fee_rate,0::double precision) AS median_fee_rate,
+  nonstandard_count
 FROM aggs
 "#,
             height
@@ -215,17 +434,19 @@ FROM aggs
         sqlx::query!(
             r#"
 INSERT INTO public.soft_facts
-(block_height, block_timestamp, total_fee, avg_ring_size, median_fee_rate, bp_total_bytes, clsag_count)
-SELECT b.height, b.block_timestamp, $2, ($3)::double precision, ($4)::double precision, $5, $6 FROM public.blocks b WHERE b.height = $1
+(block_height, block_timestamp, total_fee, avg_ring_size, median_fee_rate, bp_total_bytes, clsag_count, nonstandard_count)
+SELECT b.height, b.block_timestamp, $2, ($3)::double precision, ($4)::double precision, $5, $6, $7 FROM public.blocks b WHERE b.height = $1
 ON CONFLICT (block_height) DO UPDATE
-  SET total_fee=$2, avg_ring_size=($3)::double precision, median_fee_rate=($4)::double precision, bp_total_bytes=$5, clsag_count=$6
+  SET total_fee=$2, avg_ring_size=($3)::double precision, median_fee_rate=($4)::double precision, bp_total_bytes=$5, clsag_count=$6,
+      nonstandard_count=$7
 "#,
             height,
             rec.total_fee,
             rec.avg_inputs,
             rec.median_fee_rate,
             bp_total_bytes,
-            clsag_count
+            clsag_count,
+            rec.nonstandard_count
         )
         .execute(&mut **tx)
         .await?;
@@ -238,6 +459,17 @@ ON CONFLICT (block_height) DO UPDATE
         Ok(())
     }
 
+    pub async fn mark_analytics_pending(
+        tx: &mut Transaction<'_, Postgres>,
+        height: i64,
+    ) -> Result<PgQueryResult> {
+        sqlx::query("UPDATE public.blocks SET analytics_pending = TRUE WHERE height = $1")
+            .bind(height)
+            .execute(&mut **tx)
+            .await
+            .map_err(Into::into)
+    }
+
     pub async fn update_block_confirmations_tx(
         tx: &mut Transaction<'_, Postgres>,
         height: i64,
@@ -255,14 +487,18 @@ ON CONFLICT (block_height) DO UPDATE
         Ok(())
     }
 
+    /// Rewrites confirmations across `start_height..=tip_height`, finalizes
+    /// any older block still pending and returns every block that turned
+    /// final, in height order.
     pub async fn refresh_confirmations(
         &self,
         start_height: i64,
         tip_height: i64,
         finalized_height: i64,
-    ) -> Result<()> {
+    ) -> Result<Vec<(i64, BlockHash)>> {
         let start = start_height.min(tip_height).max(0);
-        sqlx::query!(
+        // Joining the table to itself exposes each row's pre-update value.
+        let mut flipped = sqlx::query!(
             r#"
 WITH params AS (
   SELECT $1::bigint AS start_h, $2::bigint AS tip_h, $3::bigint AS finalized_h
@@ -270,163 +506,1739 @@ WITH params AS (
 UPDATE public.blocks AS b
 SET confirmations = GREATEST(params.tip_h - b.height + 1, 0),
     is_final = b.height <= params.finalized_h
-FROM params
+FROM params, public.blocks AS old
 WHERE b.height BETWEEN params.start_h AND params.tip_h
+  AND old.height = b.height
+RETURNING b.height, b.hash AS "hash: BlockHash", NOT old.is_final AND b.is_final AS "flipped!"
 "#,
             start,
             tip_height,
             finalized_height
         )
-        .execute(self.pool())
-        .await?;
+        .fetch_all(self.pool())
+        .await?
+        .into_iter()
+        .filter(|row| row.flipped)
+        .map(|row| (row.height, row.hash))
+        .collect::<Vec<_>>();
 
-        sqlx::query!(
-            "UPDATE public.blocks SET is_final = true WHERE height <= $1 AND is_final = false",
+        let behind = sqlx::query!(
+            r#"UPDATE public.blocks SET is_final = true WHERE height <= $1 AND is_final = false
+               RETURNING height, hash AS "hash: BlockHash""#,
             finalized_height
         )
-        .execute(self.pool())
+        .fetch_all(self.pool())
         .await?;
+        flipped.extend(behind.into_iter().map(|row| (row.height, row.hash)));
+        flipped.sort_unstable_by_key(|(height, _)| *height);
 
-        Ok(())
+        Ok(flipped)
     }
 
-    pub async fn block_hash_at(&self, height: i64) -> Result<Option<Vec<u8>>> {
-        let rec = sqlx::query!("SELECT hash FROM public.blocks WHERE height=$1", height)
-            .fetch_optional(self.pool())
-            .await?;
+    /// Hashes of the txs stored at `heights`, in block order.
+    pub async fn block_tx_hashes(&self, heights: &[i64]) -> Result<Vec<(i64, TxHash)>> {
+        let rows = sqlx::query!(
+            r#"
+SELECT block_height AS "height!", tx_hash AS "hash: TxHash"
+FROM public.txs
+WHERE block_height = ANY($1)
+ORDER BY block_height, block_position NULLS LAST, tx_hash
+"#,
+            heights
+        )
+        .fetch_all(self.pool())
+        .await?;
+        Ok(rows.into_iter().map(|row| (row.height, row.hash)).collect())
+    }
+
+    pub async fn block_hash_at(&self, height: i64) -> Result<Option<BlockHash>> {
+        let rec = sqlx::query!(
+            r#"SELECT hash AS "hash: BlockHash" FROM public.blocks WHERE height=$1"#,
+            height
+        )
+        .fetch_optional(self.pool())
+        .await?;
         Ok(rec.map(|r| r.hash))
     }
 
-    pub async fn evict_mempool_on_inclusion(
-        tx: &mut Transaction<'_, Postgres>,
-        included_hashes_hex: &[String],
-    ) -> Result<PgQueryResult> {
-        for hash in included_hashes_hex {
-            let _ = sqlx::query("DELETE FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')")
-                .bind(hash)
-                .execute(&mut **tx)
-                .await?;
-        }
+    /// Stores the current value of each counter series, replacing what the
+    /// last snapshot saved.
+    pub async fn save_ingest_counters(&self, series: &[(String, i64)]) -> Result<()> {
+        let (names, values): (Vec<&str>, Vec<i64>) = series
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+            .unzip();
+        sqlx::query(
+            r#"
+INSERT INTO public.ingest_counters (name, value)
+SELECT * FROM UNNEST($1::text[], $2::bigint[])
+ON CONFLICT (name) DO UPDATE
+SET value = EXCLUDED.value, updated_at = NOW()
+"#,
+        )
+        .bind(&names)
+        .bind(&values)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
 
-        Ok(PgQueryResult::default())
+    pub async fn ingest_counters(&self) -> Result<Vec<(String, i64)>> {
+        Ok(
+            sqlx::query_as("SELECT name, value FROM public.ingest_counters")
+                .fetch_all(self.pool())
+                .await?,
+        )
     }
 
-    pub async fn requeue_mempool_from_block(
-        tx: &mut Transaction<'_, Postgres>,
-        block_height: i64,
-    ) -> Result<()> {
-        let rows = sqlx::query(
-            "SELECT encode(tx_hash, 'hex') AS h FROM public.txs WHERE block_height = $1",
+    /// Records whether this run ingests pruned transactions and returns
+    /// whether the dataset is pruned. A pruned run marks it for good: the txs
+    /// it stored lack prunable data even if later runs fetch full ones.
+    pub async fn record_pruned(&self, pruned: bool) -> Result<bool> {
+        sqlx::query(
+            r#"
+INSERT INTO public.dataset_metadata (key, value)
+VALUES ('pruned', $1::text)
+ON CONFLICT (key) DO UPDATE
+SET value = 'true', updated_at = NOW()
+WHERE EXCLUDED.value = 'true' AND public.dataset_metadata.value <> 'true'
+"#,
         )
-        .bind(block_height)
-        .fetch_all(&mut **tx)
+        .bind(pruned.to_string())
+        .execute(self.pool())
         .await?;
-
-        for row in rows {
-            if let Ok(hash) = row.try_get::<String, _>("h") {
-                sqlx::query(
-                    r#"INSERT INTO public.mempool_txs (tx_hash, first_seen, last_seen)
-                       VALUES (decode($1,'hex'), NOW(), NOW())
-                       ON CONFLICT (tx_hash) DO UPDATE SET last_seen = NOW()"#,
-                )
-                .bind(&hash)
-                .execute(&mut **tx)
+        let value: String =
+            sqlx::query_scalar("SELECT value FROM public.dataset_metadata WHERE key = 'pruned'")
+                .fetch_one(self.pool())
                 .await?;
-            }
-        }
+        Ok(value == "true")
+    }
 
+    /// Stores the finality window this run uses; the API derives `is_final`
+    /// from it and the tip instead of waiting for the stored column.
+    pub async fn record_finality_window(&self, window: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO public.dataset_metadata (key, value)
+VALUES ('finality_window', $1::text)
+ON CONFLICT (key) DO UPDATE
+SET value = EXCLUDED.value, updated_at = NOW()
+WHERE public.dataset_metadata.value <> EXCLUDED.value
+"#,
+        )
+        .bind(window.to_string())
+        .execute(self.pool())
+        .await?;
         Ok(())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::Store;
-    use anyhow::Result;
-    use sqlx::{migrate::Migrator, PgPool};
 
-    // Embed workspace migrations so tests stay aligned with the live schema.
-    static MIGRATOR: Migrator = sqlx::migrate!("../db/migrations");
-
-    async fn setup_pool() -> Result<Option<PgPool>> {
-        let database_url = match std::env::var("DATABASE_URL") {
-            Ok(url) => url,
-            Err(_) => return Ok(None),
-        };
-
-        let pool = PgPool::connect(&database_url).await?;
-        MIGRATOR.run(&pool).await?;
-        Ok(Some(pool))
+    /// Stores the daemon's raw tip for `GET /api/v1/status`, which can run
+    /// ahead of `current_tip` when `--tip-confirmations` holds blocks back.
+    pub async fn record_daemon_tip(&self, height: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO public.dataset_metadata (key, value)
+VALUES ('daemon_tip', $1::text)
+ON CONFLICT (key) DO UPDATE
+SET value = EXCLUDED.value, updated_at = NOW()
+"#,
+        )
+        .bind(height.to_string())
+        .execute(self.pool())
+        .await?;
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn evict_mempool_removes_included_transactions() -> Result<()> {
-        let Some(pool) = setup_pool().await? else {
-            eprintln!("skipping evict_mempool_removes_included_transactions: DATABASE_URL not set");
-            return Ok(());
-        };
-
-        let mut tx = pool.begin().await?;
-        let hash = "01".repeat(32);
-
+    /// Replaces the daemon fee estimate served by `GET /api/v1/fees/base`.
+    pub async fn record_fee_estimate(&self, estimate: &FeeEstimate) -> Result<()> {
+        let to_i64 = |fee: u64| i64::try_from(fee).unwrap_or(i64::MAX);
+        let fees: Vec<i64> = estimate.fees.iter().copied().map(to_i64).collect();
         sqlx::query(
-            r#"INSERT INTO public.mempool_txs (tx_hash, first_seen, last_seen)
-               VALUES (decode($1,'hex'), NOW(), NOW())"#,
+            r#"
+INSERT INTO public.fee_estimate (id, fee_per_byte, fees, quantization_mask, updated_at)
+VALUES (1, $1, $2, $3, NOW())
+ON CONFLICT (id) DO UPDATE
+SET fee_per_byte = EXCLUDED.fee_per_byte,
+    fees = EXCLUDED.fees,
+    quantization_mask = EXCLUDED.quantization_mask,
+    updated_at = NOW()
+"#,
         )
-        .bind(&hash)
-        .execute(&mut *tx)
+        .bind(to_i64(estimate.fee))
+        .bind(&fees)
+        .bind(to_i64(estimate.quantization_mask))
+        .execute(self.pool())
         .await?;
+        Ok(())
+    }
 
-        Store::evict_mempool_on_inclusion(&mut tx, &[hash.clone()]).await?;
+    /// Coins emitted below `height`, if every lower height has an emission row.
+    pub async fn supply_before(&self, height: i64) -> Result<Option<u64>> {
+        if height == 0 {
+            return Ok(Some(0));
+        }
+        let before: Option<String> = sqlx::query_scalar(
+            "SELECT cumulative::text FROM public.block_emission WHERE height = $1 - 1",
+        )
+        .bind(height)
+        .fetch_optional(self.pool())
+        .await?;
+        Ok(before.map(|v| {
+            v.parse::<u128>()
+                .map_or(u64::MAX, |n| n.min(u64::MAX as u128) as u64)
+        }))
+    }
 
-        let remaining: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')",
+    /// Stored `txs` columns of the transactions at `height`, keyed like the
+    /// persistence stage's prepared rows, for comparison by `trace-block`.
+    pub async fn stored_block_txs(&self, height: i64) -> Result<Vec<(TxHash, serde_json::Value)>> {
+        let rows = sqlx::query(
+            r#"
+SELECT tx_hash, jsonb_build_object(
+  'fee', fee_nanos, 'size_bytes', size_bytes, 'version', version,
+  'unlock_time', unlock_time, 'extra', extra, 'rct_type', rct_type,
+  'proof_type', proof_type, 'bp_plus', bp_plus, 'num_inputs', num_inputs,
+  'num_outputs', num_outputs)
+  || CASE WHEN nonstandard_reasons IS NULL THEN '{}'::jsonb
+          ELSE jsonb_build_object('nonstandard_reasons', nonstandard_reasons) END AS cols
+FROM public.txs WHERE block_height = $1
+ORDER BY block_position NULLS LAST, tx_hash
+"#,
         )
-        .bind(&hash)
-        .fetch_one(&mut *tx)
+        .bind(height)
+        .fetch_all(self.pool())
         .await?;
+        rows.iter()
+            .map(|row| Ok((row.try_get("tx_hash")?, row.try_get("cols")?)))
+            .collect()
+    }
 
-        assert_eq!(remaining, 0);
-        tx.rollback().await?;
-        Ok(())
+    pub async fn upsert_mempool_hashes(
+        tx: &mut Transaction<'_, Postgres>,
+        hashes_hex: &[String],
+    ) -> Result<PgQueryResult> {
+        sqlx::query(
+            r#"
+INSERT INTO public.mempool_txs (tx_hash)
+SELECT DISTINCT decode(h, 'hex') FROM UNNEST($1::text[]) AS t(h)
+ON CONFLICT (tx_hash) DO UPDATE SET last_seen = NOW(), mined_height = NULL
+"#,
+        )
+        .bind(hashes_hex)
+        .execute(&mut **tx)
+        .await
+        .map_err(Into::into)
     }
 
-    #[tokio::test]
-    async fn requeue_mempool_inserts_transactions() -> Result<()> {
-        let Some(pool) = setup_pool().await? else {
-            eprintln!("skipping requeue_mempool_inserts_transactions: DATABASE_URL not set");
-            return Ok(());
-        };
+    /// Like [`Store::upsert_mempool_hashes`], also refreshing the daemon's
+    /// relay flags, the blob size and the fee rate (atomic units per byte).
+    /// An unknown `do_not_relay` keeps the stored flag.
+    pub async fn upsert_mempool_pool(
+        tx: &mut Transaction<'_, Postgres>,
+        entries: &[PoolTx],
+    ) -> Result<PgQueryResult> {
+        let hashes: Vec<&str> = entries.iter().map(|e| e.id_hash.as_str()).collect();
+        let sizes: Vec<i32> = entries
+            .iter()
+            .map(|e| i32::try_from(e.blob_size).unwrap_or(i32::MAX))
+            .collect();
+        let fees: Vec<i64> = entries
+            .iter()
+            .map(|e| i64::try_from(e.fee).unwrap_or(i64::MAX))
+            .collect();
+        let relayed: Vec<bool> = entries.iter().map(|e| e.relayed).collect();
+        let do_not_relay: Vec<Option<bool>> = entries.iter().map(|e| e.do_not_relay).collect();
+        let double_spend: Vec<bool> = entries.iter().map(|e| e.double_spend_seen).collect();
 
-        let mut tx = pool.begin().await?;
-        let hash = "02".repeat(32);
-        let block_height = 42_i64;
+        sqlx::query(
+            r#"
+INSERT INTO public.mempool_txs
+  (tx_hash, size_bytes, fee_rate, relayed, do_not_relay, double_spend_seen)
+SELECT DISTINCT ON (h)
+  decode(h, 'hex'),
+  NULLIF(sz, 0),
+  CASE WHEN sz > 0 THEN fee::numeric / sz END,
+  r, dnr, ds
+FROM UNNEST($1::text[], $2::int[], $3::int8[], $4::bool[], $5::bool[], $6::bool[])
+  AS t(h, sz, fee, r, dnr, ds)
+ON CONFLICT (tx_hash) DO UPDATE
+SET last_seen = NOW(),
+    mined_height = NULL,
+    size_bytes = EXCLUDED.size_bytes,
+    fee_rate = EXCLUDED.fee_rate,
+    relayed = EXCLUDED.relayed,
+    do_not_relay = COALESCE(EXCLUDED.do_not_relay, mempool_txs.do_not_relay),
+    double_spend_seen = EXCLUDED.double_spend_seen
+"#,
+        )
+        .bind(&hashes)
+        .bind(&sizes)
+        .bind(&fees)
+        .bind(&relayed)
+        .bind(&do_not_relay)
+        .bind(&double_spend)
+        .execute(&mut **tx)
+        .await
+        .map_err(Into::into)
+    }
 
+    /// Records each tx's index within the block, following `hashes` order.
+    pub async fn set_block_positions(
+        tx: &mut Transaction<'_, Postgres>,
+        block_height: i64,
+        hashes: &[TxHash],
+    ) -> Result<()> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
         sqlx::query(
-            r#"INSERT INTO public.txs (
-                    tx_hash, block_height, block_timestamp, in_mempool, fee_nanos,
-                    size_bytes, version, unlock_time, extra, rct_type, proof_type,
-                    bp_plus, num_inputs, num_outputs
-                ) VALUES (decode($1,'hex'), $2, NOW(), FALSE, NULL,
-                          1, 2, 0, '{}'::jsonb, 0, NULL,
-                          TRUE, 0, 0)"#,
+            r#"
+UPDATE public.txs t
+SET block_position = (p.ord - 1)::int
+FROM UNNEST($2::bytea[]) WITH ORDINALITY AS p(h, ord)
+WHERE t.tx_hash = p.h
+  AND t.block_height = $1
+  AND t.block_position IS DISTINCT FROM (p.ord - 1)::int
+"#,
         )
-        .bind(&hash)
         .bind(block_height)
-        .execute(&mut *tx)
+        .bind(hashes)
+        .execute(&mut **tx)
         .await?;
+        Ok(())
+    }
 
-        Store::requeue_mempool_from_block(&mut tx, block_height).await?;
-
-        let in_mempool: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')",
+    /// Flags `hash` as the miner tx of the block it was stored with.
+    pub async fn mark_coinbase(tx: &mut Transaction<'_, Postgres>, hash: &TxHash) -> Result<()> {
+        sqlx::query(
+            "UPDATE public.txs SET is_coinbase = TRUE WHERE tx_hash = $1 AND NOT is_coinbase",
         )
-        .bind(&hash)
-        .fetch_one(&mut *tx)
+        .bind(hash)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Stores the RingCT output count for a block and extends the running
+    /// total from the previous height, then recomputes the run of
+    /// consecutive heights above it. Blocks may commit out of order, so that
+    /// also fills in totals left unknown while this height was missing.
+    pub async fn record_rct_outputs(
+        tx: &mut Transaction<'_, Postgres>,
+        height: i64,
+        rct_outputs: i32,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO public.rct_output_counts (height, rct_outputs, cumulative)
+VALUES (
+  $1,
+  $2,
+  CASE WHEN $1 = 0 THEN $2::bigint
+       ELSE (SELECT cumulative FROM public.rct_output_counts WHERE height = $1 - 1) + $2
+  END
+)
+ON CONFLICT (height) DO UPDATE
+SET rct_outputs = EXCLUDED.rct_outputs, cumulative = EXCLUDED.cumulative
+"#,
+        )
+        .bind(height)
+        .bind(rct_outputs)
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query(
+            r#"
+WITH run AS (
+  SELECT height, rct_outputs, height - $1 - ROW_NUMBER() OVER (ORDER BY height) AS gap
+  FROM public.rct_output_counts WHERE height > $1
+), filled AS (
+  SELECT height,
+         (SELECT cumulative FROM public.rct_output_counts WHERE height = $1)
+           + SUM(rct_outputs) OVER (ORDER BY height) AS cumulative
+  FROM run WHERE gap = 0
+)
+UPDATE public.rct_output_counts c SET cumulative = f.cumulative
+FROM filled f
+WHERE c.height = f.height AND c.cumulative IS DISTINCT FROM f.cumulative
+"#,
+        )
+        .bind(height)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Drops the RingCT output count at `height`, for a block with txs that
+    /// failed to parse. The running total above it is unknown until the
+    /// block is recorded again.
+    pub async fn forget_rct_outputs(tx: &mut Transaction<'_, Postgres>, height: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+WITH removed AS (
+  DELETE FROM public.rct_output_counts WHERE height = $1
+)
+UPDATE public.rct_output_counts SET cumulative = NULL
+WHERE height > $1 AND cumulative IS NOT NULL
+"#,
+        )
+        .bind(height)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Stores the coins a block emitted, with its fees and the miner tx's
+    /// payout, and extends the running supply from the previous height,
+    /// shifting the totals above on a changed re-record. Returns the supply
+    /// before this block, `None` while a lower height is still missing, and
+    /// the heights above whose running supply this filled in or changed.
+    pub async fn record_emission(
+        tx: &mut Transaction<'_, Postgres>,
+        height: i64,
+        base_reward: i64,
+        fees: i64,
+        claimed: Option<i64>,
+    ) -> Result<(Option<u64>, Vec<i64>)> {
+        let before: Option<String> = sqlx::query_scalar(
+            r#"
+WITH prev AS (
+  SELECT cumulative FROM public.block_emission WHERE height = $1 - 1
+), old AS (
+  SELECT base_reward FROM public.block_emission WHERE height = $1
+), up AS (
+  INSERT INTO public.block_emission (height, base_reward, cumulative, fees, claimed)
+  VALUES (
+    $1,
+    $2,
+    CASE WHEN $1 = 0 THEN $2::numeric ELSE (SELECT cumulative FROM prev) + $2 END,
+    $3,
+    $4
+  )
+  ON CONFLICT (height) DO UPDATE
+  SET base_reward = EXCLUDED.base_reward, cumulative = EXCLUDED.cumulative,
+      fees = EXCLUDED.fees, claimed = EXCLUDED.claimed
+)
+SELECT CASE WHEN $1 = 0 THEN '0' ELSE (SELECT cumulative FROM prev)::text END
+"#,
+        )
+        .bind(height)
+        .bind(base_reward)
+        .bind(fees)
+        .bind(claimed)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        // Recompute the run of consecutive heights above, which also fills
+        // in totals left unknown by a gap at this height.
+        let shifted: Vec<i64> = sqlx::query_scalar(
+            r#"
+WITH run AS (
+  SELECT height, base_reward, height - $1 - ROW_NUMBER() OVER (ORDER BY height) AS gap
+  FROM public.block_emission WHERE height > $1
+), filled AS (
+  SELECT height,
+         (SELECT cumulative FROM public.block_emission WHERE height = $1)
+           + SUM(base_reward) OVER (ORDER BY height) AS cumulative
+  FROM run WHERE gap = 0
+)
+UPDATE public.block_emission e SET cumulative = f.cumulative
+FROM filled f
+WHERE e.height = f.height AND e.cumulative IS DISTINCT FROM f.cumulative
+RETURNING e.height
+"#,
+        )
+        .bind(height)
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok((before.map(|v| supply_u64(&v)), shifted))
+    }
+
+    /// The recorded emission of each height in `heights` whose supply before
+    /// it is known, by height.
+    pub async fn recorded_emission(
+        tx: &mut Transaction<'_, Postgres>,
+        heights: &[i64],
+    ) -> Result<Vec<RecordedEmission>> {
+        let rows = sqlx::query(
+            r#"
+SELECT e.height, b.hash, b.size_bytes, b.major_version, e.base_reward,
+       COALESCE(e.fees, 0) AS fees, (e.cumulative - e.base_reward)::text AS generated
+FROM public.block_emission e
+JOIN public.blocks b ON b.height = e.height
+WHERE e.height = ANY($1) AND e.cumulative IS NOT NULL
+ORDER BY e.height
+"#,
+        )
+        .bind(heights)
+        .fetch_all(&mut **tx)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(RecordedEmission {
+                    height: row.try_get("height")?,
+                    hash: row.try_get("hash")?,
+                    size: u64::try_from(row.try_get::<i32, _>("size_bytes")?).unwrap_or(0),
+                    major_version: u32::try_from(row.try_get::<i32, _>("major_version")?)
+                        .unwrap_or(0),
+                    base_reward: row.try_get("base_reward")?,
+                    fees: row.try_get("fees")?,
+                    generated: supply_u64(&row.try_get::<String, _>("generated")?),
+                })
+            })
+            .collect()
+    }
+
+    /// Drops what is recorded about the block at `height`'s emission, for a
+    /// block whose fees are not fully known. The running supply above it is
+    /// unknown until the block is recorded again.
+    pub async fn forget_emission(tx: &mut Transaction<'_, Postgres>, height: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+WITH gone AS (
+  DELETE FROM public.emission_anomalies WHERE height = $1
+), removed AS (
+  DELETE FROM public.block_emission WHERE height = $1
+)
+UPDATE public.block_emission SET cumulative = NULL
+WHERE height > $1 AND cumulative IS NOT NULL
+"#,
+        )
+        .bind(height)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Sets the schedule's base reward for the block at `height`; `None` while
+    /// the supply before it is unknown.
+    pub async fn set_emission_subsidy(
+        tx: &mut Transaction<'_, Postgres>,
+        height: i64,
+        subsidy: Option<i64>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE public.block_emission SET subsidy = $2 WHERE height = $1")
+            .bind(height)
+            .bind(subsidy)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    /// Records (or, with `None`, clears) a block whose emission deviates from
+    /// the schedule as `(expected_base, reported_base, fees)`.
+    pub async fn set_emission_anomaly(
+        tx: &mut Transaction<'_, Postgres>,
+        height: i64,
+        block_hash: &BlockHash,
+        anomaly: Option<(i64, i64, i64)>,
+    ) -> Result<()> {
+        let Some((expected, reported, fees)) = anomaly else {
+            sqlx::query("DELETE FROM public.emission_anomalies WHERE height = $1")
+                .bind(height)
+                .execute(&mut **tx)
+                .await?;
+            return Ok(());
+        };
+        sqlx::query(
+            r#"
+INSERT INTO public.emission_anomalies
+  (height, block_hash, expected_base, reported_base, fees, deviation)
+VALUES ($1, $2, $3, $4, $5, $4 - $3)
+ON CONFLICT (height) DO UPDATE
+SET block_hash = EXCLUDED.block_hash,
+    expected_base = EXCLUDED.expected_base,
+    reported_base = EXCLUDED.reported_base,
+    fees = EXCLUDED.fees,
+    deviation = EXCLUDED.deviation,
+    detected_at = NOW()
+"#,
+        )
+        .bind(height)
+        .bind(block_hash)
+        .bind(expected)
+        .bind(reported)
+        .bind(fees)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Copies `mempool_txs.first_seen` onto the included txs, keeping the
+    /// earliest sighting if the tx already carries one (e.g. after a reorg).
+    pub async fn carry_mempool_first_seen(
+        tx: &mut Transaction<'_, Postgres>,
+        included: &[TxHash],
+    ) -> Result<PgQueryResult> {
+        sqlx::query(
+            r#"
+UPDATE public.txs t
+SET first_seen = LEAST(t.first_seen, m.first_seen)
+FROM public.mempool_txs m
+WHERE m.tx_hash = t.tx_hash
+  AND m.tx_hash = ANY($1::bytea[])
+"#,
+        )
+        .bind(included)
+        .execute(&mut **tx)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn evict_mempool_on_inclusion(
+        tx: &mut Transaction<'_, Postgres>,
+        included: &[TxHash],
+    ) -> Result<PgQueryResult> {
+        sqlx::query(
+            r#"
+DELETE FROM public.mempool_txs
+WHERE tx_hash = ANY($1::bytea[])
+"#,
+        )
+        .bind(included)
+        .execute(&mut **tx)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Hides pool txs mined in the daemon block at `height` while ingestion
+    /// holds it back; [`Store::evict_mempool_on_inclusion`] deletes them once
+    /// it lands. Txs marked for an earlier block at that height that this one
+    /// lacks are shown again.
+    pub async fn mark_mempool_mined(&self, height: i64, included: &[TxHash]) -> Result<()> {
+        sqlx::query(
+            r#"
+WITH released AS (
+  UPDATE public.mempool_txs
+  SET mined_height = NULL
+  WHERE mined_height = $1 AND NOT (tx_hash = ANY($2::bytea[]))
+)
+UPDATE public.mempool_txs
+SET mined_height = $1
+WHERE tx_hash = ANY($2::bytea[])
+  AND mined_height IS DISTINCT FROM $1
+"#,
+        )
+        .bind(height)
+        .bind(included)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Detaches the txs of an orphaned block: they return to the mempool and
+    /// are marked `orphaned_at` so [`Store::purge_orphaned_txs`] can drop them
+    /// if they never reconfirm. The block's miner tx cannot be mined again, so
+    /// it is detached without entering the mempool.
+    pub async fn requeue_mempool_from_block(
+        tx: &mut Transaction<'_, Postgres>,
+        block_height: i64,
+    ) -> Result<()> {
+        let hashes: Vec<String> = sqlx::query_scalar(
+            r#"
+WITH detached AS (
+  UPDATE public.txs
+  SET block_height = NULL, block_timestamp = 'infinity', in_mempool = NOT is_coinbase,
+      block_position = NULL, orphaned_at = NOW()
+  WHERE block_height = $1
+  RETURNING tx_hash, is_coinbase
+), recorded AS (
+  INSERT INTO public.orphaned_tx_blocks (tx_hash, block_height, block_hash)
+  SELECT d.tx_hash, $1, b.hash
+  FROM detached d
+  JOIN public.blocks b ON b.height = $1
+  ON CONFLICT (tx_hash, block_hash) DO UPDATE SET orphaned_at = NOW()
+)
+SELECT encode(tx_hash, 'hex') FROM detached WHERE NOT is_coinbase
+"#,
+        )
+        .bind(block_height)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        if hashes.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+INSERT INTO public.mempool_txs (tx_hash, first_seen, last_seen)
+SELECT decode(h, 'hex'), NOW(), NOW() FROM UNNEST($1::text[]) AS t(h)
+ON CONFLICT (tx_hash) DO UPDATE SET last_seen = NOW()
+"#,
+        )
+        .bind(&hashes)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Detaches txs recorded at `block_height` whose hashes are not in
+    /// `keep`, the same way reorg healing does; a stale miner tx is detached
+    /// without being marked as in the mempool.
+    pub async fn detach_block_txs_except(
+        tx: &mut Transaction<'_, Postgres>,
+        block_height: i64,
+        keep: &[TxHash],
+    ) -> Result<u64> {
+        let res = sqlx::query(
+            r#"
+UPDATE public.txs
+SET block_height = NULL, block_timestamp = 'infinity', in_mempool = NOT is_coinbase,
+    block_position = NULL, orphaned_at = NOW()
+WHERE block_height = $1
+  AND tx_hash <> ALL($2::bytea[])
+"#,
+        )
+        .bind(block_height)
+        .bind(keep)
+        .execute(&mut **tx)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Sets aside txs of the block at `block_height` that the codec could not
+    /// parse, keeping their raw JSON and the error for a later reprocess.
+    pub async fn quarantine_txs(
+        tx: &mut Transaction<'_, Postgres>,
+        block_height: i64,
+        block_hash: &BlockHash,
+        hashes: &[TxHash],
+        raw_jsons: &[String],
+        errors: &[String],
+    ) -> Result<()> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(
+            r#"
+INSERT INTO public.quarantined_txs (tx_hash, block_height, block_hash, raw_json, error)
+SELECT q.h, $1, $2, q.raw_json, q.error
+FROM UNNEST($3::bytea[], $4::text[], $5::text[]) AS q(h, raw_json, error)
+ON CONFLICT (tx_hash) DO UPDATE
+  SET block_height = EXCLUDED.block_height, block_hash = EXCLUDED.block_hash,
+      raw_json = EXCLUDED.raw_json, error = EXCLUDED.error, quarantined_at = NOW()
+"#,
+        )
+        .bind(block_height)
+        .bind(block_hash)
+        .bind(hashes)
+        .bind(raw_jsons)
+        .bind(errors)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Drops the quarantine entries of txs that have now been persisted.
+    pub async fn release_quarantined(
+        tx: &mut Transaction<'_, Postgres>,
+        hashes: &[TxHash],
+    ) -> Result<u64> {
+        let res =
+            sqlx::query("DELETE FROM public.quarantined_txs WHERE tx_hash = ANY($1::bytea[])")
+                .bind(hashes)
+                .execute(&mut **tx)
+                .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Quarantined txs as `(hash, block height, major version, raw json)`.
+    /// Entries whose block was since reorged out are dropped instead.
+    pub async fn quarantined_txs(&self) -> Result<Vec<(TxHash, i64, i32, String)>> {
+        sqlx::query(
+            r#"
+DELETE FROM public.quarantined_txs q
+WHERE NOT EXISTS (
+  SELECT 1 FROM public.blocks b WHERE b.height = q.block_height AND b.hash = q.block_hash
+)
+"#,
+        )
+        .execute(self.pool())
+        .await?;
+        let rows = sqlx::query_as(
+            r#"
+SELECT q.tx_hash, q.block_height, b.major_version, q.raw_json
+FROM public.quarantined_txs q
+JOIN public.blocks b ON b.height = q.block_height AND b.hash = q.block_hash
+ORDER BY q.block_height, q.tx_hash
+"#,
+        )
+        .fetch_all(self.pool())
+        .await?;
+        Ok(rows)
+    }
+
+    /// Marks `height` as being persisted. Runs outside the block transaction
+    /// so the marker survives a crash that rolls the block back.
+    pub async fn begin_block_ingest(&self, height: i64, hash: &BlockHash) -> Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO public.block_ingest_state (height, hash, state)
+VALUES ($1, $2, 'in_progress')
+ON CONFLICT (height) DO UPDATE
+  SET hash = EXCLUDED.hash, state = 'in_progress', started_at = NOW(), completed_at = NULL
+"#,
+        )
+        .bind(height)
+        .bind(hash)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Flips the marker to complete inside the block transaction, so it only
+    /// becomes visible together with the block's rows.
+    pub async fn complete_block_ingest(
+        tx: &mut Transaction<'_, Postgres>,
+        height: i64,
+        hash: &BlockHash,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+UPDATE public.block_ingest_state
+SET state = 'complete', completed_at = NOW()
+WHERE height = $1 AND hash = $2
+"#,
+        )
+        .bind(height)
+        .bind(hash)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Heights whose persistence started but never committed.
+    pub async fn incomplete_block_ingests(&self) -> Result<Vec<i64>> {
+        let heights = sqlx::query_scalar(
+            r#"
+SELECT height FROM public.block_ingest_state
+WHERE state = 'in_progress'
+ORDER BY height
+"#,
+        )
+        .fetch_all(self.pool())
+        .await?;
+        Ok(heights)
+    }
+
+    /// Queues a re-ingestion on behalf of the ingestor itself. A request that
+    /// is already queued or running is left alone.
+    pub async fn enqueue_reingest(&self, height: i64, requested_by: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO public.reingest_requests (height, requested_by)
+VALUES ($1, $2)
+ON CONFLICT (height) DO UPDATE
+  SET status = 'queued', last_error = NULL, requested_by = EXCLUDED.requested_by,
+      requested_at = NOW(), updated_at = NOW()
+  WHERE public.reingest_requests.status IN ('done', 'failed')
+"#,
+        )
+        .bind(height)
+        .bind(requested_by)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Claims the oldest queued re-ingestion request. Requests stuck in
+    /// `running` (e.g. the ingestor crashed mid-way) are reclaimed after
+    /// ten minutes.
+    pub async fn claim_reingest_request(&self) -> Result<Option<i64>> {
+        let height = sqlx::query_scalar(
+            r#"
+UPDATE public.reingest_requests
+SET status = 'running', attempts = attempts + 1, updated_at = NOW()
+WHERE height = (
+  SELECT height FROM public.reingest_requests
+  WHERE status = 'queued'
+     OR (status = 'running' AND updated_at < NOW() - interval '10 minutes')
+  ORDER BY requested_at
+  LIMIT 1
+  FOR UPDATE SKIP LOCKED
+)
+RETURNING height
+"#,
+        )
+        .fetch_optional(self.pool())
+        .await?;
+        Ok(height)
+    }
+
+    pub async fn finish_reingest_request(&self, height: i64, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"
+UPDATE public.reingest_requests
+SET status = CASE WHEN $2::text IS NULL THEN 'done' ELSE 'failed' END,
+    last_error = $2,
+    updated_at = NOW()
+WHERE height = $1
+"#,
+        )
+        .bind(height)
+        .bind(error)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes orphaned txs that have neither reconfirmed nor been seen in the
+    /// daemon's pool for `ttl_secs`. Returns the number of purged txs.
+    pub async fn purge_orphaned_txs(
+        tx: &mut Transaction<'_, Postgres>,
+        ttl_secs: i64,
+    ) -> Result<u64> {
+        let purged: i64 = sqlx::query_scalar(
+            r#"
+WITH purged AS (
+  DELETE FROM public.txs t
+  WHERE t.block_height IS NULL
+    AND t.orphaned_at < NOW() - make_interval(secs => $1)
+    AND NOT EXISTS (
+      SELECT 1 FROM public.mempool_txs m
+      WHERE m.tx_hash = t.tx_hash AND m.last_seen >= NOW() - make_interval(secs => $1)
+    )
+  RETURNING t.tx_hash
+), evicted AS (
+  DELETE FROM public.mempool_txs m USING purged p WHERE m.tx_hash = p.tx_hash
+), forgotten AS (
+  DELETE FROM public.orphaned_tx_blocks o
+  WHERE o.orphaned_at < NOW() - make_interval(secs => $1)
+)
+SELECT COUNT(*) FROM purged
+"#,
+        )
+        .bind(ttl_secs as f64)
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(purged as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Store, UpsertOutcome};
+    use crate::rpc::PoolTx;
+    use crate::testing::TestDb;
+    use anyhow::Result;
+    use bex_core::{BlockHash, TxHash};
+    use sqlx::Row;
+
+    // The returned guard keeps a disposable container alive for the test.
+    async fn setup_pool() -> Result<Option<TestDb>> {
+        TestDb::start().await
+    }
+
+    #[tokio::test]
+    async fn evict_mempool_removes_included_transactions() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!(
+                "skipping evict_mempool_removes_included_transactions: no database available"
+            );
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        let hash = "01".repeat(32);
+
+        sqlx::query(
+            r#"INSERT INTO public.mempool_txs (tx_hash, first_seen, last_seen)
+               VALUES (decode($1,'hex'), NOW(), NOW())"#,
+        )
+        .bind(&hash)
+        .execute(&mut *tx)
+        .await?;
+
+        Store::evict_mempool_on_inclusion(&mut tx, &[TxHash::from_hex(&hash)?]).await?;
+
+        let remaining: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')",
+        )
+        .bind(&hash)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        assert_eq!(remaining, 0);
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mined_pool_txs_are_hidden_until_back_in_the_pool() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!(
+                "skipping mined_pool_txs_are_hidden_until_back_in_the_pool: no database available"
+            );
+            return Ok(());
+        };
+        let store = Store {
+            pool: db.pool.clone(),
+        };
+        let (kept, dropped) = ("e1".repeat(32), "e2".repeat(32));
+        let mut tx = store.pool().begin().await?;
+        Store::upsert_mempool_hashes(&mut tx, &[kept.clone(), dropped.clone()]).await?;
+        tx.commit().await?;
+        let mined_height = |hash: String| {
+            let pool = store.pool().clone();
+            async move {
+                sqlx::query_scalar::<_, Option<i64>>(
+                    "SELECT mined_height FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')",
+                )
+                .bind(hash)
+                .fetch_one(&pool)
+                .await
+            }
+        };
+
+        let both = [TxHash::from_hex(&kept)?, TxHash::from_hex(&dropped)?];
+        store.mark_mempool_mined(900_001, &both).await?;
+        assert_eq!(mined_height(kept.clone()).await?, Some(900_001));
+        assert_eq!(mined_height(dropped.clone()).await?, Some(900_001));
+
+        // The block at that height was replaced by one without `dropped`.
+        store.mark_mempool_mined(900_001, &both[..1]).await?;
+        assert_eq!(mined_height(kept.clone()).await?, Some(900_001));
+        assert_eq!(mined_height(dropped.clone()).await?, None);
+
+        // Seen in the pool again after a reorg.
+        let mut tx = store.pool().begin().await?;
+        Store::upsert_mempool_hashes(&mut tx, std::slice::from_ref(&kept)).await?;
+        tx.commit().await?;
+        assert_eq!(mined_height(kept.clone()).await?, None);
+
+        sqlx::query("DELETE FROM public.mempool_txs WHERE tx_hash = ANY($1::bytea[])")
+            .bind(&both[..])
+            .execute(store.pool())
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ingest_counters_keep_the_latest_snapshot() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!("skipping ingest_counters_keep_the_latest_snapshot: no database available");
+            return Ok(());
+        };
+        let store = Store {
+            pool: db.pool.clone(),
+        };
+        let series = |value| vec![("test_total{kind=\"x\"}".to_string(), value)];
+
+        store.save_ingest_counters(&series(5)).await?;
+        store.save_ingest_counters(&series(8)).await?;
+        let saved: Vec<_> = store
+            .ingest_counters()
+            .await?
+            .into_iter()
+            .filter(|(name, _)| name.starts_with("test_total"))
+            .collect();
+        sqlx::query("DELETE FROM public.ingest_counters WHERE name LIKE 'test_total%'")
+            .execute(store.pool())
+            .await?;
+        assert_eq!(saved, series(8));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn carry_mempool_first_seen_onto_included_tx() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!("skipping carry_mempool_first_seen_onto_included_tx: no database available");
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        let hash = "03".repeat(32);
+
+        sqlx::query(
+            r#"INSERT INTO public.mempool_txs (tx_hash, first_seen, last_seen)
+               VALUES (decode($1,'hex'), '2024-01-01T00:00:00Z', NOW())"#,
+        )
+        .bind(&hash)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"INSERT INTO public.txs (
+                    tx_hash, block_height, block_timestamp, in_mempool, size_bytes, version,
+                    unlock_time, rct_type, num_inputs, num_outputs
+                ) VALUES (decode($1,'hex'), 43, NOW(), FALSE, 1, 2, 0, 6, 1, 2)"#,
+        )
+        .bind(&hash)
+        .execute(&mut *tx)
+        .await?;
+
+        let included = [TxHash::from_hex(&hash)?];
+        Store::carry_mempool_first_seen(&mut tx, &included).await?;
+        Store::evict_mempool_on_inclusion(&mut tx, &included).await?;
+
+        let first_seen: Option<i64> = sqlx::query_scalar(
+            "SELECT extract(epoch from first_seen)::bigint FROM public.txs WHERE tx_hash = decode($1,'hex')",
+        )
+        .bind(&hash)
+        .fetch_one(&mut *tx)
+        .await?;
+        assert_eq!(first_seen, Some(1_704_067_200));
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn insert_block_reconciles_conflicting_reinsert() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!(
+                "skipping insert_block_reconciles_conflicting_reinsert: no database available"
+            );
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        let height = 880_000_i64;
+        sqlx::query("DELETE FROM public.blocks WHERE height = $1")
+            .bind(height)
+            .execute(&mut *tx)
+            .await?;
+
+        let hash = [0x11u8; 32];
+        let other = [0x22u8; 32];
+        let prev = [0x10u8; 32];
+        let insert = |hash: [u8; 32], reward: i64| {
+            (
+                height,
+                hash,
+                prev,
+                1_700_000_000_i64,
+                100,
+                16,
+                16,
+                7_i64,
+                1,
+                reward,
+            )
+        };
+
+        for (expected, args) in [
+            (UpsertOutcome::Inserted, insert(hash, 5)),
+            (UpsertOutcome::Unchanged, insert(hash, 5)),
+            (UpsertOutcome::Conflict, insert(other, 6)),
+        ] {
+            let (h, hash, prev, ts, size, major, minor, nonce, count, reward) = args;
+            let outcome = Store::insert_block(
+                &mut tx,
+                h,
+                &BlockHash(hash),
+                &BlockHash(prev),
+                ts,
+                size,
+                major,
+                minor,
+                nonce,
+                count,
+                reward,
+            )
+            .await?;
+            assert_eq!(outcome, expected);
+        }
+
+        let (stored_hash, reward): (Vec<u8>, i64) =
+            sqlx::query_as("SELECT hash, reward_nanos FROM public.blocks WHERE height = $1")
+                .bind(height)
+                .fetch_one(&mut *tx)
+                .await?;
+        assert_eq!(stored_hash, other.to_vec());
+        assert_eq!(reward, 6);
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn upsert_mempool_pool_records_relay_flags() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!("skipping upsert_mempool_pool_records_relay_flags: no database available");
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        let hash = "05".repeat(32);
+        let entry = PoolTx {
+            id_hash: hash.clone(),
+            blob_size: 2_000,
+            fee: 40_000_000,
+            relayed: false,
+            do_not_relay: Some(true),
+            double_spend_seen: false,
+        };
+        Store::upsert_mempool_pool(&mut tx, std::slice::from_ref(&entry)).await?;
+        let relayed_again = PoolTx {
+            relayed: true,
+            do_not_relay: None,
+            double_spend_seen: true,
+            ..entry
+        };
+        Store::upsert_mempool_pool(&mut tx, &[relayed_again]).await?;
+
+        let row = sqlx::query(
+            "SELECT size_bytes, fee_rate::float8 AS fee_rate, relayed, do_not_relay, double_spend_seen
+             FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')",
+        )
+        .bind(&hash)
+        .fetch_one(&mut *tx)
+        .await?;
+        assert_eq!(row.get::<Option<i32>, _>("size_bytes"), Some(2_000));
+        assert_eq!(row.get::<Option<f64>, _>("fee_rate"), Some(20_000.0));
+        assert_eq!(row.get::<Option<bool>, _>("relayed"), Some(true));
+        assert_eq!(row.get::<Option<bool>, _>("do_not_relay"), Some(true));
+        assert_eq!(row.get::<Option<bool>, _>("double_spend_seen"), Some(true));
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn upsert_mempool_hashes_batches_duplicates() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!("skipping upsert_mempool_hashes_batches_duplicates: no database available");
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        let hashes = vec!["03".repeat(32), "04".repeat(32), "03".repeat(32)];
+
+        let res = Store::upsert_mempool_hashes(&mut tx, &hashes).await?;
+        assert_eq!(res.rows_affected(), 2);
+
+        let res = Store::upsert_mempool_hashes(&mut tx, &hashes[..2]).await?;
+        assert_eq!(res.rows_affected(), 2);
+
+        let included: Vec<TxHash> = hashes
+            .iter()
+            .map(|h| TxHash::from_hex(h))
+            .collect::<Result<_, _>>()?;
+        let evicted = Store::evict_mempool_on_inclusion(&mut tx, &included).await?;
+        assert_eq!(evicted.rows_affected(), 2);
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn requeue_mempool_inserts_transactions() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!("skipping requeue_mempool_inserts_transactions: no database available");
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        let hash = "02".repeat(32);
+        let block_height = 42_i64;
+
+        sqlx::query(
+            r#"INSERT INTO public.txs (
+                    tx_hash, block_height, block_timestamp, in_mempool, fee_nanos,
+                    size_bytes, version, unlock_time, extra, rct_type, proof_type,
+                    bp_plus, num_inputs, num_outputs
+                ) VALUES (decode($1,'hex'), $2, NOW(), FALSE, NULL,
+                          1, 2, 0, '{}'::jsonb, 0, NULL,
+                          TRUE, 0, 0)"#,
+        )
+        .bind(&hash)
+        .bind(block_height)
+        .execute(&mut *tx)
         .await?;
 
+        Store::requeue_mempool_from_block(&mut tx, block_height).await?;
+
+        let in_mempool: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')",
+        )
+        .bind(&hash)
+        .fetch_one(&mut *tx)
+        .await?;
         assert_eq!(in_mempool, 1);
+
+        let (height, flagged, orphaned): (Option<i64>, bool, bool) = sqlx::query_as(
+            "SELECT block_height, in_mempool, orphaned_at IS NOT NULL FROM public.txs WHERE tx_hash = decode($1,'hex')",
+        )
+        .bind(&hash)
+        .fetch_one(&mut *tx)
+        .await?;
+        assert_eq!(height, None);
+        assert!(flagged);
+        assert!(orphaned);
+
+        assert_eq!(Store::purge_orphaned_txs(&mut tx, 3600).await?, 0);
+
+        sqlx::query(
+            "UPDATE public.txs SET orphaned_at = NOW() - interval '2 hours' WHERE tx_hash = decode($1,'hex')",
+        )
+        .bind(&hash)
+        .execute(&mut *tx)
+        .await?;
+        // Still seen in the daemon pool recently, so it is kept.
+        assert_eq!(Store::purge_orphaned_txs(&mut tx, 3600).await?, 0);
+
+        sqlx::query(
+            "UPDATE public.mempool_txs SET last_seen = NOW() - interval '2 hours' WHERE tx_hash = decode($1,'hex')",
+        )
+        .bind(&hash)
+        .execute(&mut *tx)
+        .await?;
+
+        assert_eq!(Store::purge_orphaned_txs(&mut tx, 3600).await?, 1);
+        let remaining: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM public.mempool_txs WHERE tx_hash = decode($1,'hex')",
+        )
+        .bind(&hash)
+        .fetch_one(&mut *tx)
+        .await?;
+        assert_eq!(remaining, 0);
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn requeue_records_orphaned_block_and_clears_position() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!(
+                "skipping requeue_records_orphaned_block_and_clears_position: no database available"
+            );
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        let height = 9_100_000_i64;
+        let block_hash = BlockHash([0x51; 32]);
+        let tx_hashes = [TxHash([0x52; 32]), TxHash([0x53; 32])];
+
+        Store::insert_block(
+            &mut tx,
+            height,
+            &block_hash,
+            &BlockHash([0x50; 32]),
+            1_700_000_000,
+            1,
+            16,
+            16,
+            0,
+            2,
+            0,
+        )
+        .await?;
+        for hash in &tx_hashes {
+            Store::insert_tx(
+                &mut tx,
+                hash,
+                Some(height),
+                Some(1_700_000_000),
+                false,
+                None,
+                1,
+                2,
+                0,
+                &serde_json::json!({}),
+                6,
+                None,
+                true,
+                0,
+                0,
+                None,
+            )
+            .await?;
+        }
+        Store::set_block_positions(&mut tx, height, &tx_hashes).await?;
+
+        let position: Option<i32> = sqlx::query_scalar(
+            "SELECT block_position FROM public.txs WHERE tx_hash = decode($1,'hex')",
+        )
+        .bind(tx_hashes[1].to_hex())
+        .fetch_one(&mut *tx)
+        .await?;
+        assert_eq!(position, Some(1));
+
+        Store::requeue_mempool_from_block(&mut tx, height).await?;
+
+        let (position, orphaned_in): (Option<i32>, BlockHash) = sqlx::query_as(
+            r#"
+SELECT t.block_position, o.block_hash
+FROM public.txs t
+JOIN public.orphaned_tx_blocks o ON o.tx_hash = t.tx_hash
+WHERE t.tx_hash = decode($1,'hex')
+"#,
+        )
+        .bind(tx_hashes[1].to_hex())
+        .fetch_one(&mut *tx)
+        .await?;
+        assert_eq!(position, None);
+        assert_eq!(orphaned_in, block_hash);
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn orphaned_coinbase_stays_out_of_the_mempool() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!("skipping orphaned_coinbase_stays_out_of_the_mempool: no database available");
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        let height = 9_100_100_i64;
+        let coinbase = TxHash([0x61; 32]);
+        let spend = TxHash([0x62; 32]);
+
+        Store::insert_block(
+            &mut tx,
+            height,
+            &BlockHash([0x60; 32]),
+            &BlockHash([0x5f; 32]),
+            1_700_000_000,
+            1,
+            16,
+            16,
+            0,
+            2,
+            0,
+        )
+        .await?;
+        for (hash, rct_type) in [(&coinbase, 0), (&spend, 6)] {
+            Store::insert_tx(
+                &mut tx,
+                hash,
+                Some(height),
+                Some(1_700_000_000),
+                false,
+                None,
+                1,
+                2,
+                0,
+                &serde_json::json!({}),
+                rct_type,
+                None,
+                true,
+                0,
+                0,
+                None,
+            )
+            .await?;
+        }
+        Store::mark_coinbase(&mut tx, &coinbase).await?;
+
+        Store::requeue_mempool_from_block(&mut tx, height).await?;
+
+        let detached: Vec<(TxHash, bool, bool, bool)> = sqlx::query_as(
+            r#"
+SELECT t.tx_hash, t.in_mempool, t.block_height IS NULL,
+       EXISTS (SELECT 1 FROM public.mempool_txs m WHERE m.tx_hash = t.tx_hash)
+FROM public.txs t
+WHERE t.tx_hash = ANY($1::bytea[])
+ORDER BY t.tx_hash
+"#,
+        )
+        .bind(&[coinbase, spend][..])
+        .fetch_all(&mut *tx)
+        .await?;
+        assert_eq!(
+            detached,
+            vec![(coinbase, false, true, false), (spend, true, true, true)]
+        );
+
+        // Re-ingesting the height with a different miner tx drops the old one
+        // the same way.
+        for hash in [&coinbase, &spend] {
+            sqlx::query(
+                "UPDATE public.txs SET block_height = $2, block_timestamp = to_timestamp(1700000000), in_mempool = FALSE WHERE tx_hash = $1",
+            )
+            .bind(hash)
+            .bind(height)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let kept = [TxHash([0x63; 32]), spend];
+        assert_eq!(
+            Store::detach_block_txs_except(&mut tx, height, &kept).await?,
+            1
+        );
+        let in_mempool: bool =
+            sqlx::query_scalar("SELECT in_mempool FROM public.txs WHERE tx_hash = $1")
+                .bind(coinbase)
+                .fetch_one(&mut *tx)
+                .await?;
+        assert!(!in_mempool);
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reingest_requests_are_claimed_once() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!("skipping reingest_requests_are_claimed_once: no database available");
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+        let store = Store { pool: pool.clone() };
+        let height = 970_100_i64;
+
+        sqlx::query(
+            "INSERT INTO public.reingest_requests (height, requested_at) VALUES ($1, '1970-01-02')
+             ON CONFLICT (height) DO UPDATE SET status = 'queued', requested_at = '1970-01-02'",
+        )
+        .bind(height)
+        .execute(&pool)
+        .await?;
+
+        assert_eq!(store.claim_reingest_request().await?, Some(height));
+        let again = store.claim_reingest_request().await?;
+        assert_ne!(again, Some(height));
+        if let Some(other) = again {
+            store.finish_reingest_request(other, None).await?;
+        }
+
+        store
+            .finish_reingest_request(height, Some("daemon unavailable"))
+            .await?;
+        let (status, attempts, error): (String, i32, Option<String>) = sqlx::query_as(
+            "SELECT status, attempts, last_error FROM public.reingest_requests WHERE height = $1",
+        )
+        .bind(height)
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(status, "failed");
+        assert_eq!(attempts, 1);
+        assert_eq!(error.as_deref(), Some("daemon unavailable"));
+
+        sqlx::query("DELETE FROM public.reingest_requests WHERE height = $1")
+            .bind(height)
+            .execute(&pool)
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record_tip_maintains_current_tip_and_prunes() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!(
+                "skipping record_tip_maintains_current_tip_and_prunes: no database available"
+            );
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM public.chain_tips")
+            .execute(&mut *tx)
+            .await?;
+
+        for height in 500_i64..510 {
+            let hash = [height as u8; 32];
+            let prev = [(height - 1) as u8; 32];
+            Store::record_tip(&mut tx, height, &BlockHash(hash), &BlockHash(prev)).await?;
+        }
+
+        let pruned = Store::prune_chain_tips(&mut tx, 505).await?;
+        assert_eq!(pruned, 5);
+
+        let (tip_height, tip_hash): (i64, Vec<u8>) =
+            sqlx::query_as("SELECT height, hash FROM public.current_tip WHERE id = 1")
+                .fetch_one(&mut *tx)
+                .await?;
+        assert_eq!(tip_height, 509);
+        assert_eq!(tip_hash, vec![509_i64 as u8; 32]);
+
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM public.chain_tips")
+            .fetch_one(&mut *tx)
+            .await?;
+        assert_eq!(remaining, 5);
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record_rct_outputs_keeps_running_total() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!("skipping record_rct_outputs_keeps_running_total: no database available");
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM public.rct_output_counts")
+            .execute(&mut *tx)
+            .await?;
+
+        for (height, count) in [(0_i64, 2), (1, 3), (2, 4)] {
+            Store::record_rct_outputs(&mut tx, height, count).await?;
+        }
+        // Re-recording a lower height shifts everything above it.
+        Store::record_rct_outputs(&mut tx, 1, 5).await?;
+        // A gap leaves the total unknown rather than wrong.
+        Store::record_rct_outputs(&mut tx, 10, 1).await?;
+        // Heights committed ahead of a missing one are filled in once it lands.
+        Store::record_rct_outputs(&mut tx, 5, 1).await?;
+        Store::record_rct_outputs(&mut tx, 4, 2).await?;
+        Store::record_rct_outputs(&mut tx, 3, 3).await?;
+
+        let rows: Vec<(i64, Option<i64>)> = sqlx::query_as(
+            "SELECT height, cumulative FROM public.rct_output_counts ORDER BY height",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        assert_eq!(
+            rows,
+            vec![
+                (0, Some(2)),
+                (1, Some(7)),
+                (2, Some(11)),
+                (3, Some(14)),
+                (4, Some(16)),
+                (5, Some(17)),
+                (10, None)
+            ]
+        );
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record_emission_returns_supply_before_block() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!(
+                "skipping record_emission_returns_supply_before_block: no database available"
+            );
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM public.block_emission")
+            .execute(&mut *tx)
+            .await?;
+
+        assert_eq!(
+            Store::record_emission(&mut tx, 0, 100, 0, Some(100))
+                .await?
+                .0,
+            Some(0)
+        );
+        assert_eq!(
+            Store::record_emission(&mut tx, 1, 50, 3, Some(52)).await?.0,
+            Some(100)
+        );
+        assert_eq!(
+            Store::record_emission(&mut tx, 2, 25, 0, None).await?.0,
+            Some(150)
+        );
+        // Re-recording height 1 reports the same prior supply and shifts 2.
+        assert_eq!(
+            Store::record_emission(&mut tx, 1, 60, 4, Some(64)).await?,
+            (Some(100), vec![2])
+        );
+        assert_eq!(
+            Store::record_emission(&mut tx, 10, 1, 0, None).await?,
+            (None, vec![])
+        );
+        Store::set_emission_subsidy(&mut tx, 1, Some(60)).await?;
+        // Forgetting a height leaves the supply above it unknown until the
+        // height is recorded again.
+        Store::record_emission(&mut tx, 3, 5, 0, None).await?;
+        Store::forget_emission(&mut tx, 2).await?;
+        let unknown: Option<String> = sqlx::query_scalar(
+            "SELECT cumulative::text FROM public.block_emission WHERE height = 3",
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        assert_eq!(unknown, None);
+        // Recording the missing height fills in and reports the one above.
+        assert_eq!(
+            Store::record_emission(&mut tx, 2, 25, 0, None).await?,
+            (Some(160), vec![3])
+        );
+
+        let split: (Option<i64>, Option<i64>, Option<i64>) = sqlx::query_as(
+            "SELECT subsidy, fees, claimed FROM public.block_emission WHERE height = 1",
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        assert_eq!(split, (Some(60), Some(4), Some(64)));
+
+        let rows: Vec<(i64, Option<String>)> = sqlx::query_as(
+            "SELECT height, cumulative::text FROM public.block_emission ORDER BY height",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let rows: Vec<(i64, Option<&str>)> = rows.iter().map(|(h, c)| (*h, c.as_deref())).collect();
+        assert_eq!(
+            rows,
+            vec![
+                (0, Some("100")),
+                (1, Some("160")),
+                (2, Some("185")),
+                (3, Some("190")),
+                (10, None)
+            ]
+        );
+
+        tx.rollback().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn block_ingest_state_tracks_uncommitted_blocks() -> Result<()> {
+        let Some(db) = setup_pool().await? else {
+            eprintln!(
+                "skipping block_ingest_state_tracks_uncommitted_blocks: no database available"
+            );
+            return Ok(());
+        };
+        let pool = db.pool.clone();
+        let store = Store { pool: pool.clone() };
+        let (done, crashed) = (9_200_000_i64, 9_200_001_i64);
+        let hash = BlockHash([0x61; 32]);
+
+        store.begin_block_ingest(done, &hash).await?;
+        let mut tx = pool.begin().await?;
+        Store::complete_block_ingest(&mut tx, done, &hash).await?;
+        tx.commit().await?;
+
+        store.begin_block_ingest(crashed, &hash).await?;
+        let mut tx = pool.begin().await?;
+        Store::complete_block_ingest(&mut tx, crashed, &hash).await?;
         tx.rollback().await?;
+
+        let incomplete = store.incomplete_block_ingests().await?;
+        assert!(incomplete.contains(&crashed));
+        assert!(!incomplete.contains(&done));
+
+        let mut tx = pool.begin().await?;
+        Store::prune_block_ingest_state(&mut tx, crashed + 1).await?;
+        let kept: Vec<i64> = sqlx::query_scalar(
+            "SELECT height FROM public.block_ingest_state WHERE height IN ($1, $2)",
+        )
+        .bind(done)
+        .bind(crashed)
+        .fetch_all(&mut *tx)
+        .await?;
+        assert_eq!(kept, vec![crashed]);
+        tx.commit().await?;
+
+        sqlx::query("DELETE FROM public.block_ingest_state WHERE height IN ($1, $2)")
+            .bind(done)
+            .bind(crashed)
+            .execute(&pool)
+            .await?;
         Ok(())
     }
 }
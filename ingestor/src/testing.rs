@@ -0,0 +1,380 @@
+//! Database fixtures and a mock daemon shared by unit and integration tests.
+//! Built for the crate's own tests and, through the `testing` feature, for
+//! its integration tests; the shipped library leaves it out.
+//!
+//! [`TestDb::start`] connects to `TEST_DATABASE_URL`/`DATABASE_URL` when set.
+//! Otherwise, with the `testcontainers` feature enabled, it starts a disposable
+//! Postgres (image from `TEST_POSTGRES_IMAGE`, default `explorer-postgres:dev`
+//! built from `ops/Dockerfile.postgres`, which ships pg_partman) that lives as
+//! long as the returned value.
+
+use std::{collections::HashSet, sync::RwLock, time::Duration};
+
+use anyhow::{Context, Result};
+use bex_core::{BlockHash, TxHash};
+use sqlx::{migrate::Migrate, postgres::PgPoolOptions, Executor, PgPool};
+
+use crate::{
+    replay::FixtureBlock,
+    rpc::{
+        BlockHeader, Capabilities, GetBlockCountResult, GetBlockHeaderByHeightResult,
+        GetBlockResult, GetTransactionsResult, MoneroRpc, PoolTx,
+    },
+    schema::MIGRATOR,
+};
+
+pub struct TestDb {
+    pub pool: PgPool,
+    pub url: String,
+    #[cfg(feature = "testcontainers")]
+    _container: Option<testcontainers::ContainerAsync<testcontainers::GenericImage>>,
+}
+
+impl TestDb {
+    /// Returns `None` only when no database is configured and the
+    /// `testcontainers` feature is off, so callers can skip.
+    pub async fn start() -> Result<Option<Self>> {
+        if let Ok(url) =
+            std::env::var("TEST_DATABASE_URL").or_else(|_| std::env::var("DATABASE_URL"))
+        {
+            let pool = connect(&url).await?;
+            return Ok(Some(Self {
+                pool,
+                url,
+                #[cfg(feature = "testcontainers")]
+                _container: None,
+            }));
+        }
+
+        #[cfg(feature = "testcontainers")]
+        {
+            let (container, url) = container::start().await?;
+            let pool = connect(&url).await?;
+            Ok(Some(Self {
+                pool,
+                url,
+                _container: Some(container),
+            }))
+        }
+
+        #[cfg(not(feature = "testcontainers"))]
+        Ok(None)
+    }
+}
+
+async fn connect(url: &str) -> Result<PgPool> {
+    let mut attempts = 0;
+    let pool = loop {
+        match PgPoolOptions::new().max_connections(5).connect(url).await {
+            Ok(pool) => break pool,
+            // A fresh container may accept TCP before Postgres is ready.
+            Err(_) if attempts < 20 => {
+                attempts += 1;
+                tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+            }
+            Err(err) => return Err(err).with_context(|| format!("connect to {url}")),
+        }
+    };
+    apply_migrations(&pool).await?;
+    Ok(pool)
+}
+
+/// Applies the `-- migrate:up` half of each pending migration and records it
+/// in `_sqlx_migrations`, so a later `Migrator::run` is a no-op. Feeding the
+/// single-file migrations to `Migrator::run` directly would also execute their
+/// down sections.
+pub async fn apply_migrations(pool: &PgPool) -> Result<()> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied: HashSet<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect();
+
+    for migration in MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+    {
+        if applied.contains(&migration.version) {
+            continue;
+        }
+        let up = migration
+            .sql
+            .split("-- migrate:down")
+            .next()
+            .unwrap_or_default();
+        conn.execute(up)
+            .await
+            .with_context(|| format!("apply migration {}", migration.version))?;
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+             VALUES ($1, $2, TRUE, $3, 0)",
+        )
+        .bind(migration.version)
+        .bind(&*migration.description)
+        .bind(&*migration.checksum)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// An in-memory daemon serving a chain of [`MockBlock`]s with a few
+/// milliseconds of per-call jitter, so pipeline stages finish out of order.
+/// [`MockRpc::replace_from`] swaps in a competing branch to stage a reorg and
+/// [`MockRpc::set_pool`] fills its mempool.
+pub struct MockRpc {
+    blocks: RwLock<Vec<MockBlock>>,
+    caps: Capabilities,
+    pool: RwLock<Vec<PoolTx>>,
+    pool_fetches: RwLock<Vec<String>>,
+}
+
+impl MockRpc {
+    /// Heights `1..=count`, each block hashed by its height and holding two
+    /// txs.
+    pub fn new(count: u64) -> Self {
+        Self::with_caps(
+            count,
+            Capabilities {
+                headers_range: false,
+                blocks_by_height_bin: false,
+            },
+        )
+    }
+
+    pub fn with_caps(count: u64, caps: Capabilities) -> Self {
+        let blocks = (1..=count)
+            .map(|height| {
+                MockBlock::new(
+                    height,
+                    BlockHash::from_hex(&format!("{height:064x}")).expect("mock block hash"),
+                    BlockHash::from_hex(&format!("{:064x}", height.saturating_sub(1)))
+                        .expect("mock prev hash"),
+                    [1, 2]
+                        .into_iter()
+                        .map(|n| {
+                            TxHash::from_hex(&format!("{:064x}", height * 10 + n))
+                                .expect("mock tx hash")
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+        Self::from_blocks(blocks, caps)
+    }
+
+    pub fn from_blocks(blocks: Vec<MockBlock>, caps: Capabilities) -> Self {
+        Self {
+            blocks: RwLock::new(blocks),
+            caps,
+            pool: RwLock::new(Vec::new()),
+            pool_fetches: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Serves `pool` as the daemon's mempool from now on.
+    pub fn set_pool(&self, pool: Vec<PoolTx>) {
+        *self.pool.write().expect("mock pool lock") = pool;
+    }
+
+    /// Hashes whose bodies were requested since the last call, in order.
+    pub fn take_pool_fetches(&self) -> Vec<String> {
+        std::mem::take(&mut *self.pool_fetches.write().expect("mock pool lock"))
+    }
+
+    /// Drops every block from `height` up and serves `blocks` instead.
+    pub fn replace_from(&self, height: u64, blocks: Vec<MockBlock>) {
+        let mut chain = self.blocks.write().expect("mock chain lock");
+        chain.retain(|b| b.header.height < height);
+        chain.extend(blocks);
+    }
+
+    fn block_at(&self, height: u64) -> Option<MockBlock> {
+        let chain = self.blocks.read().expect("mock chain lock");
+        chain.iter().find(|b| b.header.height == height).cloned()
+    }
+
+    fn jitter(height: u64, salt: u64) -> Duration {
+        let millis = ((height * 37 + salt * 17) % 11) + 1;
+        Duration::from_millis(millis)
+    }
+
+    async fn random_delay(height: u64, salt: u64) {
+        tokio::time::sleep(Self::jitter(height, salt)).await;
+    }
+}
+
+#[derive(Clone)]
+pub struct MockBlock {
+    pub header: BlockHeader,
+    block_json: String,
+    miner_tx_hash: String,
+    tx_hashes: Vec<String>,
+    tx_jsons: Vec<String>,
+}
+
+impl MockBlock {
+    /// A version 1 block with an empty miner tx and empty `txs`; the miner
+    /// tx hash is derived from the block hash so branches never share one.
+    pub fn new(height: u64, hash: BlockHash, prev_hash: BlockHash, txs: Vec<TxHash>) -> Self {
+        let block = FixtureBlock {
+            height,
+            hash,
+            prev_hash,
+            txs,
+        };
+        let tx_hashes: Vec<String> = block.txs.iter().map(TxHash::to_hex).collect();
+        Self {
+            header: block.header(),
+            block_json: block.block_json(),
+            miner_tx_hash: block.miner_tx_hash(),
+            tx_jsons: tx_hashes
+                .iter()
+                .map(|hash| FixtureBlock::tx_json(hash))
+                .collect(),
+            tx_hashes,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MoneroRpc for MockRpc {
+    async fn get_block_headers_range(&self, start: u64, end: u64) -> Result<Vec<BlockHeader>> {
+        Self::random_delay(start, 0).await;
+        let chain = self.blocks.read().expect("mock chain lock");
+        Ok(chain
+            .iter()
+            .filter(|b| b.header.height >= start && b.header.height <= end)
+            .map(|b| b.header.clone())
+            .collect())
+    }
+
+    async fn get_block_header_by_height(
+        &self,
+        height: u64,
+    ) -> Result<GetBlockHeaderByHeightResult> {
+        Self::random_delay(height, 1).await;
+        let block = self.block_at(height).context("missing block header")?;
+        Ok(GetBlockHeaderByHeightResult {
+            block_header: block.header,
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_block(&self, hash: &BlockHash, _fill_pow: bool) -> Result<GetBlockResult> {
+        let block = {
+            let chain = self.blocks.read().expect("mock chain lock");
+            chain
+                .iter()
+                .find(|b| b.header.hash == *hash)
+                .cloned()
+                .context("missing block")?
+        };
+        Self::random_delay(block.header.height, 2).await;
+        Ok(GetBlockResult {
+            block_header: block.header,
+            json: Some(block.block_json),
+            blob: None,
+            miner_tx_hash: Some(block.miner_tx_hash),
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_transactions(&self, txs_hashes: &[String]) -> Result<GetTransactionsResult> {
+        let (jsons, height) = {
+            let chain = self.blocks.read().expect("mock chain lock");
+            let mut jsons = Vec::with_capacity(txs_hashes.len());
+            for hash in txs_hashes {
+                let (_, json) = chain
+                    .iter()
+                    .flat_map(|b| b.tx_hashes.iter().zip(b.tx_jsons.iter()))
+                    .find(|(h, _)| *h == hash)
+                    .context("missing tx json")?;
+                jsons.push(json.clone());
+            }
+            let height = txs_hashes
+                .first()
+                .and_then(|hash| chain.iter().find(|b| b.tx_hashes.contains(hash)))
+                .map(|b| b.header.height)
+                .unwrap_or_default();
+            (jsons, height)
+        };
+        Self::random_delay(height, 3).await;
+        Ok(GetTransactionsResult {
+            txs_as_json: jsons,
+            missed_tx: Vec::new(),
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_block_count(&self) -> Result<GetBlockCountResult> {
+        let chain = self.blocks.read().expect("mock chain lock");
+        let tip = chain
+            .iter()
+            .map(|b| b.header.height)
+            .max()
+            .unwrap_or_default();
+        Ok(GetBlockCountResult {
+            count: tip + 1,
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_transaction_pool_hashes(&self) -> Result<Vec<String>> {
+        let pool = self.pool.read().expect("mock pool lock");
+        Ok(pool.iter().map(|tx| tx.id_hash.clone()).collect())
+    }
+
+    async fn get_pool_transactions(&self, txs_hashes: &[String]) -> Result<Vec<PoolTx>> {
+        self.pool_fetches
+            .write()
+            .expect("mock pool lock")
+            .extend_from_slice(txs_hashes);
+        let pool = self.pool.read().expect("mock pool lock");
+        Ok(pool
+            .iter()
+            .filter(|tx| txs_hashes.contains(&tx.id_hash))
+            .cloned()
+            .collect())
+    }
+
+    async fn probe_caps(&self) -> Capabilities {
+        self.caps
+    }
+}
+
+#[cfg(feature = "testcontainers")]
+mod container {
+    use anyhow::{Context, Result};
+    use testcontainers::{
+        core::{IntoContainerPort, WaitFor},
+        runners::AsyncRunner,
+        ContainerAsync, GenericImage, ImageExt,
+    };
+
+    pub(super) async fn start() -> Result<(ContainerAsync<GenericImage>, String)> {
+        let image = std::env::var("TEST_POSTGRES_IMAGE")
+            .unwrap_or_else(|_| "explorer-postgres:dev".to_string());
+        let (name, tag) = image.rsplit_once(':').unwrap_or((image.as_str(), "latest"));
+
+        let container = GenericImage::new(name, tag)
+            .with_exposed_port(5432.tcp())
+            .with_wait_for(WaitFor::message_on_stderr(
+                "database system is ready to accept connections",
+            ))
+            .with_env_var("POSTGRES_USER", "explorer")
+            .with_env_var("POSTGRES_PASSWORD", "explorer")
+            .with_env_var("POSTGRES_DB", "explorer")
+            .start()
+            .await
+            .with_context(|| format!("start {image} container"))?;
+
+        let host = container.get_host().await?;
+        let port = container.get_host_port_ipv4(5432).await?;
+        let url = format!("postgres://explorer:explorer@{host}:{port}/explorer");
+        Ok((container, url))
+    }
+}
@@ -0,0 +1,329 @@
+//! `ingestor trace-block`: runs one height through the fetch, tx fetch and
+//! prepare stages exactly as the pipeline does, but stops before persistence.
+//! The report carries every derived column, per-stage timings and the checks
+//! persistence would apply, plus a diff against the stored rows when a
+//! database is available.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use bex_core::{BlockHeader, TxHash};
+use governor::DefaultDirectRateLimiter;
+use serde::Serialize;
+
+use crate::{
+    codec::{self, TxAnalysis},
+    pipeline::SchedMsg,
+    rpc::MoneroRpc,
+    store::Store,
+    work_block, work_persist, work_tx,
+};
+
+#[derive(Serialize)]
+pub struct BlockTrace {
+    pub height: i64,
+    pub header: BlockHeader,
+    pub miner_tx_hash: Option<TxHash>,
+    pub txs: Vec<TxTrace>,
+    pub timings_ms: Vec<StageTiming>,
+    pub checks: Vec<Check>,
+}
+
+#[derive(Serialize)]
+pub struct TxTrace {
+    pub requested_hash: TxHash,
+    pub coinbase: bool,
+    /// Columns persistence would write to `txs`.
+    pub row: Option<serde_json::Value>,
+    pub analysis: Option<TxAnalysis>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct StageTiming {
+    pub stage: &'static str,
+    pub ms: f64,
+}
+
+#[derive(Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    /// `None` when the check could not run, e.g. without a database.
+    pub ok: Option<bool>,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, ok: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            ok: Some(ok),
+            detail: detail.into(),
+        }
+    }
+
+    fn skipped(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            ok: None,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl BlockTrace {
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|c| c.ok == Some(false)).count()
+    }
+}
+
+pub async fn trace_block(
+    rpc: Arc<dyn MoneroRpc>,
+    limiter: Arc<DefaultDirectRateLimiter>,
+    store: Option<&Store>,
+    height: i64,
+) -> Result<BlockTrace> {
+    let mut timings = Vec::new();
+    let sched = SchedMsg {
+        height,
+        tip_height: height,
+        finalized_height: height,
+        started: Instant::now(),
+    };
+
+    let started = Instant::now();
+    let block = work_block::fetch_block_detached(rpc.as_ref(), &limiter, &sched)
+        .await
+        .with_context(|| format!("fetch block {height}"))?;
+    timings.push(timing("fetch_block", started.elapsed()));
+
+    let expected_txs = block.tx_hashes.len();
+    let started = Instant::now();
+    let tx_cfg = work_tx::Config {
+        rpc,
+        limiter,
+        concurrency: 1,
+        memory: Default::default(),
+    };
+    let msg = work_tx::fetch_block_txs(&tx_cfg, block)
+        .await
+        .context("fetch block transactions")?;
+    timings.push(timing("fetch_txs", started.elapsed()));
+
+    let started = Instant::now();
+    let mut jobs = Vec::with_capacity(msg.tx_jsons.len() + 1);
+    if let (Some(json), Some(hash)) = (&msg.miner_tx_json, msg.miner_tx_hash) {
+        jobs.push((json.as_str(), hash, true));
+    }
+    for (hash, json) in msg.ordered_tx_hashes.iter().zip(&msg.tx_jsons) {
+        jobs.push((json.as_str(), *hash, false));
+    }
+    let txs: Vec<TxTrace> = jobs
+        .into_iter()
+        .map(|(json, hash, coinbase)| trace_tx(json, hash, coinbase, msg.header.major_version))
+        .collect();
+    timings.push(timing("prepare", started.elapsed()));
+
+    let mut checks = vec![
+        Check::new(
+            "miner_tx",
+            msg.miner_tx_json.is_some() && msg.miner_tx_hash.is_some(),
+            "miner tx json and hash present in the block",
+        ),
+        Check::new(
+            "tx_count",
+            msg.tx_jsons.len() == expected_txs,
+            format!(
+                "block lists {expected_txs} txs, daemon returned {}",
+                msg.tx_jsons.len()
+            ),
+        ),
+    ];
+    let failed: Vec<String> = txs
+        .iter()
+        .filter_map(|t| {
+            t.error
+                .as_ref()
+                .map(|e| format!("{}: {e}", t.requested_hash))
+        })
+        .collect();
+    checks.push(Check::new(
+        "tx_parse",
+        failed.is_empty(),
+        if failed.is_empty() {
+            format!("{} txs parsed", txs.len())
+        } else {
+            failed.join("; ")
+        },
+    ));
+    let mismatched: Vec<String> = txs
+        .iter()
+        .filter_map(|t| {
+            let derived = t.row.as_ref()?.get("hash")?.as_str()?;
+            (derived != t.requested_hash.to_hex())
+                .then(|| format!("{} parsed as {derived}", t.requested_hash))
+        })
+        .collect();
+    checks.push(Check::new(
+        "tx_hashes",
+        mismatched.is_empty(),
+        if mismatched.is_empty() {
+            "parsed hashes match the block".to_string()
+        } else {
+            mismatched.join("; ")
+        },
+    ));
+
+    let started = Instant::now();
+    match store {
+        Some(store) => {
+            checks.push(emission_check(store, &msg.header, height, &txs).await?);
+            checks.push(stored_block_check(store, &msg.header, height).await?);
+            checks.push(stored_txs_check(store, height, &txs).await?);
+        }
+        None => {
+            for name in ["emission", "stored_block", "stored_txs"] {
+                checks.push(Check::skipped(name, "no database configured"));
+            }
+        }
+    }
+    timings.push(timing("validate", started.elapsed()));
+
+    Ok(BlockTrace {
+        height,
+        header: msg.header,
+        miner_tx_hash: msg.miner_tx_hash,
+        txs,
+        timings_ms: timings,
+        checks,
+    })
+}
+
+fn timing(stage: &'static str, elapsed: Duration) -> StageTiming {
+    StageTiming {
+        stage,
+        ms: elapsed.as_secs_f64() * 1000.0,
+    }
+}
+
+fn trace_tx(json: &str, requested_hash: TxHash, coinbase: bool, major_version: u32) -> TxTrace {
+    // The row shows the raw and decoded extra whatever the ingestor retains.
+    let row = work_persist::prepare_tx(
+        json,
+        Some(requested_hash),
+        Some(major_version),
+        true,
+        codec::ExtraRetention::Both,
+    )
+    .and_then(|prepared| Ok(serde_json::to_value(prepared)?));
+    let analysis = codec::parse_tx_json(json).and_then(|tx| codec::analyze_tx(&tx));
+    let error = match (&row, &analysis) {
+        (Err(err), _) | (_, Err(err)) => Some(format!("{err:#}")),
+        _ => None,
+    };
+    TxTrace {
+        requested_hash,
+        coinbase,
+        row: row.ok(),
+        analysis: analysis.ok(),
+        error,
+    }
+}
+
+async fn emission_check(
+    store: &Store,
+    header: &BlockHeader,
+    height: i64,
+    txs: &[TxTrace],
+) -> Result<Check> {
+    let Some(generated) = store
+        .supply_before(height)
+        .await
+        .context("read supply before block")?
+    else {
+        return Ok(Check::skipped(
+            "emission",
+            "supply below this height is not recorded",
+        ));
+    };
+    let fees: u64 = txs
+        .iter()
+        .filter_map(|t| t.row.as_ref()?.get("fee")?.as_u64())
+        .sum();
+    let reported = header.reward.saturating_sub(fees);
+    let expected = codec::expected_base_reward(generated, header.major_version);
+    let deviation =
+        codec::emission_deviation(reported, expected, header.size, header.major_version);
+    Ok(Check::new(
+        "emission",
+        deviation.is_none(),
+        format!(
+            "reported base {reported}, expected {expected}, fees {fees}, deviation {}",
+            deviation.unwrap_or(0)
+        ),
+    ))
+}
+
+async fn stored_block_check(store: &Store, header: &BlockHeader, height: i64) -> Result<Check> {
+    Ok(
+        match store
+            .block_hash_at(height)
+            .await
+            .context("read stored block hash")?
+        {
+            None => Check::skipped("stored_block", "height not ingested"),
+            Some(stored) => Check::new(
+                "stored_block",
+                stored == header.hash,
+                format!("stored {stored}, daemon {}", header.hash),
+            ),
+        },
+    )
+}
+
+async fn stored_txs_check(store: &Store, height: i64, txs: &[TxTrace]) -> Result<Check> {
+    let stored: HashMap<TxHash, serde_json::Value> = store
+        .stored_block_txs(height)
+        .await
+        .context("read stored txs")?
+        .into_iter()
+        .collect();
+    if stored.is_empty() {
+        return Ok(Check::skipped("stored_txs", "no txs stored at this height"));
+    }
+
+    let mut diffs = Vec::new();
+    for t in txs {
+        let Some(derived) = &t.row else { continue };
+        let Some(cols) = stored.get(&t.requested_hash).and_then(|v| v.as_object()) else {
+            diffs.push(format!("{} not stored", t.requested_hash));
+            continue;
+        };
+        for (col, stored_value) in cols {
+            let derived_value = derived.get(col).unwrap_or(&serde_json::Value::Null);
+            if derived_value != stored_value {
+                diffs.push(format!(
+                    "{}.{col}: stored {stored_value}, derived {derived_value}",
+                    t.requested_hash
+                ));
+            }
+        }
+    }
+    let extra = stored.len().saturating_sub(txs.len());
+    if extra > 0 {
+        diffs.push(format!("{extra} stored txs not in the daemon's block"));
+    }
+    Ok(Check::new(
+        "stored_txs",
+        diffs.is_empty(),
+        if diffs.is_empty() {
+            format!("{} stored txs match", stored.len())
+        } else {
+            diffs.join("; ")
+        },
+    ))
+}
@@ -1,15 +1,20 @@
 use std::{collections::VecDeque, convert::TryFrom, fmt, sync::Arc};
 
 use anyhow::{anyhow, Context, Result};
+use bex_core::TxHash;
 use governor::DefaultDirectRateLimiter;
-use hex::FromHex;
 use tokio::sync::{mpsc, Mutex};
 use tracing::{info, warn};
 
 use crate::{
-    pipeline::{BlockMsg, SchedMsg, Shutdown},
+    alerts, blob,
+    capabilities::LiveCapabilities,
+    events::{Event, Events},
+    memory::MemoryBudget,
+    pipeline::{BlockMsg, SchedMsg, Shutdown, WorkerMetrics, WorkerState},
+    prefetch::Prefetcher,
     reorg::heal_reorg,
-    rpc::{BlockHeader, Capabilities, MoneroRpc},
+    rpc::{BlockHeader, GetBlockResult, MoneroRpc},
     store::Store,
 };
 
@@ -18,9 +23,14 @@ pub struct Config {
     pub rpc: Arc<dyn MoneroRpc>,
     pub limiter: Arc<DefaultDirectRateLimiter>,
     pub store: Store,
-    pub finality_window: u64,
-    pub caps: Capabilities,
+    pub max_reorg_depth: u64,
+    pub caps: Arc<LiveCapabilities>,
     pub header_batch: u64,
+    pub events: Events,
+    /// Headers fetched ahead by the prefetch task, consulted by `run` only.
+    pub prefetch: Option<Arc<Prefetcher>>,
+    /// Shrinks header ranges while throttled.
+    pub memory: MemoryBudget,
 }
 
 pub async fn run(
@@ -32,9 +42,10 @@ pub async fn run(
     let mut headers = HeaderFetcher::new(
         Arc::clone(&cfg.rpc),
         Arc::clone(&cfg.limiter),
-        cfg.caps,
+        Some(Arc::clone(&cfg.caps)),
         cfg.header_batch,
-    );
+    )
+    .with_memory(cfg.memory.clone());
 
     if headers.using_bulk() {
         info!(batch = headers.batch_size(), "using bulk header fetch");
@@ -42,7 +53,9 @@ pub async fn run(
         info!("using single header fetch");
     }
 
+    let mut worker = WorkerMetrics::register("block");
     loop {
+        worker.enter(WorkerState::RecvWait);
         let job = {
             let mut guard = rx.lock().await;
             let job = guard.recv().await;
@@ -53,35 +66,55 @@ pub async fn run(
             break;
         };
 
-        let current = job;
-        let block = loop {
-            match process_height(&cfg, &mut headers, &current).await {
-                Ok(block) => break block,
-                Err(err) => {
-                    if err.downcast_ref::<ReorgDetected>().is_some() {
+        // Healing a reorg removes every height from the fork up, so those are
+        // fetched again, in order, before the scheduled one.
+        let mut height = job.height;
+        while height <= job.height {
+            worker.enter(WorkerState::Busy);
+            let current = SchedMsg { height, ..job };
+            let prefetched = match (&cfg.prefetch, u64::try_from(height)) {
+                (Some(prefetch), Ok(height)) => prefetch.take(height),
+                _ => None,
+            };
+            let block = match process_height(&cfg, &mut headers, prefetched, &current).await {
+                Ok(block) => block,
+                Err(err) => match err.downcast_ref::<ReorgDetected>() {
+                    Some(reorg) => {
+                        height = reorg.fork_height.min(height);
                         continue;
                     }
-                    return Err(err);
-                }
-            }
-        };
+                    None => return Err(err),
+                },
+            };
 
-        if tx.send(block).await.is_err() {
-            break;
+            worker.enter(WorkerState::SendWait);
+            if tx.send(block).await.is_err() {
+                return Ok(());
+            }
+            crate::pipeline::record_queue_depth_sender("block", &tx);
+            height += 1;
         }
-
-        crate::pipeline::record_queue_depth_sender("block", &tx);
     }
 
     Ok(())
 }
 
+/// Fetches a single height outside the scheduler, e.g. for operator
+/// re-ingestion. Uses single header requests regardless of capabilities.
+pub async fn fetch_block(cfg: &Config, msg: &SchedMsg) -> Result<BlockMsg> {
+    let mut headers = HeaderFetcher::new(Arc::clone(&cfg.rpc), Arc::clone(&cfg.limiter), None, 1);
+    process_height(cfg, &mut headers, None, msg).await
+}
+
 #[derive(Debug)]
-struct ReorgDetected;
+struct ReorgDetected {
+    /// First height the healing removed.
+    fork_height: i64,
+}
 
 impl fmt::Display for ReorgDetected {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "reorg detected")
+        write!(f, "reorg detected, healed from height {}", self.fork_height)
     }
 }
 
@@ -90,56 +123,130 @@ impl std::error::Error for ReorgDetected {}
 async fn process_height(
     cfg: &Config,
     headers: &mut HeaderFetcher,
+    prefetched: Option<(BlockHeader, Option<GetBlockResult>)>,
     msg: &SchedMsg,
 ) -> Result<BlockMsg> {
-    let height_u64 = u64::try_from(msg.height).context("height became negative")?;
-    let header = headers.fetch(height_u64).await?;
+    let (header, block) = match prefetched {
+        Some(prefetched) => prefetched,
+        None => {
+            let height_u64 = u64::try_from(msg.height).context("height became negative")?;
+            headers.fetch_with_block(height_u64).await?
+        }
+    };
 
-    let prev_hex = header.prev_hash.clone();
-    let prev_bytes = <[u8; 32]>::from_hex(&prev_hex).unwrap_or([0u8; 32]);
     if let Some(expected_prev) = cfg
         .store
         .block_hash_at((header.height as i64) - 1)
         .await
         .context("fetch previous hash")?
     {
-        if expected_prev.as_slice() != &prev_bytes {
+        if expected_prev != header.prev_hash {
             warn!(
                 height = header.height,
                 "REORG DETECTED at height {}: header.prev != stored hash(h-1)", header.height
             );
-            let finality_window = i64::try_from(cfg.finality_window).unwrap_or(i64::MAX);
-            heal_reorg(
+            let max_depth = i64::try_from(cfg.max_reorg_depth).unwrap_or(i64::MAX);
+            let fork_height = heal_reorg(
                 header.height as i64,
                 &cfg.store,
                 cfg.rpc.as_ref(),
-                finality_window,
+                max_depth,
             )
             .await?;
-            return Err(ReorgDetected.into());
+            let depth = header.height as i64 - fork_height;
+            alerts::record_reorg(u64::try_from(depth).unwrap_or(0));
+            cfg.events.publish(Event::Reorg { fork_height, depth });
+            if let Some(prefetch) = &cfg.prefetch {
+                prefetch.clear();
+            }
+            return Err(ReorgDetected { fork_height }.into());
         }
     }
 
-    let (block_json, miner_tx_hash) =
-        fetch_block_json(cfg.rpc.as_ref(), &cfg.limiter, &header).await?;
-    let block_value: serde_json::Value =
-        serde_json::from_str(&block_json).context("parse block json")?;
+    assemble_block(cfg.rpc.as_ref(), &cfg.limiter, header, block, msg).await
+}
 
-    let miner_tx_json = block_value
-        .get("miner_tx")
-        .cloned()
-        .map(|v| serde_json::to_string(&v))
-        .transpose()
-        .context("serialize miner tx")?;
+/// Fetches a single height without comparing it to the stored chain, so no
+/// reorg is healed and nothing is written. Used by `trace-block`.
+pub async fn fetch_block_detached(
+    rpc: &dyn MoneroRpc,
+    limiter: &Arc<DefaultDirectRateLimiter>,
+    msg: &SchedMsg,
+) -> Result<BlockMsg> {
+    let height = u64::try_from(msg.height).context("height became negative")?;
+    limiter.until_ready().await;
+    let header = rpc
+        .get_block_header_by_height(height)
+        .await
+        .context("fetch header")?
+        .block_header;
+    assemble_block(rpc, limiter, header, None, msg).await
+}
 
-    let mut tx_hashes = extract_tx_hashes(&block_value);
-    tx_hashes.retain(|h| !h.is_empty());
+/// Builds the pipeline message for `header`, fetching the block by hash
+/// unless `block` already holds it.
+async fn assemble_block(
+    rpc: &dyn MoneroRpc,
+    limiter: &Arc<DefaultDirectRateLimiter>,
+    header: BlockHeader,
+    block: Option<GetBlockResult>,
+    msg: &SchedMsg,
+) -> Result<BlockMsg> {
+    let blk = match block {
+        Some(blk) => blk,
+        None => {
+            limiter.until_ready().await;
+            rpc.get_block(&header.hash, false)
+                .await
+                .with_context(|| format!("fetch block {}", header.hash))?
+        }
+    };
+    let miner_tx_hash = blk
+        .miner_tx_hash
+        .as_deref()
+        .filter(|h| !h.is_empty())
+        .map(TxHash::from_hex)
+        .transpose()
+        .context("parse miner_tx_hash")?;
+
+    let parsed = blk.blob.as_deref().and_then(|blob| {
+        blob::parse_block_hex(blob)
+            .and_then(|parsed| parsed.check_header(&header).map(|_| parsed))
+            .inspect_err(|err| {
+                metrics::counter!("ingest_anomalies_total", "kind" => "block_blob").increment(1);
+                warn!(height = header.height, hash = %header.hash, error = ?err,
+                    "block blob unusable, falling back to daemon json");
+            })
+            .ok()
+    });
+    let (miner_tx_json, tx_hashes) = match parsed {
+        Some(parsed) => {
+            metrics::counter!("block_decode_total", "source" => "blob").increment(1);
+            (Some(parsed.miner_tx.to_string()), parsed.tx_hashes)
+        }
+        None => {
+            metrics::counter!("block_decode_total", "source" => "json").increment(1);
+            let block_json = blk
+                .json
+                .ok_or_else(|| anyhow!("block json missing for height {}", header.height))?;
+            let block_value: serde_json::Value =
+                serde_json::from_str(&block_json).context("parse block json")?;
+            let miner_tx_json = block_value
+                .get("miner_tx")
+                .map(serde_json::to_string)
+                .transpose()
+                .context("serialize miner tx")?;
+            let tx_hashes = extract_tx_hashes(&block_value)
+                .with_context(|| format!("parse tx_hashes of block {}", header.hash))?;
+            (miner_tx_json, tx_hashes)
+        }
+    };
 
     let ts = i64::try_from(header.timestamp).context("timestamp overflow")?;
 
     Ok(BlockMsg {
         height: msg.height,
-        hash: header.hash.clone(),
+        hash: header.hash,
         tx_hashes,
         ts,
         tip_height: msg.tip_height,
@@ -151,49 +258,40 @@ async fn process_height(
     })
 }
 
-async fn fetch_block_json(
-    rpc: &dyn MoneroRpc,
-    limiter: &Arc<DefaultDirectRateLimiter>,
-    header: &BlockHeader,
-) -> Result<(String, Option<String>)> {
-    limiter.until_ready().await;
-    let blk = rpc
-        .get_block(&header.hash, false)
-        .await
-        .with_context(|| format!("fetch block {}", header.hash))?;
-    let miner_tx_hash = blk.miner_tx_hash.clone();
-    let json = blk
-        .json
-        .ok_or_else(|| anyhow!("block json missing for height {}", header.height))?;
-    Ok((json, miner_tx_hash))
-}
-
 struct HeaderFetcher {
     rpc: Arc<dyn MoneroRpc>,
     limiter: Arc<DefaultDirectRateLimiter>,
     buffered: VecDeque<BlockHeader>,
-    use_range: bool,
+    /// `None` restricts the fetcher to single header requests.
+    caps: Option<Arc<LiveCapabilities>>,
     batch_size: u64,
+    memory: MemoryBudget,
 }
 
 impl HeaderFetcher {
     fn new(
         rpc: Arc<dyn MoneroRpc>,
         limiter: Arc<DefaultDirectRateLimiter>,
-        caps: Capabilities,
+        caps: Option<Arc<LiveCapabilities>>,
         batch_size: u64,
     ) -> Self {
         Self {
             rpc,
             limiter,
             buffered: VecDeque::new(),
-            use_range: caps.headers_range,
+            caps,
             batch_size: batch_size.max(1),
+            memory: MemoryBudget::default(),
         }
     }
 
+    fn with_memory(mut self, memory: MemoryBudget) -> Self {
+        self.memory = memory;
+        self
+    }
+
     fn using_bulk(&self) -> bool {
-        self.use_range
+        self.caps.as_ref().is_some_and(|c| c.get().headers_range)
     }
 
     fn batch_size(&self) -> u64 {
@@ -201,7 +299,7 @@ impl HeaderFetcher {
     }
 
     async fn fetch(&mut self, height: u64) -> Result<BlockHeader> {
-        if self.use_range {
+        if self.using_bulk() {
             if let Some(header) = self.take_buffered(height) {
                 return Ok(header);
             }
@@ -225,15 +323,59 @@ impl HeaderFetcher {
                 }
             }
 
-            self.use_range = false;
+            if let Some(caps) = &self.caps {
+                caps.disable_headers_range();
+            }
             self.buffered.clear();
         }
 
         self.fetch_single(height).await
     }
 
+    /// Header for `height`, plus the block itself when the header came from
+    /// `get_block` by height. Without bulk headers that saves the separate
+    /// header request; when the block's header lacks fields, the header is
+    /// fetched on its own and the block kept if it is the same one.
+    async fn fetch_with_block(
+        &mut self,
+        height: u64,
+    ) -> Result<(BlockHeader, Option<GetBlockResult>)> {
+        if self.using_bulk() {
+            // Range headers already amortize the header requests.
+            let header = self.fetch(height).await?;
+            metrics::counter!("block_fetch_total", "path" => "two_step").increment(1);
+            return Ok((header, None));
+        }
+
+        self.limiter.until_ready().await;
+        let res = self
+            .rpc
+            .get_block_by_height(height)
+            .await
+            .context("fetch block by height")?;
+        let header = match res.header() {
+            Some(header) => header,
+            None => {
+                warn!(
+                    height,
+                    "get_block by height lacks header fields, fetching header"
+                );
+                self.fetch_single(height).await?
+            }
+        };
+        let block = res.with_header(header.clone());
+        let path = if block.is_some() {
+            "by_height"
+        } else {
+            "two_step"
+        };
+        metrics::counter!("block_fetch_total", "path" => path).increment(1);
+        Ok((header, block))
+    }
+
     async fn fill_batch(&mut self, start: u64) -> Result<()> {
-        let end = start.saturating_add(self.batch_size.saturating_sub(1));
+        let batch_size = self.memory.scale(self.batch_size);
+        let end = start.saturating_add(batch_size.saturating_sub(1));
         self.limiter.until_ready().await;
         let headers = self
             .rpc
@@ -279,7 +421,7 @@ impl HeaderFetcher {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::limits;
+    use crate::{limits, rpc::Capabilities};
     use axum::{extract::State, response::Json, routing::post, Router};
     use serde::Deserialize;
     use serde_json::{json, Value};
@@ -291,7 +433,10 @@ mod tests {
     struct ServerState {
         range_calls: Arc<AtomicUsize>,
         single_calls: Arc<AtomicUsize>,
+        block_calls: Arc<AtomicUsize>,
         fail_range: bool,
+        /// `get_block` by height answers with a header missing most fields.
+        partial_header: bool,
     }
 
     #[derive(Deserialize)]
@@ -301,11 +446,16 @@ mod tests {
         params: Value,
     }
 
-    async fn spawn_server(fail_range: bool) -> (String, Arc<ServerState>, JoinHandle<()>) {
+    async fn spawn_server(
+        fail_range: bool,
+        partial_header: bool,
+    ) -> (String, Arc<ServerState>, JoinHandle<()>) {
         let state = Arc::new(ServerState {
             range_calls: Arc::new(AtomicUsize::new(0)),
             single_calls: Arc::new(AtomicUsize::new(0)),
+            block_calls: Arc::new(AtomicUsize::new(0)),
             fail_range,
+            partial_header,
         });
 
         let app_state = state.clone();
@@ -357,6 +507,28 @@ mod tests {
                                 "result": {"status": "OK", "block_header": header_json(height)},
                             })
                         }
+                        "get_block" => {
+                            state.block_calls.fetch_add(1, Ordering::SeqCst);
+                            let by_height = req.params.get("height").and_then(Value::as_u64);
+                            let height = by_height.unwrap_or_else(|| {
+                                let hash = req.params["hash"].as_str().unwrap_or_default();
+                                u64::from_str_radix(hash, 16).unwrap_or(1) - 1
+                            });
+                            let block_header = if state.partial_header && by_height.is_some() {
+                                json!({ "height": height, "hash": format!("{:064x}", height + 1) })
+                            } else {
+                                header_json(height)
+                            };
+                            json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "result": {
+                                    "status": "OK",
+                                    "block_header": block_header,
+                                    "json": json!({ "tx_hashes": [] }).to_string(),
+                                },
+                            })
+                        }
                         _ => json!({
                             "jsonrpc": "2.0",
                             "id": id,
@@ -396,16 +568,16 @@ mod tests {
 
     #[tokio::test]
     async fn header_fetcher_uses_range_when_available() {
-        let (base, state, handle) = spawn_server(false).await;
+        let (base, state, handle) = spawn_server(false, false).await;
         let rpc: Arc<dyn MoneroRpc> = Arc::new(crate::rpc::Rpc::new(format!("{}/json_rpc", base)));
         let limiter = Arc::new(limits::make_limiter(100, false));
         let mut fetcher = HeaderFetcher::new(
             rpc,
             limiter,
-            Capabilities {
+            Some(Arc::new(LiveCapabilities::new(Capabilities {
                 headers_range: true,
                 blocks_by_height_bin: false,
-            },
+            }))),
             3,
         );
 
@@ -423,16 +595,16 @@ mod tests {
 
     #[tokio::test]
     async fn header_fetcher_falls_back_when_range_fails() {
-        let (base, state, handle) = spawn_server(true).await;
+        let (base, state, handle) = spawn_server(true, false).await;
         let rpc: Arc<dyn MoneroRpc> = Arc::new(crate::rpc::Rpc::new(format!("{}/json_rpc", base)));
         let limiter = Arc::new(limits::make_limiter(100, false));
         let mut fetcher = HeaderFetcher::new(
             rpc,
             limiter,
-            Capabilities {
+            Some(Arc::new(LiveCapabilities::new(Capabilities {
                 headers_range: true,
                 blocks_by_height_bin: false,
-            },
+            }))),
             3,
         );
 
@@ -447,16 +619,52 @@ mod tests {
         handle.abort();
         let _ = handle.await;
     }
+
+    #[tokio::test]
+    async fn single_fetches_take_header_from_block_by_height() {
+        for partial_header in [false, true] {
+            let (base, state, handle) = spawn_server(false, partial_header).await;
+            let rpc: Arc<dyn MoneroRpc> =
+                Arc::new(crate::rpc::Rpc::new(format!("{}/json_rpc", base)));
+            let limiter = Arc::new(limits::make_limiter(100, false));
+            let mut fetcher = HeaderFetcher::new(Arc::clone(&rpc), Arc::clone(&limiter), None, 1);
+
+            let (header, block) = fetcher.fetch_with_block(5).await.expect("fetch 5");
+            assert_eq!(header.height, 5);
+            assert_eq!(header.hash.to_hex(), format!("{:064x}", 6));
+            let msg = SchedMsg {
+                height: 5,
+                tip_height: 5,
+                finalized_height: 0,
+                started: std::time::Instant::now(),
+            };
+            let block = assemble_block(rpc.as_ref(), &limiter, header, block, &msg)
+                .await
+                .expect("assemble");
+            assert_eq!(block.height, 5);
+
+            // A full header costs one call; a partial one adds the header
+            // request but keeps the block it came with.
+            let single = usize::from(partial_header);
+            assert_eq!(state.single_calls.load(Ordering::SeqCst), single);
+            assert_eq!(state.block_calls.load(Ordering::SeqCst), 1);
+
+            handle.abort();
+            let _ = handle.await;
+        }
+    }
 }
 
-fn extract_tx_hashes(block: &serde_json::Value) -> Vec<String> {
+fn extract_tx_hashes(block: &serde_json::Value) -> Result<Vec<TxHash>> {
     block
         .get("tx_hashes")
         .and_then(|v| v.as_array())
         .map(|arr| {
             arr.iter()
-                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .filter_map(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(|s| TxHash::from_hex(s).with_context(|| format!("invalid tx hash {s:?}")))
                 .collect()
         })
-        .unwrap_or_default()
+        .unwrap_or_else(|| Ok(Vec::new()))
 }
@@ -1,14 +1,19 @@
 use std::{convert::TryFrom, sync::Arc};
 
 use anyhow::{Context, Result};
-use tokio::sync::mpsc;
+use bex_core::TxHash;
+use serde::Serialize;
+use sqlx::{Postgres, Transaction};
+use tokio::sync::{mpsc, watch, Notify, Semaphore};
 use tracing::{info, warn};
 
 use crate::{
     checkpoint::Checkpoint,
-    codec::{analyze_tx, parse_tx_json},
-    pipeline::{Shutdown, TxMsg},
-    store::Store,
+    codec::{self, analyze_tx, parse_tx_json, ExtraRetention},
+    confirmations::{self, ChainPosition},
+    events::{Event, Events},
+    pipeline::{Shutdown, TxMsg, WorkerMetrics, WorkerState},
+    store::{RecordedEmission, Store, UpsertOutcome},
 };
 
 pub struct Config {
@@ -16,6 +21,98 @@ pub struct Config {
     pub checkpoint: Arc<Checkpoint>,
     pub finality_window: u64,
     pub do_analytics: bool,
+    pub chain_tips_retention: u64,
+    /// Signalled after each commit so the analytics worker picks up the block.
+    pub analytics_wake: Option<Arc<Notify>>,
+    /// Receives the chain position after each commit; the confirmation
+    /// refresher rewrites the finality window from it.
+    pub position_tx: Option<watch::Sender<ChainPosition>>,
+    pub prepare_pool: PreparePool,
+    /// Receives a `new_block` event for each block the chain advances by.
+    pub events: Events,
+    /// What `txs.extra` keeps of each tx's extra field.
+    pub extra_retention: ExtraRetention,
+}
+
+/// Bounded set of blocking threads that parse and analyze a block's txs in
+/// parallel. Results are collected in block order, so commit order is
+/// unchanged; the permits cap how many txs are in flight at once.
+#[derive(Clone)]
+pub struct PreparePool {
+    permits: Arc<Semaphore>,
+}
+
+impl PreparePool {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(workers.max(1))),
+        }
+    }
+
+    async fn prepare_all(
+        &self,
+        jobs: Vec<(String, TxHash)>,
+        major_version: u32,
+        do_analytics: bool,
+        extra_retention: ExtraRetention,
+    ) -> Result<PreparedBlock> {
+        let mut handles = Vec::with_capacity(jobs.len());
+        for (json, hash) in jobs {
+            let permit = Arc::clone(&self.permits)
+                .acquire_owned()
+                .await
+                .context("prepare pool closed")?;
+            handles.push(tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                let prepared = prepare_tx(
+                    &json,
+                    Some(hash),
+                    Some(major_version),
+                    do_analytics,
+                    extra_retention,
+                );
+                (json, hash, prepared)
+            }));
+        }
+
+        let mut block = PreparedBlock {
+            order: Vec::with_capacity(handles.len()),
+            txs: Vec::with_capacity(handles.len()),
+            quarantined: Vec::new(),
+        };
+        for handle in handles {
+            let (raw_json, hash, prepared) = handle.await.context("tx prepare task failed")?;
+            match prepared {
+                Ok(tx) => {
+                    block.order.push(tx.hash);
+                    block.txs.push(tx);
+                }
+                Err(err) => {
+                    block.order.push(hash);
+                    block.quarantined.push(QuarantinedTx {
+                        hash,
+                        raw_json,
+                        error: format!("{err:#}"),
+                    });
+                }
+            }
+        }
+        Ok(block)
+    }
+}
+
+/// A block's txs after parsing. Txs the codec rejects are quarantined rather
+/// than failing the block; `order` still lists them in their block position.
+pub(crate) struct PreparedBlock {
+    pub(crate) order: Vec<TxHash>,
+    pub(crate) txs: Vec<PreparedTx>,
+    pub(crate) quarantined: Vec<QuarantinedTx>,
+}
+
+pub(crate) struct QuarantinedTx {
+    pub(crate) hash: TxHash,
+    pub(crate) raw_json: String,
+    pub(crate) error: String,
 }
 
 pub async fn run(
@@ -24,30 +121,93 @@ pub async fn run(
     _shutdown: Option<Shutdown>,
 ) -> Result<()> {
     let mut processed = 0u64;
+    let mut last_position = None;
+    let mut worker = WorkerMetrics::register("persist");
     loop {
+        worker.enter(WorkerState::RecvWait);
         let maybe_msg = rx.recv().await;
         crate::pipeline::record_queue_depth_receiver("tx", &rx);
         let Some(msg) = maybe_msg else {
             break;
         };
-        let prepared = prepare_block(&msg, cfg.do_analytics)?;
-        persist_block(&cfg, &msg, &prepared).await?;
+        worker.enter(WorkerState::Busy);
+        let prepared = prepare_block(&msg, &cfg).await?;
+        persist_block(&cfg, &msg, &prepared, PersistMode::Advance).await?;
+        let position = ChainPosition {
+            tip_height: msg.tip_height,
+            finalized_height: msg.finalized_height,
+        };
+        if let Some(position_tx) = &cfg.position_tx {
+            position_tx.send_replace(position);
+        }
+        last_position = Some(position);
         metrics::histogram!("block_process_ms").record(msg.started.elapsed().as_millis() as f64);
         processed += 1;
         if processed % 100 == 0 {
             info!(processed, "persistence progress");
         }
     }
+    if let Some(position) = last_position {
+        confirmations::refresh(&cfg.store, cfg.finality_window, position, &cfg.events).await?;
+    }
     info!(processed, "persistence complete");
     Ok(())
 }
 
-fn prepare_block(msg: &TxMsg, do_analytics: bool) -> Result<Vec<PreparedTx>> {
-    let mut prepared = Vec::with_capacity(msg.tx_jsons.len() + 1);
+/// Re-persists an already ingested block in place. Unlike the pipeline path it
+/// leaves the checkpoint and chain tip untouched, and detaches txs stored at
+/// this height that the daemon's copy of the block no longer lists.
+pub async fn reingest_block(cfg: &Config, msg: &TxMsg) -> Result<()> {
+    let prepared = prepare_block(msg, cfg).await?;
+    persist_block(cfg, msg, &prepared, PersistMode::Reingest).await
+}
+
+/// Re-processes blocks whose persistence started but never committed, as
+/// recorded in `block_ingest_state`. Heights above the checkpoint are picked
+/// up again by the scheduler; those at or below it (an interrupted
+/// re-ingestion) go back on the re-ingestion queue. Returns how many
+/// incomplete blocks were found.
+pub async fn resume_incomplete(store: &Store, checkpoint: &Checkpoint) -> Result<usize> {
+    let heights = store
+        .incomplete_block_ingests()
+        .await
+        .context("read block ingest state")?;
+    if heights.is_empty() {
+        return Ok(0);
+    }
+
+    let ingested = checkpoint.get().await.context("read checkpoint")?;
+    for &height in &heights {
+        if height <= ingested {
+            store
+                .enqueue_reingest(height, "ingestor-resume")
+                .await
+                .context("queue interrupted re-ingestion")?;
+            warn!(height, "re-queued block whose re-ingestion was interrupted");
+        } else {
+            warn!(
+                height,
+                "block persistence was interrupted; scheduler resumes it"
+            );
+        }
+    }
+    Ok(heights.len())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PersistMode {
+    /// Normal pipeline order: the block becomes the new tip.
+    Advance,
+    /// Operator re-ingestion of a height at or below the checkpoint.
+    Reingest,
+}
+
+async fn prepare_block(msg: &TxMsg, cfg: &Config) -> Result<PreparedBlock> {
+    let mut jobs = Vec::with_capacity(msg.tx_jsons.len() + 1);
 
     if let Some(json) = &msg.miner_tx_json {
-        if let Some(fallback_hash) = msg.miner_tx_hash.as_deref() {
-            prepared.push(prepare_tx(json, Some(fallback_hash), do_analytics)?);
+        if let Some(fallback_hash) = msg.miner_tx_hash {
+            jobs.push((json.clone(), fallback_hash));
         } else {
             warn!(height = msg.height, "miner_tx hash missing for block");
         }
@@ -65,22 +225,38 @@ fn prepare_block(msg: &TxMsg, do_analytics: bool) -> Result<Vec<PreparedTx>> {
     }
 
     for (hash, json) in msg.ordered_tx_hashes.iter().zip(msg.tx_jsons.iter()) {
-        prepared.push(prepare_tx(json, Some(hash), do_analytics)?);
+        jobs.push((json.clone(), *hash));
     }
 
-    Ok(prepared)
+    cfg.prepare_pool
+        .prepare_all(
+            jobs,
+            msg.header.major_version,
+            cfg.do_analytics,
+            cfg.extra_retention,
+        )
+        .await
 }
 
-async fn persist_block(cfg: &Config, msg: &TxMsg, txs: &[PreparedTx]) -> Result<()> {
+async fn persist_block(
+    cfg: &Config,
+    msg: &TxMsg,
+    prepared: &PreparedBlock,
+    mode: PersistMode,
+) -> Result<()> {
+    let txs = &prepared.txs;
+    let block_height = i64::try_from(msg.header.height).context("height overflow")?;
+    cfg.store
+        .begin_block_ingest(block_height, &msg.header.hash)
+        .await
+        .context("mark block ingest in progress")?;
+
     let mut db_tx = cfg
         .store
         .begin_block()
         .await
         .context("open sql transaction")?;
-    let mut mark_analytics_pending = false;
 
-    let hash_bytes = hex::decode(&msg.header.hash).context("decode block hash")?;
-    let prev_hash_bytes = hex::decode(&msg.header.prev_hash).context("decode prev hash")?;
     let ts = i64::try_from(msg.header.timestamp).context("timestamp overflow")?;
     let size_bytes = i32::try_from(msg.header.size).unwrap_or(i32::MAX);
     let major = i32::try_from(msg.header.major_version).context("major version overflow")?;
@@ -88,26 +264,38 @@ async fn persist_block(cfg: &Config, msg: &TxMsg, txs: &[PreparedTx]) -> Result<
     let nonce = i64::try_from(msg.header.nonce).context("nonce overflow")?;
     let reward = i64::try_from(msg.header.reward).context("reward overflow")?;
 
-    let block_height = i64::try_from(msg.header.height).context("height overflow")?;
-
-    Store::insert_block(
+    let block_outcome = Store::insert_block(
         &mut db_tx,
         block_height,
-        &hash_bytes,
-        &prev_hash_bytes,
+        &msg.header.hash,
+        &msg.header.prev_hash,
         ts,
         size_bytes,
         major,
         minor,
         nonce,
-        i32::try_from(txs.len()).unwrap_or(i32::MAX),
+        i32::try_from(prepared.order.len()).unwrap_or(i32::MAX),
         reward,
     )
     .await
     .context("insert block")?;
+    if msg.header.difficulty > 0 {
+        let difficulty = i64::try_from(msg.header.difficulty).unwrap_or(i64::MAX);
+        Store::set_block_difficulty(&mut db_tx, block_height, difficulty)
+            .await
+            .context("record block difficulty")?;
+    }
+    if block_outcome == UpsertOutcome::Conflict {
+        warn!(
+            height = block_height,
+            hash = %msg.header.hash,
+            "stored block differed from incoming data; overwritten"
+        );
+        metrics::counter!("ingest_anomalies_total", "kind" => "block_conflict").increment(1);
+    }
 
     for tx in txs {
-        Store::insert_tx(
+        let tx_outcome = Store::insert_tx(
             &mut db_tx,
             &tx.hash,
             Some(block_height),
@@ -123,28 +311,125 @@ async fn persist_block(cfg: &Config, msg: &TxMsg, txs: &[PreparedTx]) -> Result<
             tx.bp_plus,
             tx.num_inputs,
             tx.num_outputs,
+            tx.nonstandard_reasons.as_deref(),
         )
         .await
         .context("insert tx")?;
+        if tx_outcome == UpsertOutcome::Conflict {
+            warn!(
+                height = block_height,
+                tx = %tx.hash,
+                "stored tx differed from incoming data; overwritten"
+            );
+            metrics::counter!("ingest_anomalies_total", "kind" => "tx_conflict").increment(1);
+        }
+    }
+
+    if let (Some(_), Some(miner_tx_hash)) = (&msg.miner_tx_json, msg.miner_tx_hash) {
+        Store::mark_coinbase(&mut db_tx, &miner_tx_hash)
+            .await
+            .context("mark miner tx")?;
     }
 
-    let included_hex: Vec<String> = txs.iter().map(|tx| tx.hash_hex.clone()).collect();
-    Store::evict_mempool_on_inclusion(&mut db_tx, &included_hex)
+    // Quarantined txs keep their place in `included`: positions, mempool
+    // eviction and re-ingestion detaching all see the daemon's full block.
+    let persisted: Vec<TxHash> = txs.iter().map(|tx| tx.hash).collect();
+    Store::release_quarantined(&mut db_tx, &persisted)
+        .await
+        .context("release quarantined txs")?;
+    if !prepared.quarantined.is_empty() {
+        let (hashes, (raw_jsons, errors)): (Vec<_>, (Vec<_>, Vec<_>)) = prepared
+            .quarantined
+            .iter()
+            .map(|q| (q.hash, (q.raw_json.clone(), q.error.clone())))
+            .unzip();
+        Store::quarantine_txs(
+            &mut db_tx,
+            block_height,
+            &msg.header.hash,
+            &hashes,
+            &raw_jsons,
+            &errors,
+        )
+        .await
+        .context("quarantine txs")?;
+        for q in &prepared.quarantined {
+            warn!(
+                height = block_height,
+                tx = %q.hash,
+                error = %q.error,
+                "tx failed to parse; quarantined"
+            );
+            metrics::counter!("ingest_anomalies_total", "kind" => "tx_quarantined").increment(1);
+        }
+    }
+    let included = &prepared.order;
+    Store::set_block_positions(&mut db_tx, block_height, included)
+        .await
+        .context("record tx block positions")?;
+    // v2 outputs, the miner tx's included, are indexed under amount 0. A
+    // quarantined tx's outputs are unknown, so the count waits for the block
+    // to be re-ingested rather than undercounting every total above it.
+    if prepared.quarantined.is_empty() {
+        let rct_outputs = txs
+            .iter()
+            .filter(|tx| tx.version >= 2)
+            .map(|tx| tx.num_outputs)
+            .sum();
+        Store::record_rct_outputs(&mut db_tx, block_height, rct_outputs)
+            .await
+            .context("record rct output count")?;
+    } else {
+        Store::forget_rct_outputs(&mut db_tx, block_height)
+            .await
+            .context("forget rct output count")?;
+    }
+    check_emission(&mut db_tx, msg, reward, prepared).await?;
+    Store::carry_mempool_first_seen(&mut db_tx, included)
+        .await
+        .context("carry mempool first_seen")?;
+    Store::evict_mempool_on_inclusion(&mut db_tx, included)
         .await
         .context("evict mempool on inclusion")?;
 
-    Store::record_tip(&mut db_tx, block_height, &hash_bytes, &prev_hash_bytes)
+    if mode == PersistMode::Reingest {
+        let detached = Store::detach_block_txs_except(&mut db_tx, block_height, included)
+            .await
+            .context("detach stale block txs")?;
+        if detached > 0 {
+            warn!(
+                height = block_height,
+                detached, "re-ingestion detached txs not in the daemon's block"
+            );
+        }
+    } else {
+        Store::record_tip(
+            &mut db_tx,
+            block_height,
+            &msg.header.hash,
+            &msg.header.prev_hash,
+        )
         .await
         .context("record chain tip")?;
 
-    if cfg.do_analytics {
-        Store::upsert_soft_facts_for_block(&mut db_tx, block_height)
-            .await
-            .context("upsert soft facts")?;
-    } else {
-        mark_analytics_pending = true;
+        if cfg.chain_tips_retention > 0 {
+            let retention = i64::try_from(cfg.chain_tips_retention).unwrap_or(i64::MAX);
+            let keep_from = block_height.saturating_sub(retention).saturating_add(1);
+            Store::prune_chain_tips(&mut db_tx, keep_from)
+                .await
+                .context("prune chain tips")?;
+            Store::prune_block_ingest_state(&mut db_tx, keep_from)
+                .await
+                .context("prune block ingest state")?;
+        }
     }
 
+    // Soft facts are computed off the critical path by the analytics worker
+    // (or `analytics-backfill` in bootstrap mode); persistence only enqueues.
+    Store::mark_analytics_pending(&mut db_tx, block_height)
+        .await
+        .context("enqueue analytics")?;
+
     let confirmations = msg
         .tip_height
         .saturating_sub(block_height)
@@ -155,64 +440,180 @@ async fn persist_block(cfg: &Config, msg: &TxMsg, txs: &[PreparedTx]) -> Result<
         .await
         .context("update block confirmations")?;
 
+    Store::complete_block_ingest(&mut db_tx, block_height, &msg.header.hash)
+        .await
+        .context("mark block ingest complete")?;
     db_tx.commit().await.context("commit block")?;
 
-    if mark_analytics_pending {
-        sqlx::query("UPDATE public.blocks SET analytics_pending = TRUE WHERE height=$1")
-            .bind(block_height)
-            .execute(cfg.store.pool())
+    if mode == PersistMode::Advance {
+        // Re-ingestion repeats work already counted when the height first landed.
+        metrics::counter!("ingested_blocks_total").increment(1);
+        metrics::counter!("ingested_txs_total").increment(txs.len() as u64);
+        metrics::counter!("ingested_bytes_total").increment(msg.header.size);
+        cfg.events.publish(Event::NewBlock {
+            height: msg.header.height,
+            hash: msg.header.hash,
+            prev_hash: msg.header.prev_hash,
+            timestamp: msg.header.timestamp,
+            tx_count: prepared.order.len(),
+        });
+    }
+
+    if cfg.do_analytics {
+        if let Some(wake) = &cfg.analytics_wake {
+            wake.notify_one();
+        }
+    }
+
+    if mode == PersistMode::Advance {
+        cfg.checkpoint
+            .set(block_height, msg.finalized_height)
             .await
-            .ok();
+            .context("update checkpoint")?;
     }
 
-    cfg.checkpoint
-        .set(block_height, msg.finalized_height)
-        .await
-        .context("update checkpoint")?;
+    Ok(())
+}
 
-    let window_extra = 16i64;
-    let finality_i64 = i64::try_from(cfg.finality_window).unwrap_or(i64::MAX / 2);
-    let span = finality_i64.max(1) + window_extra;
-    let start_height = (msg.tip_height - span).max(0);
-    cfg.store
-        .refresh_confirmations(start_height, msg.tip_height, msg.finalized_height)
+/// Records the coins the block emitted, split into the schedule's subsidy,
+/// the fees and what the miner tx claimed, and flags the block when the
+/// amount is off the emission schedule. The subsidy and the check wait until
+/// every lower height has been ingested, since the expected reward depends on
+/// the supply before the block; heights above that were waiting on this one
+/// are checked here. A block with quarantined txs is left
+/// unrecorded, as their fees are unknown, until it is re-ingested.
+async fn check_emission(
+    db_tx: &mut Transaction<'_, Postgres>,
+    msg: &TxMsg,
+    reward: i64,
+    prepared: &PreparedBlock,
+) -> Result<()> {
+    let height = i64::try_from(msg.header.height).context("height overflow")?;
+    if !prepared.quarantined.is_empty() {
+        return Store::forget_emission(db_tx, height)
+            .await
+            .context("forget block emission");
+    }
+    let fees: i64 = prepared.txs.iter().filter_map(|tx| tx.fee).sum();
+    let reported_base = reward.saturating_sub(fees).max(0);
+    let claimed = msg
+        .miner_tx_json
+        .as_deref()
+        .and_then(|json| codec::parse_tx_json(json).ok())
+        .and_then(|miner_tx| codec::coinbase_amount(&miner_tx))
+        .map(|amount| i64::try_from(amount).unwrap_or(i64::MAX));
+    let (generated, shifted) = Store::record_emission(db_tx, height, reported_base, fees, claimed)
         .await
-        .context("refresh confirmation window")?;
+        .context("record block emission")?;
+    let expected =
+        generated.map(|generated| codec::expected_base_reward(generated, msg.header.major_version));
+    Store::set_emission_subsidy(
+        db_tx,
+        height,
+        expected.map(|expected| i64::try_from(expected).unwrap_or(i64::MAX)),
+    )
+    .await
+    .context("record block subsidy")?;
+    if let (Some(generated), Some(expected)) = (generated, expected) {
+        let recorded = RecordedEmission {
+            height,
+            hash: msg.header.hash,
+            size: msg.header.size,
+            major_version: msg.header.major_version,
+            base_reward: reported_base,
+            fees,
+            generated,
+        };
+        flag_emission(db_tx, &recorded, expected).await?;
+    }
 
+    // Blocks that committed ahead of this one learn their prior supply only
+    // now, so their subsidy and check are recorded here.
+    for above in Store::recorded_emission(db_tx, &shifted)
+        .await
+        .context("read recorded emission")?
+    {
+        let expected = codec::expected_base_reward(above.generated, above.major_version);
+        Store::set_emission_subsidy(
+            db_tx,
+            above.height,
+            Some(i64::try_from(expected).unwrap_or(i64::MAX)),
+        )
+        .await
+        .context("record block subsidy")?;
+        flag_emission(db_tx, &above, expected).await?;
+    }
     Ok(())
 }
 
-struct PreparedTx {
-    hash: Vec<u8>,
-    hash_hex: String,
-    fee: Option<i64>,
-    size_bytes: i32,
-    version: i32,
-    unlock_time: i64,
-    extra: serde_json::Value,
-    rct_type: i32,
-    proof_type: Option<String>,
-    bp_plus: bool,
-    num_inputs: i32,
-    num_outputs: i32,
+/// Records whether `block` emitted more or less than the schedule's
+/// `expected` base reward by more than its size explains.
+async fn flag_emission(
+    db_tx: &mut Transaction<'_, Postgres>,
+    block: &RecordedEmission,
+    expected: u64,
+) -> Result<()> {
+    let reported = block.base_reward;
+    let anomaly =
+        codec::emission_deviation(reported as u64, expected, block.size, block.major_version).map(
+            |deviation| {
+                warn!(
+                    height = block.height,
+                    hash = %block.hash,
+                    expected,
+                    reported,
+                    deviation,
+                    "block reward deviates from the emission schedule"
+                );
+                metrics::counter!("ingest_anomalies_total", "kind" => "emission").increment(1);
+                (
+                    i64::try_from(expected).unwrap_or(i64::MAX),
+                    reported,
+                    block.fees,
+                )
+            },
+        );
+    Store::set_emission_anomaly(db_tx, block.height, &block.hash, anomaly)
+        .await
+        .context("record emission anomaly")
+}
+
+/// Column values persisted to `txs` for one transaction.
+#[derive(Serialize)]
+pub(crate) struct PreparedTx {
+    pub(crate) hash: TxHash,
+    pub(crate) fee: Option<i64>,
+    pub(crate) size_bytes: i32,
+    pub(crate) version: i32,
+    pub(crate) unlock_time: i64,
+    pub(crate) extra: serde_json::Value,
+    pub(crate) rct_type: i32,
+    pub(crate) proof_type: Option<String>,
+    pub(crate) bp_plus: bool,
+    pub(crate) num_inputs: i32,
+    pub(crate) num_outputs: i32,
+    /// Hard-fork rules the tx breaks; `None` when the block version is unknown.
+    pub(crate) nonstandard_reasons: Option<Vec<&'static str>>,
 }
 
-fn prepare_tx(
+pub(crate) fn prepare_tx(
     json_str: &str,
-    fallback_hash: Option<&str>,
+    fallback_hash: Option<TxHash>,
+    major_version: Option<u32>,
     do_analytics: bool,
+    extra_retention: ExtraRetention,
 ) -> Result<PreparedTx> {
     let tx_json = parse_tx_json(json_str).context("parse tx json")?;
     let value: serde_json::Value = serde_json::from_str(json_str).context("tx json to value")?;
 
-    let hash_str = value
+    let hash = match value
         .get("tx_hash")
         .or_else(|| value.get("hash"))
         .and_then(|v| v.as_str())
-        .or(fallback_hash)
-        .context("transaction hash missing")?;
-    let hash = hex::decode(hash_str).context("decode tx hash")?;
-    let hash_hex = hash_str.to_string();
+    {
+        Some(hash_str) => TxHash::from_hex(hash_str).context("decode tx hash")?,
+        None => fallback_hash.context("transaction hash missing")?,
+    };
 
     let size = value_u64(&value, &["size", "blob_size", "weight"])
         .unwrap_or_else(|| json_str.len() as u64);
@@ -226,7 +627,17 @@ fn prepare_tx(
     let version = i32::try_from(tx_json.version).context("tx version overflow")?;
     let unlock_time = i64::try_from(tx_json.unlock_time).context("unlock time overflow")?;
     let size_bytes = i32::try_from(size).unwrap_or(i32::MAX);
-    let (num_inputs_usize, num_outputs_usize, bp_plus, proof_type) = if do_analytics {
+    let (num_inputs_usize, num_outputs_usize, bp_plus, proof_type) = if codec::is_pruned(&tx_json) {
+        // Range proofs were pruned away; the RingCT type alone tells whether
+        // they were Bulletproofs+.
+        let bp_plus = rct_type == codec::RCT_TYPE_BULLETPROOF_PLUS;
+        (
+            tx_json.vin.len(),
+            tx_json.vout.len(),
+            bp_plus,
+            bp_plus.then(|| "CLSAG".to_string()),
+        )
+    } else if do_analytics {
         let analysis = analyze_tx(&tx_json).context("analyze tx")?;
         let proof_type = if analysis.bp_plus {
             Some("CLSAG".to_string())
@@ -259,11 +670,14 @@ fn prepare_tx(
     let num_outputs = i32::try_from(num_outputs_usize).context("outputs overflow")?;
     let rct_type_i32 = i32::try_from(rct_type).unwrap_or_default();
 
-    let extra = serde_json::json!({ "extra": tx_json.extra });
+    let extra = codec::extra_column(&tx_json.extra, extra_retention);
+    let nonstandard_reasons = major_version.map(|v| codec::fork_violations(&tx_json, v));
+    if nonstandard_reasons.as_ref().is_some_and(|r| !r.is_empty()) {
+        metrics::counter!("nonstandard_txs_total").increment(1);
+    }
 
     Ok(PreparedTx {
         hash,
-        hash_hex,
         fee,
         size_bytes,
         version,
@@ -274,6 +688,7 @@ fn prepare_tx(
         bp_plus,
         num_inputs,
         num_outputs,
+        nonstandard_reasons,
     })
 }
 
@@ -307,6 +722,8 @@ fn parse_fee(value: &serde_json::Value) -> Option<i64> {
 
 #[cfg(test)]
 mod tests {
+    use bex_core::{BlockHash, BlockHeader};
+
     use super::*;
 
     #[test]
@@ -318,13 +735,382 @@ mod tests {
             "vout": [],
             "extra": []
         }"#;
-        let fallback =
-            "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_string();
+        let fallback = TxHash([0xaa; 32]);
+
+        let prepared = prepare_tx(json, Some(fallback), None, true, ExtraRetention::Raw)
+            .expect("prepare tx with fallback hash");
+
+        assert_eq!(prepared.hash, fallback);
+    }
+
+    #[test]
+    fn pruned_txs_take_proof_type_from_rct_type() {
+        let pruned = |rct_type: i64| {
+            serde_json::json!({
+                "version": 2,
+                "unlock_time": 0,
+                "vin": [],
+                "vout": [],
+                "extra": [],
+                "rct_signatures": { "type": rct_type, "txnFee": 30_000_000 },
+            })
+            .to_string()
+        };
+
+        for do_analytics in [true, false] {
+            let bp_plus = prepare_tx(
+                &pruned(6),
+                Some(TxHash([1; 32])),
+                Some(16),
+                do_analytics,
+                ExtraRetention::Raw,
+            )
+            .expect("prepare pruned bp+ tx");
+            assert!(bp_plus.bp_plus);
+            assert_eq!(bp_plus.proof_type.as_deref(), Some("CLSAG"));
+            assert_eq!(bp_plus.fee, Some(30_000_000));
+
+            let older = prepare_tx(
+                &pruned(4),
+                Some(TxHash([2; 32])),
+                Some(16),
+                do_analytics,
+                ExtraRetention::Raw,
+            )
+            .expect("prepare pruned bp tx");
+            assert!(!older.bp_plus);
+            assert_eq!(older.proof_type, None);
+        }
+    }
+
+    #[test]
+    fn extra_retention_picks_raw_or_decoded() {
+        // A tx pubkey and an encrypted payment id nonce.
+        let extra = format!("01{}020901{}", "ab".repeat(32), "cd".repeat(8));
+        let json = serde_json::json!({
+            "version": 2,
+            "unlock_time": 0,
+            "vin": [],
+            "vout": [],
+            "extra": extra,
+        })
+        .to_string();
+        let prepare = |retention| {
+            prepare_tx(&json, Some(TxHash([3; 32])), None, false, retention)
+                .expect("prepare tx")
+                .extra
+        };
+        let tags = serde_json::json!([
+            { "type": "pub_key", "key": "ab".repeat(32) },
+            { "type": "nonce", "len": 9, "payment_id": "encrypted" },
+        ]);
+
+        assert_eq!(
+            prepare(ExtraRetention::Raw),
+            serde_json::json!({ "extra": extra })
+        );
+        assert_eq!(
+            prepare(ExtraRetention::Decoded),
+            serde_json::json!({ "tags": tags })
+        );
+        assert_eq!(
+            prepare(ExtraRetention::Both),
+            serde_json::json!({ "extra": extra, "tags": tags })
+        );
+    }
+
+    #[tokio::test]
+    async fn prepare_pool_keeps_block_order() {
+        let json = r#"{
+            "version": 1,
+            "unlock_time": 0,
+            "vin": [],
+            "vout": [],
+            "extra": []
+        }"#;
+        let jobs: Vec<_> = (0..16u8)
+            .map(|i| (json.to_string(), TxHash([i; 32])))
+            .collect();
+
+        let prepared = PreparePool::new(3)
+            .prepare_all(jobs, 16, true, ExtraRetention::Raw)
+            .await
+            .expect("prepare txs");
+
+        let hashes: Vec<_> = prepared.txs.iter().map(|tx| tx.hash).collect();
+        let expected: Vec<_> = (0..16u8).map(|i| TxHash([i; 32])).collect();
+        assert_eq!(hashes, expected);
+    }
+
+    #[tokio::test]
+    async fn quarantined_tx_leaves_emission_and_rct_outputs_unknown() -> Result<()> {
+        let Some(db) = crate::testing::TestDb::start().await? else {
+            eprintln!(
+                "skipping quarantined_tx_leaves_emission_and_rct_outputs_unknown: no database available"
+            );
+            return Ok(());
+        };
+        let height = 950_000_000_i64;
+        let cleanup = || async {
+            for table in [
+                "block_emission",
+                "emission_anomalies",
+                "rct_output_counts",
+                "quarantined_txs",
+                "block_ingest_state",
+            ] {
+                sqlx::query(&format!(
+                    "DELETE FROM public.{table} WHERE {} BETWEEN $1 - 1 AND $1",
+                    if table == "quarantined_txs" {
+                        "block_height"
+                    } else {
+                        "height"
+                    }
+                ))
+                .bind(height)
+                .execute(&db.pool)
+                .await?;
+            }
+            sqlx::query("DELETE FROM public.txs WHERE block_height = $1")
+                .bind(height)
+                .execute(&db.pool)
+                .await?;
+            sqlx::query("DELETE FROM public.blocks WHERE height = $1")
+                .bind(height)
+                .execute(&db.pool)
+                .await?;
+            anyhow::Ok(())
+        };
+        cleanup().await?;
+        sqlx::query(
+            "INSERT INTO public.block_emission (height, base_reward, cumulative)
+             VALUES ($1 - 1, 1, 18400000000000000000)",
+        )
+        .bind(height)
+        .execute(&db.pool)
+        .await?;
+        sqlx::query(
+            "INSERT INTO public.rct_output_counts (height, rct_outputs, cumulative)
+             VALUES ($1 - 1, 2, 100)",
+        )
+        .bind(height)
+        .execute(&db.pool)
+        .await?;
+
+        let store = Store::connect(&db.url).await?;
+        let cfg = Config {
+            store: store.clone(),
+            checkpoint: Arc::new(Checkpoint::new(store.pool().clone(), "stagenet")),
+            finality_window: 30,
+            do_analytics: false,
+            chain_tips_retention: 0,
+            analytics_wake: None,
+            position_tx: None,
+            prepare_pool: PreparePool::new(1),
+            events: Events::default(),
+            extra_retention: ExtraRetention::Raw,
+        };
+        let paying = serde_json::json!({
+            "version": 2,
+            "unlock_time": 0,
+            "vin": [],
+            "vout": [],
+            "extra": [],
+            "rct_signatures": { "type": 6, "txnFee": 40_000_000 },
+        })
+        .to_string();
+        let msg = |second_tx: &str| TxMsg {
+            height,
+            block_hash: BlockHash([0xe1; 32]),
+            tx_jsons: vec![paying.clone(), second_tx.to_string()],
+            ts: 1_700_000_000,
+            tip_height: height,
+            finalized_height: height - 30,
+            header: BlockHeader {
+                hash: BlockHash([0xe1; 32]),
+                height: height as u64,
+                timestamp: 1_700_000_000,
+                prev_hash: BlockHash([0xe0; 32]),
+                major_version: 16,
+                minor_version: 16,
+                nonce: 0,
+                reward: 600_100_000_000,
+                size: 3000,
+                difficulty: 0,
+            },
+            miner_tx_json: None,
+            miner_tx_hash: None,
+            ordered_tx_hashes: vec![TxHash([0xe2; 32]), TxHash([0xe3; 32])],
+            started: std::time::Instant::now(),
+        };
+        let emission = || async {
+            sqlx::query_as::<_, (i64, Option<i64>)>(
+                "SELECT base_reward, fees FROM public.block_emission WHERE height = $1",
+            )
+            .bind(height)
+            .fetch_optional(&db.pool)
+            .await
+        };
+
+        let rct_outputs = || async {
+            sqlx::query_as::<_, (i32, Option<i64>)>(
+                "SELECT rct_outputs, cumulative FROM public.rct_output_counts WHERE height = $1",
+            )
+            .bind(height)
+            .fetch_optional(&db.pool)
+            .await
+        };
+
+        // The quarantined tx's fee and outputs are unknown, so nothing is
+        // recorded.
+        reingest_block(&cfg, &msg(r#"{"version": "two"}"#)).await?;
+        assert_eq!(emission().await?, None);
+        assert_eq!(rct_outputs().await?, None);
+
+        let fixed = paying.replace("40000000", "60000000");
+        reingest_block(&cfg, &msg(&fixed)).await?;
+        assert_eq!(
+            emission().await?,
+            Some((600_000_000_000, Some(100_000_000)))
+        );
+        assert_eq!(rct_outputs().await?, Some((0, Some(100))));
+
+        cleanup().await
+    }
+
+    #[tokio::test]
+    async fn block_committed_before_its_parent_is_checked_later() -> Result<()> {
+        let Some(db) = crate::testing::TestDb::start().await? else {
+            eprintln!(
+                "skipping block_committed_before_its_parent_is_checked_later: no database available"
+            );
+            return Ok(());
+        };
+        let height = 950_000_010_i64;
+        let cleanup = || async {
+            for table in [
+                "block_emission",
+                "emission_anomalies",
+                "rct_output_counts",
+                "block_ingest_state",
+                "blocks",
+            ] {
+                sqlx::query(&format!(
+                    "DELETE FROM public.{table} WHERE height BETWEEN $1 - 1 AND $1 + 1"
+                ))
+                .bind(height)
+                .execute(&db.pool)
+                .await?;
+            }
+            anyhow::Ok(())
+        };
+        cleanup().await?;
+        sqlx::query(
+            "INSERT INTO public.block_emission (height, base_reward, cumulative)
+             VALUES ($1 - 1, 1, 18400000000000000000)",
+        )
+        .bind(height)
+        .execute(&db.pool)
+        .await?;
+
+        let store = Store::connect(&db.url).await?;
+        let cfg = Config {
+            store: store.clone(),
+            checkpoint: Arc::new(Checkpoint::new(store.pool().clone(), "stagenet")),
+            finality_window: 30,
+            do_analytics: false,
+            chain_tips_retention: 0,
+            analytics_wake: None,
+            position_tx: None,
+            prepare_pool: PreparePool::new(1),
+            events: Events::default(),
+            extra_retention: ExtraRetention::Raw,
+        };
+        // Tail emission pays 0.6 XMR; the block above claims 0.7.
+        let msg = |height: i64, reward: u64| {
+            let hash = BlockHash([(height - 950_000_000) as u8; 32]);
+            TxMsg {
+                height,
+                block_hash: hash,
+                tx_jsons: vec![],
+                ts: 1_700_000_000,
+                tip_height: height + 1,
+                finalized_height: height - 30,
+                header: BlockHeader {
+                    hash,
+                    height: height as u64,
+                    timestamp: 1_700_000_000,
+                    prev_hash: BlockHash([0; 32]),
+                    major_version: 16,
+                    minor_version: 16,
+                    nonce: 0,
+                    reward,
+                    size: 3000,
+                    difficulty: 0,
+                },
+                miner_tx_json: None,
+                miner_tx_hash: None,
+                ordered_tx_hashes: vec![],
+                started: std::time::Instant::now(),
+            }
+        };
+        let flagged = || async {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT height FROM public.emission_anomalies WHERE height BETWEEN $1 AND $1 + 1",
+            )
+            .bind(height)
+            .fetch_all(&db.pool)
+            .await
+        };
+
+        let subsidy = || async {
+            sqlx::query_scalar::<_, Option<i64>>(
+                "SELECT subsidy FROM public.block_emission WHERE height = $1 + 1",
+            )
+            .bind(height)
+            .fetch_one(&db.pool)
+            .await
+        };
+
+        reingest_block(&cfg, &msg(height + 1, 700_000_000_000)).await?;
+        assert_eq!(flagged().await?, Vec::<i64>::new());
+        assert_eq!(subsidy().await?, None);
+
+        reingest_block(&cfg, &msg(height, 600_000_000_000)).await?;
+        assert_eq!(flagged().await?, vec![height + 1]);
+        assert_eq!(subsidy().await?, Some(600_000_000_000));
+
+        cleanup().await
+    }
+
+    #[tokio::test]
+    async fn prepare_pool_quarantines_unparseable_txs() {
+        let json = r#"{
+            "version": 1,
+            "unlock_time": 0,
+            "vin": [],
+            "vout": [],
+            "extra": []
+        }"#;
+        let jobs = vec![
+            (json.to_string(), TxHash([1; 32])),
+            (r#"{"version": "two"}"#.to_string(), TxHash([2; 32])),
+            (json.to_string(), TxHash([3; 32])),
+        ];
 
-        let prepared =
-            prepare_tx(json, Some(&fallback), true).expect("prepare tx with fallback hash");
+        let prepared = PreparePool::new(2)
+            .prepare_all(jobs, 16, true, ExtraRetention::Raw)
+            .await
+            .expect("prepare txs");
 
-        assert_eq!(prepared.hash_hex, fallback);
-        assert_eq!(prepared.hash, hex::decode(&fallback).expect("hex decode"));
+        assert_eq!(
+            prepared.order,
+            vec![TxHash([1; 32]), TxHash([2; 32]), TxHash([3; 32])]
+        );
+        assert_eq!(prepared.txs.len(), 2);
+        assert_eq!(prepared.quarantined.len(), 1);
+        assert_eq!(prepared.quarantined[0].hash, TxHash([2; 32]));
+        assert_eq!(prepared.quarantined[0].raw_json, r#"{"version": "two"}"#);
+        assert!(prepared.quarantined[0].error.contains("parse tx json"));
     }
 }
@@ -6,15 +6,24 @@ use std::{
 
 use anyhow::{Context, Result};
 use governor::DefaultDirectRateLimiter;
-use tokio::{sync::mpsc, time::sleep};
-use tracing::{debug, info};
+use tokio::{
+    sync::{mpsc, watch},
+    time::sleep,
+};
+use tracing::{debug, info, warn};
 
 use crate::{
+    capabilities::LiveCapabilities,
     checkpoint::Checkpoint,
-    pipeline::{SchedMsg, Shutdown},
-    rpc::{Capabilities, MoneroRpc},
+    health::Readiness,
+    memory::MemoryBudget,
+    pipeline::{SchedMsg, Shutdown, WorkerMetrics, WorkerState},
+    rpc::MoneroRpc,
 };
 
+/// How often a throttled scheduler checks whether its queue drained.
+const THROTTLE_POLL: Duration = Duration::from_millis(200);
+
 pub struct Config {
     pub checkpoint: Arc<Checkpoint>,
     pub rpc: Arc<dyn MoneroRpc>,
@@ -22,8 +31,16 @@ pub struct Config {
     pub start_height: Option<i64>,
     pub limit: Option<u64>,
     pub finality_window: u64,
-    pub caps: Capabilities,
+    pub caps: Arc<LiveCapabilities>,
     pub header_batch: u64,
+    pub readiness: Arc<Readiness>,
+    /// Blocks below the daemon tip left unscheduled until they are buried
+    /// that deep; `0` ingests the tip itself.
+    pub tip_confirmations: u64,
+    /// Receives every raw tip the scheduler sees, held-back blocks included.
+    pub daemon_tip: watch::Sender<u64>,
+    /// Holds back queueing while it is throttled.
+    pub memory: MemoryBudget,
 }
 
 pub async fn run(
@@ -31,7 +48,7 @@ pub async fn run(
     cfg: Config,
     _shutdown: Option<Shutdown>,
 ) -> Result<()> {
-    if cfg.caps.headers_range {
+    if cfg.caps.get().headers_range {
         info!(
             batch = cfg.header_batch,
             "scheduler using bulk header queues"
@@ -51,7 +68,10 @@ pub async fn run(
         next_height = 0;
     }
 
+    let mut regressed = false;
+    let mut worker = WorkerMetrics::register("sched");
     loop {
+        worker.enter(WorkerState::Busy);
         if let Some(limit) = cfg.limit {
             if processed_blocks >= limit {
                 info!(processed = processed_blocks, "block limit reached");
@@ -61,8 +81,16 @@ pub async fn run(
 
         let height_u64 = u64::try_from(next_height).context("height became negative")?;
         let (tip_height_u64, finalized_height_i64) = loop {
+            worker.enter(WorkerState::Busy);
             let tip_height_u64 = fetch_chain_tip(cfg.rpc.as_ref(), &cfg.limiter).await?;
-            if height_u64 <= tip_height_u64 {
+            cfg.daemon_tip.send_if_modified(|seen| {
+                let changed = *seen != tip_height_u64;
+                *seen = tip_height_u64;
+                changed
+            });
+            track_regression(&cfg, &mut regressed, height_u64, tip_height_u64);
+            let ingestable = tip_height_u64.checked_sub(cfg.tip_confirmations);
+            if ingestable.is_some_and(|ingestable| height_u64 <= ingestable) {
                 let finalized_height_u64 = tip_height_u64.saturating_sub(cfg.finality_window);
                 let finalized_height_i64 =
                     i64::try_from(finalized_height_u64).context("finalized height overflow")?;
@@ -71,14 +99,31 @@ pub async fn run(
             debug!(
                 height = height_u64,
                 tip = tip_height_u64,
+                tip_confirmations = cfg.tip_confirmations,
                 "waiting for new blocks"
             );
+            worker.enter(WorkerState::RecvWait);
             sleep(Duration::from_secs(2)).await;
         };
 
         let tip_height_i64 = i64::try_from(tip_height_u64).context("tip height overflow")?;
 
+        // Near the memory budget a height is only queued once the workers
+        // took the previous one, so the pipeline drains but ingestion still
+        // trickles on if RSS never falls back.
+        if cfg.memory.throttled() {
+            worker.enter(WorkerState::SendWait);
+            while cfg.memory.throttled() && tx.capacity() < tx.max_capacity() {
+                tokio::select! {
+                    _ = cfg.memory.wait_unthrottled() => {}
+                    _ = sleep(THROTTLE_POLL) => {}
+                }
+            }
+            worker.enter(WorkerState::Busy);
+        }
+
         info!(height = height_u64, tip = tip_height_u64, "queueing block");
+        worker.enter(WorkerState::SendWait);
         if tx
             .send(SchedMsg {
                 height: next_height,
@@ -106,6 +151,41 @@ pub async fn run(
     Ok(())
 }
 
+/// A daemon tip more than the finality window below the last scheduled height
+/// means the daemon was swapped or is resyncing, not an ordinary reorg. The
+/// scheduler keeps waiting (queueing nothing) and readiness reports the
+/// condition until the daemon catches up; reorg healing then reconciles any
+/// blocks that differ.
+fn track_regression(cfg: &Config, regressed: &mut bool, next_height: u64, tip: u64) {
+    let ingested = next_height.saturating_sub(1);
+    let behind = ingested.saturating_sub(tip);
+    metrics::gauge!("daemon_height_regression_blocks").set(behind as f64);
+
+    if next_height > 0 && behind > cfg.finality_window {
+        if !*regressed {
+            warn!(
+                ingested,
+                daemon_tip = tip,
+                behind,
+                finality_window = cfg.finality_window,
+                "DAEMON HEIGHT REGRESSION: daemon is behind the stored chain, pausing ingestion"
+            );
+            *regressed = true;
+        }
+        cfg.readiness.set_daemon_regression(Some(format!(
+            "daemon tip {tip} is {behind} blocks below ingested height {ingested}"
+        )));
+    } else if *regressed {
+        info!(
+            ingested,
+            daemon_tip = tip,
+            "daemon caught up after height regression, resuming ingestion"
+        );
+        *regressed = false;
+        cfg.readiness.set_daemon_regression(None);
+    }
+}
+
 async fn fetch_chain_tip(
     rpc: &dyn MoneroRpc,
     limiter: &Arc<DefaultDirectRateLimiter>,
@@ -1,12 +1,14 @@
 use std::sync::Arc;
 
 use anyhow::{anyhow, Context, Result};
+use bex_core::TxHash;
 use governor::DefaultDirectRateLimiter;
 use tokio::sync::{mpsc, Mutex};
 
 use crate::{
     fetch::fetch_txs_adaptive,
-    pipeline::{BlockMsg, Shutdown, TxMsg},
+    memory::MemoryBudget,
+    pipeline::{BlockMsg, Shutdown, TxMsg, WorkerMetrics, WorkerState},
     rpc::MoneroRpc,
 };
 
@@ -15,6 +17,8 @@ pub struct Config {
     pub rpc: Arc<dyn MoneroRpc>,
     pub limiter: Arc<DefaultDirectRateLimiter>,
     pub concurrency: usize,
+    /// Shrinks tx batches while throttled.
+    pub memory: MemoryBudget,
 }
 
 pub async fn run(
@@ -23,7 +27,9 @@ pub async fn run(
     cfg: Config,
     _shutdown: Option<Shutdown>,
 ) -> Result<()> {
+    let mut worker = WorkerMetrics::register("tx");
     loop {
+        worker.enter(WorkerState::RecvWait);
         let block_job = {
             let mut guard = rx.lock().await;
             let job = guard.recv().await;
@@ -34,31 +40,10 @@ pub async fn run(
             break;
         };
 
-        let pairs = fetch_transactions(
-            &cfg.rpc,
-            &cfg.limiter,
-            &block_job.tx_hashes,
-            cfg.concurrency,
-        )
-        .await?;
-
-        let ordered_hashes: Vec<String> = pairs.iter().map(|(hash, _)| hash.clone()).collect();
-        let tx_jsons: Vec<String> = pairs.into_iter().map(|(_, json)| json).collect();
-
-        let msg = TxMsg {
-            height: block_job.height,
-            block_hash: block_job.hash,
-            tx_jsons,
-            ts: block_job.ts,
-            tip_height: block_job.tip_height,
-            finalized_height: block_job.finalized_height,
-            header: block_job.header,
-            miner_tx_json: block_job.miner_tx_json,
-            miner_tx_hash: block_job.miner_tx_hash,
-            ordered_tx_hashes: ordered_hashes,
-            started: block_job.started,
-        };
+        worker.enter(WorkerState::Busy);
+        let msg = fetch_block_txs(&cfg, block_job).await?;
 
+        worker.enter(WorkerState::SendWait);
         if tx.send(msg).await.is_err() {
             break;
         }
@@ -69,18 +54,56 @@ pub async fn run(
     Ok(())
 }
 
+/// Fetches the transactions of `block_job` and assembles the persistence
+/// message.
+pub async fn fetch_block_txs(cfg: &Config, block_job: BlockMsg) -> Result<TxMsg> {
+    let pairs = fetch_transactions(
+        &cfg.rpc,
+        &cfg.limiter,
+        &block_job.tx_hashes,
+        cfg.concurrency,
+        &cfg.memory,
+    )
+    .await?;
+
+    let ordered_hashes: Vec<TxHash> = pairs.iter().map(|(hash, _)| *hash).collect();
+    let tx_jsons: Vec<String> = pairs.into_iter().map(|(_, json)| json).collect();
+
+    Ok(TxMsg {
+        height: block_job.height,
+        block_hash: block_job.hash,
+        tx_jsons,
+        ts: block_job.ts,
+        tip_height: block_job.tip_height,
+        finalized_height: block_job.finalized_height,
+        header: block_job.header,
+        miner_tx_json: block_job.miner_tx_json,
+        miner_tx_hash: block_job.miner_tx_hash,
+        ordered_tx_hashes: ordered_hashes,
+        started: block_job.started,
+    })
+}
+
+/// Txs asked for in the first `get_transactions` call of a block; the
+/// adaptive fetch shrinks it on failure.
+pub fn start_chunk(concurrency: usize) -> usize {
+    (concurrency.max(1) * 50).clamp(10, 300)
+}
+
 async fn fetch_transactions(
     rpc: &Arc<dyn MoneroRpc>,
     limiter: &Arc<DefaultDirectRateLimiter>,
-    hashes: &[String],
+    hashes: &[TxHash],
     concurrency: usize,
-) -> Result<Vec<(String, String)>> {
+    memory: &MemoryBudget,
+) -> Result<Vec<(TxHash, String)>> {
     if hashes.is_empty() {
         return Ok(Vec::new());
     }
+    let hashes_hex: Vec<String> = hashes.iter().map(TxHash::to_hex).collect();
 
-    let start_chunk = (concurrency.max(1) * 50).clamp(10, 300);
-    let tx_jsons = fetch_txs_adaptive(rpc.as_ref(), hashes, start_chunk, limiter.as_ref())
+    let start_chunk = memory.scale(start_chunk(concurrency) as u64) as usize;
+    let tx_jsons = fetch_txs_adaptive(rpc.as_ref(), &hashes_hex, start_chunk, limiter.as_ref())
         .await
         .with_context(|| "fetch transactions adaptive")?;
 
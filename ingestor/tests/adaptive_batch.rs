@@ -1,11 +1,12 @@
 use std::{collections::HashMap, num::NonZeroU32, sync::Mutex};
 
 use anyhow::Result;
+use bex_core::BlockHash;
 use governor::{Quota, RateLimiter};
 use ingestor::fetch::fetch_txs_adaptive;
 use ingestor::rpc::{
     BlockHeader, Capabilities, GetBlockCountResult, GetBlockHeaderByHeightResult, GetBlockResult,
-    GetTransactionsResult, MoneroRpc,
+    GetTransactionsResult, MoneroRpc, PoolTx,
 };
 use serde_json::json;
 
@@ -49,7 +50,7 @@ impl MoneroRpc for AdaptiveMockRpc {
         unimplemented!()
     }
 
-    async fn get_block(&self, _hash: &str, _fill_pow: bool) -> Result<GetBlockResult> {
+    async fn get_block(&self, _hash: &BlockHash, _fill_pow: bool) -> Result<GetBlockResult> {
         unimplemented!()
     }
 
@@ -85,6 +86,10 @@ impl MoneroRpc for AdaptiveMockRpc {
         unimplemented!()
     }
 
+    async fn get_pool_transactions(&self, _txs_hashes: &[String]) -> Result<Vec<PoolTx>> {
+        unimplemented!()
+    }
+
     async fn get_block_headers_range(&self, _start: u64, _end: u64) -> Result<Vec<BlockHeader>> {
         unimplemented!()
     }
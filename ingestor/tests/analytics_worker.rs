@@ -0,0 +1,61 @@
+#[tokio::test]
+async fn analytics_worker_drains_pending_blocks_on_timeout_pool() {
+    use ingestor::analytics;
+
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        eprintln!("skipping analytics_worker_drains_pending_blocks: DATABASE_URL not set");
+        return;
+    };
+
+    let pool = sqlx::PgPool::connect(&database_url).await.unwrap();
+    let analytics_pool = analytics::connect(&database_url, 5_000).await.unwrap();
+
+    let timeout: String = sqlx::query_scalar("SHOW statement_timeout")
+        .fetch_one(&analytics_pool)
+        .await
+        .unwrap();
+    assert_eq!(timeout, "5s");
+
+    let height = 990_100i64;
+    sqlx::query("DELETE FROM public.soft_facts WHERE block_height = $1")
+        .bind(height)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM public.blocks WHERE height = $1")
+        .bind(height)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos, analytics_pending)
+         VALUES ($1, decode($2,'hex'), decode($3,'hex'), NOW(), 100,14,14,0,0,0, TRUE)",
+    )
+    .bind(height)
+    .bind("ee".repeat(32))
+    .bind("ff".repeat(32))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let processed = analytics::process_pending(&analytics_pool, 10)
+        .await
+        .unwrap();
+    assert!(processed >= 1);
+
+    let pending: bool =
+        sqlx::query_scalar("SELECT analytics_pending FROM public.blocks WHERE height = $1")
+            .bind(height)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(!pending);
+
+    let facts: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM public.soft_facts WHERE block_height = $1")
+            .bind(height)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(facts, 1);
+}
@@ -0,0 +1,32 @@
+use ingestor::blob::parse_block_hex;
+use ingestor::rpc::GetBlockResult;
+use serde_json::Value;
+use std::{fs, path::PathBuf};
+
+/// Mainnet blocks 2751506 (miner tx only) and 2751210 (two txs), copied from
+/// the `get_block` examples in monerod-rpc-docs.md.
+#[test]
+fn parse_mainnet_blobs_against_daemon_json() {
+    let mut p = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    p.push("tests/fixtures/get_block_mainnet.json");
+    let data = fs::read_to_string(p).expect("fixture missing");
+    let blocks: Vec<GetBlockResult> = serde_json::from_str(&data).expect("fixture parse");
+    assert_eq!(blocks.len(), 2);
+
+    for block in blocks {
+        let height = block.block_header.height;
+        let parsed = parse_block_hex(block.blob.as_deref().expect("blob")).expect("parse blob");
+        parsed
+            .check_header(&block.block_header)
+            .unwrap_or_else(|e| panic!("block {height}: {e:#}"));
+
+        let json: Value = serde_json::from_str(block.json.as_deref().expect("json")).unwrap();
+        assert_eq!(parsed.miner_tx, json["miner_tx"], "block {height} miner tx");
+        let hashes: Vec<String> = parsed.tx_hashes.iter().map(|h| h.to_hex()).collect();
+        assert_eq!(
+            Value::from(hashes),
+            json["tx_hashes"],
+            "block {height} tx hashes"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use bex_core::BlockHash;
+use ingestor::{
+    capabilities::{self, LiveCapabilities},
+    rpc::{
+        BlockHeader, Capabilities, GetBlockCountResult, GetBlockHeaderByHeightResult,
+        GetBlockResult, GetTransactionsResult, MoneroRpc, PoolTx,
+    },
+};
+
+const FULL: Capabilities = Capabilities {
+    headers_range: true,
+    blocks_by_height_bin: true,
+};
+
+/// Daemon that refuses the first `down_for` block count requests.
+struct FlakyDaemon {
+    down_for: u32,
+    calls: AtomicU32,
+}
+
+impl FlakyDaemon {
+    fn new(down_for: u32) -> Self {
+        Self {
+            down_for,
+            calls: AtomicU32::new(0),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MoneroRpc for FlakyDaemon {
+    async fn get_block_header_by_height(
+        &self,
+        _height: u64,
+    ) -> Result<GetBlockHeaderByHeightResult> {
+        bail!("unused")
+    }
+
+    async fn get_block_headers_range(&self, _start: u64, _end: u64) -> Result<Vec<BlockHeader>> {
+        bail!("unused")
+    }
+
+    async fn get_block(&self, _hash: &BlockHash, _fill_pow: bool) -> Result<GetBlockResult> {
+        bail!("unused")
+    }
+
+    async fn get_transactions(&self, _txs_hashes: &[String]) -> Result<GetTransactionsResult> {
+        bail!("unused")
+    }
+
+    async fn get_block_count(&self) -> Result<GetBlockCountResult> {
+        if self.calls.fetch_add(1, Ordering::SeqCst) < self.down_for {
+            bail!("connection refused");
+        }
+        Ok(GetBlockCountResult {
+            count: 1,
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_transaction_pool_hashes(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_pool_transactions(&self, _txs_hashes: &[String]) -> Result<Vec<PoolTx>> {
+        Ok(Vec::new())
+    }
+
+    async fn probe_caps(&self) -> Capabilities {
+        FULL
+    }
+}
+
+#[tokio::test]
+async fn probe_retries_until_the_daemon_answers() {
+    let daemon = FlakyDaemon::new(2);
+    let caps = capabilities::probe_with_retry(&daemon, 5, Duration::from_millis(1)).await;
+    assert_eq!(caps, Some(FULL));
+    assert_eq!(daemon.calls.load(Ordering::SeqCst), 3);
+
+    let daemon = FlakyDaemon::new(u32::MAX);
+    let caps = capabilities::probe_with_retry(&daemon, 3, Duration::from_millis(1)).await;
+    assert_eq!(caps, None);
+    assert_eq!(daemon.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn reprobe_restores_disabled_bulk_headers() {
+    let live = Arc::new(LiveCapabilities::new(FULL));
+    live.disable_headers_range();
+    assert!(!live.get().headers_range);
+    assert!(live.get().blocks_by_height_bin);
+
+    let handle = capabilities::spawn_reprobe(
+        Arc::new(FlakyDaemon::new(1)),
+        Arc::clone(&live),
+        Duration::from_millis(5),
+    );
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while live.get() != FULL {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("re-probe restores capabilities");
+    handle.abort();
+}
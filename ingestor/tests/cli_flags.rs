@@ -10,6 +10,12 @@ fn parse_defaults() {
     env::remove_var("RPC_RPS");
     env::remove_var("BOOTSTRAP");
     env::remove_var("CONCURRENCY");
+    env::remove_var("NETWORK");
+    env::remove_var("RPC_MAX_RESPONSE_BYTES");
+    env::remove_var("LEADERBOARD_REFRESH_SECS");
+    env::remove_var("CAPS_PROBE_ATTEMPTS");
+    env::remove_var("CAPS_REPROBE_SECS");
+    env::remove_var("TX_EXTRA_RETENTION");
     let mut v = vec![OsString::from("ingestor"), OsString::from("run")];
     v.push("--database-url".into());
     v.push("postgres://x:x@localhost/x".into());
@@ -17,6 +23,34 @@ fn parse_defaults() {
     assert_eq!(args.ingest_concurrency, 8);
     assert_eq!(args.rpc_rps, 10);
     assert!(!args.bootstrap);
+    assert_eq!(args.max_reorg_depth, None);
+    assert_eq!(args.effective_max_reorg_depth(), args.finality_window);
+    assert_eq!(args.orphaned_tx_ttl_secs, 86_400);
+    assert_eq!(args.mempool_full_refresh_secs, 5);
+    assert_eq!(args.network, "stagenet");
+    assert_eq!(args.rpc_max_response_bytes, 64 * 1024 * 1024);
+    assert_eq!(args.leaderboard_refresh_secs, 600);
+    assert_eq!(args.caps_probe_attempts, 5);
+    assert_eq!(args.caps_reprobe_secs, 300);
+    assert_eq!(args.tx_extra_retention, ExtraRetention::Raw);
+}
+
+#[test]
+#[serial]
+fn max_reorg_depth_overrides_finality_window() {
+    env::remove_var("MAX_REORG_DEPTH");
+    let args = super_args(vec![
+        OsString::from("ingestor"),
+        OsString::from("run"),
+        OsString::from("--database-url"),
+        OsString::from("postgres://x:x@localhost/x"),
+        OsString::from("--finality-window"),
+        OsString::from("10"),
+        OsString::from("--max-reorg-depth"),
+        OsString::from("120"),
+    ]);
+    assert_eq!(args.finality_window, 10);
+    assert_eq!(args.effective_max_reorg_depth(), 120);
 }
 
 #[test]
@@ -35,12 +69,50 @@ fn parse_env_overrides() {
     assert_eq!(args.ingest_concurrency, 32);
     assert_eq!(args.rpc_rps, 99);
     assert!(args.bootstrap);
+
+    env::set_var("TX_EXTRA_RETENTION", "decoded");
+    let args = super_args(vec![
+        OsString::from("ingestor"),
+        OsString::from("run"),
+        OsString::from("--database-url"),
+        OsString::from("postgres://x:x@localhost/x"),
+    ]);
+    assert_eq!(args.tx_extra_retention, ExtraRetention::Decoded);
+    env::remove_var("TX_EXTRA_RETENTION");
     env::remove_var("INGEST_CONCURRENCY");
     env::remove_var("RPC_RPS");
     env::remove_var("BOOTSTRAP");
     env::remove_var("CONCURRENCY");
 }
 
+#[test]
+#[serial]
+fn alert_sinks_are_comma_separated() {
+    env::remove_var("ALERT_SINKS");
+    env::remove_var("ALERT_RPC_ERROR_PERCENT");
+    let args = super_args(vec![
+        OsString::from("ingestor"),
+        OsString::from("run"),
+        OsString::from("--database-url"),
+        OsString::from("postgres://x:x@localhost/x"),
+    ]);
+    assert!(args.alert_sinks.is_empty());
+    assert_eq!(args.alert_rules().rpc_error_percent, 5.0);
+
+    env::set_var("ALERT_SINKS", "stderr,webhook");
+    env::set_var("ALERT_RPC_ERROR_PERCENT", "12.5");
+    let args = super_args(vec![
+        OsString::from("ingestor"),
+        OsString::from("run"),
+        OsString::from("--database-url"),
+        OsString::from("postgres://x:x@localhost/x"),
+    ]);
+    assert_eq!(args.alert_sinks, vec![Sink::Stderr, Sink::Webhook]);
+    assert_eq!(args.alert_rules().rpc_error_percent, 12.5);
+    env::remove_var("ALERT_SINKS");
+    env::remove_var("ALERT_RPC_ERROR_PERCENT");
+}
+
 fn super_args<I>(itr: I) -> RunArgs
 where
     I: IntoIterator<Item = OsString>,
@@ -52,7 +124,7 @@ where
 }
 
 // Bring in Args
-use ingestor::cli::RunArgs;
+use ingestor::{alerts::Sink, cli::RunArgs, codec::ExtraRetention};
 
 #[derive(Parser)]
 struct TestCli {
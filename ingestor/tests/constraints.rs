@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use bex_core::TxHash;
+use ingestor::{constraints, store::Store, testing::TestDb};
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn deferred_foreign_keys_are_restored_and_validated() -> Result<()> {
+    let Some(db) = TestDb::start().await.context("start test database")? else {
+        eprintln!(
+            "skipping deferred_foreign_keys_are_restored_and_validated: no database available"
+        );
+        return Ok(());
+    };
+    let pool = db.pool.clone();
+
+    // A previous interrupted run may have left keys dropped.
+    constraints::restore(&pool).await?;
+    assert!(constraints::pending(&pool).await?.is_empty());
+
+    assert_eq!(constraints::defer(&pool).await?, 6);
+    assert_eq!(constraints::pending(&pool).await?.len(), 6);
+    let missing: i64 = sqlx::query_scalar(
+        "SELECT 6 - count(*) FROM pg_constraint
+         WHERE contype = 'f' AND conparentid = 0 AND NOT convalidated
+           AND conname IN ('fk_tx_inputs_tx', 'fk_outputs_tx', 'fk_rings_tx_input',
+                           'fk_rings_output', 'fk_ring_members_tx_input',
+                           'fk_ring_members_output')",
+    )
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(missing, 0);
+    assert_eq!(constraints::defer(&pool).await?, 0);
+
+    constraints::restore(&pool).await?;
+    assert!(constraints::pending(&pool).await?.is_empty());
+    let unvalidated: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM pg_constraint
+         WHERE conname LIKE 'fk\\_%' AND contype = 'f' AND NOT convalidated",
+    )
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(unvalidated, 0);
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn mined_mempool_tx_keeps_its_children_while_deferred() -> Result<()> {
+    let Some(db) = TestDb::start().await.context("start test database")? else {
+        eprintln!(
+            "skipping mined_mempool_tx_keeps_its_children_while_deferred: no database available"
+        );
+        return Ok(());
+    };
+    let pool = db.pool.clone();
+    let hash = TxHash([0xc4; 32]);
+    sqlx::query("DELETE FROM public.txs WHERE tx_hash = $1")
+        .bind(hash)
+        .execute(&pool)
+        .await?;
+
+    constraints::restore(&pool).await?;
+    constraints::defer(&pool).await?;
+
+    // A tx requeued by a reorg, with its children, waits in the pool.
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO public.txs (tx_hash, block_height, block_timestamp, in_mempool, size_bytes,
+           version, unlock_time, rct_type, num_inputs, num_outputs)
+         VALUES ($1, NULL, 'infinity', TRUE, 1500, 2, 0, 6, 1, 1)",
+    )
+    .bind(hash)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO public.tx_inputs (tx_hash, idx, key_image, ring_size)
+         VALUES ($1, 0, decode(repeat('c5', 32), 'hex'), 16)",
+    )
+    .bind(hash)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO public.outputs (tx_hash, idx_in_tx, commitment, stealth_public_key)
+         VALUES ($1, 0, '\\x01', '\\x02')",
+    )
+    .bind(hash)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    // Mining it moves the row to a dated partition.
+    let mut tx = pool.begin().await?;
+    Store::insert_tx(
+        &mut tx,
+        &hash,
+        Some(940_000_000),
+        Some(940_000_000),
+        false,
+        Some(30_000),
+        1500,
+        2,
+        0,
+        &serde_json::json!({}),
+        6,
+        None,
+        true,
+        1,
+        1,
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+
+    constraints::restore(&pool).await?;
+    assert!(constraints::pending(&pool).await?.is_empty());
+    let children: Vec<i64> = sqlx::query_scalar(
+        "SELECT extract(epoch FROM tx_block_timestamp)::bigint FROM public.tx_inputs WHERE tx_hash = $1
+         UNION ALL
+         SELECT extract(epoch FROM tx_block_timestamp)::bigint FROM public.outputs WHERE tx_hash = $1",
+    )
+    .bind(hash)
+    .fetch_all(&pool)
+    .await?;
+    assert_eq!(children, [940_000_000, 940_000_000]);
+
+    sqlx::query("DELETE FROM public.txs WHERE tx_hash = $1")
+        .bind(hash)
+        .execute(&pool)
+        .await?;
+    let orphans: i64 = sqlx::query_scalar(
+        "SELECT (SELECT count(*) FROM public.tx_inputs WHERE tx_hash = $1)
+              + (SELECT count(*) FROM public.outputs WHERE tx_hash = $1)",
+    )
+    .bind(hash)
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(orphans, 0);
+    Ok(())
+}
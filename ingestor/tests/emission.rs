@@ -0,0 +1,51 @@
+use ingestor::codec::{coinbase_amount, emission_deviation, expected_base_reward, parse_tx_json};
+
+#[test]
+fn genesis_and_tail_rewards_match_the_daemon() {
+    // Mainnet genesis paid (MONEY_SUPPLY >> 20) under the 60s target.
+    assert_eq!(expected_base_reward(0, 1), 17_592_186_044_415);
+    // Tail emission: 0.6 XMR per 120s block once the curve drops below it.
+    assert_eq!(
+        expected_base_reward(u64::MAX - 1_000_000, 16),
+        600_000_000_000
+    );
+    assert_eq!(expected_base_reward(u64::MAX, 16), 600_000_000_000);
+}
+
+#[test]
+fn shortfalls_are_only_flagged_below_the_penalty_zone() {
+    let expected = 1_000_000_000_000;
+    assert_eq!(emission_deviation(expected, expected, 500_000, 16), None);
+    assert_eq!(
+        emission_deviation(expected + 5, expected, 500_000, 16),
+        Some(5)
+    );
+    // A large block may legitimately pay the size penalty.
+    assert_eq!(
+        emission_deviation(expected - 5, expected, 500_000, 16),
+        None
+    );
+    assert_eq!(
+        emission_deviation(expected - 5, expected, 100_000, 16),
+        Some(-5)
+    );
+}
+
+#[test]
+fn coinbase_amount_sums_cleartext_outputs() {
+    let miner_tx = parse_tx_json(
+        r#"{"version":2,"unlock_time":70,"vin":[{"gen":{"height":10}}],
+            "vout":[{"amount":600000000000,"target":{"tagged_key":{}}},
+                    {"amount":1234,"target":{"tagged_key":{}}}],
+            "extra":[],"rct_signatures":{"type":0}}"#,
+    )
+    .unwrap();
+    assert_eq!(coinbase_amount(&miner_tx), Some(600_000_001_234));
+
+    let hidden = parse_tx_json(
+        r#"{"version":2,"unlock_time":0,"vin":[],"vout":[{"target":{}}],
+            "extra":[],"rct_signatures":{"type":6}}"#,
+    )
+    .unwrap();
+    assert_eq!(coinbase_amount(&hidden), None);
+}
@@ -0,0 +1,60 @@
+use ingestor::codec::{fork_violations, parse_tx_json};
+use serde_json::json;
+
+fn tx(version: u64, rct_type: i64, ring: usize) -> ingestor::codec::TxJson {
+    let input =
+        json!({ "key": { "amount": 0, "key_offsets": vec![1; ring], "k_image": "00".repeat(32) } });
+    let mut tx = json!({
+        "version": version,
+        "unlock_time": 0,
+        "vin": [input.clone(), input],
+        "vout": [],
+        "extra": [1],
+    });
+    if version >= 2 {
+        tx["rct_signatures"] = json!({ "type": rct_type });
+    }
+    parse_tx_json(&tx.to_string()).expect("tx json")
+}
+
+#[test]
+fn current_fork_requires_bp_plus_and_sixteen_member_rings() {
+    assert!(fork_violations(&tx(2, 6, 16), 16).is_empty());
+    assert_eq!(fork_violations(&tx(2, 5, 16), 16), vec!["rct_type"]);
+    assert_eq!(fork_violations(&tx(2, 6, 11), 16), vec!["ring_size"]);
+    assert_eq!(
+        fork_violations(&tx(1, 0, 3), 16),
+        vec!["version", "rct_type", "ring_size"]
+    );
+}
+
+#[test]
+fn transition_forks_accept_both_proof_types() {
+    assert!(fork_violations(&tx(2, 5, 16), 15).is_empty());
+    assert!(fork_violations(&tx(2, 6, 16), 15).is_empty());
+    assert!(fork_violations(&tx(2, 4, 11), 13).is_empty());
+    assert_eq!(fork_violations(&tx(2, 4, 11), 14), vec!["rct_type"]);
+    // v1 txs with small rings were fine before RingCT became mandatory.
+    assert!(fork_violations(&tx(1, 0, 3), 4).is_empty());
+    assert_eq!(
+        fork_violations(&tx(1, 0, 5), 6),
+        vec!["version", "rct_type"]
+    );
+}
+
+#[test]
+fn coinbase_txs_are_never_flagged() {
+    let coinbase = parse_tx_json(
+        &json!({
+            "version": 2,
+            "unlock_time": 60,
+            "vin": [{ "gen": { "height": 1 } }],
+            "vout": [],
+            "extra": [],
+            "rct_signatures": { "type": 0 },
+        })
+        .to_string(),
+    )
+    .expect("coinbase json");
+    assert!(fork_violations(&coinbase, 16).is_empty());
+}
@@ -0,0 +1,52 @@
+#[tokio::test]
+async fn refresh_leaderboards_ranks_busiest_blocks() {
+    use ingestor::analytics;
+
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        eprintln!("skipping refresh_leaderboards_ranks_busiest_blocks: DATABASE_URL not set");
+        return;
+    };
+
+    let pool = sqlx::PgPool::connect(&database_url).await.unwrap();
+
+    let height = 990_200i64;
+    sqlx::query("DELETE FROM public.blocks WHERE height = $1")
+        .bind(height)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos, analytics_pending)
+         VALUES ($1, decode($2,'hex'), decode($3,'hex'), NOW(), 2000000000,14,14,0,2000000000,0, FALSE)",
+    )
+    .bind(height)
+    .bind("ed".repeat(32))
+    .bind("fe".repeat(32))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    analytics::refresh_leaderboards(&pool, 5).await.unwrap();
+
+    for metric in ["tx_count", "size"] {
+        let rows: Vec<(i32, i64, i64)> = sqlx::query_as(
+            "SELECT rank, height, value FROM public.top_blocks WHERE metric = $1 ORDER BY rank",
+        )
+        .bind(metric)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert!(rows.len() <= 5);
+        assert_eq!(rows.first(), Some(&(1, height, 2_000_000_000)));
+        assert!(rows.windows(2).all(|w| w[0].2 >= w[1].2));
+    }
+
+    sqlx::query("DELETE FROM public.blocks WHERE height = $1")
+        .bind(height)
+        .execute(&pool)
+        .await
+        .unwrap();
+    analytics::refresh_leaderboards(&pool, analytics::LEADERBOARD_SIZE)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,185 @@
+#[tokio::test]
+async fn mining_stats_bucket_nonces_and_residuals_per_day() {
+    use ingestor::analytics;
+
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        eprintln!(
+            "skipping mining_stats_bucket_nonces_and_residuals_per_day: DATABASE_URL not set"
+        );
+        return;
+    };
+
+    let pool = sqlx::PgPool::connect(&database_url).await.unwrap();
+
+    // A parent on 1970-01-01 and sixteen blocks on 1970-01-02, one per nonce
+    // bucket, each solved in exactly the 120s its difficulty predicts.
+    let first = 7_900_000i64;
+    let day_start = 86_400i64;
+    let cleanup = || async {
+        sqlx::query("DELETE FROM public.blocks WHERE height BETWEEN $1 AND $1 + 16")
+            .bind(first)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM public.mining_daily WHERE day IN ('1970-01-01', '1970-01-02')")
+            .execute(&pool)
+            .await
+            .unwrap();
+    };
+    cleanup().await;
+    for i in 0..=16i64 {
+        sqlx::query(
+            "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos, difficulty, analytics_pending)
+             VALUES ($1, decode(lpad(to_hex($1), 64, '0'), 'hex'), decode(lpad(to_hex($1 - 1), 64, '0'), 'hex'),
+                     to_timestamp($2), 100, 16, 16, $3, 1, 0, 240000, FALSE)",
+        )
+        .bind(first + i)
+        .bind((day_start - 120 + 120 * i) as f64)
+        .bind(((i + 15) % 16) << 28)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    // An orphan at the parent's height; the first block is not its child.
+    sqlx::query(
+        "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos, difficulty, analytics_pending)
+         VALUES ($1, decode(repeat('ee', 32), 'hex'), decode(repeat('00', 32), 'hex'),
+                 to_timestamp(0), 100, 16, 16, 0, 1, 0, 240000, FALSE)",
+    )
+    .bind(first)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    analytics::refresh_mining_stats(&pool, Some(day_start))
+        .await
+        .unwrap();
+
+    let (blocks, buckets, chi2, hashrate, mean_solve, residual): (
+        i32,
+        Vec<i32>,
+        f64,
+        Option<f64>,
+        Option<f64>,
+        Option<f64>,
+    ) = sqlx::query_as(
+        "SELECT blocks, nonce_buckets, nonce_chi2, implied_hashrate, mean_solve_secs,
+                residual_mean_abs_secs
+         FROM public.mining_daily WHERE day = '1970-01-02'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(blocks, 16);
+    assert_eq!(buckets, vec![1; 16]);
+    assert_eq!(chi2, 0.0);
+    assert_eq!(hashrate, Some(2_000.0));
+    assert_eq!(mean_solve, Some(120.0));
+    assert_eq!(residual, Some(0.0));
+
+    cleanup().await;
+}
+
+#[tokio::test]
+async fn difficulty_backfill_fills_only_the_daemons_blocks() {
+    use bex_core::BlockHash;
+    use ingestor::{
+        analytics,
+        rpc::Capabilities,
+        testing::{MockBlock, MockRpc},
+    };
+
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        eprintln!(
+            "skipping difficulty_backfill_fills_only_the_daemons_blocks: DATABASE_URL not set"
+        );
+        return;
+    };
+
+    let pool = sqlx::PgPool::connect(&database_url).await.unwrap();
+    let first = 7_910_000u64;
+    let hash = |height: u64| BlockHash::from_hex(&format!("{height:064x}")).unwrap();
+    let cleanup = || async {
+        sqlx::query("DELETE FROM public.blocks WHERE height BETWEEN $1 AND $1 + 4")
+            .bind(first as i64)
+            .execute(&pool)
+            .await
+            .unwrap();
+    };
+    cleanup().await;
+    // Five stored blocks without a difficulty, plus an orphan at the third
+    // height that the daemon does not know.
+    for height in first..first + 5 {
+        sqlx::query(
+            "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos, analytics_pending)
+             VALUES ($1, decode(lpad(to_hex($1), 64, '0'), 'hex'), decode(lpad(to_hex($1 - 1), 64, '0'), 'hex'),
+                     to_timestamp($1), 100, 16, 16, 0, 1, 0, FALSE)",
+        )
+        .bind(height as i64)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    sqlx::query(
+        "INSERT INTO public.blocks (height, hash, prev_hash, block_timestamp, size_bytes, major_version, minor_version, nonce, tx_count, reward_nanos, analytics_pending)
+         VALUES ($1, decode(repeat('ee', 32), 'hex'), decode(lpad(to_hex($1 - 1), 64, '0'), 'hex'),
+                 to_timestamp($1 + 1), 100, 16, 16, 0, 1, 0, FALSE)",
+    )
+    .bind((first + 2) as i64)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let blocks: Vec<MockBlock> = (first..first + 5)
+        .map(|height| {
+            let mut block = MockBlock::new(height, hash(height), hash(height - 1), Vec::new());
+            block.header.difficulty = height * 2;
+            block
+        })
+        .collect();
+    for headers_range in [true, false] {
+        let rpc = MockRpc::from_blocks(
+            blocks.clone(),
+            Capabilities {
+                headers_range,
+                blocks_by_height_bin: false,
+            },
+        );
+        sqlx::query(
+            "UPDATE public.blocks SET difficulty = NULL WHERE height BETWEEN $1 AND $1 + 4",
+        )
+        .bind(first as i64)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let filled = analytics::backfill_difficulty(
+            &pool,
+            &rpc,
+            headers_range,
+            first as i64..=(first + 4) as i64,
+            2,
+        )
+        .await
+        .unwrap();
+        assert_eq!(filled, 5, "headers_range {headers_range}");
+
+        let rows: Vec<(i64, Vec<u8>, Option<i64>)> = sqlx::query_as(
+            "SELECT height, hash, difficulty FROM public.blocks
+             WHERE height BETWEEN $1 AND $1 + 4 ORDER BY height, hash",
+        )
+        .bind(first as i64)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        for (height, stored, difficulty) in rows {
+            if stored == [0xee; 32] {
+                assert_eq!(difficulty, None);
+            } else {
+                assert_eq!(difficulty, Some(height * 2));
+            }
+        }
+    }
+
+    cleanup().await;
+}
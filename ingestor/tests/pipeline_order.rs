@@ -1,19 +1,19 @@
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use ingestor::{
+    capabilities::LiveCapabilities,
     checkpoint::Checkpoint,
+    events::{Event, Events},
     limits,
     pipeline::{self, PipelineCfg},
-    rpc::{
-        BlockHeader, Capabilities, GetBlockCountResult, GetBlockHeaderByHeightResult,
-        GetBlockResult, GetTransactionsResult, MoneroRpc,
-    },
+    rpc::MoneroRpc,
     store::Store,
+    testing::MockRpc,
     work_block, work_persist, work_sched, work_tx,
 };
 use sqlx::{migrate::Migrator, PgPool};
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 
 static MIGRATOR: Migrator = sqlx::migrate!("../db/migrations");
 
@@ -59,10 +59,10 @@ async fn pipeline_persists_in_order() -> Result<()> {
     let store = Store::connect(&database_url)
         .await
         .context("connect store")?;
-    let checkpoint = Arc::new(Checkpoint::new(store.pool().clone()));
+    let checkpoint = Arc::new(Checkpoint::new(store.pool().clone(), "stagenet"));
     let mock_rpc = Arc::new(MockRpc::new(BLOCK_COUNT));
-    let caps = mock_rpc.probe_caps().await;
-    let header_batch = if caps.headers_range { 200 } else { 1 };
+    let caps = Arc::new(LiveCapabilities::new(mock_rpc.probe_caps().await));
+    let header_batch = if caps.get().headers_range { 200 } else { 1 };
     let rpc: Arc<dyn MoneroRpc> = mock_rpc.clone();
     let limiter = Arc::new(limits::make_limiter(100, false));
 
@@ -81,8 +81,12 @@ async fn pipeline_persists_in_order() -> Result<()> {
         start_height: Some(1),
         limit: Some(BLOCK_COUNT),
         finality_window: 0,
-        caps,
+        caps: Arc::clone(&caps),
         header_batch,
+        readiness: Arc::default(),
+        tip_confirmations: 0,
+        daemon_tip: watch::channel(0).0,
+        memory: Default::default(),
     };
     let scheduler = tokio::spawn(async move { work_sched::run(tx_sched, sched_cfg, None).await });
 
@@ -91,9 +95,12 @@ async fn pipeline_persists_in_order() -> Result<()> {
         rpc: Arc::clone(&rpc),
         limiter: limiter.clone(),
         store: store.clone(),
-        finality_window: 0,
+        max_reorg_depth: 0,
         caps,
         header_batch,
+        events: Events::default(),
+        prefetch: None,
+        memory: Default::default(),
     };
     let mut block_handles = Vec::with_capacity(pipeline_cfg.block_workers);
     for _ in 0..pipeline_cfg.block_workers {
@@ -111,6 +118,7 @@ async fn pipeline_persists_in_order() -> Result<()> {
         rpc: Arc::clone(&rpc),
         limiter: limiter.clone(),
         concurrency: 3,
+        memory: Default::default(),
     };
     let mut tx_handles = Vec::with_capacity(pipeline_cfg.tx_workers);
     for _ in 0..pipeline_cfg.tx_workers {
@@ -123,11 +131,18 @@ async fn pipeline_persists_in_order() -> Result<()> {
     }
     drop(tx_tx);
 
+    let (events, mut published) = Events::channel();
     let persist_cfg = work_persist::Config {
         store: store.clone(),
         checkpoint: checkpoint.clone(),
         finality_window: 0,
         do_analytics: false,
+        chain_tips_retention: 0,
+        analytics_wake: None,
+        position_tx: None,
+        prepare_pool: work_persist::PreparePool::new(2),
+        events,
+        extra_retention: Default::default(),
     };
     let persister = tokio::spawn(async move { work_persist::run(rx_tx, persist_cfg, None).await });
 
@@ -147,6 +162,16 @@ async fn pipeline_persists_in_order() -> Result<()> {
         panic!("persister failed: {:?}", err);
     }
 
+    let mut announced = Vec::new();
+    while let Some(event) = published.recv().await {
+        match event {
+            Event::NewBlock { height, .. } => announced.push(height),
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+    announced.sort_unstable();
+    assert_eq!(announced, (1..=BLOCK_COUNT).collect::<Vec<_>>());
+
     let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM public.blocks")
         .fetch_one(store.pool())
         .await?;
@@ -157,181 +182,3 @@ async fn pipeline_persists_in_order() -> Result<()> {
 
     Ok(())
 }
-
-struct MockRpc {
-    blocks: Vec<MockBlock>,
-    caps: Capabilities,
-}
-
-impl MockRpc {
-    fn new(count: u64) -> Self {
-        Self::with_caps(
-            count,
-            Capabilities {
-                headers_range: false,
-                blocks_by_height_bin: false,
-            },
-        )
-    }
-
-    fn with_caps(count: u64, caps: Capabilities) -> Self {
-        let mut blocks = Vec::with_capacity(count as usize);
-        for height in 1..=count {
-            let hash = format!("{:064x}", height);
-            let prev_hash = format!("{:064x}", height.saturating_sub(1));
-            let tx_hashes = vec![
-                format!("{:064x}", height * 10 + 1),
-                format!("{:064x}", height * 10 + 2),
-            ];
-            let block_json = serde_json::json!({
-                "miner_tx": {
-                    "version": 1,
-                    "extra": "",
-                    "vin": [],
-                    "vout": [],
-                    "rct_signatures": {},
-                    "rctsig_prunable": {},
-                    "unlock_time": 0,
-                },
-                "tx_hashes": tx_hashes,
-            })
-            .to_string();
-            let tx_jsons = tx_hashes
-                .iter()
-                .map(|hash| {
-                    serde_json::json!({
-                        "tx_hash": hash,
-                        "version": 1,
-                        "vin": [],
-                        "vout": [],
-                        "extra": "",
-                        "rct_signatures": {},
-                        "rctsig_prunable": {},
-                        "unlock_time": 0,
-                    })
-                    .to_string()
-                })
-                .collect();
-
-            blocks.push(MockBlock {
-                header: BlockHeader {
-                    hash: hash.clone(),
-                    height,
-                    timestamp: height * 100,
-                    prev_hash,
-                    major_version: 1,
-                    minor_version: 1,
-                    nonce: 0,
-                    reward: 0,
-                    size: 1,
-                },
-                block_json,
-                miner_tx_hash: Some(format!("{:064x}", height * 1000)),
-                tx_hashes,
-                tx_jsons,
-            });
-        }
-        Self { blocks, caps }
-    }
-
-    fn jitter(height: u64, salt: u64) -> Duration {
-        let millis = ((height * 37 + salt * 17) % 11) + 1;
-        Duration::from_millis(millis)
-    }
-
-    async fn random_delay(height: u64, salt: u64) {
-        tokio::time::sleep(Self::jitter(height, salt)).await;
-    }
-}
-
-struct MockBlock {
-    header: BlockHeader,
-    block_json: String,
-    miner_tx_hash: Option<String>,
-    tx_hashes: Vec<String>,
-    tx_jsons: Vec<String>,
-}
-
-#[async_trait::async_trait]
-impl MoneroRpc for MockRpc {
-    async fn get_block_headers_range(&self, start: u64, end: u64) -> Result<Vec<BlockHeader>> {
-        Self::random_delay(start, 0).await;
-        Ok(self
-            .blocks
-            .iter()
-            .filter(|b| b.header.height >= start && b.header.height <= end)
-            .map(|b| b.header.clone())
-            .collect())
-    }
-
-    async fn get_block_header_by_height(
-        &self,
-        height: u64,
-    ) -> Result<GetBlockHeaderByHeightResult> {
-        Self::random_delay(height, 1).await;
-        let block = self
-            .blocks
-            .iter()
-            .find(|b| b.header.height == height)
-            .context("missing block header")?;
-        Ok(GetBlockHeaderByHeightResult {
-            block_header: block.header.clone(),
-            status: "OK".to_string(),
-        })
-    }
-
-    async fn get_block(&self, hash: &str, _fill_pow: bool) -> Result<GetBlockResult> {
-        let block = self
-            .blocks
-            .iter()
-            .find(|b| b.header.hash == hash)
-            .context("missing block")?;
-        Self::random_delay(block.header.height, 2).await;
-        Ok(GetBlockResult {
-            block_header: block.header.clone(),
-            json: Some(block.block_json.clone()),
-            blob: None,
-            miner_tx_hash: block.miner_tx_hash.clone(),
-            status: "OK".to_string(),
-        })
-    }
-
-    async fn get_transactions(&self, txs_hashes: &[String]) -> Result<GetTransactionsResult> {
-        let mut jsons = Vec::with_capacity(txs_hashes.len());
-        for hash in txs_hashes.iter() {
-            let tx = self
-                .blocks
-                .iter()
-                .flat_map(|b| b.tx_hashes.iter().zip(b.tx_jsons.iter()))
-                .find(|(h, _)| h.as_str() == hash.as_str())
-                .context("missing tx json")?;
-            jsons.push(tx.1.clone());
-        }
-        let height = txs_hashes
-            .first()
-            .and_then(|hash| self.blocks.iter().find(|b| b.tx_hashes.contains(hash)))
-            .map(|b| b.header.height)
-            .unwrap_or_default();
-        Self::random_delay(height, 3).await;
-        Ok(GetTransactionsResult {
-            txs_as_json: jsons,
-            missed_tx: Vec::new(),
-            status: "OK".to_string(),
-        })
-    }
-
-    async fn get_block_count(&self) -> Result<GetBlockCountResult> {
-        Ok(GetBlockCountResult {
-            count: BLOCK_COUNT + 1,
-            status: "OK".to_string(),
-        })
-    }
-
-    async fn get_transaction_pool_hashes(&self) -> Result<Vec<String>> {
-        Ok(Vec::new())
-    }
-
-    async fn probe_caps(&self) -> Capabilities {
-        self.caps
-    }
-}
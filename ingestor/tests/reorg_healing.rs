@@ -1,25 +1,17 @@
 use anyhow::{Context, Result};
 use httpmock::{prelude::*, Mock};
-use ingestor::{reorg::heal_reorg, rpc::Rpc, store::Store};
+use ingestor::{
+    checkpoint::Checkpoint, reorg::heal_reorg, rpc::Rpc, store::Store, testing::TestDb,
+};
 use serde_json::json;
-use sqlx::{migrate::Migrator, PgPool};
-
-static MIGRATOR: Migrator = sqlx::migrate!("../db/migrations");
 
 #[tokio::test]
 async fn heals_three_block_reorg_db_only() -> Result<()> {
-    let database_url = match std::env::var("DATABASE_URL") {
-        Ok(url) => url,
-        Err(_) => {
-            eprintln!("skipping heals_three_block_reorg_db_only: DATABASE_URL not set");
-            return Ok(());
-        }
+    let Some(db) = TestDb::start().await.context("start test database")? else {
+        eprintln!("skipping heals_three_block_reorg_db_only: no database available");
+        return Ok(());
     };
-
-    let pool = PgPool::connect(&database_url)
-        .await
-        .context("connect to database")?;
-    MIGRATOR.run(&pool).await.context("run migrations")?;
+    let pool = db.pool.clone();
 
     let mut cleanup = pool.begin().await?;
     sqlx::query!("DELETE FROM public.chain_tips WHERE height >= $1", 100_i64)
@@ -31,11 +23,15 @@ async fn heals_three_block_reorg_db_only() -> Result<()> {
     sqlx::query!("DELETE FROM public.mempool_txs")
         .execute(&mut *cleanup)
         .await?;
+    sqlx::query("DELETE FROM public.txs WHERE tx_hash = decode(repeat('de', 32), 'hex')")
+        .execute(&mut *cleanup)
+        .await?;
+    sqlx::query!("DELETE FROM public.ingestor_checkpoint")
+        .execute(&mut *cleanup)
+        .await?;
     cleanup.commit().await?;
 
-    let store = Store::connect(&database_url)
-        .await
-        .context("connect store")?;
+    let store = Store::connect(&db.url).await.context("connect store")?;
 
     let mut seed = store.pool().begin().await?;
     let blocks = vec![
@@ -122,9 +118,16 @@ async fn heals_three_block_reorg_db_only() -> Result<()> {
     let _mock_101 = mock_header(&server, 101, &"ef".repeat(32), &"ee".repeat(32));
     let _mock_100 = mock_header(&server, 100, &"aa".repeat(32), &"00".repeat(32));
 
+    let checkpoint = Checkpoint::new(store.pool().clone(), "stagenet");
+    checkpoint.set(103, 102).await?;
+
     let rpc = Rpc::new(server.url("/"));
     heal_reorg(103, &store, &rpc, 10).await?;
 
+    // The removed heights are scheduled again after a restart.
+    let state = checkpoint.get_state().await?;
+    assert_eq!((state.ingested_height, state.finalized_height), (100, 100));
+
     let remaining: i64 =
         sqlx::query_scalar("SELECT COUNT(*) FROM public.blocks WHERE height >= $1")
             .bind(101_i64)
@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use ingestor::{
+    replay::{self, Fixture},
+    store::Store,
+    testing::TestDb,
+};
+
+#[test]
+fn fixture_must_fork_off_the_chain() -> Result<()> {
+    let mut fixture = Fixture::load(Path::new("tests/fixtures/reorg_three_deep.json"))?;
+    fixture.validate()?;
+
+    let mut short = fixture.clone();
+    short.reorg.blocks.truncate(2);
+    assert!(short.validate().is_err());
+
+    fixture.reorg.blocks[0].prev_hash = fixture.chain[0].hash;
+    assert!(fixture.validate().is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn replayed_reorg_rewrites_the_stored_chain() -> Result<()> {
+    let Some(db) = TestDb::start().await.context("start test database")? else {
+        eprintln!("skipping replayed_reorg_rewrites_the_stored_chain: no database available");
+        return Ok(());
+    };
+    let fixture = Fixture::load(Path::new("tests/fixtures/reorg_three_deep.json"))?;
+
+    let mut cleanup = db.pool.begin().await?;
+    sqlx::query("DELETE FROM public.txs WHERE block_height BETWEEN 200 AND 207 OR substr(encode(tx_hash, 'hex'), 1, 2) IN ('a1', 'b1')")
+        .execute(&mut *cleanup)
+        .await?;
+    sqlx::query("DELETE FROM public.blocks WHERE height BETWEEN 200 AND 207")
+        .execute(&mut *cleanup)
+        .await?;
+    sqlx::query("DELETE FROM public.ingestor_checkpoint")
+        .execute(&mut *cleanup)
+        .await?;
+    cleanup.commit().await?;
+
+    let store = Store::connect(&db.url).await.context("connect store")?;
+    let report = replay::replay(&store, "stagenet", &fixture, 10).await?;
+    assert!(report.failures.is_empty(), "{:#?}", report.failures);
+    assert_eq!(report.fork_height, Some(203));
+    assert_eq!(report.checkpoint, 207);
+
+    // A second replay on the same rows is refused rather than mixed in.
+    assert!(replay::replay(&store, "stagenet", &fixture, 10)
+        .await
+        .is_err());
+    Ok(())
+}
@@ -19,7 +19,7 @@ async fn can_fetch_header_and_block_and_txs() {
         .await
         .expect("header fetch");
     assert_eq!(hdr.block_header.height, height);
-    let hash = hdr.block_header.hash.clone();
+    let hash = hdr.block_header.hash;
 
     let blk = rpc.get_block(&hash, false).await.expect("block fetch");
     assert_eq!(blk.block_header.hash, hash);
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use bex_core::BlockHash;
+use ingestor::{
+    limits,
+    rpc::{
+        BlockHeader, Capabilities, GetBlockCountResult, GetBlockHeaderByHeightResult,
+        GetBlockResult, GetTransactionsResult, MoneroRpc, PoolTx,
+    },
+    trace,
+};
+
+const TX_HASH: &str = "11111111111111111111111111111111111111111111111111111111111111aa";
+
+struct OneBlockRpc {
+    header: BlockHeader,
+    tx_json: String,
+}
+
+impl OneBlockRpc {
+    fn new(reported_tx_hash: &str) -> Self {
+        Self {
+            header: BlockHeader {
+                hash: format!("{:064x}", 7).parse().unwrap(),
+                height: 7,
+                timestamp: 700,
+                prev_hash: format!("{:064x}", 6).parse().unwrap(),
+                major_version: 16,
+                minor_version: 16,
+                nonce: 0,
+                reward: 600_000_000_000,
+                size: 100,
+                difficulty: 0,
+            },
+            tx_json: serde_json::json!({
+                "tx_hash": reported_tx_hash,
+                "version": 2,
+                "vin": [{"key": {"amount": 0, "key_offsets": [1, 2, 3], "k_image": "00"}}],
+                "vout": [{}, {}],
+                "extra": [1, 2, 3],
+                "rct_signatures": {"type": 6, "txnFee": 30_000_000},
+                "rctsig_prunable": {},
+                "unlock_time": 0,
+            })
+            .to_string(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MoneroRpc for OneBlockRpc {
+    async fn get_block_headers_range(&self, _start: u64, _end: u64) -> Result<Vec<BlockHeader>> {
+        Ok(vec![self.header.clone()])
+    }
+
+    async fn get_block_header_by_height(
+        &self,
+        height: u64,
+    ) -> Result<GetBlockHeaderByHeightResult> {
+        anyhow::ensure!(height == self.header.height, "no block at {height}");
+        Ok(GetBlockHeaderByHeightResult {
+            block_header: self.header.clone(),
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_block(&self, _hash: &BlockHash, _fill_pow: bool) -> Result<GetBlockResult> {
+        let json = serde_json::json!({
+            "miner_tx": {
+                "version": 2,
+                "extra": "",
+                "vin": [],
+                "vout": [{}],
+                "rct_signatures": {"type": 0},
+                "unlock_time": 67,
+            },
+            "tx_hashes": [TX_HASH],
+        });
+        Ok(GetBlockResult {
+            block_header: self.header.clone(),
+            json: Some(json.to_string()),
+            blob: None,
+            miner_tx_hash: Some(format!("{:064x}", 70)),
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_transactions(&self, txs_hashes: &[String]) -> Result<GetTransactionsResult> {
+        Ok(GetTransactionsResult {
+            txs_as_json: txs_hashes.iter().map(|_| self.tx_json.clone()).collect(),
+            missed_tx: Vec::new(),
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_block_count(&self) -> Result<GetBlockCountResult> {
+        Ok(GetBlockCountResult {
+            count: 8,
+            status: "OK".to_string(),
+        })
+    }
+
+    async fn get_transaction_pool_hashes(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_pool_transactions(&self, _txs_hashes: &[String]) -> Result<Vec<PoolTx>> {
+        Ok(Vec::new())
+    }
+
+    async fn probe_caps(&self) -> Capabilities {
+        Capabilities {
+            headers_range: false,
+            blocks_by_height_bin: false,
+        }
+    }
+}
+
+fn check<'a>(report: &'a trace::BlockTrace, name: &str) -> &'a trace::Check {
+    report
+        .checks
+        .iter()
+        .find(|c| c.name == name)
+        .unwrap_or_else(|| panic!("missing check {name}"))
+}
+
+#[tokio::test]
+async fn trace_reports_derived_fields_without_a_database() -> Result<()> {
+    let rpc: Arc<dyn MoneroRpc> = Arc::new(OneBlockRpc::new(TX_HASH));
+    let limiter = Arc::new(limits::make_limiter(100, false));
+    let report = trace::trace_block(rpc, limiter, None, 7)
+        .await
+        .context("trace block")?;
+
+    assert_eq!(report.failures(), 0);
+    let stages: Vec<_> = report.timings_ms.iter().map(|t| t.stage).collect();
+    assert_eq!(stages, ["fetch_block", "fetch_txs", "prepare", "validate"]);
+
+    assert_eq!(report.txs.len(), 2);
+    assert!(report.txs[0].coinbase);
+    let tx = &report.txs[1];
+    let row = tx.row.as_ref().expect("derived row");
+    assert_eq!(row["fee"], 30_000_000);
+    assert_eq!(row["num_inputs"], 1);
+    assert_eq!(row["num_outputs"], 2);
+    assert_eq!(tx.analysis.as_ref().unwrap().ring_sizes, [3]);
+    assert_eq!(check(&report, "emission").ok, None);
+    assert_eq!(check(&report, "stored_txs").ok, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn trace_flags_hash_mismatch() -> Result<()> {
+    let other = format!("{:064x}", 99);
+    let rpc: Arc<dyn MoneroRpc> = Arc::new(OneBlockRpc::new(&other));
+    let limiter = Arc::new(limits::make_limiter(100, false));
+    let report = trace::trace_block(rpc, limiter, None, 7).await?;
+
+    let hashes = check(&report, "tx_hashes");
+    assert_eq!(hashes.ok, Some(false));
+    assert!(hashes.detail.contains(&other));
+    assert_eq!(report.failures(), 1);
+    Ok(())
+}